@@ -9,13 +9,24 @@
 //!
 //! The following crate feature flags are available:
 //!
+//! * `aws-xray`: Adds an AWS X-Ray trace exporter and propagator.
 //! * `binary-propagator`: Adds Experimental binary propagator to propagate trace context using binary format.
 //! * `base64-format`: Enables base64 format support for binary propagators.
-//! * `datadog`: Adds a Datadog trace exporter.
-//! * `reqwest-blocking-client`: Export spans using the reqwest blocking http
+//! * `datadog`: Adds a Datadog trace exporter and propagator.
+//! * `dogstatsd`: Adds a metrics exporter reporting to DogStatsD.
+//! * `influxdb`: Adds a metrics exporter writing the InfluxDB line protocol.
+//! * `messaging`: Adds builders for messaging consumer spans following the
+//!   semantic conventions.
+//! * `ot`: Adds a propagator for the OpenTracing basic tracer headers.
+//! * `rdkafka`, `lapin`, `async-nats`, `rusoto_sqs`: Add propagation carriers
+//!   for the headers of the respective messaging clients.
+//! * `reqwest-blocking-client`: Export spans and metrics using the reqwest
+//!   blocking http client.
+//! * `reqwest-client`: Export spans and metrics using the reqwest non-blocking
+//!   http client.
+//! * `surf-client`: Export spans and metrics using the surf non-blocking http
 //!   client.
-//! * `reqwest-client`: Export spans using the reqwest non-blocking http client.
-//! * `surf-client`: Export spans using the surf non-blocking http client.
+//! * `tonic`: Use `tonic::metadata::MetadataMap` as a binary propagator carrier.
 #![warn(
     future_incompatible,
     missing_debug_implementations,
@@ -31,4 +42,5 @@
 )]
 #![cfg_attr(test, deny(warnings))]
 
+pub mod metrics;
 pub mod trace;
@@ -0,0 +1,653 @@
+//! # DogStatsD Metrics Exporter
+//!
+//! Reports metrics to the [DogStatsD] server of a Datadog agent, over UDP or
+//! a Unix domain socket.
+//!
+//! Metrics are exported with delta temporality, counters and sum observers
+//! are reported as DogStatsD counts of the change since the last export and
+//! value observers as gauges. Value recorders are aggregated as their min and
+//! max, reported as `.min` and `.max` gauges, and their sum and count,
+//! reported as `.sum` and `.count` counts. Histograms are reported as their
+//! `.sum` and `.count` counts and a cumulative `.bucket` count per boundary
+//! tagged with its `le` upper bound. Labels and resource attributes are
+//! reported as tags.
+//!
+//! With the `simple::Selector::Exact` aggregator selector every value recorded
+//! in a period is kept and reported as a histogram or distribution value
+//! instead. Values are packed in multi-value lines, which require Datadog
+//! agent 6.25 / 7.25 or later.
+//!
+//! Lines are batched in datagrams of at most the configured max packet size,
+//! 1432 bytes by default for UDP and 8192 bytes for Unix domain sockets.
+//!
+//! [DogStatsD]: https://docs.datadoghq.com/developers/dogstatsd/
+//!
+//! ## Example
+//!
+//! ```no_run
+//! use opentelemetry::{global, KeyValue};
+//! use opentelemetry_contrib::metrics::exporter::dogstatsd;
+//! use std::time::Duration;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> opentelemetry::metrics::Result<()> {
+//! let _controller = dogstatsd::new_pipeline(tokio::spawn, tokio::time::interval)
+//!     .with_agent_endpoint("127.0.0.1:8125")
+//!     .with_period(Duration::from_secs(10))
+//!     .try_init()?;
+//!
+//! let meter = global::meter("my_app");
+//! let counter = meter.u64_counter("requests").init();
+//! counter.add(1, &[KeyValue::new("endpoint", "/users")]);
+//! # Ok(())
+//! # }
+//! ```
+use futures::Stream;
+use opentelemetry::metrics::{Descriptor, MetricsError, NumberKind, Result};
+use opentelemetry::sdk::{
+    export::metrics::{
+        self, CheckpointSet, Count, ExportKind, ExportKindFor, ExportKindSelector, Histogram,
+        LastValue, MinMaxSumCount, Points, Record, Sum,
+    },
+    metrics::{
+        aggregators::{
+            ArrayAggregator, DDSKetchAggregator, HistogramAggregator, LastValueAggregator,
+            MinMaxSumCountAggregator, SumAggregator,
+        },
+        controllers::{self, PushController, PushControllerWorker},
+        selectors::simple,
+    },
+    Resource,
+};
+use opentelemetry::{global, metrics::Number};
+use opentelemetry_statsd::dogstatsd;
+use std::io;
+use std::net::UdpSocket;
+#[cfg(unix)]
+use std::os::unix::net::UnixDatagram;
+#[cfg(unix)]
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Default DogStatsD agent endpoint
+const DEFAULT_AGENT_ENDPOINT: &str = "127.0.0.1:8125";
+
+/// Default max packet size of UDP datagrams, fitting in an ethernet frame.
+const DEFAULT_UDP_MAX_PACKET_SIZE: usize = 1432;
+
+/// Default max packet size of Unix domain socket datagrams.
+#[cfg(unix)]
+const DEFAULT_UNIX_MAX_PACKET_SIZE: usize = 8192;
+
+/// Create a new DogStatsD exporter pipeline builder.
+pub fn new_pipeline<S, SO, I, IS, ISI>(spawn: S, interval: I) -> DogStatsdPipelineBuilder<S, I>
+where
+    S: Fn(PushControllerWorker) -> SO,
+    I: Fn(Duration) -> IS,
+    IS: Stream<Item = ISI> + Send + 'static,
+{
+    DogStatsdPipelineBuilder {
+        spawn,
+        interval,
+        agent_endpoint: DEFAULT_AGENT_ENDPOINT.to_string(),
+        #[cfg(unix)]
+        socket_path: None,
+        max_packet_size: None,
+        aggregator_selector: simple::Selector::Inexpensive,
+        recorder_type: RecorderType::Histogram,
+        period: None,
+        resource: None,
+    }
+}
+
+/// DogStatsD metric type of the values recorded by value recorders.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RecorderType {
+    /// Histograms are aggregated by each agent.
+    Histogram,
+    /// Distributions are aggregated globally by Datadog.
+    Distribution,
+}
+
+impl RecorderType {
+    fn as_str(self) -> &'static str {
+        match self {
+            RecorderType::Histogram => "h",
+            RecorderType::Distribution => "d",
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Socket {
+    Udp(UdpSocket),
+    #[cfg(unix)]
+    Unix(UnixDatagram),
+}
+
+impl Socket {
+    fn send(&self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Socket::Udp(socket) => socket.send(buf),
+            #[cfg(unix)]
+            Socket::Unix(socket) => socket.send(buf),
+        }
+    }
+}
+
+/// DogStatsD metrics exporter
+#[derive(Debug)]
+pub struct Exporter {
+    socket: Socket,
+    max_packet_size: usize,
+    recorder_type: RecorderType,
+}
+
+impl Exporter {
+    /// Appends the DogStatsD lines of `record` to `lines`.
+    fn write_lines(&self, record: &Record<'_>, lines: &mut Vec<String>) -> Result<()> {
+        let agg = record.aggregator().ok_or(MetricsError::NoDataCollected)?;
+        let desc = record.descriptor();
+        let kind = desc.number_kind();
+        let tags = tags(record);
+
+        if let Some(sum) = agg.as_any().downcast_ref::<SumAggregator>() {
+            lines.push(line(desc.name(), &sum.sum()?, kind, "c", &tags));
+        } else if let Some(last_value) = agg.as_any().downcast_ref::<LastValueAggregator>() {
+            let (value, _) = last_value.last_value()?;
+            lines.push(line(desc.name(), &value, kind, "g", &tags));
+        } else if let Some(histogram) = agg.as_any().downcast_ref::<HistogramAggregator>() {
+            histogram_lines(desc.name(), histogram, kind, &tags, lines)?;
+        } else if let Some(array) = agg.as_any().downcast_ref::<ArrayAggregator>() {
+            self.write_values(desc.name(), &array.points()?, kind, &tags, lines);
+        } else if let Some(mmsc) = agg.as_any().downcast_ref::<MinMaxSumCountAggregator>() {
+            min_max_sum_count(desc.name(), mmsc, kind, &tags, lines)?;
+        } else if let Some(sketch) = agg.as_any().downcast_ref::<DDSKetchAggregator>() {
+            min_max_sum_count(desc.name(), sketch, kind, &tags, lines)?;
+        }
+
+        Ok(())
+    }
+
+    /// Appends multi-value lines of `points`, each fitting in a packet.
+    fn write_values(
+        &self,
+        name: &str,
+        points: &[Number],
+        kind: &NumberKind,
+        tags: &str,
+        lines: &mut Vec<String>,
+    ) {
+        let name = dogstatsd::sanitize_name(name);
+        let suffix = dogstatsd::suffix(self.recorder_type.as_str(), tags);
+        let mut line = name.clone();
+        for point in points {
+            let value = format!("{:?}", point.to_debug(kind));
+            if line.len() > name.len()
+                && line.len() + 1 + value.len() + suffix.len() > self.max_packet_size
+            {
+                line.push_str(&suffix);
+                lines.push(line);
+                line = name.clone();
+            }
+            line.push(':');
+            line.push_str(&value);
+        }
+        if line.len() > name.len() {
+            line.push_str(&suffix);
+            lines.push(line);
+        }
+    }
+
+    fn send(&self, packet: &str) -> Result<()> {
+        self.socket
+            .send(packet.as_bytes())
+            .map(|_| ())
+            .map_err(|err| MetricsError::Other(err.to_string()))
+    }
+}
+
+impl metrics::Exporter for Exporter {
+    fn export(&self, checkpoint_set: &mut dyn CheckpointSet) -> Result<()> {
+        let mut lines = Vec::new();
+        checkpoint_set.try_for_each(self, &mut |record| self.write_lines(record, &mut lines))?;
+
+        // Lines longer than the max packet size are sent on their own.
+        let mut packet = String::with_capacity(self.max_packet_size);
+        for line in lines {
+            if !packet.is_empty() && packet.len() + 1 + line.len() > self.max_packet_size {
+                self.send(&packet)?;
+                packet.clear();
+            }
+            if !packet.is_empty() {
+                packet.push('\n');
+            }
+            packet.push_str(&line);
+        }
+        if !packet.is_empty() {
+            self.send(&packet)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl ExportKindFor for Exporter {
+    fn export_kind_for(&self, descriptor: &Descriptor) -> ExportKind {
+        ExportKindSelector::Delta.export_kind_for(descriptor)
+    }
+}
+
+/// Appends the `.min` and `.max` gauges and `.sum` and `.count` counts of
+/// `agg`.
+fn min_max_sum_count<A: MinMaxSumCount>(
+    name: &str,
+    agg: &A,
+    kind: &NumberKind,
+    tags: &str,
+    lines: &mut Vec<String>,
+) -> Result<()> {
+    let count = Number::from(agg.count()?);
+    lines.push(line(&format!("{}.min", name), &agg.min()?, kind, "g", tags));
+    lines.push(line(&format!("{}.max", name), &agg.max()?, kind, "g", tags));
+    lines.push(line(&format!("{}.sum", name), &agg.sum()?, kind, "c", tags));
+    lines.push(line(
+        &format!("{}.count", name),
+        &count,
+        &NumberKind::U64,
+        "c",
+        tags,
+    ));
+    Ok(())
+}
+
+/// Appends the cumulative `.bucket` counts tagged with their `le` upper bound,
+/// and the `.sum` and `.count` counts of `histogram`.
+fn histogram_lines(
+    name: &str,
+    histogram: &HistogramAggregator,
+    kind: &NumberKind,
+    tags: &str,
+    lines: &mut Vec<String>,
+) -> Result<()> {
+    let buckets = histogram.histogram()?;
+    let bucket_name = format!("{}.bucket", name);
+    let mut count = 0.0;
+    for (index, bucket_count) in buckets.counts().iter().enumerate() {
+        count += bucket_count;
+        let boundary = buckets
+            .boundaries()
+            .get(index)
+            .map(|boundary| boundary.to_string())
+            .unwrap_or_else(|| "+Inf".to_string());
+        let le = dogstatsd::tags(vec![("le", boundary.as_str())]);
+        let bucket_tags = if tags.is_empty() {
+            le
+        } else {
+            format!("{},{}", tags, le)
+        };
+        lines.push(dogstatsd::line(
+            &bucket_name,
+            &(count as u64).to_string(),
+            "c",
+            &bucket_tags,
+        ));
+    }
+    let count = Number::from(histogram.count()?);
+    lines.push(line(
+        &format!("{}.sum", name),
+        &histogram.sum()?,
+        kind,
+        "c",
+        tags,
+    ));
+    lines.push(line(
+        &format!("{}.count", name),
+        &count,
+        &NumberKind::U64,
+        "c",
+        tags,
+    ));
+    Ok(())
+}
+
+/// Formats a `name:value|type|#tags` DogStatsD line.
+fn line(name: &str, value: &Number, kind: &NumberKind, metric_type: &str, tags: &str) -> String {
+    dogstatsd::line(
+        name,
+        &format!("{:?}", value.to_debug(kind)),
+        metric_type,
+        tags,
+    )
+}
+
+/// Comma separated `key:value` tags of the resource and labels of `record`.
+fn tags(record: &Record<'_>) -> String {
+    let values = record
+        .resource()
+        .iter()
+        .chain(record.labels().iter())
+        .map(|(key, value)| (key.as_str(), value.as_str()))
+        .collect::<Vec<_>>();
+    dogstatsd::tags(values.iter().map(|(key, value)| (*key, value.as_ref())))
+}
+
+/// Pipeline builder
+#[derive(Debug)]
+pub struct DogStatsdPipelineBuilder<S, I> {
+    spawn: S,
+    interval: I,
+    agent_endpoint: String,
+    #[cfg(unix)]
+    socket_path: Option<PathBuf>,
+    max_packet_size: Option<usize>,
+    aggregator_selector: simple::Selector,
+    recorder_type: RecorderType,
+    period: Option<Duration>,
+    resource: Option<Resource>,
+}
+
+impl<S, SO, I, IS, ISI> DogStatsdPipelineBuilder<S, I>
+where
+    S: Fn(PushControllerWorker) -> SO,
+    I: Fn(Duration) -> IS,
+    IS: Stream<Item = ISI> + Send + 'static,
+{
+    /// Assign the DogStatsD agent UDP endpoint, defaults to `127.0.0.1:8125`.
+    pub fn with_agent_endpoint<T: Into<String>>(self, agent_endpoint: T) -> Self {
+        DogStatsdPipelineBuilder {
+            agent_endpoint: agent_endpoint.into(),
+            ..self
+        }
+    }
+
+    /// Send metrics to the DogStatsD Unix domain socket at `path` instead of
+    /// the UDP agent endpoint.
+    #[cfg(unix)]
+    pub fn with_unix_socket<P: Into<PathBuf>>(self, path: P) -> Self {
+        DogStatsdPipelineBuilder {
+            socket_path: Some(path.into()),
+            ..self
+        }
+    }
+
+    /// Assign the max size of sent datagrams.
+    pub fn with_max_packet_size(self, max_packet_size: usize) -> Self {
+        DogStatsdPipelineBuilder {
+            max_packet_size: Some(max_packet_size),
+            ..self
+        }
+    }
+
+    /// Assign the aggregator selector, defaults to
+    /// `simple::Selector::Inexpensive`.
+    ///
+    /// `simple::Selector::Exact` keeps every value recorded by value recorders
+    /// until the next export.
+    pub fn with_aggregator_selector(self, aggregator_selector: simple::Selector) -> Self {
+        DogStatsdPipelineBuilder {
+            aggregator_selector,
+            ..self
+        }
+    }
+
+    /// Assign the DogStatsD metric type of the values reported with the
+    /// `simple::Selector::Exact` aggregator selector, defaults to histograms.
+    pub fn with_recorder_type(self, recorder_type: RecorderType) -> Self {
+        DogStatsdPipelineBuilder {
+            recorder_type,
+            ..self
+        }
+    }
+
+    /// Set the frequency in which metrics are exported.
+    pub fn with_period(self, period: Duration) -> Self {
+        DogStatsdPipelineBuilder {
+            period: Some(period),
+            ..self
+        }
+    }
+
+    /// Set the resource reported as tags of all metrics.
+    pub fn with_resource(self, resource: Resource) -> Self {
+        DogStatsdPipelineBuilder {
+            resource: Some(resource),
+            ..self
+        }
+    }
+
+    /// Build a new push controller and install it as the global meter
+    /// provider, returning errors if they arise.
+    pub fn try_init(self) -> Result<PushController> {
+        let exporter = self.build_exporter()?;
+        let mut push_builder = controllers::push(
+            self.aggregator_selector,
+            ExportKindSelector::Delta,
+            exporter,
+            self.spawn,
+            self.interval,
+        );
+        if let Some(period) = self.period {
+            push_builder = push_builder.with_period(period);
+        }
+        if let Some(resource) = self.resource {
+            push_builder = push_builder.with_resource(resource);
+        }
+
+        let controller = push_builder.build();
+        global::set_meter_provider(controller.provider());
+        Ok(controller)
+    }
+
+    fn build_exporter(&self) -> Result<Exporter> {
+        #[cfg(unix)]
+        {
+            if let Some(path) = &self.socket_path {
+                let socket = UnixDatagram::unbound()
+                    .and_then(|socket| socket.connect(path).map(|_| socket))
+                    .map_err(|err| MetricsError::Other(err.to_string()))?;
+                return Ok(Exporter {
+                    socket: Socket::Unix(socket),
+                    max_packet_size: self.max_packet_size.unwrap_or(DEFAULT_UNIX_MAX_PACKET_SIZE),
+                    recorder_type: self.recorder_type,
+                });
+            }
+        }
+
+        let socket = opentelemetry_statsd::connect_udp(&self.agent_endpoint)
+            .map_err(|err| MetricsError::Other(err.to_string()))?;
+
+        Ok(Exporter {
+            socket: Socket::Udp(socket),
+            max_packet_size: self.max_packet_size.unwrap_or(DEFAULT_UDP_MAX_PACKET_SIZE),
+            recorder_type: self.recorder_type,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry::metrics::InstrumentKind;
+    use opentelemetry::sdk::{export::metrics::Exporter as _, metrics::aggregators};
+    use opentelemetry::testing::metrics::{new_test_descriptor, TestCheckpointSet};
+    use opentelemetry::KeyValue;
+    use std::sync::Arc;
+
+    fn checkpoint_set() -> TestCheckpointSet {
+        TestCheckpointSet::new(Resource::new(vec![KeyValue::new("service", "app")]))
+    }
+
+    fn test_exporter(max_packet_size: usize, recorder_type: RecorderType) -> (Exporter, UdpSocket) {
+        let listener = UdpSocket::bind("127.0.0.1:0").unwrap();
+        listener
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let exporter = new_pipeline(|_| (), |_| futures::stream::empty::<()>())
+            .with_agent_endpoint(listener.local_addr().unwrap().to_string())
+            .with_max_packet_size(max_packet_size)
+            .with_recorder_type(recorder_type)
+            .build_exporter()
+            .unwrap();
+
+        (exporter, listener)
+    }
+
+    fn receive(listener: &UdpSocket) -> String {
+        let mut buf = [0; 1024];
+        let len = listener.recv(&mut buf).unwrap();
+        String::from_utf8(buf[..len].to_vec()).unwrap()
+    }
+
+    #[test]
+    fn exports_lines() {
+        let (exporter, listener) = test_exporter(1432, RecorderType::Distribution);
+        let mut checkpoint_set = checkpoint_set();
+        checkpoint_set.add(
+            new_test_descriptor("requests", InstrumentKind::Counter, NumberKind::U64),
+            Arc::new(aggregators::sum()),
+            vec![1u64.into(), 2u64.into()],
+            vec![KeyValue::new("endpoint", "/users|all")],
+        );
+        checkpoint_set.add(
+            new_test_descriptor(
+                "temperature",
+                InstrumentKind::ValueObserver,
+                NumberKind::F64,
+            ),
+            Arc::new(aggregators::last_value()),
+            vec![21.5f64.into()],
+            vec![],
+        );
+        checkpoint_set.add(
+            new_test_descriptor("latency", InstrumentKind::ValueRecorder, NumberKind::I64),
+            Arc::new(aggregators::array()),
+            vec![3i64.into(), 5i64.into()],
+            vec![KeyValue::new("url", "http://localhost")],
+        );
+
+        exporter.export(&mut checkpoint_set).unwrap();
+
+        assert_eq!(
+            receive(&listener),
+            "requests:3|c|#service:app,endpoint:/users_all\n\
+             temperature:21.5|g|#service:app\n\
+             latency:3:5|d|#service:app,url:http://localhost"
+        );
+    }
+
+    #[test]
+    fn exports_min_max_sum_count() {
+        let (exporter, listener) = test_exporter(1432, RecorderType::Histogram);
+        let mut checkpoint_set = checkpoint_set();
+        let descriptor =
+            new_test_descriptor("latency", InstrumentKind::ValueRecorder, NumberKind::F64);
+        checkpoint_set.add(
+            descriptor.clone(),
+            Arc::new(aggregators::min_max_sum_count(&descriptor)),
+            vec![1.5f64.into(), 3.0f64.into()],
+            vec![],
+        );
+
+        exporter.export(&mut checkpoint_set).unwrap();
+
+        assert_eq!(
+            receive(&listener),
+            "latency.min:1.5|g|#service:app\n\
+             latency.max:3.0|g|#service:app\n\
+             latency.sum:4.5|c|#service:app\n\
+             latency.count:2|c|#service:app"
+        );
+    }
+
+    #[test]
+    fn exports_histogram() {
+        let (exporter, listener) = test_exporter(1432, RecorderType::Histogram);
+        let mut checkpoint_set = checkpoint_set();
+        let descriptor =
+            new_test_descriptor("latency", InstrumentKind::ValueRecorder, NumberKind::F64);
+        checkpoint_set.add(
+            descriptor.clone(),
+            Arc::new(aggregators::histogram(&descriptor, &[1.0, 5.0])),
+            vec![0.5f64.into(), 2.0f64.into(), 3.0f64.into(), 7.0f64.into()],
+            vec![],
+        );
+
+        exporter.export(&mut checkpoint_set).unwrap();
+
+        assert_eq!(
+            receive(&listener),
+            "latency.bucket:1|c|#service:app,le:1\n\
+             latency.bucket:3|c|#service:app,le:5\n\
+             latency.bucket:4|c|#service:app,le:+Inf\n\
+             latency.sum:12.5|c|#service:app\n\
+             latency.count:4|c|#service:app"
+        );
+    }
+
+    #[test]
+    fn batches_lines_in_packets() {
+        let (exporter, listener) = test_exporter(50, RecorderType::Histogram);
+        let mut checkpoint_set = checkpoint_set();
+        checkpoint_set.add(
+            new_test_descriptor("latency", InstrumentKind::ValueRecorder, NumberKind::U64),
+            Arc::new(aggregators::array()),
+            vec![100u64.into(), 200u64.into(), 300u64.into()],
+            vec![],
+        );
+        checkpoint_set.add(
+            new_test_descriptor("requests", InstrumentKind::Counter, NumberKind::U64),
+            Arc::new(aggregators::sum()),
+            vec![1u64.into()],
+            vec![],
+        );
+
+        exporter.export(&mut checkpoint_set).unwrap();
+
+        assert_eq!(receive(&listener), "latency:100:200:300|h|#service:app");
+        assert_eq!(receive(&listener), "requests:1|c|#service:app");
+    }
+
+    #[test]
+    fn splits_values_in_lines() {
+        let (exporter, listener) = test_exporter(30, RecorderType::Histogram);
+        let mut checkpoint_set = checkpoint_set();
+        checkpoint_set.add(
+            new_test_descriptor("latency", InstrumentKind::ValueRecorder, NumberKind::U64),
+            Arc::new(aggregators::array()),
+            vec![100u64.into(), 200u64.into(), 300u64.into()],
+            vec![],
+        );
+
+        exporter.export(&mut checkpoint_set).unwrap();
+
+        assert_eq!(receive(&listener), "latency:100:200|h|#service:app");
+        assert_eq!(receive(&listener), "latency:300|h|#service:app");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn exports_to_unix_socket() {
+        let path = std::env::temp_dir().join(format!("dogstatsd-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let listener = UnixDatagram::bind(&path).unwrap();
+        let exporter = new_pipeline(|_| (), |_| futures::stream::empty::<()>())
+            .with_unix_socket(&path)
+            .build_exporter()
+            .unwrap();
+        let mut checkpoint_set = checkpoint_set();
+        checkpoint_set.add(
+            new_test_descriptor("requests", InstrumentKind::Counter, NumberKind::I64),
+            Arc::new(aggregators::sum()),
+            vec![(-2i64).into()],
+            vec![],
+        );
+
+        exporter.export(&mut checkpoint_set).unwrap();
+
+        let mut buf = [0; 1024];
+        let len = listener.recv(&mut buf).unwrap();
+        assert_eq!(&buf[..len], b"requests:-2|c|#service:app");
+        let _ = std::fs::remove_file(&path);
+    }
+}
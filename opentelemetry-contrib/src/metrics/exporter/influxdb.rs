@@ -0,0 +1,650 @@
+//! # InfluxDB Metrics Exporter
+//!
+//! Reports metrics to [InfluxDB] or [Telegraf] in the line protocol, over
+//! HTTP or UDP.
+//!
+//! Each exported record is written as one line, the measurement is the
+//! instrument name, the tags are the resource attributes and labels and the
+//! timestamp is the end of the collection interval in nanoseconds. Metrics
+//! are exported with cumulative temporality and the fields depend on the
+//! aggregation of the instrument:
+//!
+//! * Sums of counters and sum observers are written as a `sum` field.
+//! * Last values of value observers are written as a `value` field.
+//! * Value recorders are written as `min`, `max`, `sum` and `count` fields,
+//!   histograms as `sum`, `count` and a field per bucket boundary holding the
+//!   count of values lower or equal to it, the last named `+Inf`.
+//!
+//! Values of `u64` instruments are written as floats, InfluxDB 1.x doesn't
+//! support unsigned integer fields.
+//!
+//! Over HTTP all lines of an export are written in a single request to the
+//! configured write endpoint, `http://localhost:8086/write?db=opentelemetry`
+//! by default. Requests are sent from a dedicated thread so exports don't
+//! block the runtime of the push controller, an export fails if the previous
+//! request is still pending. The client must therefore not depend on an async
+//! runtime: the `reqwest-blocking-client` and `surf-client` features select
+//! such a default [`HttpClient`], other clients can be set with
+//! [`InfluxDbPipelineBuilder::with_http_client`].
+//!
+//! Over UDP lines are batched in datagrams of at most the configured max
+//! packet size, 1432 bytes by default.
+//!
+//! [InfluxDB]: https://docs.influxdata.com/influxdb/v1.8/write_protocols/line_protocol_tutorial/
+//! [Telegraf]: https://docs.influxdata.com/telegraf/v1.16/
+//! [`HttpClient`]: opentelemetry::sdk::export::trace::HttpClient
+//! [`InfluxDbPipelineBuilder::with_http_client`]: struct.InfluxDbPipelineBuilder.html#method.with_http_client
+//!
+//! ## Example
+//!
+//! ```no_run
+//! use opentelemetry::{global, KeyValue};
+//! use opentelemetry_contrib::metrics::exporter::influxdb;
+//! use std::time::Duration;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> opentelemetry::metrics::Result<()> {
+//! let _controller = influxdb::new_pipeline(tokio::spawn, tokio::time::interval)
+//!     .with_udp_endpoint("127.0.0.1:8089")
+//!     .with_period(Duration::from_secs(10))
+//!     .try_init()?;
+//!
+//! let meter = global::meter("my_app");
+//! let counter = meter.u64_counter("requests").init();
+//! counter.add(1, &[KeyValue::new("endpoint", "/users")]);
+//!
+//! // requests,endpoint=/users sum=1.0 1600000000000000000
+//! # Ok(())
+//! # }
+//! ```
+use futures::Stream;
+use http::{header, Method, Request, Uri};
+use opentelemetry::global;
+use opentelemetry::metrics::{Descriptor, MetricsError, Number, NumberKind, Result};
+use opentelemetry::sdk::{
+    export::{
+        metrics::{
+            self, CheckpointSet, Count, ExportKind, ExportKindFor, ExportKindSelector, Histogram,
+            LastValue, MinMaxSumCount, Record, Sum,
+        },
+        trace::HttpClient,
+    },
+    metrics::{
+        aggregators::{
+            ArrayAggregator, DDSKetchAggregator, HistogramAggregator, LastValueAggregator,
+            MinMaxSumCountAggregator, SumAggregator,
+        },
+        controllers::{self, PushController, PushControllerWorker},
+        selectors::simple,
+    },
+    Resource,
+};
+use std::fmt::Write as _;
+use std::net::UdpSocket;
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+/// Default InfluxDB HTTP write endpoint
+const DEFAULT_HTTP_ENDPOINT: &str = "http://localhost:8086/write?db=opentelemetry";
+
+/// Default max packet size of UDP datagrams, fitting in an ethernet frame.
+const DEFAULT_MAX_PACKET_SIZE: usize = 1432;
+
+/// Create a new InfluxDB exporter pipeline builder.
+pub fn new_pipeline<S, SO, I, IS, ISI>(spawn: S, interval: I) -> InfluxDbPipelineBuilder<S, I>
+where
+    S: Fn(PushControllerWorker) -> SO,
+    I: Fn(Duration) -> IS,
+    IS: Stream<Item = ISI> + Send + 'static,
+{
+    InfluxDbPipelineBuilder {
+        spawn,
+        interval,
+        http_endpoint: DEFAULT_HTTP_ENDPOINT.to_string(),
+        udp_endpoint: None,
+        token: None,
+        // The async reqwest client needs a tokio runtime, which the thread
+        // sending requests doesn't run.
+        #[cfg(all(not(feature = "reqwest-blocking-client"), not(feature = "surf-client"),))]
+        client: None,
+        #[cfg(all(not(feature = "reqwest-blocking-client"), feature = "surf-client"))]
+        client: Some(Box::new(surf::Client::new())),
+        #[cfg(feature = "reqwest-blocking-client")]
+        client: Some(Box::new(reqwest::blocking::Client::new())),
+        max_packet_size: DEFAULT_MAX_PACKET_SIZE,
+        aggregator_selector: simple::Selector::Inexpensive,
+        period: None,
+        resource: None,
+    }
+}
+
+#[derive(Debug)]
+enum Transport {
+    Http {
+        sender: mpsc::SyncSender<Request<Vec<u8>>>,
+        endpoint: Uri,
+        token: Option<String>,
+    },
+    Udp {
+        socket: UdpSocket,
+        max_packet_size: usize,
+    },
+}
+
+/// InfluxDB line protocol metrics exporter
+#[derive(Debug)]
+pub struct Exporter {
+    transport: Transport,
+}
+
+impl Exporter {
+    /// Formats the line protocol line of `record`.
+    fn line(&self, record: &Record<'_>) -> Result<Option<String>> {
+        let agg = record.aggregator().ok_or(MetricsError::NoDataCollected)?;
+        let desc = record.descriptor();
+        let kind = desc.number_kind();
+
+        let mut fields = Vec::new();
+        if let Some(sum) = agg.as_any().downcast_ref::<SumAggregator>() {
+            fields.push(("sum".to_string(), field_value(&sum.sum()?, kind)));
+        } else if let Some(last_value) = agg.as_any().downcast_ref::<LastValueAggregator>() {
+            let (value, _) = last_value.last_value()?;
+            fields.push(("value".to_string(), field_value(&value, kind)));
+        } else if let Some(histogram) = agg.as_any().downcast_ref::<HistogramAggregator>() {
+            let buckets = histogram.histogram()?;
+            fields.push(("sum".to_string(), field_value(&histogram.sum()?, kind)));
+            fields.push(("count".to_string(), format!("{}i", histogram.count()?)));
+            let mut count = 0.0;
+            for (index, bucket_count) in buckets.counts().iter().enumerate() {
+                count += bucket_count;
+                let boundary = buckets
+                    .boundaries()
+                    .get(index)
+                    .map(|boundary| boundary.to_string())
+                    .unwrap_or_else(|| "+Inf".to_string());
+                fields.push((boundary, format!("{}i", count as u64)));
+            }
+        } else if let Some(array) = agg.as_any().downcast_ref::<ArrayAggregator>() {
+            min_max_sum_count(array, kind, &mut fields)?;
+        } else if let Some(mmsc) = agg.as_any().downcast_ref::<MinMaxSumCountAggregator>() {
+            min_max_sum_count(mmsc, kind, &mut fields)?;
+        } else if let Some(sketch) = agg.as_any().downcast_ref::<DDSKetchAggregator>() {
+            min_max_sum_count(sketch, kind, &mut fields)?;
+        } else {
+            return Ok(None);
+        }
+
+        let mut line = escape(desc.name(), &[',', ' ']);
+        for (key, value) in record.resource().iter().chain(record.labels().iter()) {
+            let value = value.as_str();
+            // Tags with empty values are invalid in the line protocol.
+            if value.is_empty() {
+                continue;
+            }
+            let _ = write!(
+                line,
+                ",{}={}",
+                escape(key.as_str(), &[',', '=', ' ']),
+                escape(&value, &[',', '=', ' '])
+            );
+        }
+        for (index, (key, value)) in fields.iter().enumerate() {
+            line.push(if index == 0 { ' ' } else { ',' });
+            let _ = write!(line, "{}={}", escape(key, &[',', '=', ' ']), value);
+        }
+        let timestamp = record
+            .end_time()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|time| time.as_nanos())
+            .unwrap_or(0);
+        let _ = write!(line, " {}", timestamp);
+
+        Ok(Some(line))
+    }
+}
+
+impl metrics::Exporter for Exporter {
+    fn export(&self, checkpoint_set: &mut dyn CheckpointSet) -> Result<()> {
+        let mut lines = Vec::new();
+        checkpoint_set.try_for_each(self, &mut |record| {
+            lines.extend(self.line(record)?);
+            Ok(())
+        })?;
+        if lines.is_empty() {
+            return Ok(());
+        }
+
+        match &self.transport {
+            Transport::Http {
+                sender,
+                endpoint,
+                token,
+            } => {
+                let mut request = Request::builder()
+                    .method(Method::POST)
+                    .uri(endpoint.clone())
+                    .header(header::CONTENT_TYPE, "text/plain; charset=utf-8");
+                if let Some(token) = token {
+                    request = request.header(header::AUTHORIZATION, format!("Token {}", token));
+                }
+                let request = request
+                    .body(lines.join("\n").into_bytes())
+                    .map_err(|err| MetricsError::Other(err.to_string()))?;
+                sender.try_send(request).map_err(|err| match err {
+                    mpsc::TrySendError::Full(_) => {
+                        MetricsError::Other("previous request still pending".to_string())
+                    }
+                    mpsc::TrySendError::Disconnected(_) => {
+                        MetricsError::Other("http sender thread stopped".to_string())
+                    }
+                })
+            }
+            Transport::Udp {
+                socket,
+                max_packet_size,
+            } => {
+                // Lines longer than the max packet size are sent on their own.
+                let mut packet = String::with_capacity(*max_packet_size);
+                for line in lines {
+                    if !packet.is_empty() && packet.len() + 1 + line.len() > *max_packet_size {
+                        send(socket, &packet)?;
+                        packet.clear();
+                    }
+                    if !packet.is_empty() {
+                        packet.push('\n');
+                    }
+                    packet.push_str(&line);
+                }
+                if !packet.is_empty() {
+                    send(socket, &packet)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl ExportKindFor for Exporter {
+    fn export_kind_for(&self, descriptor: &Descriptor) -> ExportKind {
+        ExportKindSelector::Cumulative.export_kind_for(descriptor)
+    }
+}
+
+fn send(socket: &UdpSocket, packet: &str) -> Result<()> {
+    socket
+        .send(packet.as_bytes())
+        .map(|_| ())
+        .map_err(|err| MetricsError::Other(err.to_string()))
+}
+
+/// Appends the `min`, `max`, `sum` and `count` fields of `agg`.
+fn min_max_sum_count<A: MinMaxSumCount>(
+    agg: &A,
+    kind: &NumberKind,
+    fields: &mut Vec<(String, String)>,
+) -> Result<()> {
+    fields.push(("min".to_string(), field_value(&agg.min()?, kind)));
+    fields.push(("max".to_string(), field_value(&agg.max()?, kind)));
+    fields.push(("sum".to_string(), field_value(&agg.sum()?, kind)));
+    fields.push(("count".to_string(), format!("{}i", agg.count()?)));
+    Ok(())
+}
+
+/// Formats `number` as a field value, signed integers are suffixed with `i`
+/// and unsigned integers are written as floats.
+fn field_value(number: &Number, kind: &NumberKind) -> String {
+    match kind {
+        NumberKind::F64 => format!("{:?}", number.to_debug(kind)),
+        NumberKind::I64 => format!("{:?}i", number.to_debug(kind)),
+        NumberKind::U64 => format!("{:?}", number.to_f64(kind)),
+    }
+}
+
+/// Escapes `chars` with a backslash, newlines can't be escaped and are
+/// replaced by spaces.
+fn escape(s: &str, chars: &[char]) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        let c = if c == '\n' { ' ' } else { c };
+        if chars.contains(&c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Spawns the thread sending the requests of the HTTP transport, it stops
+/// once the returned sender is dropped.
+fn spawn_sender(client: Box<dyn HttpClient>) -> Result<mpsc::SyncSender<Request<Vec<u8>>>> {
+    let (sender, receiver) = mpsc::sync_channel::<Request<Vec<u8>>>(1);
+    thread::Builder::new()
+        .name("opentelemetry-influxdb".to_string())
+        .spawn(move || {
+            for request in receiver {
+                if let Err(err) = futures::executor::block_on(client.send(request)) {
+                    global::handle_error(MetricsError::Other(err.to_string()));
+                }
+            }
+        })
+        .map_err(|err| MetricsError::Other(err.to_string()))?;
+
+    Ok(sender)
+}
+
+/// Pipeline builder
+#[derive(Debug)]
+pub struct InfluxDbPipelineBuilder<S, I> {
+    spawn: S,
+    interval: I,
+    http_endpoint: String,
+    udp_endpoint: Option<String>,
+    token: Option<String>,
+    client: Option<Box<dyn HttpClient>>,
+    max_packet_size: usize,
+    aggregator_selector: simple::Selector,
+    period: Option<Duration>,
+    resource: Option<Resource>,
+}
+
+impl<S, SO, I, IS, ISI> InfluxDbPipelineBuilder<S, I>
+where
+    S: Fn(PushControllerWorker) -> SO,
+    I: Fn(Duration) -> IS,
+    IS: Stream<Item = ISI> + Send + 'static,
+{
+    /// Assign the HTTP write endpoint including its query parameters, e.g.
+    /// `http://localhost:8086/api/v2/write?org=my_org&bucket=my_bucket`.
+    /// Defaults to `http://localhost:8086/write?db=opentelemetry`.
+    pub fn with_http_endpoint<T: Into<String>>(self, endpoint: T) -> Self {
+        InfluxDbPipelineBuilder {
+            http_endpoint: endpoint.into(),
+            ..self
+        }
+    }
+
+    /// Assign the token sent in the `Authorization` header of HTTP requests.
+    pub fn with_token<T: Into<String>>(self, token: T) -> Self {
+        InfluxDbPipelineBuilder {
+            token: Some(token.into()),
+            ..self
+        }
+    }
+
+    /// Choose the http client used to send HTTP requests.
+    ///
+    /// Requests are sent from a thread without async runtime, the client must
+    /// be able to complete them there, e.g. a blocking client.
+    pub fn with_http_client<T: HttpClient + 'static>(self, client: T) -> Self {
+        InfluxDbPipelineBuilder {
+            client: Some(Box::new(client)),
+            ..self
+        }
+    }
+
+    /// Send metrics to the UDP endpoint `endpoint` instead of the HTTP write
+    /// endpoint.
+    pub fn with_udp_endpoint<T: Into<String>>(self, endpoint: T) -> Self {
+        InfluxDbPipelineBuilder {
+            udp_endpoint: Some(endpoint.into()),
+            ..self
+        }
+    }
+
+    /// Assign the max size of UDP datagrams, defaults to 1432 bytes.
+    pub fn with_max_packet_size(self, max_packet_size: usize) -> Self {
+        InfluxDbPipelineBuilder {
+            max_packet_size,
+            ..self
+        }
+    }
+
+    /// Assign the aggregator selector, defaults to
+    /// `simple::Selector::Inexpensive`.
+    pub fn with_aggregator_selector(self, aggregator_selector: simple::Selector) -> Self {
+        InfluxDbPipelineBuilder {
+            aggregator_selector,
+            ..self
+        }
+    }
+
+    /// Set the frequency in which metrics are exported.
+    pub fn with_period(self, period: Duration) -> Self {
+        InfluxDbPipelineBuilder {
+            period: Some(period),
+            ..self
+        }
+    }
+
+    /// Set the resource written as tags of all metrics.
+    pub fn with_resource(self, resource: Resource) -> Self {
+        InfluxDbPipelineBuilder {
+            resource: Some(resource),
+            ..self
+        }
+    }
+
+    /// Build a new push controller and install it as the global meter
+    /// provider, returning errors if they arise.
+    pub fn try_init(mut self) -> Result<PushController> {
+        let exporter = self.build_exporter()?;
+        let mut push_builder = controllers::push(
+            self.aggregator_selector,
+            ExportKindSelector::Cumulative,
+            exporter,
+            self.spawn,
+            self.interval,
+        );
+        if let Some(period) = self.period {
+            push_builder = push_builder.with_period(period);
+        }
+        if let Some(resource) = self.resource {
+            push_builder = push_builder.with_resource(resource);
+        }
+
+        let controller = push_builder.build();
+        global::set_meter_provider(controller.provider());
+        Ok(controller)
+    }
+
+    fn build_exporter(&mut self) -> Result<Exporter> {
+        let transport = if let Some(endpoint) = &self.udp_endpoint {
+            let socket = opentelemetry_statsd::connect_udp(endpoint)
+                .map_err(|err| MetricsError::Other(err.to_string()))?;
+            Transport::Udp {
+                socket,
+                max_packet_size: self.max_packet_size,
+            }
+        } else {
+            let client = self
+                .client
+                .take()
+                .ok_or_else(|| MetricsError::Other("no http client".to_string()))?;
+            Transport::Http {
+                sender: spawn_sender(client)?,
+                endpoint: self
+                    .http_endpoint
+                    .parse()
+                    .map_err(|err: http::uri::InvalidUri| MetricsError::Other(err.to_string()))?,
+                token: self.token.clone(),
+            }
+        };
+
+        Ok(Exporter { transport })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use opentelemetry::metrics::InstrumentKind;
+    use opentelemetry::sdk::export::{metrics::Exporter as _, trace::ExportResult};
+    use opentelemetry::sdk::metrics::aggregators;
+    use opentelemetry::testing::metrics::{new_test_descriptor, TestCheckpointSet};
+    use opentelemetry::KeyValue;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::sync::Arc;
+    use std::thread;
+
+    fn checkpoint_set() -> TestCheckpointSet {
+        TestCheckpointSet::new(Resource::new(vec![KeyValue::new("service", "my app")]))
+    }
+
+    #[derive(Debug)]
+    struct IsahcClient(isahc::HttpClient);
+
+    #[async_trait]
+    impl HttpClient for IsahcClient {
+        async fn send(&self, request: Request<Vec<u8>>) -> ExportResult {
+            let response = self
+                .0
+                .send_async(request)
+                .await
+                .map_err(|err| err.to_string())?;
+            if response.status().is_success() {
+                Ok(())
+            } else {
+                Err(response.status().to_string().into())
+            }
+        }
+    }
+
+    /// Accepts a single HTTP request on `listener`, returning its head and body.
+    fn serve_one(listener: TcpListener) -> thread::JoinHandle<(String, String)> {
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut received = Vec::new();
+            let mut buf = [0; 1024];
+            let head_end = loop {
+                let len = stream.read(&mut buf).unwrap();
+                received.extend_from_slice(&buf[..len]);
+                if let Some(pos) = received.windows(4).position(|w| w == b"\r\n\r\n") {
+                    break pos + 4;
+                }
+            };
+            let head = String::from_utf8(received[..head_end].to_vec()).unwrap();
+            let content_length = head
+                .lines()
+                .find_map(|line| {
+                    let (name, value) = line.split_at(line.find(':')?);
+                    if name.eq_ignore_ascii_case("content-length") {
+                        value[1..].trim().parse::<usize>().ok()
+                    } else {
+                        None
+                    }
+                })
+                .unwrap_or(0);
+            while received.len() < head_end + content_length {
+                let len = stream.read(&mut buf).unwrap();
+                received.extend_from_slice(&buf[..len]);
+            }
+            stream
+                .write_all(b"HTTP/1.1 204 No Content\r\nContent-Length: 0\r\n\r\n")
+                .unwrap();
+            let body = String::from_utf8(received[head_end..].to_vec()).unwrap();
+            (head, body)
+        })
+    }
+
+    #[test]
+    fn writes_lines_over_http() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = format!(
+            "http://{}/api/v2/write?org=my_org&bucket=metrics",
+            listener.local_addr().unwrap()
+        );
+        let server = serve_one(listener);
+        let exporter = new_pipeline(|_| (), |_| futures::stream::empty::<()>())
+            .with_http_endpoint(endpoint)
+            .with_token("secret")
+            .with_http_client(IsahcClient(isahc::HttpClient::new().unwrap()))
+            .build_exporter()
+            .unwrap();
+        let mut checkpoint_set = checkpoint_set();
+        checkpoint_set.add(
+            new_test_descriptor("requests", InstrumentKind::Counter, NumberKind::U64),
+            Arc::new(aggregators::sum()),
+            vec![1u64.into(), 2u64.into()],
+            vec![
+                KeyValue::new("route", "/users,all"),
+                KeyValue::new("empty", ""),
+            ],
+        );
+        checkpoint_set.add(
+            new_test_descriptor(
+                "temperature",
+                InstrumentKind::ValueObserver,
+                NumberKind::F64,
+            ),
+            Arc::new(aggregators::last_value()),
+            vec![21.5f64.into()],
+            vec![],
+        );
+        let latency =
+            new_test_descriptor("latency", InstrumentKind::ValueRecorder, NumberKind::I64);
+        checkpoint_set.add(
+            latency.clone(),
+            Arc::new(aggregators::min_max_sum_count(&latency)),
+            vec![3i64.into(), 5i64.into()],
+            vec![],
+        );
+
+        exporter.export(&mut checkpoint_set).unwrap();
+
+        let (head, body) = server.join().unwrap();
+        assert!(head.starts_with("POST /api/v2/write?org=my_org&bucket=metrics HTTP/1.1\r\n"));
+        assert!(head.lines().any(|line| {
+            line.to_lowercase().starts_with("authorization:") && line[14..].trim() == "Token secret"
+        }));
+        assert_eq!(
+            body,
+            "requests,service=my\\ app,route=/users\\,all sum=3.0 1600000000000000000\n\
+             temperature,service=my\\ app value=21.5 1600000000000000000\n\
+             latency,service=my\\ app min=3i,max=5i,sum=8i,count=2i 1600000000000000000"
+        );
+    }
+
+    #[test]
+    fn writes_histogram_buckets_over_udp() {
+        let listener = UdpSocket::bind("127.0.0.1:0").unwrap();
+        listener
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let exporter = new_pipeline(|_| (), |_| futures::stream::empty::<()>())
+            .with_udp_endpoint(listener.local_addr().unwrap().to_string())
+            .build_exporter()
+            .unwrap();
+        let latency =
+            new_test_descriptor("latency", InstrumentKind::ValueRecorder, NumberKind::F64);
+        let mut checkpoint_set = checkpoint_set();
+        checkpoint_set.add(
+            latency.clone(),
+            Arc::new(aggregators::histogram(&latency, &[1.0, 5.0])),
+            vec![0.5f64.into(), 2.0f64.into(), 3.0f64.into(), 7.0f64.into()],
+            vec![KeyValue::new("method", "GET")],
+        );
+
+        exporter.export(&mut checkpoint_set).unwrap();
+
+        let mut buf = [0; 1024];
+        let len = listener.recv(&mut buf).unwrap();
+        assert_eq!(
+            String::from_utf8(buf[..len].to_vec()).unwrap(),
+            "latency,service=my\\ app,method=GET sum=12.5,count=4i,1=1i,5=3i,+Inf=4i 1600000000000000000"
+        );
+    }
+
+    #[test]
+    fn requires_http_client() {
+        let result = new_pipeline(|_| (), |_| futures::stream::empty::<()>())
+            .with_http_endpoint("http://localhost:8086/write?db=metrics")
+            .build_exporter();
+        #[cfg(not(any(feature = "reqwest-blocking-client", feature = "surf-client")))]
+        assert!(result.is_err());
+        #[cfg(any(feature = "reqwest-blocking-client", feature = "surf-client"))]
+        assert!(result.is_ok());
+    }
+}
@@ -0,0 +1,14 @@
+//! # Opentelemetry metrics exporter contrib
+//!
+//! This module provides metrics exporter implementations from third party
+//! vendors.
+//!
+//! Currently, the following exporters are supported:
+//! * `dogstatsd`, enable `dogstatsd` feature to use it.
+//! * `influxdb`, enable `influxdb` feature to use it.
+#[cfg(feature = "dogstatsd")]
+#[cfg_attr(docsrs, doc(cfg(feature = "dogstatsd")))]
+pub mod dogstatsd;
+#[cfg(feature = "influxdb")]
+#[cfg_attr(docsrs, doc(cfg(feature = "influxdb")))]
+pub mod influxdb;
@@ -0,0 +1,4 @@
+//! # Opentelemetry metrics contrib
+//!
+
+pub mod exporter;
@@ -31,6 +31,29 @@
 //!
 //! For standard values see here - https://github.com/DataDog/dd-trace-go/blob/ecb0b805ef25b00888a2fb62d465a5aa95e7301e/ddtrace/ext/app_types.go#L31
 //!
+//! By default the resource_name is derived from the `http.method` and `http.route` attributes of
+//! http spans, or the `db.statement` attribute of database spans, so that requests to the same
+//! endpoint are grouped together. The name, resource and type mappings can all be replaced with
+//! [`DatadogPipelineBuilder::with_name_mapping`], [`DatadogPipelineBuilder::with_resource_mapping`]
+//! and [`DatadogPipelineBuilder::with_type_mapping`].
+//!
+//! [`DatadogPipelineBuilder::with_name_mapping`]: struct.DatadogPipelineBuilder.html#method.with_name_mapping
+//! [`DatadogPipelineBuilder::with_resource_mapping`]: struct.DatadogPipelineBuilder.html#method.with_resource_mapping
+//! [`DatadogPipelineBuilder::with_type_mapping`]: struct.DatadogPipelineBuilder.html#method.with_type_mapping
+//!
+//! ## Sampling and Stats
+//!
+//! Root spans and spans of `Server` or `Consumer` kind are marked as top level, and carry the
+//! `_sampling_priority_v1` metric derived from the trace flags so that the agent keeps sampled
+//! and debug traces. Spans of `Client` or `Producer` kind are marked as measured with `_dd.measured`.
+//!
+//! The agent computes hits, errors and durations of top level and measured spans from the traces
+//! it receives. Enable [`DatadogPipelineBuilder::with_apm_stats`] to compute them from every span
+//! that ends, sampled or not, and post them to the agent `/v0.6/stats` endpoint every 10 seconds
+//! instead. Use a sampler that records the spans it does not sample for the stats to cover them.
+//!
+//! [`DatadogPipelineBuilder::with_apm_stats`]: struct.DatadogPipelineBuilder.html#method.with_apm_stats
+//!
 //! ## Performance
 //!
 //! For optimal performance, a batch exporter is recommended as the simple
@@ -102,6 +125,8 @@
 //!         .with_service_name("my_app")
 //!         .with_version(ApiVersion::Version05)
 //!         .with_agent_endpoint("http://localhost:8126")
+//!         .with_resource_mapping(|span| span.name.clone())
+//!         .with_apm_stats(true)
 //!         .with_trace_config(
 //!             trace::config()
 //!                 .with_default_sampler(Sampler::AlwaysOn)
@@ -121,16 +146,22 @@
 
 mod intern;
 mod model;
+mod propagator;
+mod stats;
 
 pub use model::ApiVersion;
 pub use model::Error;
+pub use model::FieldMappingFn;
+pub use propagator::DatadogPropagator;
 
 use async_trait::async_trait;
 use http::{Method, Request, Uri};
+use model::Mapping;
 use opentelemetry::sdk::export::trace;
 use opentelemetry::sdk::export::trace::{HttpClient, SpanData};
 use opentelemetry::trace::TraceError;
 use opentelemetry::{global, sdk, trace::TracerProvider};
+use std::sync::Arc;
 
 /// Default Datadog collector endpoint
 const DEFAULT_AGENT_ENDPOINT: &str = "http://127.0.0.1:8126";
@@ -141,10 +172,12 @@ const DEFAULT_SERVICE_NAME: &str = "OpenTelemetry";
 /// Datadog span exporter
 #[derive(Debug)]
 pub struct DatadogExporter {
-    client: Box<dyn HttpClient>,
+    client: Arc<dyn HttpClient>,
     request_url: Uri,
     service_name: String,
     version: ApiVersion,
+    mapping: Mapping,
+    client_computed_stats: bool,
 }
 
 impl DatadogExporter {
@@ -152,13 +185,17 @@ impl DatadogExporter {
         service_name: String,
         request_url: Uri,
         version: ApiVersion,
-        client: Box<dyn HttpClient>,
+        client: Arc<dyn HttpClient>,
+        mapping: Mapping,
+        client_computed_stats: bool,
     ) -> Self {
         DatadogExporter {
             client,
             request_url,
             service_name,
             version,
+            mapping,
+            client_computed_stats,
         }
     }
 }
@@ -176,6 +213,8 @@ pub struct DatadogPipelineBuilder {
     trace_config: Option<sdk::trace::Config>,
     version: ApiVersion,
     client: Option<Box<dyn HttpClient>>,
+    mapping: Mapping,
+    apm_stats: bool,
 }
 
 impl Default for DatadogPipelineBuilder {
@@ -185,6 +224,8 @@ impl Default for DatadogPipelineBuilder {
             agent_endpoint: DEFAULT_AGENT_ENDPOINT.to_string(),
             trace_config: None,
             version: ApiVersion::Version05,
+            mapping: Mapping::default(),
+            apm_stats: false,
             #[cfg(all(
                 not(feature = "reqwest-client"),
                 not(feature = "reqwest-blocking-client"),
@@ -213,15 +254,29 @@ impl DatadogPipelineBuilder {
     /// Create `ExporterConfig` struct from current `ExporterConfigBuilder`
     pub fn install(mut self) -> Result<(sdk::trace::Tracer, Uninstall), TraceError> {
         if let Some(client) = self.client {
+            let client: Arc<dyn HttpClient> = Arc::from(client);
+            let mut provider_builder = sdk::trace::TracerProvider::builder();
+            if self.apm_stats {
+                let stats_endpoint = self.agent_endpoint.clone() + stats::STATS_PATH;
+                let concentrator =
+                    stats::Concentrator::new(self.service_name.clone(), self.mapping.clone());
+                provider_builder =
+                    provider_builder.with_span_processor(stats::StatsProcessor::new(
+                        concentrator,
+                        client.clone(),
+                        stats_endpoint.parse().map_err::<Error, _>(Into::into)?,
+                    ));
+            }
             let endpoint = self.agent_endpoint + self.version.path();
             let exporter = DatadogExporter::new(
                 self.service_name.clone(),
                 endpoint.parse().map_err::<Error, _>(Into::into)?,
                 self.version,
                 client,
+                self.mapping,
+                self.apm_stats,
             );
-            let mut provider_builder =
-                sdk::trace::TracerProvider::builder().with_exporter(exporter);
+            provider_builder = provider_builder.with_exporter(exporter);
             if let Some(config) = self.trace_config.take() {
                 provider_builder = provider_builder.with_config(config);
             }
@@ -267,20 +322,71 @@ impl DatadogPipelineBuilder {
         self.version = version;
         self
     }
+
+    /// Custom mapping of spans to their Datadog resource name, replacing the
+    /// default derived from http and database attributes.
+    pub fn with_resource_mapping<F>(mut self, mapping: F) -> Self
+    where
+        F: Fn(&SpanData) -> String + Send + Sync + 'static,
+    {
+        self.mapping.resource = Some(Arc::new(mapping));
+        self
+    }
+
+    /// Custom mapping of spans to their Datadog operation name, replacing the
+    /// default instrumentation library name.
+    pub fn with_name_mapping<F>(mut self, mapping: F) -> Self
+    where
+        F: Fn(&SpanData) -> String + Send + Sync + 'static,
+    {
+        self.mapping.name = Some(Arc::new(mapping));
+        self
+    }
+
+    /// Custom mapping of spans to their Datadog span type, replacing the
+    /// default `span.type` attribute.
+    pub fn with_type_mapping<F>(mut self, mapping: F) -> Self
+    where
+        F: Fn(&SpanData) -> String + Send + Sync + 'static,
+    {
+        self.mapping.span_type = Some(Arc::new(mapping));
+        self
+    }
+
+    /// Compute APM stats from every span that ends and post them to the
+    /// agent, instead of having the agent compute them from the received
+    /// traces.
+    ///
+    /// Stats are posted from a background thread, the http client must not
+    /// depend on an async runtime.
+    pub fn with_apm_stats(mut self, apm_stats: bool) -> Self {
+        self.apm_stats = apm_stats;
+        self
+    }
 }
 
 #[async_trait]
 impl trace::SpanExporter for DatadogExporter {
     /// Export spans to datadog-agent
     async fn export(&mut self, batch: Vec<SpanData>) -> trace::ExportResult {
-        let data = self.version.encode(&self.service_name, batch)?;
-        let req = Request::builder()
+        let data = self
+            .version
+            .encode(&self.service_name, &self.mapping, batch)?;
+        let mut req = Request::builder()
             .method(Method::POST)
             .uri(self.request_url.clone())
-            .header(http::header::CONTENT_TYPE, self.version.content_type())
-            .body(data)
-            .map_err::<Error, _>(Into::into)?;
-        self.client.send(req).await
+            .header(http::header::CONTENT_TYPE, self.version.content_type());
+        if self.client_computed_stats {
+            // The agent checks the presence of the headers rather than their value. Top level
+            // spans must match the ones stats were computed for.
+            req = req
+                .header("Datadog-Client-Computed-Top-Level", "yes")
+                .header("Datadog-Client-Computed-Stats", "yes");
+        }
+        let req = req.body(data).map_err::<Error, _>(Into::into)?;
+        self.client.send(req).await?;
+
+        Ok(())
     }
 }
 
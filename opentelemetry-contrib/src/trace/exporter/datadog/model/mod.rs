@@ -1,8 +1,122 @@
 use opentelemetry::sdk::export::{trace, ExportError};
+use opentelemetry::trace::SpanKind;
+use opentelemetry::{Key, Value};
+use opentelemetry_semantic_conventions::trace as semcov;
+use std::fmt;
+use std::sync::Arc;
 
 mod v03;
 mod v05;
 
+/// Sampling priority of the trace the span belongs to, read by the agent on
+/// top level spans.
+const SAMPLING_PRIORITY_KEY: &str = "_sampling_priority_v1";
+/// Marks spans the agent computes stats for.
+const TOP_LEVEL_KEY: &str = "_top_level";
+/// Marks spans that are not top level but still get stats computed.
+const MEASURED_KEY: &str = "_dd.measured";
+
+/// Maps an exported span to one of its Datadog fields.
+pub type FieldMappingFn = dyn Fn(&trace::SpanData) -> String + Send + Sync;
+
+/// Mapping of exported spans to the Datadog `resource`, `name` and `type`
+/// fields, falling back to the default mappings for the fields without a
+/// custom mapping function.
+#[derive(Clone, Default)]
+pub(crate) struct Mapping {
+    pub(crate) resource: Option<Arc<FieldMappingFn>>,
+    pub(crate) name: Option<Arc<FieldMappingFn>>,
+    pub(crate) span_type: Option<Arc<FieldMappingFn>>,
+}
+
+impl fmt::Debug for Mapping {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Mapping")
+            .field("resource", &self.resource.is_some())
+            .field("name", &self.name.is_some())
+            .field("span_type", &self.span_type.is_some())
+            .finish()
+    }
+}
+
+impl Mapping {
+    /// The resource is the `http.method` and `http.route` of http spans, the
+    /// `db.statement` of database spans, or the span name otherwise.
+    pub(crate) fn resource(&self, span: &trace::SpanData) -> String {
+        if let Some(mapping) = &self.resource {
+            return mapping(span);
+        }
+
+        match (
+            span.attributes.get(&semcov::HTTP_METHOD),
+            span.attributes.get(&semcov::HTTP_ROUTE),
+        ) {
+            (Some(method), Some(route)) => format!("{} {}", method.as_str(), route.as_str()),
+            (Some(method), None) => method.as_str().into_owned(),
+            _ => match span.attributes.get(&semcov::DB_STATEMENT) {
+                Some(statement) => statement.as_str().into_owned(),
+                None => span.name.clone(),
+            },
+        }
+    }
+
+    /// The name is the instrumentation library name, see the module docs.
+    pub(crate) fn name(&self, span: &trace::SpanData) -> String {
+        match &self.name {
+            Some(mapping) => mapping(span),
+            None => span.instrumentation_lib.name.to_string(),
+        }
+    }
+
+    /// The type is the `span.type` attribute, empty if unset.
+    pub(crate) fn span_type(&self, span: &trace::SpanData) -> String {
+        match &self.span_type {
+            Some(mapping) => mapping(span),
+            None => match span.attributes.get(&Key::new("span.type")) {
+                Some(Value::String(s)) => s.to_string(),
+                _ => String::new(),
+            },
+        }
+    }
+}
+
+/// Top level spans are the entry points of a service, either root spans or
+/// spans handling a request from another service.
+pub(crate) fn is_top_level(span: &trace::SpanData) -> bool {
+    span.parent_span_id.to_u64() == 0
+        || span.span_kind == SpanKind::Server
+        || span.span_kind == SpanKind::Consumer
+}
+
+/// Spans calling other services get stats computed even if not top level.
+pub(crate) fn is_measured(span: &trace::SpanData) -> bool {
+    span.span_kind == SpanKind::Client || span.span_kind == SpanKind::Producer
+}
+
+/// Datadog sampling priority from the trace flags, `2` (user keep) for debug
+/// traces, `1` (auto keep) for sampled traces and `0` (auto reject) otherwise.
+fn sampling_priority(span: &trace::SpanData) -> f64 {
+    if span.span_context.is_debug() {
+        2.0
+    } else if span.span_context.is_sampled() {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+/// Numeric span metrics read by the agent.
+pub(crate) fn metrics(span: &trace::SpanData) -> Vec<(&'static str, f64)> {
+    let mut metrics = Vec::with_capacity(2);
+    if is_top_level(span) {
+        metrics.push((TOP_LEVEL_KEY, 1.0));
+        metrics.push((SAMPLING_PRIORITY_KEY, sampling_priority(span)));
+    } else if is_measured(span) {
+        metrics.push((MEASURED_KEY, 1.0));
+    }
+    metrics
+}
+
 /// Wrap type for errors from opentelemetry datadog exporter
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
@@ -62,11 +176,12 @@ impl ApiVersion {
     pub(crate) fn encode(
         self,
         service_name: &str,
+        mapping: &Mapping,
         spans: Vec<trace::SpanData>,
     ) -> Result<Vec<u8>, Error> {
         match self {
-            Self::Version03 => v03::encode(service_name, spans),
-            Self::Version05 => v05::encode(service_name, spans),
+            Self::Version03 => v03::encode(service_name, mapping, spans),
+            Self::Version05 => v05::encode(service_name, mapping, spans),
         }
     }
 }
@@ -77,7 +192,10 @@ mod tests {
     use opentelemetry::sdk;
     use opentelemetry::sdk::InstrumentationLibrary;
     use opentelemetry::{
-        trace::{SpanContext, SpanId, SpanKind, StatusCode, TraceId, TraceState},
+        trace::{
+            SpanContext, SpanId, SpanKind, StatusCode, TraceId, TraceState, TRACE_FLAG_DEBUG,
+            TRACE_FLAG_SAMPLED,
+        },
         Key,
     };
     use std::sync::Arc;
@@ -128,9 +246,13 @@ mod tests {
     #[test]
     fn test_encode_v03() -> Result<(), Box<dyn std::error::Error>> {
         let spans = get_spans();
-        let encoded = base64::encode(ApiVersion::Version03.encode("service_name", spans)?);
+        let encoded = base64::encode(ApiVersion::Version03.encode(
+            "service_name",
+            &Mapping::default(),
+            spans,
+        )?);
 
-        assert_eq!(encoded.as_str(), "kZGLpHR5cGWjd2Vip3NlcnZpY2Wsc2VydmljZV9uYW1lpG5hbWWpY29tcG9uZW50qHJlc291cmNlqHJlc291cmNlqHRyYWNlX2lkzwAAAAAAAAAHp3NwYW5faWTPAAAAAAAAAGOpcGFyZW50X2lkzwAAAAAAAAABpXN0YXJ00wAAAAAAAAAAqGR1cmF0aW9u0wAAAAA7msoApWVycm9y0gAAAAGkbWV0YYGpc3Bhbi50eXBlo3dlYg==");
+        assert_eq!(encoded.as_str(), "kZGMpHR5cGWjd2Vip3NlcnZpY2Wsc2VydmljZV9uYW1lpG5hbWWpY29tcG9uZW50qHJlc291cmNlqHJlc291cmNlqHRyYWNlX2lkzwAAAAAAAAAHp3NwYW5faWTPAAAAAAAAAGOpcGFyZW50X2lkzwAAAAAAAAABpXN0YXJ00wAAAAAAAAAAqGR1cmF0aW9u0wAAAAA7msoApWVycm9y0gAAAAGkbWV0YYGpc3Bhbi50eXBlo3dlYqdtZXRyaWNzgaxfZGQubWVhc3VyZWTLP/AAAAAAAAA=");
 
         Ok(())
     }
@@ -138,10 +260,77 @@ mod tests {
     #[test]
     fn test_encode_v05() -> Result<(), Box<dyn std::error::Error>> {
         let spans = get_spans();
-        let encoded = base64::encode(ApiVersion::Version05.encode("service_name", spans)?);
+        let encoded = base64::encode(ApiVersion::Version05.encode(
+            "service_name",
+            &Mapping::default(),
+            spans,
+        )?);
 
-        assert_eq!(encoded.as_str(), "kpWsc2VydmljZV9uYW1lo3dlYqljb21wb25lbnSocmVzb3VyY2Wpc3Bhbi50eXBlkZGczgAAAADOAAAAAs4AAAADzwAAAAAAAAAHzwAAAAAAAABjzwAAAAAAAAAB0wAAAAAAAAAA0wAAAAA7msoA0gAAAAGBzgAAAATOAAAAAYDOAAAAAQ==");
+        assert_eq!(encoded.as_str(), "kpasc2VydmljZV9uYW1lo3dlYqljb21wb25lbnSocmVzb3VyY2Wpc3Bhbi50eXBlrF9kZC5tZWFzdXJlZJGRnM4AAAAAzgAAAALOAAAAA88AAAAAAAAAB88AAAAAAAAAY88AAAAAAAAAAdMAAAAAAAAAANMAAAAAO5rKANIAAAABgc4AAAAEzgAAAAGBzgAAAAXLP/AAAAAAAADOAAAAAQ==");
 
         Ok(())
     }
+
+    #[test]
+    fn test_resource_mapping() {
+        let mapping = Mapping::default();
+        let mut span = get_spans().remove(0);
+        assert_eq!(mapping.resource(&span), "resource");
+        assert_eq!(mapping.span_type(&span), "web");
+
+        span.attributes
+            .insert(Key::new("db.statement").string("SELECT * FROM users"));
+        assert_eq!(mapping.resource(&span), "SELECT * FROM users");
+
+        span.attributes
+            .insert(Key::new("http.method").string("GET"));
+        assert_eq!(mapping.resource(&span), "GET");
+
+        span.attributes
+            .insert(Key::new("http.route").string("/users/:id"));
+        assert_eq!(mapping.resource(&span), "GET /users/:id");
+
+        let mapping = Mapping {
+            resource: Some(Arc::new(|span: &trace::SpanData| span.name.to_uppercase())),
+            ..Default::default()
+        };
+        assert_eq!(mapping.resource(&span), "RESOURCE");
+        assert_eq!(mapping.name(&span), "component");
+    }
+
+    #[test]
+    fn test_metrics() {
+        let mut span = get_spans().remove(0);
+        assert_eq!(metrics(&span), vec![(MEASURED_KEY, 1.0)]);
+
+        span.span_kind = SpanKind::Server;
+        span.span_context = SpanContext::new(
+            TraceId::from_u128(7),
+            SpanId::from_u64(99),
+            TRACE_FLAG_SAMPLED,
+            false,
+            TraceState::default(),
+        );
+        assert_eq!(
+            metrics(&span),
+            vec![(TOP_LEVEL_KEY, 1.0), (SAMPLING_PRIORITY_KEY, 1.0)]
+        );
+
+        span.span_kind = SpanKind::Internal;
+        span.parent_span_id = SpanId::invalid();
+        span.span_context = SpanContext::new(
+            TraceId::from_u128(7),
+            SpanId::from_u64(99),
+            TRACE_FLAG_SAMPLED | TRACE_FLAG_DEBUG,
+            false,
+            TraceState::default(),
+        );
+        assert_eq!(
+            metrics(&span),
+            vec![(TOP_LEVEL_KEY, 1.0), (SAMPLING_PRIORITY_KEY, 2.0)]
+        );
+
+        span.parent_span_id = SpanId::from_u64(1);
+        assert!(metrics(&span).is_empty());
+    }
 }
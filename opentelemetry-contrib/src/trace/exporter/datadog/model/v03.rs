@@ -1,9 +1,12 @@
-use crate::trace::exporter::datadog::model::Error;
+use crate::trace::exporter::datadog::model::{metrics, Error, Mapping};
 use opentelemetry::sdk::export::trace;
-use opentelemetry::{Key, Value};
 use std::time::SystemTime;
 
-pub(crate) fn encode(service_name: &str, spans: Vec<trace::SpanData>) -> Result<Vec<u8>, Error> {
+pub(crate) fn encode(
+    service_name: &str,
+    mapping: &Mapping,
+    spans: Vec<trace::SpanData>,
+) -> Result<Vec<u8>, Error> {
     let mut encoded = Vec::new();
     rmp::encode::write_array_len(&mut encoded, spans.len() as u32)?;
 
@@ -24,12 +27,13 @@ pub(crate) fn encode(service_name: &str, spans: Vec<trace::SpanData>) -> Result<
             .map(|x| x.as_nanos() as i64)
             .unwrap_or(0);
 
-        if let Some(Value::String(s)) = span.attributes.get(&Key::new("span.type")) {
-            rmp::encode::write_map_len(&mut encoded, 11)?;
+        let span_type = mapping.span_type(&span);
+        if !span_type.is_empty() {
+            rmp::encode::write_map_len(&mut encoded, 12)?;
             rmp::encode::write_str(&mut encoded, "type")?;
-            rmp::encode::write_str(&mut encoded, s.as_ref())?;
+            rmp::encode::write_str(&mut encoded, &span_type)?;
         } else {
-            rmp::encode::write_map_len(&mut encoded, 10)?;
+            rmp::encode::write_map_len(&mut encoded, 11)?;
         }
 
         // Datadog span name is OpenTelemetry component name - see module docs for more information
@@ -37,10 +41,10 @@ pub(crate) fn encode(service_name: &str, spans: Vec<trace::SpanData>) -> Result<
         rmp::encode::write_str(&mut encoded, service_name)?;
 
         rmp::encode::write_str(&mut encoded, "name")?;
-        rmp::encode::write_str(&mut encoded, span.instrumentation_lib.name)?;
+        rmp::encode::write_str(&mut encoded, &mapping.name(&span))?;
 
         rmp::encode::write_str(&mut encoded, "resource")?;
-        rmp::encode::write_str(&mut encoded, &span.name)?;
+        rmp::encode::write_str(&mut encoded, &mapping.resource(&span))?;
 
         rmp::encode::write_str(&mut encoded, "trace_id")?;
         rmp::encode::write_u64(&mut encoded, span.span_context.trace_id().to_u128() as u64)?;
@@ -66,6 +70,14 @@ pub(crate) fn encode(service_name: &str, spans: Vec<trace::SpanData>) -> Result<
             rmp::encode::write_str(&mut encoded, key.as_str())?;
             rmp::encode::write_str(&mut encoded, value.as_str().as_ref())?;
         }
+
+        let metrics = metrics(&span);
+        rmp::encode::write_str(&mut encoded, "metrics")?;
+        rmp::encode::write_map_len(&mut encoded, metrics.len() as u32)?;
+        for (key, value) in metrics {
+            rmp::encode::write_str(&mut encoded, key)?;
+            rmp::encode::write_f64(&mut encoded, value)?;
+        }
     }
 
     Ok(encoded)
@@ -1,7 +1,7 @@
 use crate::trace::exporter::datadog::intern::StringInterner;
+use crate::trace::exporter::datadog::model::{metrics, Mapping};
 use crate::trace::exporter::datadog::Error;
 use opentelemetry::sdk::export::trace;
-use opentelemetry::{Key, Value};
 use std::time::SystemTime;
 
 // Protocol documentation sourced from https://github.com/DataDog/datadog-agent/blob/c076ea9a1ffbde4c76d35343dbc32aecbbf99cb9/pkg/trace/api/version.go
@@ -49,9 +49,13 @@ use std::time::SystemTime;
 //
 // 		The dictionary in this case would be []string{""}, having only the empty string at index 0.
 //
-pub(crate) fn encode(service_name: &str, spans: Vec<trace::SpanData>) -> Result<Vec<u8>, Error> {
+pub(crate) fn encode(
+    service_name: &str,
+    mapping: &Mapping,
+    spans: Vec<trace::SpanData>,
+) -> Result<Vec<u8>, Error> {
     let mut interner = StringInterner::new();
-    let mut encoded_spans = encode_spans(&mut interner, service_name, spans)?;
+    let mut encoded_spans = encode_spans(&mut interner, service_name, mapping, spans)?;
 
     let mut payload = Vec::new();
     rmp::encode::write_array_len(&mut payload, 2)?;
@@ -69,6 +73,7 @@ pub(crate) fn encode(service_name: &str, spans: Vec<trace::SpanData>) -> Result<
 fn encode_spans(
     interner: &mut StringInterner,
     service_name: &str,
+    mapping: &Mapping,
     spans: Vec<trace::SpanData>,
 ) -> Result<Vec<u8>, Error> {
     let mut encoded = Vec::new();
@@ -93,16 +98,13 @@ fn encode_spans(
             .map(|x| x.as_nanos() as i64)
             .unwrap_or(0);
 
-        let span_type = match span.attributes.get(&Key::new("span.type")) {
-            Some(Value::String(s)) => interner.intern(s.as_ref()),
-            _ => interner.intern(""),
-        };
+        let span_type = interner.intern(&mapping.span_type(&span));
 
         // Datadog span name is OpenTelemetry component name - see module docs for more information
         rmp::encode::write_array_len(&mut encoded, 12)?;
         rmp::encode::write_u32(&mut encoded, service_interned)?;
-        rmp::encode::write_u32(&mut encoded, interner.intern(span.instrumentation_lib.name))?;
-        rmp::encode::write_u32(&mut encoded, interner.intern(&span.name))?;
+        rmp::encode::write_u32(&mut encoded, interner.intern(&mapping.name(&span)))?;
+        rmp::encode::write_u32(&mut encoded, interner.intern(&mapping.resource(&span)))?;
         rmp::encode::write_u64(&mut encoded, span.span_context.trace_id().to_u128() as u64)?;
         rmp::encode::write_u64(&mut encoded, span.span_context.span_id().to_u64())?;
         rmp::encode::write_u64(&mut encoded, span.parent_span_id.to_u64())?;
@@ -114,7 +116,12 @@ fn encode_spans(
             rmp::encode::write_u32(&mut encoded, interner.intern(key.as_str()))?;
             rmp::encode::write_u32(&mut encoded, interner.intern(value.as_str().as_ref()))?;
         }
-        rmp::encode::write_map_len(&mut encoded, 0)?;
+        let metrics = metrics(&span);
+        rmp::encode::write_map_len(&mut encoded, metrics.len() as u32)?;
+        for (key, value) in metrics {
+            rmp::encode::write_u32(&mut encoded, interner.intern(key))?;
+            rmp::encode::write_f64(&mut encoded, value)?;
+        }
         rmp::encode::write_u32(&mut encoded, span_type)?;
     }
 
@@ -0,0 +1,335 @@
+use opentelemetry::{
+    propagation::{text_map_propagator::FieldIter, Extractor, Injector, TextMapPropagator},
+    trace::{
+        SpanContext, SpanId, TraceContextExt, TraceId, TraceState, TRACE_FLAG_NOT_SAMPLED,
+        TRACE_FLAG_SAMPLED, TRACE_FLAG_SAMPLING_DEFERRED,
+    },
+    Context,
+};
+
+const DATADOG_TRACE_ID_HEADER: &str = "x-datadog-trace-id";
+const DATADOG_PARENT_ID_HEADER: &str = "x-datadog-parent-id";
+const DATADOG_SAMPLING_PRIORITY_HEADER: &str = "x-datadog-sampling-priority";
+const DATADOG_ORIGIN_HEADER: &str = "x-datadog-origin";
+
+/// The trace state key the `x-datadog-origin` header is stored under.
+const TRACE_STATE_ORIGIN_KEY: &str = "dd_origin";
+/// The trace state key the `x-datadog-sampling-priority` header is stored
+/// under, so that user priorities are propagated unchanged.
+const TRACE_STATE_PRIORITY_KEY: &str = "dd_priority";
+
+lazy_static::lazy_static! {
+    static ref DATADOG_HEADER_FIELDS: [String; 4] = [
+        DATADOG_TRACE_ID_HEADER.to_string(),
+        DATADOG_PARENT_ID_HEADER.to_string(),
+        DATADOG_SAMPLING_PRIORITY_HEADER.to_string(),
+        DATADOG_ORIGIN_HEADER.to_string(),
+    ];
+}
+
+/// Datadog sampling priorities, as carried by the
+/// `x-datadog-sampling-priority` header.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum SamplingPriority {
+    UserReject,
+    AutoReject,
+    AutoKeep,
+    UserKeep,
+}
+
+impl SamplingPriority {
+    fn from_header(value: &str) -> Option<Self> {
+        match value.trim().parse::<i32>().ok()? {
+            priority if priority < 0 => Some(SamplingPriority::UserReject),
+            0 => Some(SamplingPriority::AutoReject),
+            1 => Some(SamplingPriority::AutoKeep),
+            _ => Some(SamplingPriority::UserKeep),
+        }
+    }
+
+    fn to_header(self) -> i32 {
+        match self {
+            SamplingPriority::UserReject => -1,
+            SamplingPriority::AutoReject => 0,
+            SamplingPriority::AutoKeep => 1,
+            SamplingPriority::UserKeep => 2,
+        }
+    }
+
+    fn from_sampled(sampled: bool) -> Self {
+        if sampled {
+            SamplingPriority::AutoKeep
+        } else {
+            SamplingPriority::AutoReject
+        }
+    }
+
+    fn keep(self) -> bool {
+        match self {
+            SamplingPriority::UserReject | SamplingPriority::AutoReject => false,
+            SamplingPriority::AutoKeep | SamplingPriority::UserKeep => true,
+        }
+    }
+
+    fn trace_flags(self) -> u8 {
+        if self.keep() {
+            TRACE_FLAG_SAMPLED
+        } else {
+            TRACE_FLAG_NOT_SAMPLED
+        }
+    }
+}
+
+/// Extracts and injects `SpanContext`s into `Extractor`s or `Injector`s using
+/// the Datadog header format.
+///
+/// Datadog ids are unsigned 64 bit decimal numbers, so only the lower 64 bits
+/// of the trace id are propagated. The sampling decision is carried by
+/// `x-datadog-sampling-priority`. The extracted priority and `x-datadog-origin`
+/// are kept in the trace state under the `dd_priority` and `dd_origin` keys so
+/// they survive a round trip. A propagated priority is only re-injected while it
+/// agrees with the sampling decision of the span.
+///
+/// ## Example
+///
+/// ```
+/// use opentelemetry::global;
+/// use opentelemetry_contrib::trace::exporter::datadog::DatadogPropagator;
+///
+/// global::set_text_map_propagator(DatadogPropagator::new());
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct DatadogPropagator {
+    _private: (),
+}
+
+impl DatadogPropagator {
+    /// Creates a new `DatadogPropagator`.
+    pub fn new() -> Self {
+        DatadogPropagator::default()
+    }
+
+    fn extract_span_context(&self, extractor: &dyn Extractor) -> Result<SpanContext, ()> {
+        let trace_id = extractor
+            .get(DATADOG_TRACE_ID_HEADER)
+            .ok_or(())?
+            .trim()
+            .parse::<u64>()
+            .map_err(|_| ())?;
+        if trace_id == 0 {
+            return Err(());
+        }
+
+        // The parent id is missing for synthetics requests, which only carry
+        // the trace id and origin. Spans started from the extracted context
+        // are the root spans of that trace.
+        let span_id = match extractor.get(DATADOG_PARENT_ID_HEADER) {
+            Some(parent_id) => parent_id.trim().parse::<u64>().map_err(|_| ())?,
+            None => 0,
+        };
+
+        let sampling_priority = extractor
+            .get(DATADOG_SAMPLING_PRIORITY_HEADER)
+            .and_then(SamplingPriority::from_header);
+        let trace_flags =
+            sampling_priority.map_or(TRACE_FLAG_SAMPLING_DEFERRED, SamplingPriority::trace_flags);
+
+        let mut trace_state = Vec::new();
+        if let Some(sampling_priority) = sampling_priority {
+            trace_state.push((
+                TRACE_STATE_PRIORITY_KEY,
+                sampling_priority.to_header().to_string(),
+            ));
+        }
+        if let Some(origin) = extractor.get(DATADOG_ORIGIN_HEADER) {
+            trace_state.push((TRACE_STATE_ORIGIN_KEY, origin.to_string()));
+        }
+        let trace_state = TraceState::from_key_value(trace_state).unwrap_or_default();
+
+        Ok(SpanContext::new(
+            TraceId::from_u128(trace_id as u128),
+            SpanId::from_u64(span_id),
+            trace_flags,
+            true,
+            trace_state,
+        ))
+    }
+}
+
+impl TextMapPropagator for DatadogPropagator {
+    fn inject_context(&self, cx: &Context, injector: &mut dyn Injector) {
+        let span_context = cx.span().span_context();
+        if !span_context.is_valid() {
+            return;
+        }
+
+        injector.set(
+            DATADOG_TRACE_ID_HEADER,
+            (span_context.trace_id().to_u128() as u64).to_string(),
+        );
+        injector.set(
+            DATADOG_PARENT_ID_HEADER,
+            span_context.span_id().to_u64().to_string(),
+        );
+
+        if !span_context.is_deferred() {
+            let sampled = span_context.is_sampled();
+            let sampling_priority = span_context
+                .trace_state()
+                .get(TRACE_STATE_PRIORITY_KEY)
+                .and_then(SamplingPriority::from_header)
+                .filter(|priority| priority.keep() == sampled)
+                .unwrap_or_else(|| SamplingPriority::from_sampled(sampled));
+            injector.set(
+                DATADOG_SAMPLING_PRIORITY_HEADER,
+                sampling_priority.to_header().to_string(),
+            );
+        }
+
+        if let Some(origin) = span_context.trace_state().get(TRACE_STATE_ORIGIN_KEY) {
+            injector.set(DATADOG_ORIGIN_HEADER, origin.to_string());
+        }
+    }
+
+    fn extract_with_context(&self, cx: &Context, extractor: &dyn Extractor) -> Context {
+        let extracted = self
+            .extract_span_context(extractor)
+            .unwrap_or_else(|_| SpanContext::empty_context());
+
+        cx.with_remote_span_context(extracted)
+    }
+
+    fn fields(&self) -> FieldIter<'_> {
+        FieldIter::new(DATADOG_HEADER_FIELDS.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry::sdk;
+    use opentelemetry::testing::trace::TestSpan;
+    use opentelemetry::trace::{Span, Tracer, TracerProvider};
+    use std::collections::HashMap;
+    use std::str::FromStr;
+
+    #[rustfmt::skip]
+    fn extract_test_data() -> Vec<(Vec<(&'static str, &'static str)>, SpanContext)> {
+        vec![
+            (vec![], SpanContext::empty_context()),
+            (vec![(DATADOG_SAMPLING_PRIORITY_HEADER, "0")], SpanContext::empty_context()),
+            (vec![(DATADOG_TRACE_ID_HEADER, "garbage")], SpanContext::empty_context()),
+            (vec![(DATADOG_TRACE_ID_HEADER, "1234"), (DATADOG_PARENT_ID_HEADER, "garbage")], SpanContext::empty_context()),
+            (vec![(DATADOG_TRACE_ID_HEADER, "1234"), (DATADOG_PARENT_ID_HEADER, "12")], SpanContext::new(TraceId::from_u128(1234), SpanId::from_u64(12), TRACE_FLAG_SAMPLING_DEFERRED, true, TraceState::default())),
+            (vec![(DATADOG_TRACE_ID_HEADER, "1234"), (DATADOG_PARENT_ID_HEADER, "12"), (DATADOG_SAMPLING_PRIORITY_HEADER, "-1")], SpanContext::new(TraceId::from_u128(1234), SpanId::from_u64(12), TRACE_FLAG_NOT_SAMPLED, true, TraceState::from_str("dd_priority=-1").unwrap())),
+            (vec![(DATADOG_TRACE_ID_HEADER, "1234"), (DATADOG_PARENT_ID_HEADER, "12"), (DATADOG_SAMPLING_PRIORITY_HEADER, "0")], SpanContext::new(TraceId::from_u128(1234), SpanId::from_u64(12), TRACE_FLAG_NOT_SAMPLED, true, TraceState::from_str("dd_priority=0").unwrap())),
+            (vec![(DATADOG_TRACE_ID_HEADER, "1234"), (DATADOG_PARENT_ID_HEADER, "12"), (DATADOG_SAMPLING_PRIORITY_HEADER, "1")], SpanContext::new(TraceId::from_u128(1234), SpanId::from_u64(12), TRACE_FLAG_SAMPLED, true, TraceState::from_str("dd_priority=1").unwrap())),
+            (vec![(DATADOG_TRACE_ID_HEADER, "1234"), (DATADOG_PARENT_ID_HEADER, "12"), (DATADOG_SAMPLING_PRIORITY_HEADER, "2")], SpanContext::new(TraceId::from_u128(1234), SpanId::from_u64(12), TRACE_FLAG_SAMPLED, true, TraceState::from_str("dd_priority=2").unwrap())),
+            (vec![(DATADOG_TRACE_ID_HEADER, "1234"), (DATADOG_PARENT_ID_HEADER, "12"), (DATADOG_SAMPLING_PRIORITY_HEADER, "2"), (DATADOG_ORIGIN_HEADER, "rum")], SpanContext::new(TraceId::from_u128(1234), SpanId::from_u64(12), TRACE_FLAG_SAMPLED, true, TraceState::from_str("dd_priority=2,dd_origin=rum").unwrap())),
+            (vec![(DATADOG_TRACE_ID_HEADER, "1234"), (DATADOG_ORIGIN_HEADER, "synthetics")], SpanContext::new(TraceId::from_u128(1234), SpanId::invalid(), TRACE_FLAG_SAMPLING_DEFERRED, true, TraceState::from_str("dd_origin=synthetics").unwrap())),
+        ]
+    }
+
+    #[rustfmt::skip]
+    fn inject_test_data() -> Vec<(Vec<(&'static str, &'static str)>, SpanContext)> {
+        vec![
+            (vec![], SpanContext::empty_context()),
+            (vec![(DATADOG_TRACE_ID_HEADER, "1234"), (DATADOG_PARENT_ID_HEADER, "12")], SpanContext::new(TraceId::from_u128(1234), SpanId::from_u64(12), TRACE_FLAG_SAMPLING_DEFERRED, true, TraceState::default())),
+            (vec![(DATADOG_TRACE_ID_HEADER, "1234"), (DATADOG_PARENT_ID_HEADER, "12"), (DATADOG_SAMPLING_PRIORITY_HEADER, "0")], SpanContext::new(TraceId::from_u128(1234), SpanId::from_u64(12), TRACE_FLAG_NOT_SAMPLED, true, TraceState::default())),
+            (vec![(DATADOG_TRACE_ID_HEADER, "1234"), (DATADOG_PARENT_ID_HEADER, "12"), (DATADOG_SAMPLING_PRIORITY_HEADER, "1")], SpanContext::new(TraceId::from_u128(1234), SpanId::from_u64(12), TRACE_FLAG_SAMPLED, true, TraceState::default())),
+            (vec![(DATADOG_TRACE_ID_HEADER, "1234"), (DATADOG_PARENT_ID_HEADER, "12"), (DATADOG_SAMPLING_PRIORITY_HEADER, "1"), (DATADOG_ORIGIN_HEADER, "synthetics")], SpanContext::new(TraceId::from_u128(1234), SpanId::from_u64(12), TRACE_FLAG_SAMPLED, true, TraceState::from_str("dd_origin=synthetics").unwrap())),
+            (vec![(DATADOG_TRACE_ID_HEADER, "1"), (DATADOG_PARENT_ID_HEADER, "12")], SpanContext::new(TraceId::from_u128((7 << 64) + 1), SpanId::from_u64(12), TRACE_FLAG_SAMPLING_DEFERRED, true, TraceState::default())),
+            (vec![(DATADOG_TRACE_ID_HEADER, "1234"), (DATADOG_PARENT_ID_HEADER, "12"), (DATADOG_SAMPLING_PRIORITY_HEADER, "2")], SpanContext::new(TraceId::from_u128(1234), SpanId::from_u64(12), TRACE_FLAG_SAMPLED, true, TraceState::from_str("dd_priority=2").unwrap())),
+            (vec![(DATADOG_TRACE_ID_HEADER, "1234"), (DATADOG_PARENT_ID_HEADER, "12"), (DATADOG_SAMPLING_PRIORITY_HEADER, "-1")], SpanContext::new(TraceId::from_u128(1234), SpanId::from_u64(12), TRACE_FLAG_NOT_SAMPLED, true, TraceState::from_str("dd_priority=-1").unwrap())),
+            (vec![(DATADOG_TRACE_ID_HEADER, "1234"), (DATADOG_PARENT_ID_HEADER, "12"), (DATADOG_SAMPLING_PRIORITY_HEADER, "0")], SpanContext::new(TraceId::from_u128(1234), SpanId::from_u64(12), TRACE_FLAG_NOT_SAMPLED, true, TraceState::from_str("dd_priority=2").unwrap())),
+        ]
+    }
+
+    #[test]
+    fn test_extract() {
+        let propagator = DatadogPropagator::new();
+        for (headers, expected) in extract_test_data() {
+            let map: HashMap<String, String> = headers
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect();
+
+            let context = propagator.extract(&map);
+            assert_eq!(context.remote_span_context(), Some(&expected));
+        }
+    }
+
+    #[test]
+    fn test_extract_synthetics() {
+        let propagator = DatadogPropagator::new();
+        let extractor: HashMap<String, String> = vec![
+            (DATADOG_TRACE_ID_HEADER, "1234"),
+            (DATADOG_SAMPLING_PRIORITY_HEADER, "1"),
+            (DATADOG_ORIGIN_HEADER, "synthetics"),
+        ]
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect();
+        let provider = sdk::trace::TracerProvider::default();
+        let tracer = provider.get_tracer("test", None);
+
+        let span = tracer.start_from_context("request", &propagator.extract(&extractor));
+
+        let span_context = span.span_context();
+        assert_eq!(span_context.trace_id(), TraceId::from_u128(1234));
+        assert_eq!(
+            span_context.trace_state().get(TRACE_STATE_ORIGIN_KEY),
+            Some("synthetics")
+        );
+    }
+
+    #[test]
+    fn test_inject() {
+        let propagator = DatadogPropagator::new();
+        for (headers, span_context) in inject_test_data() {
+            let mut injector: HashMap<String, String> = HashMap::new();
+            propagator.inject_context(
+                &Context::current_with_span(TestSpan(span_context)),
+                &mut injector,
+            );
+
+            let expected: HashMap<String, String> = headers
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect();
+            assert_eq!(injector, expected);
+        }
+    }
+
+    #[test]
+    fn test_user_priority_round_trip() {
+        let propagator = DatadogPropagator::new();
+        for priority in &["-1", "2"] {
+            let extractor: HashMap<String, String> = vec![
+                (DATADOG_TRACE_ID_HEADER, "1234"),
+                (DATADOG_PARENT_ID_HEADER, "12"),
+                (DATADOG_SAMPLING_PRIORITY_HEADER, priority),
+            ]
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+            let span_context = propagator
+                .extract(&extractor)
+                .remote_span_context()
+                .cloned()
+                .unwrap();
+
+            let mut injector: HashMap<String, String> = HashMap::new();
+            propagator.inject_context(
+                &Context::current_with_span(TestSpan(span_context)),
+                &mut injector,
+            );
+            assert_eq!(
+                injector
+                    .get(DATADOG_SAMPLING_PRIORITY_HEADER)
+                    .map(String::as_str),
+                Some(*priority)
+            );
+        }
+    }
+}
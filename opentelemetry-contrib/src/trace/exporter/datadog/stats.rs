@@ -0,0 +1,525 @@
+//! # Client Side APM Stats
+//!
+//! Datadog computes hits, errors and duration distributions of top level and
+//! measured spans. The agent computes them from the received traces unless
+//! the tracer reports them itself.
+//!
+//! The [`StatsProcessor`] aggregates every span that ends in 10 seconds
+//! buckets, including recorded spans that are not sampled and spans the batch
+//! processor drops when its queue is full. A background thread posts the
+//! aggregated buckets to the agent as a msgpack `ClientStatsPayload` every 10
+//! seconds, on force flush and on shutdown, see [stats.proto]. Duration distributions are DDSketches encoded as protobuf,
+//! see [ddsketch.proto].
+//!
+//! [stats.proto]: https://github.com/DataDog/datadog-agent/blob/main/pkg/trace/pb/stats.proto
+//! [ddsketch.proto]: https://github.com/DataDog/sketches-go/blob/master/ddsketch/pb/ddsketch.proto
+use crate::trace::exporter::datadog::model::{is_measured, is_top_level, Error, Mapping};
+use futures::executor;
+use http::{Method, Request, Uri};
+use opentelemetry::sdk::{export::trace, trace::Span, trace::SpanProcessor};
+use opentelemetry::trace::{StatusCode, TraceError, TraceResult};
+use opentelemetry::{global, Context, Value};
+use opentelemetry_semantic_conventions::trace as semcov;
+use prost::Message;
+use std::collections::{BTreeMap, HashMap};
+use std::mem;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+/// Path of the agent stats endpoint.
+pub(crate) const STATS_PATH: &str = "/v0.6/stats";
+
+/// Duration of stats buckets in nanoseconds.
+const BUCKET_DURATION: u64 = 10_000_000_000;
+
+/// Relative accuracy of the duration sketches.
+const RELATIVE_ACCURACY: f64 = 0.01;
+
+/// Stats of the spans sharing the same aggregation key in a bucket.
+#[derive(Debug, Default)]
+struct GroupedStats {
+    hits: u64,
+    top_level_hits: u64,
+    errors: u64,
+    duration: u64,
+    ok_summary: Sketch,
+    error_summary: Sketch,
+}
+
+/// Spans are aggregated by service, name, resource, type and http status.
+type AggregationKey = (String, String, String, String, u32);
+
+/// Stats buckets by start time.
+type Buckets = BTreeMap<u64, HashMap<AggregationKey, GroupedStats>>;
+
+/// Aggregates the stats of ended spans until the exporter flushes them.
+#[derive(Debug)]
+pub(crate) struct Concentrator {
+    service_name: String,
+    mapping: Mapping,
+    buckets: Mutex<Buckets>,
+}
+
+impl Concentrator {
+    pub(crate) fn new(service_name: String, mapping: Mapping) -> Self {
+        Concentrator {
+            service_name,
+            mapping,
+            buckets: Mutex::new(Buckets::new()),
+        }
+    }
+
+    // Aggregation never leaves the buckets half updated, the stats of a
+    // poisoned lock are still valid.
+    fn add(&self, span: &trace::SpanData) {
+        let mut buckets = self.buckets.lock().unwrap_or_else(PoisonError::into_inner);
+        aggregate(&mut buckets, &self.service_name, &self.mapping, span);
+    }
+
+    /// Encodes and resets the aggregated stats, `None` if there are none.
+    pub(crate) fn flush(&self) -> Result<Option<Vec<u8>>, Error> {
+        let buckets = {
+            let mut buckets = self.buckets.lock().unwrap_or_else(PoisonError::into_inner);
+            mem::take(&mut *buckets)
+        };
+
+        encode(buckets)
+    }
+
+    /// Flushes the aggregated stats and posts them to `url`.
+    fn send(&self, client: &dyn trace::HttpClient, url: &Uri) -> TraceResult<()> {
+        if let Some(stats) = self.flush()? {
+            let req = Request::builder()
+                .method(Method::POST)
+                .uri(url.clone())
+                .header(http::header::CONTENT_TYPE, "application/msgpack")
+                .body(stats)
+                .map_err::<Error, _>(Into::into)?;
+            executor::block_on(client.send(req))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Span processor adding every span that ends to the stats of a
+/// [`Concentrator`], and posting them to the agent from a background thread.
+#[derive(Debug)]
+pub(crate) struct StatsProcessor {
+    concentrator: Arc<Concentrator>,
+    client: Arc<dyn trace::HttpClient>,
+    url: Uri,
+    worker: Option<(mpsc::Sender<()>, thread::JoinHandle<()>)>,
+}
+
+impl StatsProcessor {
+    pub(crate) fn new(
+        concentrator: Concentrator,
+        client: Arc<dyn trace::HttpClient>,
+        url: Uri,
+    ) -> Self {
+        let concentrator = Arc::new(concentrator);
+        let (shutdown_tx, shutdown_rx) = mpsc::channel();
+        let worker = {
+            let concentrator = concentrator.clone();
+            let client = client.clone();
+            let url = url.clone();
+            thread::spawn(move || {
+                // Stops once the processor shuts down or is dropped.
+                while let Err(RecvTimeoutError::Timeout) =
+                    shutdown_rx.recv_timeout(Duration::from_nanos(BUCKET_DURATION))
+                {
+                    if let Err(err) = concentrator.send(client.as_ref(), &url) {
+                        global::handle_error(err);
+                    }
+                }
+            })
+        };
+
+        StatsProcessor {
+            concentrator,
+            client,
+            url,
+            worker: Some((shutdown_tx, worker)),
+        }
+    }
+}
+
+impl SpanProcessor for StatsProcessor {
+    fn on_start(&self, _span: &Span, _cx: &Context) {}
+
+    fn on_end(&self, span: trace::SpanData) {
+        self.concentrator.add(&span);
+    }
+
+    fn force_flush(&self) -> TraceResult<()> {
+        self.concentrator.send(self.client.as_ref(), &self.url)
+    }
+
+    fn shutdown(&mut self) -> TraceResult<()> {
+        if let Some((shutdown_tx, worker)) = self.worker.take() {
+            let _ = shutdown_tx.send(());
+            worker
+                .join()
+                .map_err(|_| TraceError::from("stats worker panicked"))?;
+        }
+
+        self.concentrator.send(self.client.as_ref(), &self.url)
+    }
+}
+
+/// Encodes the stats of `buckets`, `None` if there are none.
+fn encode(buckets: Buckets) -> Result<Option<Vec<u8>>, Error> {
+    if buckets.is_empty() {
+        return Ok(None);
+    }
+
+    let mut encoded = Vec::new();
+    rmp::encode::write_map_len(&mut encoded, 6)?;
+    rmp::encode::write_str(&mut encoded, "Hostname")?;
+    rmp::encode::write_str(&mut encoded, "")?;
+    rmp::encode::write_str(&mut encoded, "Env")?;
+    rmp::encode::write_str(&mut encoded, "")?;
+    rmp::encode::write_str(&mut encoded, "Version")?;
+    rmp::encode::write_str(&mut encoded, "")?;
+    rmp::encode::write_str(&mut encoded, "Lang")?;
+    rmp::encode::write_str(&mut encoded, "rust")?;
+    rmp::encode::write_str(&mut encoded, "TracerVersion")?;
+    rmp::encode::write_str(&mut encoded, env!("CARGO_PKG_VERSION"))?;
+
+    rmp::encode::write_str(&mut encoded, "Stats")?;
+    rmp::encode::write_array_len(&mut encoded, buckets.len() as u32)?;
+    for (start, groups) in buckets {
+        rmp::encode::write_map_len(&mut encoded, 3)?;
+        rmp::encode::write_str(&mut encoded, "Start")?;
+        rmp::encode::write_u64(&mut encoded, start)?;
+        rmp::encode::write_str(&mut encoded, "Duration")?;
+        rmp::encode::write_u64(&mut encoded, BUCKET_DURATION)?;
+
+        rmp::encode::write_str(&mut encoded, "Stats")?;
+        rmp::encode::write_array_len(&mut encoded, groups.len() as u32)?;
+        for ((service, name, resource, span_type, http_status_code), stats) in groups {
+            rmp::encode::write_map_len(&mut encoded, 12)?;
+            rmp::encode::write_str(&mut encoded, "Service")?;
+            rmp::encode::write_str(&mut encoded, &service)?;
+            rmp::encode::write_str(&mut encoded, "Name")?;
+            rmp::encode::write_str(&mut encoded, &name)?;
+            rmp::encode::write_str(&mut encoded, "Resource")?;
+            rmp::encode::write_str(&mut encoded, &resource)?;
+            rmp::encode::write_str(&mut encoded, "HTTPStatusCode")?;
+            rmp::encode::write_u32(&mut encoded, http_status_code)?;
+            rmp::encode::write_str(&mut encoded, "Type")?;
+            rmp::encode::write_str(&mut encoded, &span_type)?;
+            rmp::encode::write_str(&mut encoded, "Hits")?;
+            rmp::encode::write_u64(&mut encoded, stats.hits)?;
+            rmp::encode::write_str(&mut encoded, "Errors")?;
+            rmp::encode::write_u64(&mut encoded, stats.errors)?;
+            rmp::encode::write_str(&mut encoded, "Duration")?;
+            rmp::encode::write_u64(&mut encoded, stats.duration)?;
+            rmp::encode::write_str(&mut encoded, "OkSummary")?;
+            rmp::encode::write_bin(&mut encoded, &stats.ok_summary.encode()?)?;
+            rmp::encode::write_str(&mut encoded, "ErrorSummary")?;
+            rmp::encode::write_bin(&mut encoded, &stats.error_summary.encode()?)?;
+            rmp::encode::write_str(&mut encoded, "Synthetics")?;
+            rmp::encode::write_bool(&mut encoded, false).map_err(|_| Error::MessagePackError)?;
+            rmp::encode::write_str(&mut encoded, "TopLevelHits")?;
+            rmp::encode::write_u64(&mut encoded, stats.top_level_hits)?;
+        }
+    }
+
+    Ok(Some(encoded))
+}
+
+/// Adds `span` to its bucket if it is top level or measured.
+fn aggregate(buckets: &mut Buckets, service_name: &str, mapping: &Mapping, span: &trace::SpanData) {
+    let top_level = is_top_level(span);
+    if !top_level && !is_measured(span) {
+        return;
+    }
+
+    let end = span
+        .end_time
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_else(|_| Duration::from_secs(0))
+        .as_nanos() as u64;
+    let duration = span
+        .end_time
+        .duration_since(span.start_time)
+        .map(|duration| duration.as_nanos() as u64)
+        .unwrap_or(0);
+    let http_status_code = match span.attributes.get(&semcov::HTTP_STATUS_CODE) {
+        Some(Value::I64(code)) => *code as u32,
+        Some(code) => code.as_str().parse().unwrap_or(0),
+        None => 0,
+    };
+    let key = (
+        service_name.to_string(),
+        mapping.name(span),
+        mapping.resource(span),
+        mapping.span_type(span),
+        http_status_code,
+    );
+
+    let stats = buckets
+        .entry(end - end % BUCKET_DURATION)
+        .or_default()
+        .entry(key)
+        .or_default();
+    stats.hits += 1;
+    if top_level {
+        stats.top_level_hits += 1;
+    }
+    stats.duration += duration;
+    if span.status_code == StatusCode::Error {
+        stats.errors += 1;
+        stats.error_summary.add(duration as f64);
+    } else {
+        stats.ok_summary.add(duration as f64);
+    }
+}
+
+/// Logarithmic DDSketch of positive values, `gamma^(i-1) < v <= gamma^i` for
+/// values `v` in the bin of index `i`.
+#[derive(Debug, Default)]
+struct Sketch {
+    bins: HashMap<i32, f64>,
+    zero_count: f64,
+}
+
+impl Sketch {
+    fn gamma() -> f64 {
+        (1.0 + RELATIVE_ACCURACY) / (1.0 - RELATIVE_ACCURACY)
+    }
+
+    fn add(&mut self, value: f64) {
+        if value < 1.0 {
+            self.zero_count += 1.0;
+        } else {
+            let index = (value.ln() / Self::gamma().ln()).ceil() as i32;
+            *self.bins.entry(index).or_default() += 1.0;
+        }
+    }
+
+    fn encode(&self) -> Result<Vec<u8>, Error> {
+        let sketch = DdSketch {
+            mapping: Some(IndexMapping {
+                gamma: Self::gamma(),
+                index_offset: 0.0,
+                interpolation: 0,
+            }),
+            positive_values: Some(Store {
+                bin_counts: self.bins.clone(),
+                contiguous_bin_counts: Vec::new(),
+                contiguous_bin_index_offset: 0,
+            }),
+            negative_values: None,
+            zero_count: self.zero_count,
+        };
+        let mut encoded = Vec::with_capacity(sketch.encoded_len());
+        sketch
+            .encode(&mut encoded)
+            .map_err(|err| Error::Other(err.to_string()))?;
+
+        Ok(encoded)
+    }
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct DdSketch {
+    #[prost(message, optional, tag = "1")]
+    mapping: Option<IndexMapping>,
+    #[prost(message, optional, tag = "2")]
+    positive_values: Option<Store>,
+    #[prost(message, optional, tag = "3")]
+    negative_values: Option<Store>,
+    #[prost(double, tag = "4")]
+    zero_count: f64,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct IndexMapping {
+    #[prost(double, tag = "1")]
+    gamma: f64,
+    #[prost(double, tag = "2")]
+    index_offset: f64,
+    #[prost(int32, tag = "3")]
+    interpolation: i32,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct Store {
+    #[prost(map = "sint32, double", tag = "1")]
+    bin_counts: HashMap<i32, f64>,
+    #[prost(double, repeated, tag = "2")]
+    contiguous_bin_counts: Vec<f64>,
+    #[prost(sint32, tag = "3")]
+    contiguous_bin_index_offset: i32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use opentelemetry::sdk::{self, InstrumentationLibrary};
+    use opentelemetry::trace::{SpanContext, SpanId, SpanKind, TraceId, TraceState};
+    use opentelemetry::Key;
+    use std::sync::Arc;
+
+    #[derive(Debug, Default)]
+    struct RecordingClient(Mutex<Vec<Request<Vec<u8>>>>);
+
+    #[async_trait]
+    impl trace::HttpClient for RecordingClient {
+        async fn send(&self, request: Request<Vec<u8>>) -> trace::ExportResult {
+            self.0.lock().unwrap().push(request);
+            Ok(())
+        }
+    }
+
+    fn processor() -> (StatsProcessor, Arc<RecordingClient>) {
+        let client = Arc::new(RecordingClient::default());
+        let processor = StatsProcessor::new(
+            Concentrator::new("service".to_string(), Mapping::default()),
+            client.clone(),
+            "http://127.0.0.1:8126/v0.6/stats".parse().unwrap(),
+        );
+
+        (processor, client)
+    }
+
+    fn span(
+        span_kind: SpanKind,
+        parent_span_id: u64,
+        status_code: StatusCode,
+        duration: u64,
+    ) -> trace::SpanData {
+        let start_time = SystemTime::UNIX_EPOCH + Duration::from_secs(12);
+        let mut attributes = sdk::trace::EvictedHashMap::new(3, 3);
+        attributes.insert(Key::new("http.method").string("GET"));
+        attributes.insert(Key::new("http.status_code").i64(200));
+
+        trace::SpanData {
+            span_context: SpanContext::new(
+                TraceId::from_u128(7),
+                SpanId::from_u64(99),
+                0,
+                false,
+                TraceState::default(),
+            ),
+            parent_span_id: SpanId::from_u64(parent_span_id),
+            span_kind,
+            name: "request".to_string(),
+            start_time,
+            end_time: start_time + Duration::from_nanos(duration),
+            attributes,
+            message_events: sdk::trace::EvictedQueue::new(3),
+            links: sdk::trace::EvictedQueue::new(3),
+            status_code,
+            status_message: String::new(),
+            resource: Arc::new(sdk::Resource::default()),
+            instrumentation_lib: InstrumentationLibrary::new("component", None),
+        }
+    }
+
+    #[test]
+    fn aggregates_top_level_and_measured_spans() {
+        let spans = vec![
+            span(SpanKind::Server, 1, StatusCode::Ok, 1_000),
+            span(SpanKind::Server, 1, StatusCode::Error, 3_000),
+            span(SpanKind::Client, 1, StatusCode::Ok, 500),
+            span(SpanKind::Internal, 1, StatusCode::Ok, 100),
+        ];
+
+        let (processor, _client) = processor();
+        for span in spans {
+            processor.on_end(span);
+        }
+
+        let buckets = mem::take(&mut *processor.concentrator.buckets.lock().unwrap());
+        assert_eq!(buckets.len(), 1);
+        let (start, groups) = buckets.into_iter().next().unwrap();
+        assert_eq!(start, BUCKET_DURATION);
+
+        let key = (
+            "service".to_string(),
+            "component".to_string(),
+            "GET".to_string(),
+            String::new(),
+            200,
+        );
+        let stats = &groups[&key];
+        assert_eq!(stats.hits, 3);
+        assert_eq!(stats.top_level_hits, 2);
+        assert_eq!(stats.errors, 1);
+        assert_eq!(stats.duration, 4_500);
+        assert_eq!(stats.ok_summary.bins.values().sum::<f64>(), 2.0);
+        assert_eq!(stats.error_summary.bins.values().sum::<f64>(), 1.0);
+    }
+
+    #[test]
+    fn skips_payload_without_stats() {
+        let concentrator = Concentrator::new("service".to_string(), Mapping::default());
+        concentrator.add(&span(SpanKind::Internal, 1, StatusCode::Ok, 100));
+
+        assert!(concentrator.flush().unwrap().is_none());
+    }
+
+    #[test]
+    fn flush_resets_stats() {
+        let concentrator = Concentrator::new("service".to_string(), Mapping::default());
+        concentrator.add(&span(SpanKind::Server, 1, StatusCode::Ok, 100));
+
+        assert!(concentrator.flush().unwrap().is_some());
+        assert!(concentrator.flush().unwrap().is_none());
+    }
+
+    #[test]
+    fn flush_recovers_poisoned_stats() {
+        let concentrator = Arc::new(Concentrator::new("service".to_string(), Mapping::default()));
+        concentrator.add(&span(SpanKind::Server, 1, StatusCode::Ok, 100));
+        let poisoned = concentrator.clone();
+        let _ = thread::spawn(move || {
+            let _buckets = poisoned.buckets.lock().unwrap();
+            panic!("poison the stats lock");
+        })
+        .join();
+
+        assert!(concentrator.buckets.is_poisoned());
+        assert!(concentrator.flush().unwrap().is_some());
+    }
+
+    #[test]
+    fn posts_stats_on_force_flush_and_shutdown() {
+        let (mut processor, client) = processor();
+
+        processor.on_end(span(SpanKind::Server, 1, StatusCode::Ok, 100));
+        processor.force_flush().unwrap();
+        processor.on_end(span(SpanKind::Server, 1, StatusCode::Ok, 100));
+        processor.shutdown().unwrap();
+
+        let requests = client.0.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].uri(), "http://127.0.0.1:8126/v0.6/stats");
+        assert_eq!(
+            requests[0].headers()[http::header::CONTENT_TYPE],
+            "application/msgpack"
+        );
+    }
+
+    #[test]
+    fn sketch_round_trip() {
+        let mut sketch = Sketch::default();
+        sketch.add(0.0);
+        sketch.add(1_000.0);
+
+        let decoded = DdSketch::decode(&sketch.encode().unwrap()[..]).unwrap();
+        assert_eq!(decoded.zero_count, 1.0);
+        let bins = decoded.positive_values.unwrap().bin_counts;
+        let (index, count) = bins.into_iter().next().unwrap();
+        assert_eq!(count, 1.0);
+        let gamma = decoded.mapping.unwrap().gamma;
+        assert!(gamma.powi(index - 1) < 1_000.0 && 1_000.0 <= gamma.powi(index));
+    }
+}
@@ -6,8 +6,12 @@
 //!
 //! Currently, the following exporters are supported:
 //! * `datadog`, enable `datadog` feature to use it.
+//! * `xray`, enable `aws-xray` feature to use it.
 //!
 //! [`Opentelemetry`](https://github.com/open-telemetry/opentelemetry-rust#related-crates)
 #[cfg(feature = "datadog")]
 #[cfg_attr(docsrs, doc(cfg(feature = "datadog")))]
 pub mod datadog;
+#[cfg(feature = "aws-xray")]
+#[cfg_attr(docsrs, doc(cfg(feature = "aws-xray")))]
+pub mod xray;
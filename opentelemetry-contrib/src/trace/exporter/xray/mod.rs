@@ -0,0 +1,329 @@
+//! # OpenTelemetry AWS X-Ray Exporter
+//!
+//! Sends spans as [segment documents] to the [X-Ray daemon] over UDP, without
+//! going through an OpenTelemetry collector.
+//!
+//! ## Segments and Subsegments
+//!
+//! Root spans and spans of `Server` or `Consumer` kind are sent as segments
+//! named after the service, other spans as independent subsegments of their
+//! parent. Subsegments of AWS SDK calls, spans with the `rpc.system` attribute
+//! set to `aws-api`, are named after the `rpc.service` attribute and other
+//! subsegments after the `peer.service` attribute or the span name.
+//!
+//! Http, AWS SDK and database attributes are mapped to the `http`, `aws` and
+//! `sql` objects of documents, and the remaining attributes are recorded as
+//! metadata. Attributes selected with [`XrayPipelineBuilder::with_indexed_attributes`]
+//! or [`XrayPipelineBuilder::with_index_all_attributes`] are recorded as
+//! annotations instead, which X-Ray indexes for filter expressions. Database
+//! statements are sent with their string and numeric literals replaced by `?`,
+//! and connection strings without their credentials.
+//!
+//! Documents are flagged with `error` and `throttle` for `4xx` and `429`
+//! http status codes, and with `fault` for `5xx` status codes or spans with
+//! an error status.
+//!
+//! [segment documents]: https://docs.aws.amazon.com/xray/latest/devguide/xray-api-segmentdocuments.html
+//! [X-Ray daemon]: https://docs.aws.amazon.com/xray/latest/devguide/xray-daemon.html
+//! [`XrayPipelineBuilder::with_indexed_attributes`]: struct.XrayPipelineBuilder.html#method.with_indexed_attributes
+//! [`XrayPipelineBuilder::with_index_all_attributes`]: struct.XrayPipelineBuilder.html#method.with_index_all_attributes
+//!
+//! ## Trace IDs
+//!
+//! X-Ray rejects trace ids that don't start with the epoch time of the trace,
+//! the pipeline installs the [`XrayIdGenerator`] unless a trace config is
+//! given, in which case it should use it as well. Use the [`XrayPropagator`]
+//! to propagate trace contexts to and from other X-Ray instrumented services.
+//!
+//! [`XrayIdGenerator`]: opentelemetry::sdk::trace::XrayIdGenerator
+//! [`XrayPropagator`]: crate::trace::propagator::XrayPropagator
+//!
+//! ## Example
+//!
+//! ```no_run
+//! use opentelemetry::{Key, trace::Tracer};
+//! use opentelemetry_contrib::trace::exporter::xray;
+//!
+//! fn main() -> Result<(), opentelemetry::trace::TraceError> {
+//!     let (tracer, _uninstall) = xray::new_pipeline()
+//!         .with_service_name("my_app")
+//!         .with_daemon_endpoint("127.0.0.1:2000")
+//!         .with_indexed_attributes(vec![Key::new("user.id")])
+//!         .install()?;
+//!
+//!     tracer.in_span("doing_work", |cx| {
+//!         // Traced app logic here...
+//!     });
+//!
+//!     Ok(())
+//! }
+//! ```
+mod segment;
+
+use async_trait::async_trait;
+use opentelemetry::sdk::export::{trace, ExportError};
+use opentelemetry::trace::TraceError;
+use opentelemetry::{global, sdk, trace::TracerProvider, Key};
+use segment::Indexing;
+use std::io;
+use std::net::{ToSocketAddrs, UdpSocket};
+
+/// Default X-Ray daemon endpoint
+const DEFAULT_DAEMON_ENDPOINT: &str = "127.0.0.1:2000";
+
+/// Environment variable overriding the default daemon endpoint.
+const DAEMON_ADDRESS_ENV: &str = "AWS_XRAY_DAEMON_ADDRESS";
+
+/// Default service name if no service is configured.
+const DEFAULT_SERVICE_NAME: &str = "OpenTelemetry";
+
+/// Header preceding each document sent to the daemon.
+const HEADER: &str = "{\"format\":\"json\",\"version\":1}\n";
+
+/// Max size of the packets accepted by the daemon, header included.
+const MAX_PACKET_SIZE: usize = 64 * 1024;
+
+/// Wrap type for errors from the X-Ray exporter
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// Sending documents to the daemon failed
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    /// Encoding documents failed
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    /// The document of a span is larger than the daemon accepts
+    #[error("document of {0} bytes exceeds the 64 KiB packet limit")]
+    TooLarge(usize),
+}
+
+impl ExportError for Error {
+    fn exporter_name(&self) -> &'static str {
+        "aws-xray"
+    }
+}
+
+/// X-Ray span exporter
+#[derive(Debug)]
+pub struct XrayExporter {
+    socket: UdpSocket,
+    service_name: String,
+    indexing: Indexing,
+}
+
+impl XrayExporter {
+    /// Sends the document of `span`, without blocking if the socket buffer
+    /// is full.
+    fn send(&self, span: &trace::SpanData) -> Result<(), Error> {
+        let document = segment::segment(span, &self.service_name, &self.indexing);
+        let mut packet = HEADER.as_bytes().to_vec();
+        serde_json::to_writer(&mut packet, &document)?;
+        if packet.len() > MAX_PACKET_SIZE {
+            return Err(Error::TooLarge(packet.len()));
+        }
+        self.socket.send(&packet)?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl trace::SpanExporter for XrayExporter {
+    /// Export spans to the X-Ray daemon
+    ///
+    /// Spans that fail to be sent are skipped, the first error is returned
+    /// once the batch is sent and the others are reported to the global
+    /// error handler.
+    async fn export(&mut self, batch: Vec<trace::SpanData>) -> trace::ExportResult {
+        let mut result = Ok(());
+        for span in batch {
+            if let Err(err) = self.send(&span) {
+                if result.is_ok() {
+                    result = Err(err.into());
+                } else {
+                    global::handle_error(TraceError::from(err));
+                }
+            }
+        }
+
+        result
+    }
+}
+
+/// Create a new X-Ray exporter pipeline builder.
+pub fn new_pipeline() -> XrayPipelineBuilder {
+    XrayPipelineBuilder::default()
+}
+
+/// Builder for the X-Ray exporter pipeline.
+#[derive(Debug)]
+pub struct XrayPipelineBuilder {
+    service_name: String,
+    daemon_endpoint: String,
+    trace_config: Option<sdk::trace::Config>,
+    indexing: Indexing,
+}
+
+impl Default for XrayPipelineBuilder {
+    fn default() -> Self {
+        XrayPipelineBuilder {
+            service_name: DEFAULT_SERVICE_NAME.to_string(),
+            daemon_endpoint: std::env::var(DAEMON_ADDRESS_ENV)
+                .unwrap_or_else(|_| DEFAULT_DAEMON_ENDPOINT.to_string()),
+            trace_config: None,
+            indexing: Indexing::default(),
+        }
+    }
+}
+
+impl XrayPipelineBuilder {
+    /// Install the X-Ray exporter pipeline with the recommended defaults.
+    pub fn install(mut self) -> Result<(sdk::trace::Tracer, Uninstall), TraceError> {
+        let exporter = self.build_exporter()?;
+        let config = self.trace_config.take().unwrap_or_else(|| {
+            sdk::trace::config().with_id_generator(sdk::trace::XrayIdGenerator::default())
+        });
+        let provider = sdk::trace::TracerProvider::builder()
+            .with_exporter(exporter)
+            .with_config(config)
+            .build();
+        let tracer = provider.get_tracer("opentelemetry-xray", Some(env!("CARGO_PKG_VERSION")));
+        let provider_guard = global::set_tracer_provider(provider);
+        Ok((tracer, Uninstall(provider_guard)))
+    }
+
+    fn build_exporter(&mut self) -> Result<XrayExporter, Error> {
+        let addr = self
+            .daemon_endpoint
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| {
+                io::Error::new(io::ErrorKind::NotFound, "daemon endpoint not resolved")
+            })?;
+        let bind_addr = if addr.is_ipv4() {
+            "0.0.0.0:0"
+        } else {
+            "[::]:0"
+        };
+        let socket = UdpSocket::bind(bind_addr)?;
+        socket.connect(addr)?;
+        socket.set_nonblocking(true)?;
+
+        Ok(XrayExporter {
+            socket,
+            service_name: self.service_name.clone(),
+            indexing: std::mem::take(&mut self.indexing),
+        })
+    }
+
+    /// Assign the service name of segments
+    pub fn with_service_name<T: Into<String>>(mut self, name: T) -> Self {
+        self.service_name = name.into();
+        self
+    }
+
+    /// Assign the X-Ray daemon UDP endpoint, defaults to the
+    /// `AWS_XRAY_DAEMON_ADDRESS` environment variable or `127.0.0.1:2000`.
+    pub fn with_daemon_endpoint<T: Into<String>>(mut self, endpoint: T) -> Self {
+        self.daemon_endpoint = endpoint.into();
+        self
+    }
+
+    /// Assign the SDK trace configuration
+    pub fn with_trace_config(mut self, config: sdk::trace::Config) -> Self {
+        self.trace_config = Some(config);
+        self
+    }
+
+    /// Record the attributes with the given keys as annotations rather than
+    /// metadata.
+    pub fn with_indexed_attributes<I: IntoIterator<Item = Key>>(mut self, keys: I) -> Self {
+        self.indexing.keys.extend(keys);
+        self
+    }
+
+    /// Record all attributes that are not mapped to other fields as
+    /// annotations rather than metadata.
+    pub fn with_index_all_attributes(mut self, index_all: bool) -> Self {
+        self.indexing.all = index_all;
+        self
+    }
+}
+
+/// Uninstalls the X-Ray pipeline on drop
+#[must_use]
+#[derive(Debug)]
+pub struct Uninstall(global::TracerProviderGuard);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry::sdk::export::trace::SpanExporter;
+    use opentelemetry::testing::trace::new_test_export_span_data;
+    use opentelemetry::trace::{Span, Tracer};
+    use opentelemetry::KeyValue;
+    use std::time::Duration;
+
+    #[test]
+    fn sends_documents_to_daemon() {
+        let daemon = UdpSocket::bind("127.0.0.1:0").unwrap();
+        daemon
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let exporter = new_pipeline()
+            .with_service_name("my-service")
+            .with_daemon_endpoint(daemon.local_addr().unwrap().to_string())
+            .build_exporter()
+            .unwrap();
+        let provider = sdk::trace::TracerProvider::builder()
+            .with_simple_exporter(exporter)
+            .with_config(
+                sdk::trace::config().with_id_generator(sdk::trace::XrayIdGenerator::default()),
+            )
+            .build();
+        let tracer = provider.get_tracer("test", None);
+
+        let span = tracer.start("work");
+        let span_context = span.span_context();
+        span.end();
+
+        let mut buf = [0; 4096];
+        let len = daemon.recv(&mut buf).unwrap();
+        let packet = std::str::from_utf8(&buf[..len]).unwrap();
+        assert!(packet.starts_with(HEADER));
+        let document: serde_json::Value = serde_json::from_str(&packet[HEADER.len()..]).unwrap();
+        assert_eq!(document["name"], "my-service");
+        assert_eq!(document["id"], span_context.span_id().to_hex());
+        let trace_id = span_context.trace_id().to_hex();
+        assert_eq!(
+            document["trace_id"],
+            format!("1-{}-{}", &trace_id[..8], &trace_id[8..])
+        );
+        assert!(document.get("parent_id").is_none());
+    }
+    #[tokio::test]
+    async fn skips_documents_too_large_for_daemon() {
+        let daemon = UdpSocket::bind("127.0.0.1:0").unwrap();
+        daemon
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let mut exporter = new_pipeline()
+            .with_daemon_endpoint(daemon.local_addr().unwrap().to_string())
+            .build_exporter()
+            .unwrap();
+        let mut large = new_test_export_span_data();
+        large
+            .attributes
+            .insert(KeyValue::new("payload", "x".repeat(MAX_PACKET_SIZE)));
+        let small = new_test_export_span_data();
+
+        let result = exporter.export(vec![large, small]).await;
+
+        assert!(result.is_err());
+        let mut buf = [0; 4096];
+        let len = daemon.recv(&mut buf).unwrap();
+        let packet = std::str::from_utf8(&buf[..len]).unwrap();
+        let document: serde_json::Value = serde_json::from_str(&packet[HEADER.len()..]).unwrap();
+        assert_eq!(document["name"], DEFAULT_SERVICE_NAME);
+        assert!(document.get("metadata").is_none());
+    }
+}
@@ -0,0 +1,566 @@
+use opentelemetry::sdk::export::trace::SpanData;
+use opentelemetry::trace::{SpanId, SpanKind, StatusCode, TraceId};
+use opentelemetry::{Array, Key, Value};
+use opentelemetry_semantic_conventions::trace as semcov;
+use serde_json::{json, Map, Value as Json};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// `rpc.system` of spans of AWS SDK calls.
+const AWS_API_RPC_SYSTEM: &str = "aws-api";
+/// Attributes of AWS SDK calls copied to the `aws` object.
+const AWS_ATTRIBUTES: [(&str, &str); 5] = [
+    ("aws.account_id", "account_id"),
+    ("aws.region", "region"),
+    ("aws.request_id", "request_id"),
+    ("aws.queue_url", "queue_url"),
+    ("aws.table_name", "table_name"),
+];
+/// Namespace of the metadata of attributes that are not indexed.
+const METADATA_NAMESPACE: &str = "default";
+/// Max length of segment names.
+const MAX_NAME_LEN: usize = 200;
+
+/// Attributes indexed as X-Ray annotations.
+#[derive(Debug, Default)]
+pub(crate) struct Indexing {
+    pub(crate) all: bool,
+    pub(crate) keys: Vec<Key>,
+}
+
+impl Indexing {
+    fn is_indexed(&self, key: &Key) -> bool {
+        self.all || self.keys.contains(key)
+    }
+}
+
+/// Converts `span` to an X-Ray segment document, server spans and spans
+/// without parent are segments, other spans are subsegments of their parent.
+pub(crate) fn segment(span: &SpanData, service_name: &str, indexing: &Indexing) -> Json {
+    let attributes = &span.attributes;
+    let is_aws = attribute_str(span, &semcov::RPC_SYSTEM).as_deref() == Some(AWS_API_RPC_SYSTEM);
+    let is_segment = span.parent_span_id == SpanId::invalid()
+        || span.span_kind == SpanKind::Server
+        || span.span_kind == SpanKind::Consumer;
+
+    let mut doc = Map::new();
+    let name = if is_segment {
+        service_name.to_string()
+    } else if is_aws {
+        attribute_str(span, &semcov::RPC_SERVICE).unwrap_or_else(|| span.name.clone())
+    } else {
+        attribute_str(span, &semcov::PEER_SERVICE).unwrap_or_else(|| span.name.clone())
+    };
+    doc.insert("name".into(), sanitize_name(&name).into());
+    doc.insert("id".into(), span.span_context.span_id().to_hex().into());
+    doc.insert(
+        "trace_id".into(),
+        trace_id(span.span_context.trace_id()).into(),
+    );
+    if span.parent_span_id != SpanId::invalid() {
+        doc.insert("parent_id".into(), span.parent_span_id.to_hex().into());
+    }
+    doc.insert("start_time".into(), timestamp(span.start_time).into());
+    doc.insert("end_time".into(), timestamp(span.end_time).into());
+    if !is_segment {
+        doc.insert("type".into(), "subsegment".into());
+        match (is_aws, &span.span_kind) {
+            (true, _) => {
+                doc.insert("namespace".into(), "aws".into());
+            }
+            (false, SpanKind::Client) | (false, SpanKind::Producer) => {
+                doc.insert("namespace".into(), "remote".into());
+            }
+            _ => {}
+        }
+    }
+
+    let status = attributes
+        .get(&semcov::HTTP_STATUS_CODE)
+        .and_then(|status| match status {
+            Value::I64(status) => Some(*status),
+            value => value.as_str().parse().ok(),
+        });
+    match status {
+        Some(429) => {
+            doc.insert("error".into(), true.into());
+            doc.insert("throttle".into(), true.into());
+        }
+        Some(400..=499) => {
+            doc.insert("error".into(), true.into());
+        }
+        Some(500..=599) => {
+            doc.insert("fault".into(), true.into());
+        }
+        _ if span.status_code == StatusCode::Error => {
+            doc.insert("fault".into(), true.into());
+        }
+        _ => {}
+    }
+
+    if let Some(http) = http(span, status) {
+        doc.insert("http".into(), http);
+    }
+    if is_aws {
+        let mut aws = Map::new();
+        if let Some(operation) = attribute_str(span, &semcov::RPC_METHOD) {
+            aws.insert("operation".into(), operation.into());
+        }
+        for (attribute, field) in AWS_ATTRIBUTES.iter() {
+            if let Some(value) = attributes.get(&Key::from_static_str(attribute)) {
+                aws.insert((*field).into(), value.as_str().into_owned().into());
+            }
+        }
+        doc.insert("aws".into(), aws.into());
+    }
+    if let Some(sql) = sql(span) {
+        doc.insert("sql".into(), sql);
+    }
+
+    let mut annotations = Map::new();
+    let mut metadata = Map::new();
+    for (key, value) in attributes.iter() {
+        if is_mapped(key) {
+            continue;
+        }
+        match value {
+            Value::Array(array) => {
+                metadata.insert(key.as_str().into(), array_json(array));
+            }
+            value if indexing.is_indexed(key) => {
+                annotations.insert(sanitize_annotation_key(key.as_str()), scalar_json(value));
+            }
+            value => {
+                metadata.insert(key.as_str().into(), scalar_json(value));
+            }
+        }
+    }
+    if !annotations.is_empty() {
+        doc.insert("annotations".into(), annotations.into());
+    }
+    if !metadata.is_empty() {
+        doc.insert("metadata".into(), json!({ METADATA_NAMESPACE: metadata }));
+    }
+
+    doc.into()
+}
+
+/// The `http` object of spans with http attributes.
+fn http(span: &SpanData, status: Option<i64>) -> Option<Json> {
+    let mut request = Map::new();
+    if let Some(method) = attribute_str(span, &semcov::HTTP_METHOD) {
+        request.insert("method".into(), method.into());
+    }
+    let url = attribute_str(span, &semcov::HTTP_URL).or_else(|| {
+        let host = attribute_str(span, &semcov::HTTP_HOST)?;
+        Some(format!(
+            "{}://{}{}",
+            attribute_str(span, &semcov::HTTP_SCHEME).unwrap_or_else(|| "http".to_string()),
+            host,
+            attribute_str(span, &semcov::HTTP_TARGET).unwrap_or_default()
+        ))
+    });
+    if let Some(url) = url {
+        request.insert("url".into(), url.into());
+    }
+    if let Some(user_agent) = attribute_str(span, &semcov::HTTP_USER_AGENT) {
+        request.insert("user_agent".into(), user_agent.into());
+    }
+    if let Some(client_ip) = attribute_str(span, &semcov::HTTP_CLIENT_IP) {
+        request.insert("client_ip".into(), client_ip.into());
+    }
+
+    let mut response = Map::new();
+    if let Some(status) = status {
+        response.insert("status".into(), status.into());
+    }
+    if let Some(Value::I64(length)) = span.attributes.get(&semcov::HTTP_RESPONSE_CONTENT_LENGTH) {
+        response.insert("content_length".into(), (*length).into());
+    }
+
+    if request.is_empty() && response.is_empty() {
+        return None;
+    }
+    let mut http = Map::new();
+    if !request.is_empty() {
+        http.insert("request".into(), request.into());
+    }
+    if !response.is_empty() {
+        http.insert("response".into(), response.into());
+    }
+    Some(http.into())
+}
+
+/// The `sql` object of spans with database attributes.
+fn sql(span: &SpanData) -> Option<Json> {
+    let mut sql = Map::new();
+    for (key, field) in [
+        (semcov::DB_CONNECTION_STRING, "url"),
+        (semcov::DB_SYSTEM, "database_type"),
+        (semcov::DB_USER, "user"),
+        (semcov::DB_STATEMENT, "sanitized_query"),
+    ]
+    .iter()
+    {
+        if let Some(value) = attribute_str(span, key) {
+            let value = if *key == semcov::DB_CONNECTION_STRING {
+                strip_credentials(&value)
+            } else if *key == semcov::DB_STATEMENT {
+                sanitize_query(&value)
+            } else {
+                value
+            };
+            sql.insert((*field).into(), value.into());
+        }
+    }
+
+    if sql.is_empty() {
+        None
+    } else {
+        Some(sql.into())
+    }
+}
+
+/// Removes the user info of connection urls and the password of
+/// `key=value;...` connection strings.
+fn strip_credentials(connection_string: &str) -> String {
+    if let Some(scheme_end) = connection_string.find("://") {
+        let authority_start = scheme_end + 3;
+        let authority_end = connection_string[authority_start..]
+            .find(&['/', '?', '#'][..])
+            .map_or(connection_string.len(), |end| authority_start + end);
+        match connection_string[authority_start..authority_end].rfind('@') {
+            Some(at) => format!(
+                "{}{}",
+                &connection_string[..authority_start],
+                &connection_string[authority_start + at + 1..]
+            ),
+            None => connection_string.to_string(),
+        }
+    } else {
+        connection_string
+            .split(';')
+            .filter(|part| {
+                let key = part.split('=').next().unwrap_or("").trim();
+                !key.eq_ignore_ascii_case("password") && !key.eq_ignore_ascii_case("pwd")
+            })
+            .collect::<Vec<_>>()
+            .join(";")
+    }
+}
+
+/// Replaces the string and numeric literals of `statement` with `?`.
+fn sanitize_query(statement: &str) -> String {
+    let mut sanitized = String::with_capacity(statement.len());
+    let mut chars = statement.chars().peekable();
+    let mut in_word = false;
+    while let Some(c) = chars.next() {
+        if c == '\'' {
+            // Quotes are escaped by doubling them, or with a backslash in MySQL.
+            while let Some(c) = chars.next() {
+                match c {
+                    '\\' => {
+                        chars.next();
+                    }
+                    '\'' if chars.peek() == Some(&'\'') => {
+                        chars.next();
+                    }
+                    '\'' => break,
+                    _ => {}
+                }
+            }
+            sanitized.push('?');
+            in_word = false;
+        } else if c.is_ascii_digit() && !in_word {
+            while let Some(&c) = chars.peek() {
+                if !c.is_ascii_alphanumeric() && c != '.' {
+                    break;
+                }
+                chars.next();
+            }
+            sanitized.push('?');
+        } else {
+            // `$` keeps the numbers of positional parameters like `$1`.
+            in_word = c.is_alphanumeric() || c == '_' || c == '$';
+            sanitized.push(c);
+        }
+    }
+    sanitized
+}
+
+/// Whether the attribute `key` is mapped to a field of the `http`, `aws` or
+/// `sql` objects rather than annotations or metadata.
+fn is_mapped(key: &Key) -> bool {
+    [
+        semcov::HTTP_METHOD,
+        semcov::HTTP_URL,
+        semcov::HTTP_HOST,
+        semcov::HTTP_SCHEME,
+        semcov::HTTP_TARGET,
+        semcov::HTTP_USER_AGENT,
+        semcov::HTTP_CLIENT_IP,
+        semcov::HTTP_STATUS_CODE,
+        semcov::HTTP_RESPONSE_CONTENT_LENGTH,
+        semcov::RPC_SYSTEM,
+        semcov::RPC_SERVICE,
+        semcov::RPC_METHOD,
+        semcov::PEER_SERVICE,
+        semcov::DB_CONNECTION_STRING,
+        semcov::DB_SYSTEM,
+        semcov::DB_USER,
+        semcov::DB_STATEMENT,
+    ]
+    .contains(key)
+        || AWS_ATTRIBUTES
+            .iter()
+            .any(|(attribute, _)| *attribute == key.as_str())
+}
+
+fn attribute_str(span: &SpanData, key: &Key) -> Option<String> {
+    span.attributes
+        .get(key)
+        .map(|value| value.as_str().into_owned())
+}
+
+/// Formats `trace_id` as `1-{timestamp}-{random}`.
+fn trace_id(trace_id: TraceId) -> String {
+    let hex = trace_id.to_hex();
+    let (timestamp, random) = hex.split_at(8);
+    format!("1-{}-{}", timestamp, random)
+}
+
+/// Seconds since the epoch with microsecond precision.
+fn timestamp(time: SystemTime) -> f64 {
+    let micros = time
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_micros())
+        .unwrap_or(0);
+    micros as f64 / 1_000_000.0
+}
+
+fn scalar_json(value: &Value) -> Json {
+    match value {
+        Value::Bool(value) => (*value).into(),
+        Value::I64(value) => (*value).into(),
+        Value::F64(value) => (*value).into(),
+        value => value.as_str().into_owned().into(),
+    }
+}
+
+fn array_json(array: &Array) -> Json {
+    match array {
+        Array::Bool(values) => values.clone().into(),
+        Array::I64(values) => values.clone().into(),
+        Array::F64(values) => values.clone().into(),
+        Array::String(values) => values
+            .iter()
+            .map(|value| Json::from(value.as_ref()))
+            .collect(),
+    }
+}
+
+/// Replaces the characters not allowed in segment names and truncates them.
+fn sanitize_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c.is_whitespace() || "_.:/%&#=+\\-@".contains(c) {
+                c
+            } else {
+                '_'
+            }
+        })
+        .take(MAX_NAME_LEN)
+        .collect()
+}
+
+/// Annotation keys may only contain alphanumeric characters and underscores.
+fn sanitize_annotation_key(key: &str) -> String {
+    key.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry::sdk::{self, trace::EvictedHashMap};
+    use opentelemetry::trace::{SpanContext, TraceState, TRACE_FLAG_SAMPLED};
+    use opentelemetry::KeyValue;
+    use std::borrow::Cow;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    fn span(kind: SpanKind, parent_span_id: u64, attributes: Vec<KeyValue>) -> SpanData {
+        let mut attribute_map = EvictedHashMap::new(32, attributes.len());
+        for attribute in attributes {
+            attribute_map.insert(attribute);
+        }
+        let start_time = UNIX_EPOCH + Duration::from_micros(1_600_000_000_500_000);
+        SpanData {
+            span_context: SpanContext::new(
+                TraceId::from_hex("5f4f0d5a0123456789abcdef01234567"),
+                SpanId::from_u64(10),
+                TRACE_FLAG_SAMPLED,
+                false,
+                TraceState::default(),
+            ),
+            parent_span_id: SpanId::from_u64(parent_span_id),
+            span_kind: kind,
+            name: "GET /users".to_string(),
+            start_time,
+            end_time: start_time + Duration::from_millis(250),
+            attributes: attribute_map,
+            message_events: sdk::trace::EvictedQueue::new(0),
+            links: sdk::trace::EvictedQueue::new(0),
+            status_code: StatusCode::Unset,
+            status_message: String::new(),
+            resource: Arc::new(sdk::Resource::default()),
+            instrumentation_lib: sdk::InstrumentationLibrary::new("component", None),
+        }
+    }
+
+    #[test]
+    fn maps_server_span_to_segment() {
+        let span = span(
+            SpanKind::Server,
+            0,
+            vec![
+                semcov::HTTP_METHOD.string("GET"),
+                semcov::HTTP_SCHEME.string("https"),
+                semcov::HTTP_HOST.string("example.com"),
+                semcov::HTTP_TARGET.string("/users?id=1"),
+                semcov::HTTP_STATUS_CODE.i64(429),
+                KeyValue::new("user.id", "42"),
+                KeyValue::new("retries", 2),
+                KeyValue::new(
+                    "tags",
+                    Value::Array(vec![Cow::from("a"), Cow::from("b")].into()),
+                ),
+            ],
+        );
+        let indexing = Indexing {
+            all: false,
+            keys: vec![Key::new("user.id")],
+        };
+
+        assert_eq!(
+            segment(&span, "my service!", &indexing),
+            json!({
+                "name": "my service_",
+                "id": "000000000000000a",
+                "trace_id": "1-5f4f0d5a-0123456789abcdef01234567",
+                "start_time": 1_600_000_000.5,
+                "end_time": 1_600_000_000.75,
+                "error": true,
+                "throttle": true,
+                "http": {
+                    "request": {"method": "GET", "url": "https://example.com/users?id=1"},
+                    "response": {"status": 429},
+                },
+                "annotations": {"user_id": "42"},
+                "metadata": {"default": {"retries": 2, "tags": ["a", "b"]}},
+            })
+        );
+    }
+
+    #[test]
+    fn maps_client_spans_to_subsegments() {
+        let mut aws_span = span(
+            SpanKind::Client,
+            5,
+            vec![
+                semcov::RPC_SYSTEM.string("aws-api"),
+                semcov::RPC_SERVICE.string("DynamoDB"),
+                semcov::RPC_METHOD.string("GetItem"),
+                KeyValue::new("aws.region", "us-east-1"),
+                KeyValue::new("aws.table_name", "users"),
+            ],
+        );
+        aws_span.status_code = StatusCode::Error;
+
+        assert_eq!(
+            segment(&aws_span, "my-service", &Indexing::default()),
+            json!({
+                "name": "DynamoDB",
+                "id": "000000000000000a",
+                "trace_id": "1-5f4f0d5a-0123456789abcdef01234567",
+                "parent_id": "0000000000000005",
+                "start_time": 1_600_000_000.5,
+                "end_time": 1_600_000_000.75,
+                "type": "subsegment",
+                "namespace": "aws",
+                "fault": true,
+                "aws": {"operation": "GetItem", "region": "us-east-1", "table_name": "users"},
+            })
+        );
+
+        let sql_span = span(
+            SpanKind::Client,
+            5,
+            vec![
+                semcov::PEER_SERVICE.string("users-db"),
+                semcov::DB_SYSTEM.string("postgresql"),
+                semcov::DB_CONNECTION_STRING.string("postgresql://app:secret@db:5432/users"),
+                semcov::DB_USER.string("app"),
+                semcov::DB_STATEMENT.string("SELECT * FROM users WHERE id = 42"),
+                KeyValue::new("db.rows", 1),
+            ],
+        );
+        let indexing = Indexing {
+            all: true,
+            keys: Vec::new(),
+        };
+
+        assert_eq!(
+            segment(&sql_span, "my-service", &indexing),
+            json!({
+                "name": "users-db",
+                "id": "000000000000000a",
+                "trace_id": "1-5f4f0d5a-0123456789abcdef01234567",
+                "parent_id": "0000000000000005",
+                "start_time": 1_600_000_000.5,
+                "end_time": 1_600_000_000.75,
+                "type": "subsegment",
+                "namespace": "remote",
+                "sql": {
+                    "url": "postgresql://db:5432/users",
+                    "database_type": "postgresql",
+                    "user": "app",
+                    "sanitized_query": "SELECT * FROM users WHERE id = ?",
+                },
+                "annotations": {"db_rows": 1},
+            })
+        );
+    }
+
+    #[test]
+    fn strips_connection_string_credentials() {
+        assert_eq!(
+            strip_credentials("mysql://app:secret@db:3306/users?ssl=true"),
+            "mysql://db:3306/users?ssl=true"
+        );
+        assert_eq!(
+            strip_credentials("redis://db:6379/0?user=a@b"),
+            "redis://db:6379/0?user=a@b"
+        );
+        assert_eq!(
+            strip_credentials("Server=db;Database=users;User Id=app;Password=secret;"),
+            "Server=db;Database=users;User Id=app;"
+        );
+    }
+
+    #[test]
+    fn sanitizes_query_literals() {
+        assert_eq!(
+            sanitize_query("SELECT * FROM t2 WHERE name = 'O''Brien' AND age > 30.5"),
+            "SELECT * FROM t2 WHERE name = ? AND age > ?"
+        );
+        assert_eq!(
+            sanitize_query("INSERT INTO logs (msg, level) VALUES ('it\\'s', -1)"),
+            "INSERT INTO logs (msg, level) VALUES (?, -?)"
+        );
+        assert_eq!(
+            sanitize_query("UPDATE users SET token = 0xFF WHERE id = $1"),
+            "UPDATE users SET token = ? WHERE id = $1"
+        );
+    }
+}
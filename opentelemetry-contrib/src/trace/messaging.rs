@@ -0,0 +1,405 @@
+//! # Messaging spans
+//!
+//! Builders for consumer spans following the OpenTelemetry [messaging semantic
+//! conventions].
+//!
+//! A `receive` span covers pulling messages from a broker, and a `process`
+//! span covers handling them. When a batch of messages is handled at once, the
+//! context propagated with each message is added as a link rather than a parent.
+//!
+//! [messaging semantic conventions]: https://github.com/open-telemetry/opentelemetry-specification/blob/master/specification/trace/semantic_conventions/messaging.md
+use opentelemetry::{
+    propagation::{links::extract_links, Extractor, TextMapPropagator},
+    trace::{Link, SpanBuilder, SpanContext, SpanKind, Tracer},
+    KeyValue,
+};
+use opentelemetry_semantic_conventions::trace::{
+    MESSAGING_CONVERSATION_ID, MESSAGING_DESTINATION, MESSAGING_DESTINATION_KIND,
+    MESSAGING_MESSAGE_ID, MESSAGING_MESSAGE_PAYLOAD_SIZE_BYTES, MESSAGING_OPERATION,
+    MESSAGING_PROTOCOL, MESSAGING_PROTOCOL_VERSION, MESSAGING_SYSTEM, MESSAGING_TEMP_DESTINATION,
+    MESSAGING_URL,
+};
+
+/// The default maximum number of links extracted from a batch of messages.
+const DEFAULT_MAX_LINKS: usize = 128;
+
+/// The kind of consumer operation a span represents.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MessagingOperation {
+    /// Receiving messages from a broker.
+    Receive,
+    /// Processing previously received messages.
+    Process,
+}
+
+impl MessagingOperation {
+    fn as_str(self) -> &'static str {
+        match self {
+            MessagingOperation::Receive => "receive",
+            MessagingOperation::Process => "process",
+        }
+    }
+}
+
+/// The kind of a message destination.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DestinationKind {
+    /// A queue, where each message is consumed by a single consumer.
+    Queue,
+    /// A topic, where each message may be consumed by several consumers.
+    Topic,
+}
+
+impl DestinationKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            DestinationKind::Queue => "queue",
+            DestinationKind::Topic => "topic",
+        }
+    }
+}
+
+/// Builds `receive` and `process` consumer spans with the messaging semantic
+/// convention attributes.
+///
+/// ## Example
+///
+/// ```
+/// use opentelemetry::{global, sdk::propagation::TraceContextPropagator};
+/// use opentelemetry_contrib::trace::messaging::{DestinationKind, MessagingSpanBuilder};
+/// use std::collections::HashMap;
+///
+/// let batch: Vec<HashMap<String, String>> = Vec::new();
+/// let tracer = global::tracer("consumer");
+///
+/// let span = MessagingSpanBuilder::process("kafka", "shop.orders")
+///     .with_destination_kind(DestinationKind::Topic)
+///     .with_extracted_links(&TraceContextPropagator::new(), &batch)
+///     .start(&tracer);
+/// ```
+#[derive(Clone, Debug)]
+pub struct MessagingSpanBuilder {
+    operation: MessagingOperation,
+    system: String,
+    destination: String,
+    destination_kind: Option<DestinationKind>,
+    temp_destination: bool,
+    protocol: Option<(String, Option<String>)>,
+    url: Option<String>,
+    message_id: Option<String>,
+    conversation_id: Option<String>,
+    payload_size: Option<i64>,
+    parent_context: Option<SpanContext>,
+    links: Vec<Link>,
+    max_links: usize,
+    attributes: Vec<KeyValue>,
+}
+
+impl MessagingSpanBuilder {
+    /// Create a builder for a span receiving messages from `destination` of the
+    /// messaging `system`, e.g. `kafka` or `rabbitmq`.
+    pub fn receive<S, D>(system: S, destination: D) -> Self
+    where
+        S: Into<String>,
+        D: Into<String>,
+    {
+        MessagingSpanBuilder::new(MessagingOperation::Receive, system, destination)
+    }
+
+    /// Create a builder for a span processing messages from `destination` of the
+    /// messaging `system`, e.g. `kafka` or `rabbitmq`.
+    pub fn process<S, D>(system: S, destination: D) -> Self
+    where
+        S: Into<String>,
+        D: Into<String>,
+    {
+        MessagingSpanBuilder::new(MessagingOperation::Process, system, destination)
+    }
+
+    /// Create a builder for a span of the given consumer `operation`.
+    pub fn new<S, D>(operation: MessagingOperation, system: S, destination: D) -> Self
+    where
+        S: Into<String>,
+        D: Into<String>,
+    {
+        MessagingSpanBuilder {
+            operation,
+            system: system.into(),
+            destination: destination.into(),
+            destination_kind: None,
+            temp_destination: false,
+            protocol: None,
+            url: None,
+            message_id: None,
+            conversation_id: None,
+            payload_size: None,
+            parent_context: None,
+            links: Vec::new(),
+            max_links: DEFAULT_MAX_LINKS,
+            attributes: Vec::new(),
+        }
+    }
+
+    /// Set the kind of the destination.
+    pub fn with_destination_kind(self, destination_kind: DestinationKind) -> Self {
+        MessagingSpanBuilder {
+            destination_kind: Some(destination_kind),
+            ..self
+        }
+    }
+
+    /// Mark the destination as temporary. Temporary destinations are named
+    /// `(temporary)` in the span name, as their names are usually random.
+    pub fn with_temp_destination(self, temp_destination: bool) -> Self {
+        MessagingSpanBuilder {
+            temp_destination,
+            ..self
+        }
+    }
+
+    /// Set the name and optional version of the transport protocol, e.g. `AMQP`.
+    pub fn with_protocol<N: Into<String>>(self, name: N, version: Option<String>) -> Self {
+        MessagingSpanBuilder {
+            protocol: Some((name.into(), version)),
+            ..self
+        }
+    }
+
+    /// Set the connection string of the broker.
+    pub fn with_url<T: Into<String>>(self, url: T) -> Self {
+        MessagingSpanBuilder {
+            url: Some(url.into()),
+            ..self
+        }
+    }
+
+    /// Set the identifier of the message this span handles.
+    pub fn with_message_id<T: Into<String>>(self, message_id: T) -> Self {
+        MessagingSpanBuilder {
+            message_id: Some(message_id.into()),
+            ..self
+        }
+    }
+
+    /// Set the conversation, or correlation, id of the message this span handles.
+    pub fn with_conversation_id<T: Into<String>>(self, conversation_id: T) -> Self {
+        MessagingSpanBuilder {
+            conversation_id: Some(conversation_id.into()),
+            ..self
+        }
+    }
+
+    /// Set the uncompressed size of the message payload in bytes.
+    pub fn with_payload_size(self, payload_size: i64) -> Self {
+        MessagingSpanBuilder {
+            payload_size: Some(payload_size),
+            ..self
+        }
+    }
+
+    /// Set the parent of the span, usually the context propagated with the
+    /// single message this span handles.
+    pub fn with_parent(self, parent_context: SpanContext) -> Self {
+        MessagingSpanBuilder {
+            parent_context: Some(parent_context),
+            ..self
+        }
+    }
+
+    /// Set the maximum number of links added by [`with_extracted_links`].
+    /// Defaults to `128`.
+    ///
+    /// [`with_extracted_links`]: #method.with_extracted_links
+    pub fn with_max_links(self, max_links: usize) -> Self {
+        MessagingSpanBuilder { max_links, ..self }
+    }
+
+    /// Add links to the span.
+    pub fn with_links(mut self, mut links: Vec<Link>) -> Self {
+        self.links.append(&mut links);
+        self
+    }
+
+    /// Add a link to the context propagated with each message of a batch,
+    /// ignoring duplicates and messages without a valid context.
+    pub fn with_extracted_links<'a, P, I, E>(self, propagator: &P, extractors: I) -> Self
+    where
+        P: TextMapPropagator + ?Sized,
+        I: IntoIterator<Item = &'a E>,
+        E: Extractor + 'a,
+    {
+        let max_links = self.max_links.saturating_sub(self.links.len());
+        let links = extract_links(propagator, extractors, max_links);
+        self.with_links(links)
+    }
+
+    /// Add extra attributes to the span.
+    pub fn with_attributes(mut self, mut attributes: Vec<KeyValue>) -> Self {
+        self.attributes.append(&mut attributes);
+        self
+    }
+
+    /// The span name, `{destination} {operation}`.
+    fn span_name(&self) -> String {
+        let destination = if self.temp_destination {
+            "(temporary)"
+        } else {
+            self.destination.as_str()
+        };
+        format!("{} {}", destination, self.operation.as_str())
+    }
+
+    /// Convert into a consumer `SpanBuilder`.
+    pub fn into_span_builder(self) -> SpanBuilder {
+        let mut attributes = vec![
+            MESSAGING_SYSTEM.string(self.system.clone()),
+            MESSAGING_DESTINATION.string(self.destination.clone()),
+            MESSAGING_OPERATION.string(self.operation.as_str()),
+        ];
+        if let Some(destination_kind) = self.destination_kind {
+            attributes.push(MESSAGING_DESTINATION_KIND.string(destination_kind.as_str()));
+        }
+        if self.temp_destination {
+            attributes.push(MESSAGING_TEMP_DESTINATION.bool(true));
+        }
+        if let Some((name, version)) = &self.protocol {
+            attributes.push(MESSAGING_PROTOCOL.string(name.clone()));
+            if let Some(version) = version {
+                attributes.push(MESSAGING_PROTOCOL_VERSION.string(version.clone()));
+            }
+        }
+        if let Some(url) = &self.url {
+            attributes.push(MESSAGING_URL.string(url.clone()));
+        }
+        if let Some(message_id) = &self.message_id {
+            attributes.push(MESSAGING_MESSAGE_ID.string(message_id.clone()));
+        }
+        if let Some(conversation_id) = &self.conversation_id {
+            attributes.push(MESSAGING_CONVERSATION_ID.string(conversation_id.clone()));
+        }
+        if let Some(payload_size) = self.payload_size {
+            attributes.push(MESSAGING_MESSAGE_PAYLOAD_SIZE_BYTES.i64(payload_size));
+        }
+
+        let name = self.span_name();
+        attributes.extend(self.attributes);
+        let mut builder = SpanBuilder::from_name(name)
+            .with_kind(SpanKind::Consumer)
+            .with_attributes(attributes)
+            .with_links(self.links);
+        builder.parent_context = self.parent_context;
+        builder
+    }
+
+    /// Start the span with the given tracer.
+    pub fn start<T: Tracer>(self, tracer: &T) -> T::Span {
+        tracer.build(self.into_span_builder())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry::{
+        sdk::propagation::TraceContextPropagator,
+        trace::{SpanId, TraceId},
+        Value,
+    };
+    use std::collections::HashMap;
+
+    fn attribute(builder: &SpanBuilder, key: &str) -> Option<Value> {
+        builder
+            .attributes
+            .as_ref()
+            .and_then(|attributes| attributes.iter().find(|kv| kv.key.as_str() == key))
+            .map(|kv| kv.value.clone())
+    }
+
+    #[test]
+    fn process_batch_span() {
+        let batch = (1..=3)
+            .map(|i| {
+                let mut carrier = HashMap::new();
+                carrier.insert(
+                    "traceparent".to_string(),
+                    format!("00-4bf92f3577b34da6a3ce929d0e0e4736-{:016x}-01", i),
+                );
+                carrier
+            })
+            .collect::<Vec<_>>();
+
+        let builder = MessagingSpanBuilder::process("kafka", "shop.orders")
+            .with_destination_kind(DestinationKind::Topic)
+            .with_payload_size(42)
+            .with_max_links(2)
+            .with_extracted_links(&TraceContextPropagator::new(), &batch)
+            .with_attributes(vec![KeyValue::new("custom", "value")])
+            .into_span_builder();
+
+        assert_eq!(builder.name, "shop.orders process");
+        assert_eq!(builder.span_kind, Some(SpanKind::Consumer));
+        assert_eq!(
+            attribute(&builder, "messaging.system"),
+            Some("kafka".into())
+        );
+        assert_eq!(
+            attribute(&builder, "messaging.destination"),
+            Some("shop.orders".into())
+        );
+        assert_eq!(
+            attribute(&builder, "messaging.destination_kind"),
+            Some("topic".into())
+        );
+        assert_eq!(
+            attribute(&builder, "messaging.operation"),
+            Some("process".into())
+        );
+        assert_eq!(
+            attribute(&builder, "messaging.message_payload_size_bytes"),
+            Some(42.into())
+        );
+        assert_eq!(attribute(&builder, "custom"), Some("value".into()));
+        assert_eq!(attribute(&builder, "messaging.temp_destination"), None);
+        assert_eq!(builder.links.map(|links| links.len()), Some(2));
+    }
+
+    #[test]
+    fn receive_span_with_parent() {
+        let parent = SpanContext::new(
+            TraceId::from_u128(1),
+            SpanId::from_u64(1),
+            1,
+            true,
+            Default::default(),
+        );
+        let builder = MessagingSpanBuilder::receive("rabbitmq", "amq.gen-12345")
+            .with_temp_destination(true)
+            .with_protocol("AMQP", Some("0.9.1".to_string()))
+            .with_message_id("message-1")
+            .with_parent(parent.clone())
+            .into_span_builder();
+
+        assert_eq!(builder.name, "(temporary) receive");
+        assert_eq!(builder.parent_context, Some(parent));
+        assert_eq!(
+            attribute(&builder, "messaging.operation"),
+            Some("receive".into())
+        );
+        assert_eq!(
+            attribute(&builder, "messaging.temp_destination"),
+            Some(true.into())
+        );
+        assert_eq!(
+            attribute(&builder, "messaging.protocol"),
+            Some("AMQP".into())
+        );
+        assert_eq!(
+            attribute(&builder, "messaging.protocol_version"),
+            Some("0.9.1".into())
+        );
+        assert_eq!(
+            attribute(&builder, "messaging.message_id"),
+            Some("message-1".into())
+        );
+    }
+}
@@ -2,4 +2,7 @@
 //!
 
 pub mod exporter;
+#[cfg(feature = "messaging")]
+#[cfg_attr(docsrs, doc(cfg(feature = "messaging")))]
+pub mod messaging;
 pub mod propagator;
@@ -5,8 +5,65 @@
 //!
 //! `BinaryFormat` MUST expose the APIs that serializes values into bytes,
 //! and deserializes values from bytes.
-use opentelemetry::trace::{SpanContext, SpanId, TraceId, TraceState};
+//!
+//! `BinaryMapPropagator` is the binary counterpart of `TextMapPropagator`,
+//! injecting and extracting a whole `Context` into and from binary carriers.
+use crate::trace::propagator::binary::carrier::{BinaryExtractor, BinaryInjector};
+use opentelemetry::{
+    baggage::BaggageExt,
+    propagation::text_map_propagator::FieldIter,
+    trace::{SpanContext, SpanId, TraceContextExt, TraceId, TraceState, TRACE_FLAG_SAMPLED},
+    Context, KeyValue,
+};
 use std::convert::TryInto;
+use std::fmt::Debug;
+use std::str::FromStr;
+
+const GRPC_TRACE_BIN_HEADER: &str = "grpc-trace-bin";
+const GRPC_TAGS_BIN_HEADER: &str = "grpc-tags-bin";
+
+/// Field id of the optional tracestate field following the trace flags.
+const TRACE_STATE_FIELD_ID: u8 = 3;
+/// Field id of a tag in the `grpc-tags-bin` format.
+const TAG_FIELD_ID: u8 = 0;
+/// Maximum encoded size of the `grpc-tags-bin` value.
+const MAX_TAGS_LEN: usize = 8192;
+
+lazy_static::lazy_static! {
+    static ref BINARY_HEADER_FIELDS: [String; 2] = [
+        GRPC_TRACE_BIN_HEADER.to_string(),
+        GRPC_TAGS_BIN_HEADER.to_string(),
+    ];
+}
+
+/// Methods to inject and extract a value as bytes into injectors and extractors
+/// that travel in-band across process boundaries.
+pub trait BinaryMapPropagator: Debug {
+    /// Properly encodes the values of the current `Context` and injects them
+    /// into the `BinaryInjector`.
+    fn inject(&self, injector: &mut dyn BinaryInjector) {
+        self.inject_context(&Context::current(), injector)
+    }
+
+    /// Properly encodes the values of the `Context` and injects them into the
+    /// `BinaryInjector`.
+    fn inject_context(&self, cx: &Context, injector: &mut dyn BinaryInjector);
+
+    /// Retrieves encoded data using the provided `BinaryExtractor`. If no data
+    /// for this format was retrieved OR if the retrieved data is invalid, then
+    /// the current `Context` is returned.
+    fn extract(&self, extractor: &dyn BinaryExtractor) -> Context {
+        self.extract_with_context(&Context::current(), extractor)
+    }
+
+    /// Retrieves encoded data using the provided `BinaryExtractor`. If no data
+    /// for this format was retrieved OR if the retrieved data is invalid, then
+    /// the given `Context` is returned.
+    fn extract_with_context(&self, cx: &Context, extractor: &dyn BinaryExtractor) -> Context;
+
+    /// Returns iter of fields used by the `BinaryMapPropagator`.
+    fn fields(&self) -> FieldIter<'_>;
+}
 
 /// Used to serialize and deserialize `SpanContext`s to and from a binary
 /// representation.
@@ -19,6 +76,42 @@ pub trait BinaryFormat {
 }
 
 /// Extracts and injects `SpanContext`s from byte arrays.
+///
+/// As a `BinaryMapPropagator` the span context and its tracestate are
+/// propagated in the `grpc-trace-bin` field and baggage in the
+/// `grpc-tags-bin` field.
+///
+/// ## Example
+///
+/// ```
+/// use opentelemetry::trace::{SpanContext, SpanId, TraceContextExt, TraceId, TraceState};
+/// use opentelemetry_contrib::trace::propagator::binary::{
+///     BinaryFormat, BinaryMapPropagator, BinaryPropagator,
+/// };
+/// use std::collections::HashMap;
+///
+/// let propagator = BinaryPropagator::new();
+/// let span_context = SpanContext::new(
+///     TraceId::from_u128(1),
+///     SpanId::from_u64(1),
+///     1,
+///     true,
+///     TraceState::default(),
+/// );
+///
+/// // Extract the remote context from incoming metadata
+/// let mut incoming: HashMap<String, Vec<u8>> = HashMap::new();
+/// incoming.insert(
+///     "grpc-trace-bin".to_string(),
+///     propagator.to_bytes(&span_context).to_vec(),
+/// );
+/// let cx = propagator.extract(&incoming);
+/// assert_eq!(cx.remote_span_context(), Some(&span_context));
+///
+/// // Inject the active span into outgoing metadata
+/// let mut outgoing: HashMap<String, Vec<u8>> = HashMap::new();
+/// propagator.inject(&mut outgoing);
+/// ```
 #[derive(Debug, Default)]
 pub struct BinaryPropagator {}
 
@@ -40,7 +133,8 @@ impl BinaryFormat for BinaryPropagator {
         res[18] = 1;
         res[19..27].copy_from_slice(&context.span_id().to_u64().to_be_bytes());
         res[27] = 2;
-        res[28] = context.trace_flags();
+        // The options byte only carries the sampled bit.
+        res[28] = context.trace_flags() & TRACE_FLAG_SAMPLED;
 
         res
     }
@@ -65,7 +159,17 @@ impl BinaryFormat for BinaryPropagator {
             b = &b[9..];
         }
         if b.len() >= 2 && b[0] == 2 {
-            trace_flags = b[1]
+            trace_flags = b[1];
+            b = &b[2..];
+        }
+        let mut trace_state = TraceState::default();
+        if !b.is_empty() && b[0] == TRACE_STATE_FIELD_ID {
+            if let Some((value, _)) = read_length_prefixed(&b[1..]) {
+                trace_state = std::str::from_utf8(value)
+                    .ok()
+                    .and_then(|header| TraceState::from_str(header).ok())
+                    .unwrap_or_default();
+            }
         }
 
         let span_context = SpanContext::new(
@@ -73,8 +177,7 @@ impl BinaryFormat for BinaryPropagator {
             SpanId::from_u64(span_id),
             trace_flags,
             true,
-            // TODO traceparent and tracestate should both begin with a 0 byte, figure out how to differentiate
-            TraceState::default(),
+            trace_state,
         );
 
         if span_context.is_valid() {
@@ -85,10 +188,138 @@ impl BinaryFormat for BinaryPropagator {
     }
 }
 
+impl BinaryPropagator {
+    fn encode_span_context(&self, span_context: &SpanContext) -> Vec<u8> {
+        let mut bytes = self.to_bytes(span_context).to_vec();
+        let trace_state = span_context.trace_state().header();
+        if !trace_state.is_empty() {
+            bytes.push(TRACE_STATE_FIELD_ID);
+            write_length_prefixed(&mut bytes, trace_state.as_bytes());
+        }
+
+        bytes
+    }
+}
+
+impl BinaryMapPropagator for BinaryPropagator {
+    fn inject_context(&self, cx: &Context, injector: &mut dyn BinaryInjector) {
+        let span_context = cx.span().span_context();
+        if span_context.is_valid() {
+            injector.set(
+                GRPC_TRACE_BIN_HEADER,
+                self.encode_span_context(span_context),
+            );
+        }
+
+        if let Some(tags) = encode_tags(cx) {
+            injector.set(GRPC_TAGS_BIN_HEADER, tags);
+        }
+    }
+
+    fn extract_with_context(&self, cx: &Context, extractor: &dyn BinaryExtractor) -> Context {
+        let span_context = extractor
+            .get(GRPC_TRACE_BIN_HEADER)
+            .map(|bytes| self.from_bytes(bytes.into_owned()))
+            .unwrap_or_else(SpanContext::empty_context);
+        let cx = cx.with_remote_span_context(span_context);
+
+        match extractor
+            .get(GRPC_TAGS_BIN_HEADER)
+            .and_then(|bytes| decode_tags(&bytes))
+        {
+            Some(tags) if !tags.is_empty() => cx.with_baggage(tags),
+            _ => cx,
+        }
+    }
+
+    fn fields(&self) -> FieldIter<'_> {
+        FieldIter::new(BINARY_HEADER_FIELDS.as_ref())
+    }
+}
+
+/// Encodes the context baggage in the `grpc-tags-bin` format, a version byte
+/// followed by `(field id, key, value)` tags. Tags that would exceed the
+/// maximum encoded size are dropped.
+fn encode_tags(cx: &Context) -> Option<Vec<u8>> {
+    let baggage = cx.baggage();
+    if baggage.is_empty() {
+        return None;
+    }
+
+    let mut bytes = vec![0];
+    for (key, (value, _metadata)) in baggage {
+        let mut tag = vec![TAG_FIELD_ID];
+        write_length_prefixed(&mut tag, key.as_str().as_bytes());
+        write_length_prefixed(&mut tag, value.to_string().as_bytes());
+        if bytes.len() + tag.len() <= MAX_TAGS_LEN {
+            bytes.extend(tag);
+        }
+    }
+
+    Some(bytes)
+}
+
+fn decode_tags(bytes: &[u8]) -> Option<Vec<KeyValue>> {
+    if bytes.is_empty() || bytes[0] != 0 || bytes.len() > MAX_TAGS_LEN {
+        return None;
+    }
+
+    let mut tags = Vec::new();
+    let mut b = &bytes[1..];
+    while !b.is_empty() {
+        if b[0] != TAG_FIELD_ID {
+            // Unknown fields end the tag list, as their length is unknown.
+            break;
+        }
+        let (key, rest) = read_length_prefixed(&b[1..])?;
+        let (value, rest) = read_length_prefixed(rest)?;
+        let key = std::str::from_utf8(key).ok()?;
+        let value = std::str::from_utf8(value).ok()?;
+        tags.push(KeyValue::new(key.to_string(), value.to_string()));
+        b = rest;
+    }
+
+    Some(tags)
+}
+
+/// Writes a varint length followed by the value.
+fn write_length_prefixed(bytes: &mut Vec<u8>, value: &[u8]) {
+    let mut len = value.len();
+    while len >= 0x80 {
+        bytes.push((len as u8 & 0x7f) | 0x80);
+        len >>= 7;
+    }
+    bytes.push(len as u8);
+    bytes.extend_from_slice(value);
+}
+
+/// Reads a varint length prefixed value, returning the value and the
+/// remaining bytes.
+fn read_length_prefixed(bytes: &[u8]) -> Option<(&[u8], &[u8])> {
+    let mut len = 0usize;
+    for (i, byte) in bytes.iter().enumerate().take(4) {
+        len |= ((byte & 0x7f) as usize) << (7 * i);
+        if byte & 0x80 == 0 {
+            let rest = &bytes[i + 1..];
+            if rest.len() < len {
+                return None;
+            }
+            return Some(rest.split_at(len));
+        }
+    }
+
+    None
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use opentelemetry::trace::TraceState;
+    use opentelemetry::{
+        testing::trace::TestSpan,
+        trace::{TraceState, TRACE_FLAG_RANDOM},
+        Value,
+    };
+    use std::collections::HashMap;
 
     #[rustfmt::skip]
     fn to_bytes_data() -> Vec<(SpanContext, [u8; 29])> {
@@ -109,6 +340,14 @@ mod tests {
                 0x01, 0x00, 0xf0, 0x67, 0xaa, 0x0b, 0xa9, 0x02, 0xb7,
                 0x02, 0x00,
             ]),
+            // Root context with the random flag
+            (SpanContext::new(
+               TraceId::from_u128(0x4bf9_2f35_77b3_4da6_a3ce_929d_0e0e_4736),
+               SpanId::from_u64(0x00f0_67aa_0ba9_02b7), TRACE_FLAG_SAMPLED | TRACE_FLAG_RANDOM, false, TraceState::default()), [
+                0x00, 0x00, 0x4b, 0xf9, 0x2f, 0x35, 0x77, 0xb3, 0x4d, 0xa6, 0xa3, 0xce, 0x92, 0x9d, 0x0e, 0x0e, 0x47, 0x36,
+                0x01, 0x00, 0xf0, 0x67, 0xaa, 0x0b, 0xa9, 0x02, 0xb7,
+                0x02, 0x01,
+            ]),
             // Invalid context
             (SpanContext::empty_context(), [0u8; 29]),
         ]
@@ -175,4 +414,58 @@ mod tests {
             assert_eq!(propagator.from_bytes(data), context)
         }
     }
+
+    #[test]
+    fn context_round_trip() {
+        let propagator = BinaryPropagator::new();
+        let span_context = SpanContext::new(
+            TraceId::from_u128(0x4bf9_2f35_77b3_4da6_a3ce_929d_0e0e_4736),
+            SpanId::from_u64(0x00f0_67aa_0ba9_02b7),
+            1,
+            true,
+            TraceState::from_str("foo=bar,congo=t61rcWkgMzE").unwrap(),
+        );
+        let cx = Context::new()
+            .with_span(TestSpan(span_context.clone()))
+            .with_baggage(vec![
+                KeyValue::new("user", "alice"),
+                KeyValue::new("tenant", "42"),
+            ]);
+
+        let mut carrier: HashMap<String, Vec<u8>> = HashMap::new();
+        propagator.inject_context(&cx, &mut carrier);
+        assert_eq!(
+            &carrier[GRPC_TRACE_BIN_HEADER][..29],
+            &propagator.to_bytes(&span_context)[..]
+        );
+
+        let extracted = propagator.extract_with_context(&Context::new(), &carrier);
+        assert_eq!(extracted.remote_span_context(), Some(&span_context));
+        assert_eq!(extracted.baggage().get("user"), Some(&Value::from("alice")));
+        assert_eq!(extracted.baggage().get("tenant"), Some(&Value::from("42")));
+    }
+
+    #[test]
+    fn extract_invalid() {
+        let propagator = BinaryPropagator::new();
+        let mut carrier: HashMap<String, Vec<u8>> = HashMap::new();
+        carrier.insert(GRPC_TRACE_BIN_HEADER.to_string(), vec![0, 1, 2]);
+        carrier.insert(GRPC_TAGS_BIN_HEADER.to_string(), vec![0, 0, 10, b'a']);
+
+        let extracted = propagator.extract_with_context(&Context::new(), &carrier);
+        assert_eq!(
+            extracted.remote_span_context(),
+            Some(&SpanContext::empty_context())
+        );
+        assert!(extracted.baggage().is_empty());
+    }
+
+    #[test]
+    fn varint_lengths() {
+        let value = vec![b'x'; 300];
+        let mut bytes = Vec::new();
+        write_length_prefixed(&mut bytes, &value);
+        assert_eq!(&bytes[..2], &[0xac, 0x02]);
+        assert_eq!(read_length_prefixed(&bytes), Some((&value[..], &[][..])));
+    }
 }
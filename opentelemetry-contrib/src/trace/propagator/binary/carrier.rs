@@ -0,0 +1,113 @@
+//! # Binary Carriers
+//!
+//! `BinaryInjector` and `BinaryExtractor` are the byte valued counterparts of
+//! the text map `Injector` and `Extractor`, used by `BinaryMapPropagator`s to
+//! write to and read from transports that carry binary metadata.
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+/// Injector provides an interface for adding binary fields to an underlying
+/// struct like `tonic::metadata::MetadataMap`.
+pub trait BinaryInjector {
+    /// Add a key and value to the underlying data.
+    fn set(&mut self, key: &str, value: Vec<u8>);
+}
+
+/// Extractor provides an interface for removing binary fields from an
+/// underlying struct like `tonic::metadata::MetadataMap`.
+pub trait BinaryExtractor {
+    /// Get a value from a key from the underlying data.
+    fn get(&self, key: &str) -> Option<Cow<'_, [u8]>>;
+
+    /// Collect all the keys from the underlying data.
+    fn keys(&self) -> Vec<&str>;
+}
+
+impl<S: std::hash::BuildHasher> BinaryInjector for HashMap<String, Vec<u8>, S> {
+    /// Set a key and value in the HashMap.
+    fn set(&mut self, key: &str, value: Vec<u8>) {
+        self.insert(key.to_lowercase(), value);
+    }
+}
+
+impl<S: std::hash::BuildHasher> BinaryExtractor for HashMap<String, Vec<u8>, S> {
+    /// Get a value for a key from the HashMap.
+    fn get(&self, key: &str) -> Option<Cow<'_, [u8]>> {
+        self.get(&key.to_lowercase())
+            .map(|value| Cow::Borrowed(value.as_slice()))
+    }
+
+    /// Collect all the keys from the HashMap.
+    fn keys(&self) -> Vec<&str> {
+        self.keys().map(|key| key.as_str()).collect()
+    }
+}
+
+#[cfg(feature = "tonic")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tonic")))]
+impl BinaryInjector for tonic::metadata::MetadataMap {
+    /// Set a binary value in the MetadataMap. Does nothing if the key is
+    /// invalid or does not end with `-bin`.
+    fn set(&mut self, key: &str, value: Vec<u8>) {
+        if !key.ends_with("-bin") {
+            return;
+        }
+        if let Ok(key) = tonic::metadata::BinaryMetadataKey::from_bytes(key.as_bytes()) {
+            self.insert_bin(key, tonic::metadata::MetadataValue::from_bytes(&value));
+        }
+    }
+}
+
+#[cfg(feature = "tonic")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tonic")))]
+impl BinaryExtractor for tonic::metadata::MetadataMap {
+    /// Get a decoded binary value from the MetadataMap. Returns `None` if the
+    /// key is not a `-bin` key or the value is not valid base64.
+    fn get(&self, key: &str) -> Option<Cow<'_, [u8]>> {
+        self.get_bin(key)
+            .and_then(|value| value.to_bytes().ok())
+            .map(|bytes| Cow::Owned(bytes.to_vec()))
+    }
+
+    /// Collect the binary keys from the MetadataMap.
+    fn keys(&self) -> Vec<&str> {
+        self.keys()
+            .filter_map(|key| match key {
+                tonic::metadata::KeyRef::Binary(key) => Some(key.as_str()),
+                tonic::metadata::KeyRef::Ascii(_) => None,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hash_map_round_trip() {
+        let mut carrier: HashMap<String, Vec<u8>> = HashMap::new();
+        BinaryInjector::set(&mut carrier, "Grpc-Trace-Bin", vec![0, 1, 2]);
+
+        assert_eq!(
+            BinaryExtractor::get(&carrier, "grpc-trace-bin"),
+            Some(Cow::Borrowed(&[0u8, 1, 2][..]))
+        );
+        assert_eq!(BinaryExtractor::keys(&carrier), vec!["grpc-trace-bin"]);
+    }
+
+    #[cfg(feature = "tonic")]
+    #[test]
+    fn metadata_map_round_trip() {
+        let mut carrier = tonic::metadata::MetadataMap::new();
+        BinaryInjector::set(&mut carrier, "grpc-trace-bin", vec![0, 1, 2]);
+        BinaryInjector::set(&mut carrier, "not-binary", vec![0, 1, 2]);
+        carrier.insert("ascii", "value".parse().unwrap());
+
+        assert_eq!(
+            BinaryExtractor::get(&carrier, "grpc-trace-bin"),
+            Some(Cow::Owned(vec![0u8, 1, 2]))
+        );
+        assert_eq!(BinaryExtractor::keys(&carrier), vec!["grpc-trace-bin"]);
+    }
+}
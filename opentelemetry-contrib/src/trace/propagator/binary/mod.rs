@@ -32,11 +32,20 @@
 //!
 //! Returns a value deserialized from bytes.
 //!
+//! ## Binary Map Propagator
+//!
+//! `BinaryMapPropagator` injects and extracts a whole `Context` into and from
+//! carriers of binary values, like gRPC `-bin` metadata. With the `tonic`
+//! feature enabled `tonic::metadata::MetadataMap` can be used as a carrier, so
+//! services can propagate context in `grpc-trace-bin` instead of text headers.
+//!
 
 #[cfg(feature = "base64")]
 mod base64_format;
 mod binary_propagator;
+mod carrier;
 
 #[cfg(feature = "base64")]
 pub use base64_format::Base64Format;
-pub use binary_propagator::{BinaryFormat, BinaryPropagator};
+pub use binary_propagator::{BinaryFormat, BinaryMapPropagator, BinaryPropagator};
+pub use carrier::{BinaryExtractor, BinaryInjector};
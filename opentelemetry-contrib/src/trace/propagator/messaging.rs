@@ -0,0 +1,444 @@
+//! # Messaging Carriers
+//!
+//! `Injector` and `Extractor` implementations for the headers of messaging
+//! clients. The client types are wrapped as the propagation traits can not be
+//! implemented for them outside of `opentelemetry`.
+//!
+//! Header names are compared case insensitively, values that are not valid
+//! UTF-8 are skipped when extracting, and `get` returns the first value of a
+//! multi-valued header.
+//!
+//! ## Example
+//!
+//! ```ignore
+//! use opentelemetry::{global, Context};
+//! use opentelemetry_contrib::trace::propagator::messaging::KafkaHeadersInjector;
+//! use rdkafka::message::OwnedHeaders;
+//!
+//! let mut headers = OwnedHeaders::new();
+//! global::get_text_map_propagator(|propagator| {
+//!     propagator.inject_context(&Context::current(), &mut KafkaHeadersInjector(&mut headers))
+//! });
+//! ```
+
+#[cfg(feature = "rdkafka")]
+#[cfg_attr(docsrs, doc(cfg(feature = "rdkafka")))]
+mod kafka {
+    use opentelemetry::propagation::{distinct_keys, first_utf8, Extractor, Injector};
+    use rdkafka::message::{Headers, OwnedHeaders};
+
+    /// Injects into [rdkafka] message headers.
+    ///
+    /// [rdkafka]: https://crates.io/crates/rdkafka
+    #[derive(Debug)]
+    pub struct KafkaHeadersInjector<'a>(pub &'a mut OwnedHeaders);
+
+    impl Injector for KafkaHeadersInjector<'_> {
+        /// Replace all values of a header with a single value.
+        fn set(&mut self, key: &str, value: String) {
+            let headers = std::mem::replace(self.0, OwnedHeaders::new());
+            let mut replaced = OwnedHeaders::new_with_capacity(headers.count() + 1);
+            for idx in 0..headers.count() {
+                if let Some((name, value)) = Headers::get(&headers, idx) {
+                    if !name.eq_ignore_ascii_case(key) {
+                        replaced = replaced.add(name, value);
+                    }
+                }
+            }
+            *self.0 = replaced.add(key, value.as_str());
+        }
+    }
+
+    /// Extracts from [rdkafka] owned or borrowed message headers.
+    ///
+    /// [rdkafka]: https://crates.io/crates/rdkafka
+    #[derive(Debug)]
+    pub struct KafkaHeadersExtractor<'a, H: ?Sized>(pub &'a H);
+
+    impl<H: Headers + ?Sized> Extractor for KafkaHeadersExtractor<'_, H> {
+        /// Get the first UTF-8 value of a header.
+        fn get(&self, key: &str) -> Option<&str> {
+            first_utf8(
+                (0..self.0.count())
+                    .filter_map(|idx| self.0.get(idx))
+                    .filter(|(name, _)| name.eq_ignore_ascii_case(key))
+                    .map(|(_, value)| value),
+            )
+        }
+
+        /// Collect all the distinct header names.
+        fn keys(&self) -> Vec<&str> {
+            distinct_keys(
+                (0..self.0.count())
+                    .filter_map(|idx| self.0.get(idx))
+                    .map(|(name, _)| name),
+            )
+        }
+    }
+}
+
+#[cfg(feature = "lapin")]
+#[cfg_attr(docsrs, doc(cfg(feature = "lapin")))]
+mod amqp {
+    use lapin::types::{AMQPValue, FieldTable, LongString, ShortString};
+    use opentelemetry::propagation::{Extractor, Injector};
+
+    /// Injects into [lapin] AMQP header tables.
+    ///
+    /// [lapin]: https://crates.io/crates/lapin
+    #[derive(Debug)]
+    pub struct AmqpHeadersInjector<'a>(pub &'a mut FieldTable);
+
+    impl Injector for AmqpHeadersInjector<'_> {
+        /// Replace all values of a header with a long string value.
+        fn set(&mut self, key: &str, value: String) {
+            if self
+                .0
+                .inner()
+                .keys()
+                .any(|name| name.as_str() != key && name.as_str().eq_ignore_ascii_case(key))
+            {
+                *self.0 = self
+                    .0
+                    .inner()
+                    .iter()
+                    .filter(|(name, _)| !name.as_str().eq_ignore_ascii_case(key))
+                    .map(|(name, value)| (name.clone(), value.clone()))
+                    .collect::<std::collections::BTreeMap<_, _>>()
+                    .into();
+            }
+            self.0.insert(
+                ShortString::from(key),
+                AMQPValue::LongString(LongString::from(value)),
+            );
+        }
+    }
+
+    /// Extracts from [lapin] AMQP header tables.
+    ///
+    /// [lapin]: https://crates.io/crates/lapin
+    #[derive(Debug)]
+    pub struct AmqpHeadersExtractor<'a>(pub &'a FieldTable);
+
+    impl Extractor for AmqpHeadersExtractor<'_> {
+        /// Get the first UTF-8 string value of a header, looking into arrays
+        /// for multi-valued headers.
+        fn get(&self, key: &str) -> Option<&str> {
+            self.0.inner().get(key).and_then(as_str).or_else(|| {
+                self.0
+                    .inner()
+                    .iter()
+                    .filter(|(name, _)| name.as_str().eq_ignore_ascii_case(key))
+                    .find_map(|(_, value)| as_str(value))
+            })
+        }
+
+        /// Collect all the header names.
+        fn keys(&self) -> Vec<&str> {
+            self.0.inner().keys().map(|name| name.as_str()).collect()
+        }
+    }
+
+    fn as_str(value: &AMQPValue) -> Option<&str> {
+        match value {
+            AMQPValue::LongString(value) => std::str::from_utf8(value.as_bytes()).ok(),
+            AMQPValue::ShortString(value) => Some(value.as_str()),
+            AMQPValue::ByteArray(value) => std::str::from_utf8(value.as_slice()).ok(),
+            AMQPValue::FieldArray(values) => values.as_slice().iter().find_map(as_str),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "async-nats")]
+#[cfg_attr(docsrs, doc(cfg(feature = "async-nats")))]
+mod nats {
+    use async_nats::{HeaderMap, HeaderName, HeaderValue};
+    use opentelemetry::propagation::{Extractor, Injector};
+    use std::str::FromStr;
+
+    /// Injects into [async-nats] message headers.
+    ///
+    /// [async-nats]: https://crates.io/crates/async-nats
+    #[derive(Debug)]
+    pub struct NatsHeadersInjector<'a>(pub &'a mut HeaderMap);
+
+    impl Injector for NatsHeadersInjector<'_> {
+        /// Replace all values of a header with a single value. Does nothing if
+        /// the key is not a valid header name or the value contains a line break.
+        fn set(&mut self, key: &str, value: String) {
+            let name = match HeaderName::from_str(key) {
+                Ok(name) => name,
+                Err(_) => return,
+            };
+            if value.contains(&['\r', '\n'][..]) {
+                return;
+            }
+
+            if self.0.iter().any(|(existing, _)| {
+                let existing: &str = existing.as_ref();
+                existing != key && existing.eq_ignore_ascii_case(key)
+            }) {
+                let mut replaced = HeaderMap::new();
+                for (existing, values) in self.0.iter() {
+                    if !AsRef::<str>::as_ref(existing).eq_ignore_ascii_case(key) {
+                        for value in values {
+                            replaced.append(existing.clone(), value.clone());
+                        }
+                    }
+                }
+                *self.0 = replaced;
+            }
+            self.0.insert(name, HeaderValue::from(value.as_str()));
+        }
+    }
+
+    /// Extracts from [async-nats] message headers.
+    ///
+    /// [async-nats]: https://crates.io/crates/async-nats
+    #[derive(Debug)]
+    pub struct NatsHeadersExtractor<'a>(pub &'a HeaderMap);
+
+    impl Extractor for NatsHeadersExtractor<'_> {
+        /// Get the first value of a header.
+        fn get(&self, key: &str) -> Option<&str> {
+            self.0.get(key).map(HeaderValue::as_str).or_else(|| {
+                self.0
+                    .iter()
+                    .filter(|(name, _)| AsRef::<str>::as_ref(*name).eq_ignore_ascii_case(key))
+                    .find_map(|(_, values)| values.first())
+                    .map(HeaderValue::as_str)
+            })
+        }
+
+        /// Collect all the header names.
+        fn keys(&self) -> Vec<&str> {
+            self.0.iter().map(|(name, _)| name.as_ref()).collect()
+        }
+    }
+}
+
+#[cfg(feature = "rusoto_sqs")]
+#[cfg_attr(docsrs, doc(cfg(feature = "rusoto_sqs")))]
+mod sqs {
+    use opentelemetry::propagation::{first_utf8, Extractor, Injector};
+    use rusoto_sqs::{Message, MessageAttributeValue};
+    use std::collections::HashMap;
+
+    const STRING_DATA_TYPE: &str = "String";
+
+    /// Injects into the [rusoto_sqs] message attributes of a
+    /// `SendMessageRequest` or `SendMessageBatchRequestEntry`.
+    ///
+    /// [rusoto_sqs]: https://crates.io/crates/rusoto_sqs
+    #[derive(Debug)]
+    pub struct SqsMessageAttributesInjector<'a>(
+        pub &'a mut Option<HashMap<String, MessageAttributeValue>>,
+    );
+
+    impl Injector for SqsMessageAttributesInjector<'_> {
+        /// Replace all values of a message attribute with a `String` attribute.
+        fn set(&mut self, key: &str, value: String) {
+            let attributes = self.0.get_or_insert_with(HashMap::new);
+            attributes.retain(|name, _| !name.eq_ignore_ascii_case(key));
+            attributes.insert(
+                key.to_string(),
+                MessageAttributeValue {
+                    data_type: STRING_DATA_TYPE.to_string(),
+                    string_value: Some(value),
+                    ..Default::default()
+                },
+            );
+        }
+    }
+
+    /// Extracts from the message attributes of a received [rusoto_sqs]
+    /// `Message`.
+    ///
+    /// [rusoto_sqs]: https://crates.io/crates/rusoto_sqs
+    #[derive(Debug)]
+    pub struct SqsMessageExtractor<'a>(pub &'a Message);
+
+    impl Extractor for SqsMessageExtractor<'_> {
+        /// Get the string value of a message attribute, falling back to binary
+        /// values that are valid UTF-8 and to the first value of list attributes.
+        fn get(&self, key: &str) -> Option<&str> {
+            let attributes = self.0.message_attributes.as_ref()?;
+            attributes.get(key).and_then(as_str).or_else(|| {
+                attributes
+                    .iter()
+                    .filter(|(name, _)| name.eq_ignore_ascii_case(key))
+                    .find_map(|(_, value)| as_str(value))
+            })
+        }
+
+        /// Collect all the message attribute names.
+        fn keys(&self) -> Vec<&str> {
+            self.0
+                .message_attributes
+                .iter()
+                .flat_map(|attributes| attributes.keys())
+                .map(|name| name.as_str())
+                .collect()
+        }
+    }
+
+    fn as_str(value: &MessageAttributeValue) -> Option<&str> {
+        value
+            .string_value
+            .as_deref()
+            .or_else(|| {
+                value
+                    .binary_value
+                    .as_ref()
+                    .and_then(|bytes| std::str::from_utf8(bytes).ok())
+            })
+            .or_else(|| {
+                value
+                    .string_list_values
+                    .as_ref()
+                    .and_then(|values| values.first())
+                    .map(String::as_str)
+            })
+            .or_else(|| {
+                value
+                    .binary_list_values
+                    .as_ref()
+                    .and_then(|values| first_utf8(values.iter().map(|bytes| bytes.as_ref())))
+            })
+    }
+}
+
+#[cfg(feature = "rdkafka")]
+pub use kafka::{KafkaHeadersExtractor, KafkaHeadersInjector};
+
+#[cfg(feature = "lapin")]
+pub use amqp::{AmqpHeadersExtractor, AmqpHeadersInjector};
+
+#[cfg(feature = "async-nats")]
+pub use nats::{NatsHeadersExtractor, NatsHeadersInjector};
+
+#[cfg(feature = "rusoto_sqs")]
+pub use sqs::{SqsMessageAttributesInjector, SqsMessageExtractor};
+
+#[cfg(test)]
+mod tests {
+    #[allow(unused_imports)]
+    use super::*;
+    #[allow(unused_imports)]
+    use opentelemetry::propagation::{Extractor, Injector};
+
+    #[test]
+    #[cfg(feature = "rdkafka")]
+    fn kafka_headers() {
+        use rdkafka::message::OwnedHeaders;
+
+        let mut carrier = OwnedHeaders::new()
+            .add("Traceparent", &[0xffu8, 0xfe][..])
+            .add("traceparent", "first")
+            .add("TRACEPARENT", "second")
+            .add("other", "other");
+
+        let extractor = KafkaHeadersExtractor(&carrier);
+        assert_eq!(extractor.get("traceparent"), Some("first"));
+        assert_eq!(
+            extractor.keys(),
+            vec!["Traceparent", "traceparent", "TRACEPARENT", "other"]
+        );
+
+        KafkaHeadersInjector(&mut carrier).set("traceparent", "new".to_string());
+        let extractor = KafkaHeadersExtractor(&carrier);
+        assert_eq!(extractor.get("TraceParent"), Some("new"));
+        assert_eq!(extractor.keys(), vec!["other", "traceparent"]);
+    }
+
+    #[test]
+    #[cfg(feature = "lapin")]
+    fn amqp_field_table() {
+        use lapin::types::{AMQPValue, ByteArray, FieldArray, FieldTable, LongString};
+
+        let mut carrier = FieldTable::default();
+        carrier.insert(
+            "Traceparent".into(),
+            AMQPValue::LongString(LongString::from(vec![0xff, 0xfe])),
+        );
+        carrier.insert(
+            "tracestate".into(),
+            AMQPValue::FieldArray(FieldArray::from(vec![
+                AMQPValue::ByteArray(ByteArray::from(vec![0xff])),
+                AMQPValue::LongString("a=1".into()),
+                AMQPValue::LongString("b=2".into()),
+            ])),
+        );
+
+        let extractor = AmqpHeadersExtractor(&carrier);
+        assert_eq!(extractor.get("traceparent"), None);
+        assert_eq!(extractor.get("TraceState"), Some("a=1"));
+
+        AmqpHeadersInjector(&mut carrier).set("traceparent", "new".to_string());
+        let extractor = AmqpHeadersExtractor(&carrier);
+        assert_eq!(extractor.get("Traceparent"), Some("new"));
+        assert_eq!(extractor.keys(), vec!["traceparent", "tracestate"]);
+    }
+
+    #[test]
+    #[cfg(feature = "async-nats")]
+    fn nats_headers() {
+        use async_nats::HeaderMap;
+
+        let mut carrier = HeaderMap::new();
+        carrier.append("Traceparent", "first");
+        carrier.append("Traceparent", "second");
+
+        assert_eq!(
+            NatsHeadersExtractor(&carrier).get("traceparent"),
+            Some("first")
+        );
+
+        let mut injector = NatsHeadersInjector(&mut carrier);
+        injector.set("traceparent", "new".to_string());
+        injector.set("invalid key", "value".to_string());
+        injector.set("tracestate", "a=1\r\nb=2".to_string());
+        let extractor = NatsHeadersExtractor(&carrier);
+        assert_eq!(extractor.get("Traceparent"), Some("new"));
+        assert_eq!(extractor.keys(), vec!["traceparent"]);
+    }
+
+    #[test]
+    #[cfg(feature = "rusoto_sqs")]
+    fn sqs_message_attributes() {
+        use rusoto_sqs::{Message, MessageAttributeValue, SendMessageRequest};
+
+        let mut request = SendMessageRequest::default();
+        let mut injector = SqsMessageAttributesInjector(&mut request.message_attributes);
+        injector.set("traceparent", "first".to_string());
+        injector.set("Traceparent", "new".to_string());
+        let attributes = request.message_attributes.unwrap();
+        assert_eq!(attributes.len(), 1);
+        assert_eq!(attributes["Traceparent"].data_type, "String");
+
+        let mut message = Message {
+            message_attributes: Some(attributes),
+            ..Default::default()
+        };
+        assert_eq!(
+            SqsMessageExtractor(&message).get("traceparent"),
+            Some("new")
+        );
+        assert_eq!(SqsMessageExtractor(&message).keys(), vec!["Traceparent"]);
+
+        message.message_attributes.as_mut().unwrap().insert(
+            "tracestate".to_string(),
+            MessageAttributeValue {
+                data_type: "Binary".to_string(),
+                binary_value: Some(b"a=1".to_vec().into()),
+                ..Default::default()
+            },
+        );
+        assert_eq!(SqsMessageExtractor(&message).get("TraceState"), Some("a=1"));
+        assert_eq!(
+            SqsMessageExtractor(&Message::default()).get("traceparent"),
+            None
+        );
+    }
+}
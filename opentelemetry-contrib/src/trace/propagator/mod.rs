@@ -7,11 +7,40 @@
 //!
 //! * `binary_propagator`, propagating trace context in the binary format.
 //! * `XrayPropagator`, propagating via AWS XRay protocol.
+//! * `OtPropagator`, propagating via the OpenTracing basic tracer headers.
+//!
+//! The `messaging` module provides carriers for the headers of messaging
+//! clients, enabled by the `rdkafka`, `lapin`, `async-nats` and `rusoto_sqs`
+//! features.
+//!
+//! The Datadog propagator lives next to the Datadog exporter, see
+//! `trace::exporter::datadog::DatadogPropagator`.
 //!
 //! This module also provides relative types for those propagators.
 #[cfg(feature = "aws-xray")]
 mod aws;
 pub mod binary;
+#[cfg(any(
+    feature = "rdkafka",
+    feature = "lapin",
+    feature = "async-nats",
+    feature = "rusoto_sqs"
+))]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(any(
+        feature = "rdkafka",
+        feature = "lapin",
+        feature = "async-nats",
+        feature = "rusoto_sqs"
+    )))
+)]
+pub mod messaging;
+#[cfg(feature = "ot")]
+mod ot;
 
 #[cfg(feature = "aws-xray")]
 pub use aws::XrayPropagator;
+#[cfg(feature = "ot")]
+#[cfg_attr(docsrs, doc(cfg(feature = "ot")))]
+pub use ot::OtPropagator;
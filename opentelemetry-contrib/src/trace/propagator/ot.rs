@@ -0,0 +1,234 @@
+use opentelemetry::{
+    baggage::BaggageExt,
+    propagation::{text_map_propagator::FieldIter, Extractor, Injector, TextMapPropagator},
+    trace::{
+        SpanContext, SpanId, TraceContextExt, TraceId, TraceState, TRACE_FLAG_NOT_SAMPLED,
+        TRACE_FLAG_SAMPLED, TRACE_FLAG_SAMPLING_DEFERRED,
+    },
+    Context, KeyValue,
+};
+
+const OT_TRACE_ID_HEADER: &str = "ot-tracer-traceid";
+const OT_SPAN_ID_HEADER: &str = "ot-tracer-spanid";
+const OT_SAMPLED_HEADER: &str = "ot-tracer-sampled";
+const OT_BAGGAGE_PREFIX: &str = "ot-baggage-";
+
+lazy_static::lazy_static! {
+    static ref OT_HEADER_FIELDS: [String; 3] = [
+        OT_TRACE_ID_HEADER.to_string(),
+        OT_SPAN_ID_HEADER.to_string(),
+        OT_SAMPLED_HEADER.to_string(),
+    ];
+}
+
+/// Extracts and injects `SpanContext`s and baggage into `Extractor`s or
+/// `Injector`s using the OpenTracing basic tracer header format.
+///
+/// The trace id is read from `ot-tracer-traceid` as 16 or 32 hex characters,
+/// and written as its lower 64 bits for compatibility with OpenTracing tracers
+/// that only support 64 bit ids. The span id is read from `ot-tracer-spanid`
+/// and the sampling decision from `ot-tracer-sampled`. Baggage entries are
+/// propagated as `ot-baggage-{key}` headers.
+///
+/// ## Example
+///
+/// ```
+/// use opentelemetry::global;
+/// use opentelemetry_contrib::trace::propagator::OtPropagator;
+///
+/// global::set_text_map_propagator(OtPropagator::new());
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct OtPropagator {
+    _private: (),
+}
+
+impl OtPropagator {
+    /// Creates a new `OtPropagator`.
+    pub fn new() -> Self {
+        OtPropagator::default()
+    }
+
+    fn extract_span_context(&self, extractor: &dyn Extractor) -> Result<SpanContext, ()> {
+        let trace_id = extract_trace_id(extractor.get(OT_TRACE_ID_HEADER).unwrap_or(""))?;
+        let span_id = extract_span_id(extractor.get(OT_SPAN_ID_HEADER).unwrap_or(""))?;
+        let trace_flags = match extractor.get(OT_SAMPLED_HEADER).map(str::trim) {
+            Some("true") | Some("1") => TRACE_FLAG_SAMPLED,
+            Some("false") | Some("0") => TRACE_FLAG_NOT_SAMPLED,
+            _ => TRACE_FLAG_SAMPLING_DEFERRED,
+        };
+
+        Ok(SpanContext::new(
+            trace_id,
+            span_id,
+            trace_flags,
+            true,
+            TraceState::default(),
+        ))
+    }
+}
+
+fn extract_trace_id(trace_id: &str) -> Result<TraceId, ()> {
+    let trace_id = trace_id.trim();
+    if (trace_id.len() != 16 && trace_id.len() != 32)
+        || !trace_id.chars().all(|c| c.is_ascii_hexdigit())
+    {
+        return Err(());
+    }
+
+    u128::from_str_radix(trace_id, 16)
+        .map(TraceId::from_u128)
+        .map_err(|_| ())
+}
+
+fn extract_span_id(span_id: &str) -> Result<SpanId, ()> {
+    let span_id = span_id.trim();
+    if span_id.len() != 16 || !span_id.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(());
+    }
+
+    u64::from_str_radix(span_id, 16)
+        .map(SpanId::from_u64)
+        .map_err(|_| ())
+}
+
+impl TextMapPropagator for OtPropagator {
+    fn inject_context(&self, cx: &Context, injector: &mut dyn Injector) {
+        let span_context = cx.span().span_context();
+        if span_context.is_valid() {
+            let trace_id = span_context.trace_id().to_hex();
+            injector.set(OT_TRACE_ID_HEADER, trace_id[16..].to_string());
+            injector.set(OT_SPAN_ID_HEADER, span_context.span_id().to_hex());
+            if !span_context.is_deferred() {
+                let sampled = if span_context.is_sampled() {
+                    "true"
+                } else {
+                    "false"
+                };
+                injector.set(OT_SAMPLED_HEADER, sampled.to_string());
+            }
+        }
+
+        for (key, (value, _metadata)) in cx.baggage() {
+            injector.set(
+                &format!("{}{}", OT_BAGGAGE_PREFIX, key.as_str()),
+                value.to_string(),
+            );
+        }
+    }
+
+    fn extract_with_context(&self, cx: &Context, extractor: &dyn Extractor) -> Context {
+        let extracted = self
+            .extract_span_context(extractor)
+            .unwrap_or_else(|_| SpanContext::empty_context());
+
+        let baggage = extractor
+            .keys()
+            .into_iter()
+            .filter_map(|key| {
+                let lowercase = key.to_ascii_lowercase();
+                if !lowercase.starts_with(OT_BAGGAGE_PREFIX) {
+                    return None;
+                }
+                let name = lowercase[OT_BAGGAGE_PREFIX.len()..].to_string();
+                if name.is_empty() {
+                    return None;
+                }
+                extractor
+                    .get(key)
+                    .map(|value| KeyValue::new(name, value.to_string()))
+            })
+            .collect::<Vec<_>>();
+
+        let cx = cx.with_remote_span_context(extracted);
+        if baggage.is_empty() {
+            cx
+        } else {
+            cx.with_baggage(baggage)
+        }
+    }
+
+    fn fields(&self) -> FieldIter<'_> {
+        FieldIter::new(OT_HEADER_FIELDS.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry::testing::trace::TestSpan;
+    use opentelemetry::Value;
+    use std::collections::HashMap;
+
+    #[rustfmt::skip]
+    fn extract_test_data() -> Vec<(Vec<(&'static str, &'static str)>, SpanContext)> {
+        vec![
+            (vec![], SpanContext::empty_context()),
+            (vec![(OT_TRACE_ID_HEADER, "garbage"), (OT_SPAN_ID_HEADER, "00f067aa0ba902b7")], SpanContext::empty_context()),
+            (vec![(OT_TRACE_ID_HEADER, "4bf92f3577b34da6a3ce929d0e0e4736")], SpanContext::empty_context()),
+            (vec![(OT_TRACE_ID_HEADER, "4bf92f3577b34da6a3ce929d0e0e4736"), (OT_SPAN_ID_HEADER, "00f067aa0ba902b7")], SpanContext::new(TraceId::from_hex("4bf92f3577b34da6a3ce929d0e0e4736"), SpanId::from_hex("00f067aa0ba902b7"), TRACE_FLAG_SAMPLING_DEFERRED, true, TraceState::default())),
+            (vec![(OT_TRACE_ID_HEADER, "a3ce929d0e0e4736"), (OT_SPAN_ID_HEADER, "00f067aa0ba902b7"), (OT_SAMPLED_HEADER, "true")], SpanContext::new(TraceId::from_hex("0000000000000000a3ce929d0e0e4736"), SpanId::from_hex("00f067aa0ba902b7"), TRACE_FLAG_SAMPLED, true, TraceState::default())),
+            (vec![(OT_TRACE_ID_HEADER, "a3ce929d0e0e4736"), (OT_SPAN_ID_HEADER, "00f067aa0ba902b7"), (OT_SAMPLED_HEADER, "false")], SpanContext::new(TraceId::from_hex("0000000000000000a3ce929d0e0e4736"), SpanId::from_hex("00f067aa0ba902b7"), TRACE_FLAG_NOT_SAMPLED, true, TraceState::default())),
+        ]
+    }
+
+    #[rustfmt::skip]
+    fn inject_test_data() -> Vec<(Vec<(&'static str, &'static str)>, SpanContext)> {
+        vec![
+            (vec![], SpanContext::empty_context()),
+            (vec![(OT_TRACE_ID_HEADER, "a3ce929d0e0e4736"), (OT_SPAN_ID_HEADER, "00f067aa0ba902b7")], SpanContext::new(TraceId::from_hex("4bf92f3577b34da6a3ce929d0e0e4736"), SpanId::from_hex("00f067aa0ba902b7"), TRACE_FLAG_SAMPLING_DEFERRED, true, TraceState::default())),
+            (vec![(OT_TRACE_ID_HEADER, "a3ce929d0e0e4736"), (OT_SPAN_ID_HEADER, "00f067aa0ba902b7"), (OT_SAMPLED_HEADER, "true")], SpanContext::new(TraceId::from_hex("4bf92f3577b34da6a3ce929d0e0e4736"), SpanId::from_hex("00f067aa0ba902b7"), TRACE_FLAG_SAMPLED, true, TraceState::default())),
+            (vec![(OT_TRACE_ID_HEADER, "a3ce929d0e0e4736"), (OT_SPAN_ID_HEADER, "00f067aa0ba902b7"), (OT_SAMPLED_HEADER, "false")], SpanContext::new(TraceId::from_hex("4bf92f3577b34da6a3ce929d0e0e4736"), SpanId::from_hex("00f067aa0ba902b7"), TRACE_FLAG_NOT_SAMPLED, true, TraceState::default())),
+        ]
+    }
+
+    #[test]
+    fn test_extract() {
+        let propagator = OtPropagator::new();
+        for (headers, expected) in extract_test_data() {
+            let map: HashMap<String, String> = headers
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect();
+
+            let context = propagator.extract(&map);
+            assert_eq!(context.remote_span_context(), Some(&expected));
+        }
+    }
+
+    #[test]
+    fn test_inject() {
+        let propagator = OtPropagator::new();
+        for (headers, span_context) in inject_test_data() {
+            let mut injector: HashMap<String, String> = HashMap::new();
+            propagator.inject_context(
+                &Context::current_with_span(TestSpan(span_context)),
+                &mut injector,
+            );
+
+            let expected: HashMap<String, String> = headers
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect();
+            assert_eq!(injector, expected);
+        }
+    }
+
+    #[test]
+    fn test_baggage_round_trip() {
+        let propagator = OtPropagator::new();
+        let cx = Context::new().with_baggage(vec![
+            KeyValue::new("user", "alice"),
+            KeyValue::new("tenant", "42"),
+        ]);
+
+        let mut injector: HashMap<String, String> = HashMap::new();
+        propagator.inject_context(&cx, &mut injector);
+        assert_eq!(injector.get("ot-baggage-user"), Some(&"alice".to_string()));
+        assert_eq!(injector.get("ot-baggage-tenant"), Some(&"42".to_string()));
+
+        let extracted = propagator.extract(&injector);
+        assert_eq!(extracted.baggage().get("user"), Some(&Value::from("alice")));
+        assert_eq!(extracted.baggage().get("tenant"), Some(&Value::from("42")));
+    }
+}
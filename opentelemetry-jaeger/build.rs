@@ -0,0 +1,15 @@
+fn main() {
+    #[cfg(feature = "grpc_collector_client")]
+    tonic_build::configure()
+        .build_server(false)
+        .build_client(true)
+        .format(false)
+        .compile(
+            &[
+                "src/proto/api_v2/model.proto",
+                "src/proto/api_v2/collector.proto",
+            ],
+            &["src/proto/api_v2"],
+        )
+        .expect("Error generating protobuf");
+}
@@ -1,21 +1,76 @@
 //! # UDP Jaeger Agent Client
+//!
+//! Also supports agents listening on a Unix domain datagram socket.
 use crate::exporter::thrift::{
     agent::{self, TAgentSyncClient},
     jaeger,
 };
 use crate::exporter::transport::{TBufferChannel, TNoopChannel};
+use crate::Error;
+use opentelemetry::{global, trace::TraceError};
 use std::fmt;
+use std::io;
+use std::mem;
 use std::net::{ToSocketAddrs, UdpSocket};
+#[cfg(unix)]
+use std::{os::unix::net::UnixDatagram, path::Path};
 use thrift::{
-    protocol::{TCompactInputProtocol, TCompactOutputProtocol},
-    transport::{ReadHalf, TIoChannel, WriteHalf},
+    protocol::{
+        TBinaryOutputProtocol, TCompactInputProtocol, TCompactOutputProtocol, TOutputProtocol,
+    },
+    transport::{ReadHalf, TIoChannel, TWriteTransport},
 };
 
+/// The max size of UDP packet we want to send, synced with jaeger-agent
+pub(crate) const UDP_PACKET_MAX_LENGTH: usize = 65_000;
+
+/// Bytes reserved in each packet for the span list header and the message
+/// sequence number, whose encoded sizes grow with their values.
+const BATCH_HEADER_RESERVE: usize = 10;
+
+/// Thrift protocol used to encode the batches sent to the agent.
+///
+/// Jaeger agents listen for compact thrift on port `6831` and for binary
+/// thrift on port `6832` by default.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AgentProtocol {
+    /// Thrift compact protocol
+    Compact,
+    /// Thrift binary protocol
+    Binary,
+}
+
+impl Default for AgentProtocol {
+    fn default() -> Self {
+        AgentProtocol::Compact
+    }
+}
+
+impl AgentProtocol {
+    /// The agent endpoint used when none is provided.
+    pub(crate) fn default_endpoint(self) -> &'static str {
+        match self {
+            AgentProtocol::Compact => "127.0.0.1:6831",
+            AgentProtocol::Binary => "127.0.0.1:6832",
+        }
+    }
+
+    fn output_protocol<'a, T: TWriteTransport + Send + 'a>(
+        self,
+        transport: T,
+    ) -> Box<dyn TOutputProtocol + Send + 'a> {
+        match self {
+            AgentProtocol::Compact => Box::new(TCompactOutputProtocol::new(transport)),
+            AgentProtocol::Binary => Box::new(TBinaryOutputProtocol::new(transport, true)),
+        }
+    }
+}
+
 struct BufferClient {
     buffer: ReadHalf<TBufferChannel>,
     client: agent::AgentSyncClient<
         TCompactInputProtocol<TNoopChannel>,
-        TCompactOutputProtocol<WriteHalf<TBufferChannel>>,
+        Box<dyn TOutputProtocol + Send>,
     >,
 }
 
@@ -29,72 +84,432 @@ impl fmt::Debug for BufferClient {
     }
 }
 
-/// `AgentAsyncClientUDP` implements an async version of the `TAgentSyncClient`
-/// interface over UDP.
+/// Socket connected to the agent.
 #[derive(Debug)]
-pub(crate) struct AgentAsyncClientUDP {
+enum AgentSocket {
     #[cfg(all(not(feature = "async-std"), not(feature = "tokio")))]
-    conn: UdpSocket,
+    Udp(UdpSocket),
     #[cfg(feature = "tokio")]
-    conn: tokio::net::UdpSocket,
+    Udp(tokio::net::UdpSocket),
     #[cfg(all(feature = "async-std", not(feature = "tokio")))]
-    conn: async_std::net::UdpSocket,
+    Udp(async_std::net::UdpSocket),
+    #[cfg(all(unix, not(feature = "async-std"), not(feature = "tokio")))]
+    Unix(UnixDatagram),
+    #[cfg(all(unix, feature = "tokio"))]
+    Unix(tokio::net::UnixDatagram),
+    #[cfg(all(unix, feature = "async-std", not(feature = "tokio")))]
+    Unix(async_std::os::unix::net::UnixDatagram),
+}
+
+/// `AgentAsyncClientUDP` implements an async version of the `TAgentSyncClient`
+/// interface over UDP or Unix datagram sockets.
+#[derive(Debug)]
+pub(crate) struct AgentAsyncClientUDP {
+    conn: AgentSocket,
     buffer_client: BufferClient,
+    protocol: AgentProtocol,
+    max_packet_size: usize,
 }
 
 impl AgentAsyncClientUDP {
-    /// Create a new UDP agent client
-    pub(crate) fn new<T: ToSocketAddrs>(host_port: T) -> thrift::Result<Self> {
+    /// Create a new UDP agent client sending packets of at most
+    /// `max_packet_size` bytes
+    pub(crate) fn new<T: ToSocketAddrs>(
+        host_port: T,
+        protocol: AgentProtocol,
+        max_packet_size: usize,
+    ) -> thrift::Result<Self> {
+        let conn = UdpSocket::bind("0.0.0.0:0")?;
+        conn.connect(host_port)?;
+
+        #[cfg(all(not(feature = "async-std"), not(feature = "tokio")))]
+        let conn = AgentSocket::Udp(conn);
+        #[cfg(feature = "tokio")]
+        let conn = AgentSocket::Udp(tokio::net::UdpSocket::from_std(conn)?);
+        #[cfg(all(feature = "async-std", not(feature = "tokio")))]
+        let conn = AgentSocket::Udp(async_std::net::UdpSocket::from(conn));
+
+        AgentAsyncClientUDP::with_socket(conn, protocol, max_packet_size)
+    }
+
+    /// Create a new agent client sending packets of at most `max_packet_size`
+    /// bytes to the Unix datagram socket at `path`
+    #[cfg(unix)]
+    pub(crate) fn new_unix<P: AsRef<Path>>(
+        path: P,
+        protocol: AgentProtocol,
+        max_packet_size: usize,
+    ) -> thrift::Result<Self> {
+        let conn = UnixDatagram::unbound()?;
+        conn.connect(path)?;
+
+        #[cfg(all(not(feature = "async-std"), not(feature = "tokio")))]
+        let conn = AgentSocket::Unix(conn);
+        #[cfg(feature = "tokio")]
+        let conn = AgentSocket::Unix(tokio::net::UnixDatagram::from_std(conn)?);
+        #[cfg(all(feature = "async-std", not(feature = "tokio")))]
+        let conn = AgentSocket::Unix(async_std::os::unix::net::UnixDatagram::from(conn));
+
+        AgentAsyncClientUDP::with_socket(conn, protocol, max_packet_size)
+    }
+
+    fn with_socket(
+        conn: AgentSocket,
+        protocol: AgentProtocol,
+        max_packet_size: usize,
+    ) -> thrift::Result<Self> {
         let (buffer, write) = TBufferChannel::with_capacity(512).split()?;
         let client = agent::AgentSyncClient::new(
             TCompactInputProtocol::new(TNoopChannel),
-            TCompactOutputProtocol::new(write),
+            protocol.output_protocol(write),
         );
 
-        let conn = UdpSocket::bind("0.0.0.0:0")?;
-        conn.connect(host_port)?;
-
         Ok(AgentAsyncClientUDP {
-            #[cfg(all(not(feature = "async-std"), not(feature = "tokio")))]
             conn,
-            #[cfg(feature = "tokio")]
-            conn: tokio::net::UdpSocket::from_std(conn)?,
-            #[cfg(all(feature = "async-std", not(feature = "tokio")))]
-            conn: async_std::net::UdpSocket::from(conn),
             buffer_client: BufferClient { buffer, client },
+            protocol,
+            max_packet_size,
         })
     }
 
-    /// Emit standard Jaeger batch
+    /// Emit standard Jaeger batch, split in as many packets as needed to stay
+    /// under the max packet size.
     pub(crate) async fn emit_batch(&mut self, batch: jaeger::Batch) -> thrift::Result<()> {
-        // Write payload to buffer
+        for batch in self.split_batch(batch)? {
+            // Write payload to buffer
+            let payload = self.serialize_batch(batch)?;
+
+            // Write async to socket, reading from buffer
+            write_to_socket(self, payload).await?;
+        }
+
+        Ok(())
+    }
+
+    fn serialize_batch(&mut self, batch: jaeger::Batch) -> thrift::Result<Vec<u8>> {
         self.buffer_client.client.emit_batch(batch)?;
-        let payload = self.buffer_client.buffer.take_bytes();
+        Ok(self.buffer_client.buffer.take_bytes())
+    }
 
-        // Write async to socket, reading from buffer
-        write_to_socket(self, payload).await?;
+    /// Split `batch` in batches whose serialized size fits in a packet.
+    ///
+    /// Spans too large for a packet on their own lose their logs, or are
+    /// dropped if they still don't fit.
+    fn split_batch(&mut self, batch: jaeger::Batch) -> thrift::Result<Vec<jaeger::Batch>> {
+        let jaeger::Batch { process, spans } = batch;
+        let envelope_size = self
+            .serialize_batch(jaeger::Batch::new(process.clone(), Vec::new()))?
+            .len();
+        let max_spans_size = self
+            .max_packet_size
+            .saturating_sub(envelope_size + BATCH_HEADER_RESERVE);
 
+        let mut batches = Vec::new();
+        let mut current_spans = Vec::new();
+        let mut current_size = 0;
+        for span in spans {
+            let fitted = fit_span(span, self.protocol, max_spans_size, self.max_packet_size)?;
+            let (span, size) = match fitted {
+                Some(fitted) => fitted,
+                None => continue,
+            };
+            if current_size + size > max_spans_size && !current_spans.is_empty() {
+                batches.push(jaeger::Batch::new(
+                    process.clone(),
+                    mem::take(&mut current_spans),
+                ));
+                current_size = 0;
+            }
+            current_size += size;
+            current_spans.push(span);
+        }
+        if !current_spans.is_empty() {
+            batches.push(jaeger::Batch::new(process, current_spans));
+        }
+
+        Ok(batches)
+    }
+}
+
+/// Returns `span` with its serialized size if it fits in `max_size` bytes,
+/// dropping its logs first if needed. Spans that still don't fit are reported
+/// to the global error handler and dropped.
+fn fit_span(
+    mut span: jaeger::Span,
+    protocol: AgentProtocol,
+    max_size: usize,
+    max_packet_size: usize,
+) -> thrift::Result<Option<(jaeger::Span, usize)>> {
+    let mut size = serialized_size(&span, protocol)?;
+    if size > max_size && span.logs.is_some() {
+        span.logs = None;
+        size = serialized_size(&span, protocol)?;
+    }
+
+    if size > max_size {
+        global::handle_error(TraceError::from(Error::SpanTooLarge {
+            operation_name: span.operation_name,
+            size,
+            max_packet_size,
+        }));
+        return Ok(None);
+    }
+
+    Ok(Some((span, size)))
+}
+
+/// Size of the `protocol` encoding of `span`.
+fn serialized_size(span: &jaeger::Span, protocol: AgentProtocol) -> thrift::Result<usize> {
+    let mut counter = ByteCounter(0);
+    let mut protocol = protocol.output_protocol(&mut counter);
+    span.write_to_out_protocol(&mut protocol)?;
+    protocol.flush()?;
+    drop(protocol);
+
+    Ok(counter.0)
+}
+
+/// Writer discarding its input, only counting the bytes written.
+struct ByteCounter(usize);
+
+impl io::Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
         Ok(())
     }
 }
 
 #[cfg(all(not(feature = "async-std"), not(feature = "tokio")))]
 async fn write_to_socket(client: &mut AgentAsyncClientUDP, payload: Vec<u8>) -> thrift::Result<()> {
-    client.conn.send(&payload)?;
+    match &client.conn {
+        AgentSocket::Udp(conn) => conn.send(&payload)?,
+        #[cfg(unix)]
+        AgentSocket::Unix(conn) => conn.send(&payload)?,
+    };
 
     Ok(())
 }
 
 #[cfg(feature = "tokio")]
 async fn write_to_socket(client: &mut AgentAsyncClientUDP, payload: Vec<u8>) -> thrift::Result<()> {
-    client.conn.send(&payload).await?;
+    match &mut client.conn {
+        AgentSocket::Udp(conn) => conn.send(&payload).await?,
+        #[cfg(unix)]
+        AgentSocket::Unix(conn) => conn.send(&payload).await?,
+    };
 
     Ok(())
 }
 
 #[cfg(all(feature = "async-std", not(feature = "tokio")))]
 async fn write_to_socket(client: &mut AgentAsyncClientUDP, payload: Vec<u8>) -> thrift::Result<()> {
-    client.conn.send(&payload).await?;
+    match &client.conn {
+        AgentSocket::Udp(conn) => conn.send(&payload).await?,
+        #[cfg(unix)]
+        AgentSocket::Unix(conn) => conn.send(&payload).await?,
+    };
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exporter::thrift::agent::{AgentSyncHandler, AgentSyncProcessor};
+    use crate::exporter::thrift::zipkincore;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use thrift::protocol::{TBinaryInputProtocol, TInputProtocol};
+    use thrift::server::TProcessor;
+
+    struct TestHandler(Arc<Mutex<Vec<jaeger::Batch>>>);
+
+    impl AgentSyncHandler for TestHandler {
+        fn handle_emit_zipkin_batch(&self, _spans: Vec<zipkincore::Span>) -> thrift::Result<()> {
+            Ok(())
+        }
+
+        fn handle_emit_batch(&self, batch: jaeger::Batch) -> thrift::Result<()> {
+            self.0.lock().unwrap().push(batch);
+            Ok(())
+        }
+    }
+
+    fn span(span_id: i64, log_fields: usize) -> jaeger::Span {
+        let mut span = jaeger::Span::new(
+            0,
+            1,
+            span_id,
+            0,
+            "operation".to_string(),
+            None,
+            1,
+            0,
+            1,
+            None,
+            None,
+        );
+        if log_fields > 0 {
+            let fields = (0..log_fields)
+                .map(|idx| {
+                    jaeger::Tag::new(
+                        format!("field{}", idx),
+                        jaeger::TagType::Bool,
+                        None,
+                        None,
+                        Some(true),
+                        None,
+                        None,
+                    )
+                })
+                .collect();
+            span.logs = Some(vec![jaeger::Log::new(0, fields)]);
+        }
+        span
+    }
+
+    /// Receives the `protocol` encoded packets returned by `recv` until it
+    /// fails, returning their sizes and the batches they contain.
+    fn receive_batches<F>(protocol: AgentProtocol, mut recv: F) -> (Vec<usize>, Vec<jaeger::Batch>)
+    where
+        F: FnMut(&mut [u8]) -> io::Result<usize>,
+    {
+        let batches = Arc::new(Mutex::new(Vec::new()));
+        let processor = AgentSyncProcessor::new(TestHandler(batches.clone()));
+        let mut sizes = Vec::new();
+        let mut buf = vec![0; UDP_PACKET_MAX_LENGTH];
+        while let Ok(len) = recv(&mut buf) {
+            sizes.push(len);
+            let mut i_prot: Box<dyn TInputProtocol> = match protocol {
+                AgentProtocol::Compact => Box::new(TCompactInputProtocol::new(&buf[..len])),
+                AgentProtocol::Binary => Box::new(TBinaryInputProtocol::new(&buf[..len], true)),
+            };
+            let mut o_prot = TCompactOutputProtocol::new(Vec::new());
+            processor.process(&mut i_prot, &mut o_prot).unwrap();
+        }
+        let batches = batches.lock().unwrap().split_off(0);
+        (sizes, batches)
+    }
+
+    fn udp_listener() -> UdpSocket {
+        let listener = UdpSocket::bind("127.0.0.1:0").unwrap();
+        listener
+            .set_read_timeout(Some(Duration::from_millis(200)))
+            .unwrap();
+        listener
+    }
+
+    #[tokio::test]
+    async fn splits_batches_larger_than_max_packet_size() {
+        let listener = udp_listener();
+        let max_packet_size = 500;
+        let mut client = AgentAsyncClientUDP::new(
+            listener.local_addr().unwrap(),
+            AgentProtocol::Compact,
+            max_packet_size,
+        )
+        .unwrap();
+
+        let spans = (1..=40).map(|span_id| span(span_id, 0)).collect();
+        let process = jaeger::Process::new("service".to_string(), None);
+        client
+            .emit_batch(jaeger::Batch::new(process, spans))
+            .await
+            .unwrap();
+
+        let (sizes, batches) = receive_batches(AgentProtocol::Compact, |buf| listener.recv(buf));
+        assert!(batches.len() > 1);
+        assert!(sizes.iter().all(|size| *size <= max_packet_size));
+        let span_ids = batches
+            .iter()
+            .flat_map(|batch| batch.spans.iter().map(|span| span.span_id))
+            .collect::<Vec<_>>();
+        assert_eq!(span_ids, (1..=40).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn truncates_or_drops_oversized_spans() {
+        let listener = udp_listener();
+        let mut client =
+            AgentAsyncClientUDP::new(listener.local_addr().unwrap(), AgentProtocol::Compact, 200)
+                .unwrap();
+
+        let mut oversized = span(2, 0);
+        oversized.operation_name = "x".repeat(500);
+        let spans = vec![span(1, 100), oversized, span(3, 0)];
+        let process = jaeger::Process::new("service".to_string(), None);
+        client
+            .emit_batch(jaeger::Batch::new(process, spans))
+            .await
+            .unwrap();
+
+        let (sizes, batches) = receive_batches(AgentProtocol::Compact, |buf| listener.recv(buf));
+        assert!(sizes.iter().all(|size| *size <= 200));
+        let spans = batches
+            .into_iter()
+            .flat_map(|batch| batch.spans)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            spans.iter().map(|span| span.span_id).collect::<Vec<_>>(),
+            vec![1, 3]
+        );
+        assert_eq!(spans[0].logs, None);
+    }
+
+    #[tokio::test]
+    async fn emits_binary_thrift() {
+        let listener = udp_listener();
+        let max_packet_size = 500;
+        let mut client = AgentAsyncClientUDP::new(
+            listener.local_addr().unwrap(),
+            AgentProtocol::Binary,
+            max_packet_size,
+        )
+        .unwrap();
+
+        let spans = (1..=20).map(|span_id| span(span_id, 0)).collect();
+        let process = jaeger::Process::new("service".to_string(), None);
+        client
+            .emit_batch(jaeger::Batch::new(process, spans))
+            .await
+            .unwrap();
+
+        let (sizes, batches) = receive_batches(AgentProtocol::Binary, |buf| listener.recv(buf));
+        assert!(batches.len() > 1);
+        assert!(sizes.iter().all(|size| *size <= max_packet_size));
+        assert_eq!(
+            batches.iter().map(|batch| batch.spans.len()).sum::<usize>(),
+            20
+        );
+        assert_eq!(batches[0].process.service_name, "service");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn emits_to_unix_socket() {
+        let path = std::env::temp_dir().join(format!("jaeger-agent-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let listener = UnixDatagram::bind(&path).unwrap();
+        listener
+            .set_read_timeout(Some(Duration::from_millis(200)))
+            .unwrap();
+        let mut client =
+            AgentAsyncClientUDP::new_unix(&path, AgentProtocol::Compact, UDP_PACKET_MAX_LENGTH)
+                .unwrap();
+
+        let process = jaeger::Process::new("service".to_string(), None);
+        client
+            .emit_batch(jaeger::Batch::new(process, vec![span(1, 0)]))
+            .await
+            .unwrap();
+
+        let (_, batches) = receive_batches(AgentProtocol::Compact, |buf| listener.recv(buf));
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].spans[0].span_id, 1);
+    }
+}
@@ -0,0 +1,288 @@
+//! # gRPC Jaeger Collector Client
+use crate::exporter::proto::api_v2::{
+    self, collector_service_client::CollectorServiceClient, PostSpansRequest,
+};
+use crate::exporter::thrift::jaeger;
+use tonic::{
+    metadata::{KeyAndValueRef, MetadataMap},
+    transport::{Channel, Endpoint},
+    Request,
+};
+
+/// `CollectorAsyncClientGrpc` posts batches to the `jaeger.api_v2.CollectorService`
+/// of a Jaeger collector.
+#[derive(Debug)]
+pub(crate) struct CollectorAsyncClientGrpc {
+    client: CollectorServiceClient<Channel>,
+    metadata: Option<MetadataMap>,
+}
+
+impl CollectorAsyncClientGrpc {
+    /// Create a new gRPC collector client, connecting to the collector on the
+    /// first submitted batch.
+    pub(crate) fn new(
+        endpoint: Endpoint,
+        metadata: Option<MetadataMap>,
+    ) -> Result<Self, tonic::transport::Error> {
+        let client = CollectorServiceClient::new(endpoint.connect_lazy()?);
+
+        Ok(CollectorAsyncClientGrpc { client, metadata })
+    }
+
+    /// Submit a Jaeger batch
+    pub(crate) async fn submit_batch(&mut self, batch: jaeger::Batch) -> Result<(), tonic::Status> {
+        let mut request = Request::new(PostSpansRequest {
+            batch: Some(batch.into()),
+        });
+        if let Some(metadata) = &self.metadata {
+            for key_and_value in metadata.iter() {
+                match key_and_value {
+                    KeyAndValueRef::Ascii(key, value) => {
+                        request.metadata_mut().append(key, value.to_owned())
+                    }
+                    KeyAndValueRef::Binary(key, value) => {
+                        request.metadata_mut().append_bin(key, value.to_owned())
+                    }
+                };
+            }
+        }
+        self.client.post_spans(request).await?;
+
+        Ok(())
+    }
+}
+
+impl From<jaeger::Batch> for api_v2::Batch {
+    fn from(batch: jaeger::Batch) -> Self {
+        api_v2::Batch {
+            spans: batch.spans.into_iter().map(Into::into).collect(),
+            process: Some(batch.process.into()),
+        }
+    }
+}
+
+impl From<jaeger::Process> for api_v2::Process {
+    fn from(process: jaeger::Process) -> Self {
+        api_v2::Process {
+            service_name: process.service_name,
+            tags: key_values(process.tags),
+        }
+    }
+}
+
+impl From<jaeger::Span> for api_v2::Span {
+    fn from(span: jaeger::Span) -> Self {
+        let trace_id = trace_id_bytes(span.trace_id_high, span.trace_id_low);
+        // api_v2 spans have no parent id field, the parent is their first
+        // `CHILD_OF` reference instead.
+        let parent = if span.parent_span_id != 0 {
+            Some(api_v2::SpanRef {
+                trace_id: trace_id.clone(),
+                span_id: span.parent_span_id.to_be_bytes().to_vec(),
+                ref_type: api_v2::SpanRefType::ChildOf as i32,
+            })
+        } else {
+            None
+        };
+
+        api_v2::Span {
+            trace_id,
+            span_id: span.span_id.to_be_bytes().to_vec(),
+            operation_name: span.operation_name,
+            references: parent
+                .into_iter()
+                .chain(span.references.into_iter().flatten().map(Into::into))
+                .collect(),
+            flags: span.flags as u32,
+            start_time: Some(timestamp(span.start_time)),
+            duration: Some(duration(span.duration)),
+            tags: key_values(span.tags),
+            logs: span
+                .logs
+                .into_iter()
+                .flatten()
+                .map(|log| api_v2::Log {
+                    timestamp: Some(timestamp(log.timestamp)),
+                    fields: log.fields.into_iter().map(Into::into).collect(),
+                })
+                .collect(),
+            process: None,
+            process_id: String::new(),
+            warnings: Vec::new(),
+        }
+    }
+}
+
+impl From<jaeger::SpanRef> for api_v2::SpanRef {
+    fn from(span_ref: jaeger::SpanRef) -> Self {
+        let ref_type = match span_ref.ref_type {
+            jaeger::SpanRefType::ChildOf => api_v2::SpanRefType::ChildOf,
+            jaeger::SpanRefType::FollowsFrom => api_v2::SpanRefType::FollowsFrom,
+        };
+
+        api_v2::SpanRef {
+            trace_id: trace_id_bytes(span_ref.trace_id_high, span_ref.trace_id_low),
+            span_id: span_ref.span_id.to_be_bytes().to_vec(),
+            ref_type: ref_type as i32,
+        }
+    }
+}
+
+impl From<jaeger::Tag> for api_v2::KeyValue {
+    fn from(tag: jaeger::Tag) -> Self {
+        let mut key_value = api_v2::KeyValue {
+            key: tag.key,
+            ..Default::default()
+        };
+        let v_type = match tag.v_type {
+            jaeger::TagType::String => {
+                key_value.v_str = tag.v_str.unwrap_or_default();
+                api_v2::ValueType::String
+            }
+            jaeger::TagType::Double => {
+                key_value.v_float64 = tag.v_double.map(|v| v.into_inner()).unwrap_or_default();
+                api_v2::ValueType::Float64
+            }
+            jaeger::TagType::Bool => {
+                key_value.v_bool = tag.v_bool.unwrap_or_default();
+                api_v2::ValueType::Bool
+            }
+            jaeger::TagType::Long => {
+                key_value.v_int64 = tag.v_long.unwrap_or_default();
+                api_v2::ValueType::Int64
+            }
+            jaeger::TagType::Binary => {
+                key_value.v_binary = tag.v_binary.unwrap_or_default();
+                api_v2::ValueType::Binary
+            }
+        };
+        key_value.v_type = v_type as i32;
+
+        key_value
+    }
+}
+
+fn key_values(tags: Option<Vec<jaeger::Tag>>) -> Vec<api_v2::KeyValue> {
+    tags.into_iter().flatten().map(Into::into).collect()
+}
+
+/// Big endian bytes of the 128 bits trace id.
+fn trace_id_bytes(trace_id_high: i64, trace_id_low: i64) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(16);
+    bytes.extend_from_slice(&trace_id_high.to_be_bytes());
+    bytes.extend_from_slice(&trace_id_low.to_be_bytes());
+    bytes
+}
+
+fn timestamp(micros: i64) -> prost_types::Timestamp {
+    prost_types::Timestamp {
+        seconds: micros.div_euclid(1_000_000),
+        nanos: (micros.rem_euclid(1_000_000) * 1_000) as i32,
+    }
+}
+
+fn duration(micros: i64) -> prost_types::Duration {
+    prost_types::Duration {
+        seconds: micros / 1_000_000,
+        nanos: ((micros % 1_000_000) * 1_000) as i32,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_thrift_span() {
+        let span = jaeger::Span::new(
+            0x0a0b,
+            0x0102,
+            0x0c,
+            0x0d,
+            "operation".to_string(),
+            vec![jaeger::SpanRef::new(
+                jaeger::SpanRefType::FollowsFrom,
+                0x0a0c,
+                0x0103,
+                0x0e,
+            )],
+            1,
+            1_500_000,
+            2_000_001,
+            vec![jaeger::Tag::new(
+                "answer".to_string(),
+                jaeger::TagType::Long,
+                None,
+                None,
+                None,
+                42,
+                None,
+            )],
+            vec![jaeger::Log::new(
+                1_600_000,
+                vec![jaeger::Tag::new(
+                    "event".to_string(),
+                    jaeger::TagType::String,
+                    "retry".to_string(),
+                    None,
+                    None,
+                    None,
+                    None,
+                )],
+            )],
+        );
+
+        let span: api_v2::Span = span.into();
+
+        let trace_id = vec![0, 0, 0, 0, 0, 0, 0x01, 0x02, 0, 0, 0, 0, 0, 0, 0x0a, 0x0b];
+        assert_eq!(span.trace_id, trace_id);
+        assert_eq!(span.span_id, vec![0, 0, 0, 0, 0, 0, 0, 0x0c]);
+        assert_eq!(
+            span.references,
+            vec![
+                api_v2::SpanRef {
+                    trace_id,
+                    span_id: vec![0, 0, 0, 0, 0, 0, 0, 0x0d],
+                    ref_type: api_v2::SpanRefType::ChildOf as i32,
+                },
+                api_v2::SpanRef {
+                    trace_id: vec![0, 0, 0, 0, 0, 0, 0x01, 0x03, 0, 0, 0, 0, 0, 0, 0x0a, 0x0c],
+                    span_id: vec![0, 0, 0, 0, 0, 0, 0, 0x0e],
+                    ref_type: api_v2::SpanRefType::FollowsFrom as i32,
+                },
+            ]
+        );
+        assert_eq!(
+            span.start_time,
+            Some(prost_types::Timestamp {
+                seconds: 1,
+                nanos: 500_000_000
+            })
+        );
+        assert_eq!(
+            span.duration,
+            Some(prost_types::Duration {
+                seconds: 2,
+                nanos: 1_000
+            })
+        );
+        assert_eq!(
+            span.tags,
+            vec![api_v2::KeyValue {
+                key: "answer".to_string(),
+                v_type: api_v2::ValueType::Int64 as i32,
+                v_int64: 42,
+                ..Default::default()
+            }]
+        );
+        assert_eq!(span.logs[0].fields[0].v_str, "retry");
+    }
+
+    #[test]
+    fn root_span_has_no_parent_reference() {
+        let span = jaeger::Span::new(1, 0, 2, 0, "root".to_string(), None, 1, 0, 0, None, None);
+
+        let span: api_v2::Span = span.into();
+        assert!(span.references.is_empty());
+    }
+}
@@ -7,18 +7,27 @@ mod collector;
 #[rustfmt::skip]
 mod thrift;
 mod env;
+#[cfg(feature = "grpc_collector_client")]
+mod grpc;
+#[cfg(feature = "grpc_collector_client")]
+#[allow(clippy::all, unreachable_pub, dead_code, missing_docs)]
+mod proto;
 pub(crate) mod transport;
 mod uploader;
 
 use self::thrift::jaeger;
 use agent::AgentAsyncClientUDP;
+pub use agent::AgentProtocol;
 use async_trait::async_trait;
 #[cfg(any(feature = "collector_client", feature = "wasm_collector_client"))]
 use collector::CollectorAsyncClientHttp;
+#[cfg(feature = "grpc_collector_client")]
+use grpc::CollectorAsyncClientGrpc;
 
 #[cfg(feature = "isahc_collector_client")]
 use isahc::prelude::Configurable;
 
+use crate::propagator::DebugIdProcessor;
 #[cfg(feature = "collector_client")]
 use opentelemetry::sdk::export::trace::HttpClient;
 use opentelemetry::sdk::export::ExportError;
@@ -26,9 +35,11 @@ use opentelemetry::trace::TraceError;
 use opentelemetry::{
     global, sdk,
     sdk::export::trace,
-    trace::{Event, Link, SpanKind, StatusCode, TracerProvider},
+    trace::{Event, Link, SpanContext, SpanKind, StatusCode, TracerProvider},
     Key, KeyValue, Value,
 };
+#[cfg(unix)]
+use std::path::{Path, PathBuf};
 use std::{
     net,
     time::{Duration, SystemTime},
@@ -48,9 +59,6 @@ use headers::authorization::Credentials;
 /// Default service name if no service is configured.
 const DEFAULT_SERVICE_NAME: &str = "OpenTelemetry";
 
-/// Default agent endpoint if none is provided
-const DEFAULT_AGENT_ENDPOINT: &str = "127.0.0.1:6831";
-
 /// Instrument Library name MUST be reported in Jaeger Span tags with the following key
 const INSTRUMENTATION_LIBRARY_NAME: &str = "otel.library.name";
 
@@ -126,7 +134,10 @@ impl trace::SpanExporter for Exporter {
 /// Jaeger exporter builder
 #[derive(Debug)]
 pub struct PipelineBuilder {
-    agent_endpoint: Vec<net::SocketAddr>,
+    agent_endpoint: Option<Vec<net::SocketAddr>>,
+    #[cfg(unix)]
+    agent_socket_path: Option<PathBuf>,
+    agent_protocol: AgentProtocol,
     #[cfg(any(feature = "collector_client", feature = "wasm_collector_client"))]
     collector_endpoint: Option<http::Uri>,
     #[cfg(any(feature = "collector_client", feature = "wasm_collector_client"))]
@@ -135,7 +146,14 @@ pub struct PipelineBuilder {
     collector_password: Option<String>,
     #[cfg(feature = "collector_client")]
     client: Option<Box<dyn opentelemetry::sdk::export::trace::HttpClient>>,
+    #[cfg(feature = "grpc_collector_client")]
+    grpc_collector_endpoint: Option<http::Uri>,
+    #[cfg(feature = "grpc_collector_client")]
+    grpc_metadata: Option<tonic::metadata::MetadataMap>,
+    #[cfg(feature = "tls")]
+    grpc_tls_config: Option<tonic::transport::ClientTlsConfig>,
     export_instrument_library: bool,
+    max_packet_size: usize,
     process: Process,
     config: Option<sdk::trace::Config>,
 }
@@ -144,7 +162,10 @@ impl Default for PipelineBuilder {
     /// Return the default Exporter Builder.
     fn default() -> Self {
         PipelineBuilder {
-            agent_endpoint: vec![DEFAULT_AGENT_ENDPOINT.parse().unwrap()],
+            agent_endpoint: None,
+            #[cfg(unix)]
+            agent_socket_path: None,
+            agent_protocol: AgentProtocol::default(),
             #[cfg(any(feature = "collector_client", feature = "wasm_collector_client"))]
             collector_endpoint: None,
             #[cfg(any(feature = "collector_client", feature = "wasm_collector_client"))]
@@ -153,7 +174,14 @@ impl Default for PipelineBuilder {
             collector_password: None,
             #[cfg(feature = "collector_client")]
             client: None,
+            #[cfg(feature = "grpc_collector_client")]
+            grpc_collector_endpoint: None,
+            #[cfg(feature = "grpc_collector_client")]
+            grpc_metadata: None,
+            #[cfg(feature = "tls")]
+            grpc_tls_config: None,
             export_instrument_library: true,
+            max_packet_size: agent::UDP_PACKET_MAX_LENGTH,
             process: Process {
                 service_name: DEFAULT_SERVICE_NAME.to_string(),
                 tags: Vec::new(),
@@ -177,15 +205,43 @@ impl PipelineBuilder {
     /// Assign the agent endpoint.
     pub fn with_agent_endpoint<T: net::ToSocketAddrs>(self, agent_endpoint: T) -> Self {
         PipelineBuilder {
-            agent_endpoint: agent_endpoint
-                .to_socket_addrs()
-                .map(|addrs| addrs.collect())
-                .unwrap_or_default(),
+            agent_endpoint: Some(
+                agent_endpoint
+                    .to_socket_addrs()
+                    .map(|addrs| addrs.collect())
+                    .unwrap_or_default(),
+            ),
 
             ..self
         }
     }
 
+    /// Assign the path of the Unix datagram socket the agent listens on, in
+    /// place of the agent endpoint.
+    #[cfg(unix)]
+    #[cfg_attr(docsrs, doc(cfg(unix)))]
+    pub fn with_agent_unix_socket<P: AsRef<Path>>(self, path: P) -> Self {
+        PipelineBuilder {
+            agent_socket_path: Some(path.as_ref().to_path_buf()),
+            ..self
+        }
+    }
+
+    /// Assign the thrift protocol used to encode spans sent to the agent,
+    /// defaults to [`AgentProtocol::Compact`].
+    ///
+    /// Agents expect binary thrift on a different port, so unless an agent
+    /// endpoint is assigned the binary protocol sends to `127.0.0.1:6832`
+    /// instead of `127.0.0.1:6831`.
+    ///
+    /// [`AgentProtocol::Compact`]: enum.AgentProtocol.html#variant.Compact
+    pub fn with_agent_protocol(self, agent_protocol: AgentProtocol) -> Self {
+        PipelineBuilder {
+            agent_protocol,
+            ..self
+        }
+    }
+
     /// Config whether to export information of instrumentation library.
     pub fn with_instrumentation_library_tags(self, export: bool) -> Self {
         PipelineBuilder {
@@ -194,6 +250,18 @@ impl PipelineBuilder {
         }
     }
 
+    /// Assign the max size in bytes of the UDP packets sent to the agent.
+    ///
+    /// Batches too large for a single packet are split, defaults to 65000
+    /// bytes. Spans that can't fit in a packet are sent without their logs,
+    /// or dropped if they are still too large.
+    pub fn with_max_packet_size(self, max_packet_size: usize) -> Self {
+        PipelineBuilder {
+            max_packet_size,
+            ..self
+        }
+    }
+
     /// Assign the collector endpoint.
     ///
     /// E.g. "http://localhost:14268/api/traces"
@@ -238,6 +306,44 @@ impl PipelineBuilder {
         }
     }
 
+    /// Assign the gRPC endpoint of the collector's `jaeger.api_v2.CollectorService`.
+    ///
+    /// E.g. "http://localhost:14250"
+    ///
+    /// The gRPC collector takes precedence over the agent and the HTTP
+    /// collector. The pipeline must be built from within a tokio runtime.
+    #[cfg(feature = "grpc_collector_client")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "grpc_collector_client")))]
+    pub fn with_grpc_collector_endpoint<T>(self, grpc_collector_endpoint: T) -> Self
+    where
+        http::Uri: core::convert::TryFrom<T>,
+    {
+        PipelineBuilder {
+            grpc_collector_endpoint: core::convert::TryFrom::try_from(grpc_collector_endpoint).ok(),
+            ..self
+        }
+    }
+
+    /// Assign the metadata sent with each request to the gRPC collector.
+    #[cfg(feature = "grpc_collector_client")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "grpc_collector_client")))]
+    pub fn with_grpc_metadata(self, metadata: tonic::metadata::MetadataMap) -> Self {
+        PipelineBuilder {
+            grpc_metadata: Some(metadata),
+            ..self
+        }
+    }
+
+    /// Assign the TLS settings used to connect to the gRPC collector.
+    #[cfg(feature = "tls")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "tls")))]
+    pub fn with_grpc_tls_config(self, tls_config: tonic::transport::ClientTlsConfig) -> Self {
+        PipelineBuilder {
+            grpc_tls_config: Some(tls_config),
+            ..self
+        }
+    }
+
     /// Assign the process service name.
     pub fn with_service_name<T: Into<String>>(mut self, service_name: T) -> Self {
         self.process.service_name = service_name.into();
@@ -281,7 +387,9 @@ impl PipelineBuilder {
         let config = self.config.take();
         let exporter = self.init_exporter()?;
 
-        let mut builder = sdk::trace::TracerProvider::builder().with_exporter(exporter);
+        let mut builder = sdk::trace::TracerProvider::builder()
+            .with_span_processor(DebugIdProcessor::new())
+            .with_exporter(exporter);
 
         if let Some(config) = config {
             builder = builder.with_config(config)
@@ -295,6 +403,12 @@ impl PipelineBuilder {
     /// This is useful if you are manually constructing a pipeline.
     pub fn init_exporter(self) -> Result<Exporter, TraceError> {
         let export_instrumentation_lib = self.export_instrument_library;
+        #[cfg(feature = "grpc_collector_client")]
+        let (process, uploader) = match self.grpc_collector_endpoint.clone() {
+            Some(endpoint) => self.init_grpc_uploader(endpoint)?,
+            None => self.init_uploader()?,
+        };
+        #[cfg(not(feature = "grpc_collector_client"))]
         let (process, uploader) = self.init_uploader()?;
 
         Ok(Exporter {
@@ -304,10 +418,55 @@ impl PipelineBuilder {
         })
     }
 
+    #[cfg(feature = "grpc_collector_client")]
+    fn init_grpc_uploader(
+        self,
+        endpoint: http::Uri,
+    ) -> Result<(Process, BatchUploader), TraceError> {
+        let endpoint = tonic::transport::Channel::builder(endpoint);
+        #[cfg(feature = "tls")]
+        let endpoint = match self.grpc_tls_config {
+            Some(tls_config) => endpoint
+                .tls_config(tls_config)
+                .map_err::<Error, _>(Into::into)?,
+            None => endpoint,
+        };
+
+        let collector = CollectorAsyncClientGrpc::new(endpoint, self.grpc_metadata)
+            .map_err::<Error, _>(Into::into)?;
+        Ok((self.process, BatchUploader::GrpcCollector(collector)))
+    }
+
+    fn agent_endpoint(&self) -> Vec<net::SocketAddr> {
+        match &self.agent_endpoint {
+            Some(agent_endpoint) => agent_endpoint.clone(),
+            None => vec![self.agent_protocol.default_endpoint().parse().unwrap()],
+        }
+    }
+
+    fn init_agent(&self) -> Result<AgentAsyncClientUDP, TraceError> {
+        #[cfg(unix)]
+        {
+            if let Some(path) = &self.agent_socket_path {
+                let agent =
+                    AgentAsyncClientUDP::new_unix(path, self.agent_protocol, self.max_packet_size)
+                        .map_err::<Error, _>(Into::into)?;
+                return Ok(agent);
+            }
+        }
+
+        let agent = AgentAsyncClientUDP::new(
+            self.agent_endpoint().as_slice(),
+            self.agent_protocol,
+            self.max_packet_size,
+        )
+        .map_err::<Error, _>(Into::into)?;
+        Ok(agent)
+    }
+
     #[cfg(not(any(feature = "collector_client", feature = "wasm_collector_client")))]
     fn init_uploader(self) -> Result<(Process, BatchUploader), TraceError> {
-        let agent = AgentAsyncClientUDP::new(self.agent_endpoint.as_slice())
-            .map_err::<Error, _>(Into::into)?;
+        let agent = self.init_agent()?;
         Ok((self.process, BatchUploader::Agent(agent)))
     }
 
@@ -394,8 +553,7 @@ impl PipelineBuilder {
                 .map_err::<Error, _>(Into::into)?;
             Ok((self.process, uploader::BatchUploader::Collector(collector)))
         } else {
-            let endpoint = self.agent_endpoint.as_slice();
-            let agent = AgentAsyncClientUDP::new(endpoint).map_err::<Error, _>(Into::into)?;
+            let agent = self.init_agent()?;
             Ok((self.process, BatchUploader::Agent(agent)))
         }
     }
@@ -411,8 +569,7 @@ impl PipelineBuilder {
             .map_err::<Error, _>(Into::into)?;
             Ok((self.process, uploader::BatchUploader::Collector(collector)))
         } else {
-            let endpoint = self.agent_endpoint.as_slice();
-            let agent = AgentAsyncClientUDP::new(endpoint).map_err::<Error, _>(Into::into)?;
+            let agent = self.init_agent()?;
             Ok((self.process, BatchUploader::Agent(agent)))
         }
     }
@@ -542,6 +699,11 @@ fn links_to_references(links: sdk::trace::EvictedQueue<Link>) -> Option<Vec<jaeg
     }
 }
 
+/// Jaeger span flags, where bit 0 marks a sampled span and bit 1 a debug span.
+fn jaeger_flags(span_context: &SpanContext) -> i32 {
+    (span_context.is_sampled() as i32) | ((span_context.is_debug() as i32) << 1)
+}
+
 /// Convert spans to jaeger thrift span for exporting.
 fn convert_otel_span_into_jaeger_span(
     span: trace::SpanData,
@@ -557,7 +719,7 @@ fn convert_otel_span_into_jaeger_span(
         parent_span_id: span.parent_span_id.to_u64() as i64,
         operation_name: span.name,
         references: links_to_references(span.links),
-        flags: span.span_context.trace_flags() as i32,
+        flags: jaeger_flags(&span.span_context),
         start_time: span
             .start_time
             .duration_since(SystemTime::UNIX_EPOCH)
@@ -702,6 +864,19 @@ pub enum Error {
     /// Error from thrift agents.
     #[error("thrift agent failed with {0}")]
     ThriftAgentError(#[from] ::thrift::Error),
+    /// A span doesn't fit in the max packet size, even without its logs.
+    #[error(
+        "span {operation_name} of {size} bytes doesn't fit in the {max_packet_size} bytes \
+        max packet size"
+    )]
+    SpanTooLarge {
+        /// The operation name of the dropped span.
+        operation_name: String,
+        /// The serialized size of the span, in bytes.
+        size: usize,
+        /// The configured max packet size, in bytes.
+        max_packet_size: usize,
+    },
     /// No http client provided.
     #[cfg(feature = "collector_client")]
     #[error(
@@ -718,6 +893,14 @@ pub enum Error {
         feature = "reqwest_blocking_collector_client"
     ))]
     ReqwestClientError(#[from] reqwest::Error),
+    /// gRPC transport errors
+    #[cfg(feature = "grpc_collector_client")]
+    #[error("gRPC transport failed with {0}")]
+    GrpcTransportError(#[from] tonic::transport::Error),
+    /// gRPC status errors returned by the collector
+    #[cfg(feature = "grpc_collector_client")]
+    #[error("gRPC collector failed with {0}")]
+    GrpcStatusError(#[from] tonic::Status),
 }
 
 impl ExportError for Error {
@@ -780,6 +963,7 @@ mod collector_client_tests {
 mod tests {
     use crate::exporter::thrift::jaeger::Tag;
     use crate::exporter::{build_span_tags, OTEL_STATUS_CODE, OTEL_STATUS_DESCRIPTION};
+    use crate::{new_pipeline, AgentProtocol};
     use opentelemetry::sdk::trace::EvictedHashMap;
     use opentelemetry::trace::{SpanKind, StatusCode};
 
@@ -854,4 +1038,23 @@ mod tests {
         }
         Ok(())
     }
+
+    #[test]
+    fn test_agent_endpoint_follows_protocol() {
+        let endpoint = |builder: crate::PipelineBuilder| builder.agent_endpoint()[0].to_string();
+
+        assert_eq!(endpoint(new_pipeline()), "127.0.0.1:6831");
+        assert_eq!(
+            endpoint(new_pipeline().with_agent_protocol(AgentProtocol::Binary)),
+            "127.0.0.1:6832"
+        );
+        assert_eq!(
+            endpoint(
+                new_pipeline()
+                    .with_agent_endpoint("127.0.0.1:6831")
+                    .with_agent_protocol(AgentProtocol::Binary)
+            ),
+            "127.0.0.1:6831"
+        );
+    }
 }
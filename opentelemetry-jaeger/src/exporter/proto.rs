@@ -0,0 +1,3 @@
+pub(crate) mod api_v2 {
+    tonic::include_proto!("jaeger.api_v2");
+}
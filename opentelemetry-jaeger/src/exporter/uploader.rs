@@ -1,6 +1,8 @@
 //! # Jaeger Span Uploader
 #[cfg(any(feature = "collector_client", feature = "wasm_collector_client"))]
 use crate::exporter::collector;
+#[cfg(feature = "grpc_collector_client")]
+use crate::exporter::grpc;
 use crate::exporter::{agent, jaeger};
 use opentelemetry::sdk::export::trace;
 
@@ -12,6 +14,9 @@ pub(crate) enum BatchUploader {
     /// Collector sync client
     #[cfg(any(feature = "collector_client", feature = "wasm_collector_client"))]
     Collector(collector::CollectorAsyncClientHttp),
+    /// Collector gRPC client
+    #[cfg(feature = "grpc_collector_client")]
+    GrpcCollector(grpc::CollectorAsyncClientGrpc),
 }
 
 impl BatchUploader {
@@ -38,6 +43,14 @@ impl BatchUploader {
                     .await
                     .map_err::<crate::Error, _>(Into::into)?;
             }
+            #[cfg(feature = "grpc_collector_client")]
+            BatchUploader::GrpcCollector(collector) => {
+                // TODO Implement retry behaviour
+                collector
+                    .submit_batch(batch)
+                    .await
+                    .map_err::<crate::Error, _>(Into::into)?;
+            }
         }
         Ok(())
     }
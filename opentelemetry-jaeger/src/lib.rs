@@ -114,6 +114,46 @@
 //! }
 //! ```
 //!
+//! ### Jaeger gRPC Collector Example
+//!
+//! Jaeger collectors also accept spans over gRPC on port `14250`. Enable the
+//! optional `grpc_collector_client` feature, or the `tls` feature to connect
+//! to the collector over TLS.
+//!
+//! ```toml
+//! [dependencies]
+//! opentelemetry-jaeger = { version = "..", features = ["grpc_collector_client"] }
+//! ```
+//!
+//! Then use the [`with_grpc_collector_endpoint`] method from within a tokio
+//! runtime:
+//!
+//! [`with_grpc_collector_endpoint`]: struct.PipelineBuilder.html#method.with_grpc_collector_endpoint
+//!
+//! ```ignore
+//! // Note that this requires the `grpc_collector_client` feature.
+//! use opentelemetry::trace::{Tracer, TraceError};
+//! use tonic::metadata::MetadataMap;
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<(), TraceError> {
+//!     let mut metadata = MetadataMap::new();
+//!     metadata.insert("x-tenant", "my-tenant".parse().unwrap());
+//!
+//!     let (tracer, _uninstall) = opentelemetry_jaeger::new_pipeline()
+//!         .with_grpc_collector_endpoint("http://localhost:14250")
+//!         // optionally send metadata with each request as well.
+//!         .with_grpc_metadata(metadata)
+//!         .install()?;
+//!
+//!     tracer.in_span("doing_work", |cx| {
+//!         // Traced app logic here...
+//!     });
+//!
+//!     Ok(())
+//! }
+//! ```
+//!
 //! ## Kitchen Sink Full Configuration
 //!
 //! Example showing how to override all configuration options. See the
@@ -168,6 +208,12 @@
 //!
 //! * `wasm_collector_client`: Enable collector in wasm.
 //!
+//! * `grpc_collector_client`: Export span data to a Jaeger collector over gRPC, backed by tonic.
+//!
+//! * `tls`: Enable TLS for the gRPC collector.
+//!
+//! * `tls-roots`: Add the system trust roots to the TLS settings of the gRPC collector.
+//!
 //! ## Supported Rust Versions
 //!
 //! OpenTelemetry is built against the latest stable release. The minimum
@@ -199,5 +245,7 @@
 mod exporter;
 mod propagator;
 
-pub use exporter::{new_pipeline, Error, Exporter, PipelineBuilder, Process, Uninstall};
-pub use propagator::Propagator;
+pub use exporter::{
+    new_pipeline, AgentProtocol, Error, Exporter, PipelineBuilder, Process, Uninstall,
+};
+pub use propagator::{DebugIdProcessor, Propagator};
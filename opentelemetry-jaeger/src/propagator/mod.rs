@@ -1,24 +1,36 @@
 //! # Jaeger Propagator
 //!
-//! Extract and inject values from Jaeger's `uber-trace-id` header.
+//! Extract and inject values from Jaeger's `uber-trace-id` header, and baggage
+//! from `uberctx-{key}` and `jaeger-baggage` headers.
 //!
 //! See [`Jaeger documentation`] for detail of Jaeger propagation format.
 //!
 //! [`Jaeger documentation`]: https://www.jaegertracing.io/docs/1.18/client-libraries/#propagation-format
 use opentelemetry::{
+    baggage::BaggageExt,
     propagation::{text_map_propagator::FieldIter, Extractor, Injector, TextMapPropagator},
+    sdk::{self, export::trace::SpanData, trace::SpanProcessor},
     trace::{
-        SpanContext, SpanId, TraceContextExt, TraceId, TraceState, TRACE_FLAG_DEBUG,
-        TRACE_FLAG_NOT_SAMPLED, TRACE_FLAG_SAMPLED,
+        Span, SpanContext, SpanId, TraceContextExt, TraceId, TraceResult, TraceState,
+        TRACE_FLAG_DEBUG, TRACE_FLAG_NOT_SAMPLED, TRACE_FLAG_SAMPLED,
     },
-    Context,
+    Context, KeyValue,
 };
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
 use std::borrow::Cow;
 use std::str::FromStr;
 
 const JAEGER_HEADER: &str = "uber-trace-id";
 const JAEGER_BAGGAGE_PREFIX: &str = "uberctx-";
+const JAEGER_BAGGAGE_HEADER: &str = "jaeger-baggage";
+const JAEGER_DEBUG_HEADER: &str = "jaeger-debug-id";
 const DEPRECATED_PARENT_SPAN: &str = "0";
+/// Everything except RFC 3986 unreserved characters is percent-encoded.
+const BAGGAGE_VALUE: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
 
 lazy_static::lazy_static! {
     static ref JAEGER_HEADER_FIELD: [String; 1] = [JAEGER_HEADER.to_string()];
@@ -30,6 +42,20 @@ lazy_static::lazy_static! {
 ///
 /// Note that jaeger header can be set in http header or encoded as url
 ///
+/// Baggage is injected as percent-encoded `uberctx-{key}` headers, and
+/// extracted from both `uberctx-{key}` headers and the comma separated
+/// `jaeger-baggage` header, with `uberctx-{key}` taking precedence.
+///
+/// A `jaeger-debug-id` header forces the extracted span context to be sampled
+/// with the debug flag set. Without an `uber-trace-id` header, an invalid span
+/// context with only the sampled and debug flags is extracted, from which a
+/// parent based sampler starts a new sampled root. The id itself is kept in
+/// the extracted context, never injected, and recorded as the
+/// `jaeger-debug-id` attribute of the spans started from it by the
+/// [`DebugIdProcessor`] so it can be correlated with the client request.
+///
+/// [`DebugIdProcessor`]: struct.DebugIdProcessor.html
+///
 ///  [`Jaeger documentation`]: https://www.jaegertracing.io/docs/1.18/client-libraries/#propagation-format
 #[derive(Clone, Debug)]
 pub struct Propagator {
@@ -50,7 +76,22 @@ impl Propagator {
 
     /// Extract span context from header value
     fn extract_span_context(&self, extractor: &dyn Extractor) -> Result<SpanContext, ()> {
-        let mut header_value = Cow::from(extractor.get(JAEGER_HEADER).unwrap_or(""));
+        let debug = extractor.get(JAEGER_DEBUG_HEADER).is_some();
+        let header_value = match extractor.get(JAEGER_HEADER) {
+            Some(header_value) => header_value,
+            // A debug id without a trace to join forces sampling of a new root.
+            None if debug => {
+                return Ok(SpanContext::new(
+                    TraceId::invalid(),
+                    SpanId::invalid(),
+                    TRACE_FLAG_SAMPLED | TRACE_FLAG_DEBUG,
+                    true,
+                    TraceState::default(),
+                ))
+            }
+            None => return Err(()),
+        };
+        let mut header_value = Cow::from(header_value);
         // if there is no :, it means header_value could be encoded as url, try decode first
         if !header_value.contains(':') {
             header_value = Cow::from(header_value.replace("%3A", ":"));
@@ -65,10 +106,18 @@ impl Propagator {
         let trace_id = self.extract_trace_id(parts[0])?;
         let span_id = self.extract_span_id(parts[1])?;
         // Ignore parent span id since it's deprecated.
-        let flag = self.extract_flag(parts[3])?;
-        let trace_state = self.extract_trace_state(extractor)?;
+        let mut flag = self.extract_flag(parts[3])?;
+        if debug {
+            flag |= TRACE_FLAG_SAMPLED | TRACE_FLAG_DEBUG;
+        }
 
-        Ok(SpanContext::new(trace_id, span_id, flag, true, trace_state))
+        Ok(SpanContext::new(
+            trace_id,
+            span_id,
+            flag,
+            true,
+            TraceState::default(),
+        ))
     }
 
     /// Extract trace id from the header.
@@ -120,18 +169,91 @@ impl Propagator {
         }
     }
 
-    fn extract_trace_state(&self, extractor: &dyn Extractor) -> Result<TraceState, ()> {
-        let uber_context_keys = extractor
-            .keys()
-            .into_iter()
-            .filter(|key| key.starts_with(JAEGER_BAGGAGE_PREFIX))
-            .filter_map(|key| {
-                extractor
-                    .get(key)
-                    .map(|value| (key.to_string(), value.to_string()))
-            });
-
-        TraceState::from_key_value(uber_context_keys)
+    /// Extract baggage from the `jaeger-baggage` and `uberctx-{key}` headers.
+    fn extract_baggage(&self, extractor: &dyn Extractor) -> Vec<KeyValue> {
+        let mut baggage = Vec::new();
+
+        if let Some(header) = extractor.get(JAEGER_BAGGAGE_HEADER) {
+            for entry in header.split(',') {
+                let mut parts = entry.splitn(2, '=');
+                if let (Some(key), Some(value)) = (parts.next(), parts.next()) {
+                    let key = key.trim();
+                    if !key.is_empty() {
+                        baggage.push(KeyValue::new(key.to_string(), value.trim().to_string()));
+                    }
+                }
+            }
+        }
+
+        for key in extractor.keys() {
+            let lowercase = key.to_ascii_lowercase();
+            if !lowercase.starts_with(JAEGER_BAGGAGE_PREFIX) {
+                continue;
+            }
+            let name = &lowercase[JAEGER_BAGGAGE_PREFIX.len()..];
+            if name.is_empty() {
+                continue;
+            }
+            if let Some(value) = extractor.get(key).and_then(percent_decode) {
+                baggage.push(KeyValue::new(name.to_string(), value));
+            }
+        }
+
+        baggage
+    }
+}
+
+/// Decode the `%XX` sequences of a value, returns `None` if the result is not
+/// UTF-8.
+fn percent_decode(value: &str) -> Option<String> {
+    percent_decode_str(value)
+        .decode_utf8()
+        .ok()
+        .map(Cow::into_owned)
+}
+
+/// The `jaeger-debug-id` of an extracted context.
+#[derive(Debug, PartialEq)]
+struct DebugId(String);
+
+/// Records the `jaeger-debug-id` extracted by the [`Propagator`] as an
+/// attribute of the spans started directly from the extracted context.
+///
+/// The Jaeger pipeline registers it, register it when building a tracer
+/// provider by hand.
+///
+/// [`Propagator`]: struct.Propagator.html
+#[derive(Debug, Default)]
+pub struct DebugIdProcessor {
+    _private: (),
+}
+
+impl DebugIdProcessor {
+    /// Create a Jaeger debug id processor
+    pub fn new() -> Self {
+        DebugIdProcessor::default()
+    }
+}
+
+impl SpanProcessor for DebugIdProcessor {
+    fn on_start(&self, span: &sdk::trace::Span, cx: &Context) {
+        // Children of local spans share the context but not the remote parent.
+        if cx.has_active_span() {
+            return;
+        }
+        if let Some(DebugId(debug_id)) = cx.get::<DebugId>() {
+            span.set_attribute(KeyValue::new(JAEGER_DEBUG_HEADER, debug_id.clone()));
+        }
+    }
+
+    fn on_end(&self, _span: SpanData) {}
+
+    fn force_flush(&self) -> TraceResult<()> {
+        Ok(())
+    }
+
+    fn shutdown(&mut self) -> TraceResult<()> {
+        Ok(())
     }
 }
 
@@ -157,13 +279,32 @@ impl TextMapPropagator for Propagator {
             );
             injector.set(JAEGER_HEADER, header_value);
         }
+
+        for (key, (value, _metadata)) in cx.baggage() {
+            injector.set(
+                &format!("{}{}", JAEGER_BAGGAGE_PREFIX, key.as_str()),
+                utf8_percent_encode(&value.to_string(), BAGGAGE_VALUE).to_string(),
+            );
+        }
     }
 
     fn extract_with_context(&self, cx: &Context, extractor: &dyn Extractor) -> Context {
-        cx.with_remote_span_context(
+        let cx = cx.with_remote_span_context(
             self.extract_span_context(extractor)
                 .unwrap_or_else(|_| SpanContext::empty_context()),
-        )
+        );
+
+        let cx = match extractor.get(JAEGER_DEBUG_HEADER) {
+            Some(debug_id) => cx.with_value(DebugId(debug_id.to_string())),
+            None => cx,
+        };
+
+        let baggage = self.extract_baggage(extractor);
+        if baggage.is_empty() {
+            cx
+        } else {
+            cx.with_baggage(baggage)
+        }
     }
 
     fn fields(&self) -> FieldIter<'_> {
@@ -176,12 +317,12 @@ mod tests {
     use super::*;
     use opentelemetry::{
         propagation::{Injector, TextMapPropagator},
-        testing::trace::TestSpan,
+        testing::trace::{new_test_exporter, TestSpan},
         trace::{
-            SpanContext, SpanId, TraceContextExt, TraceId, TraceState, TRACE_FLAG_DEBUG,
-            TRACE_FLAG_NOT_SAMPLED, TRACE_FLAG_SAMPLED,
+            SpanContext, SpanId, TraceContextExt, TraceId, TraceState, Tracer, TracerProvider,
+            TRACE_FLAG_DEBUG, TRACE_FLAG_NOT_SAMPLED, TRACE_FLAG_SAMPLED,
         },
-        Context,
+        Context, KeyValue, Value,
     };
     use std::collections::HashMap;
 
@@ -384,4 +525,206 @@ mod tests {
             assert_eq!(injector.get(JAEGER_HEADER), Some(&header_value));
         }
     }
+
+    #[test]
+    fn test_extract_baggage() {
+        let mut map: HashMap<String, String> = HashMap::new();
+        map.set(
+            JAEGER_HEADER,
+            format!("{}:{}:0:1", LONG_TRACE_ID_STR, SPAN_ID_STR),
+        );
+        map.set("uberctx-tenant", "acme%20corp".to_string());
+        map.set("uberctx-user", "alice+smith".to_string());
+        map.set(
+            JAEGER_BAGGAGE_HEADER,
+            "tenant=ignored, region=eu".to_string(),
+        );
+        let propagator = Propagator::new();
+        let context = propagator.extract(&map);
+
+        let baggage = context.baggage();
+        assert_eq!(baggage.get("tenant"), Some(&Value::from("acme corp")));
+        assert_eq!(baggage.get("user"), Some(&Value::from("alice+smith")));
+        assert_eq!(baggage.get("region"), Some(&Value::from("eu")));
+        assert_eq!(
+            context.remote_span_context(),
+            Some(&SpanContext::new(
+                TraceId::from_u128(TRACE_ID),
+                SpanId::from_u64(SPAN_ID),
+                TRACE_FLAG_SAMPLED,
+                true,
+                TraceState::default(),
+            ))
+        );
+    }
+
+    #[test]
+    fn test_extract_baggage_only() {
+        let mut map: HashMap<String, String> = HashMap::new();
+        map.set(JAEGER_BAGGAGE_HEADER, "tenant=acme".to_string());
+        let propagator = Propagator::new();
+        let context = propagator.extract(&map);
+
+        assert_eq!(context.baggage().get("tenant"), Some(&Value::from("acme")));
+        assert_eq!(
+            context.remote_span_context(),
+            Some(&SpanContext::empty_context())
+        );
+    }
+
+    #[test]
+    fn test_extract_debug_id() {
+        let mut map: HashMap<String, String> = HashMap::new();
+        map.set(
+            JAEGER_HEADER,
+            format!("{}:{}:0:0", LONG_TRACE_ID_STR, SPAN_ID_STR),
+        );
+        map.set(JAEGER_DEBUG_HEADER, "debug-123".to_string());
+        let propagator = Propagator::new();
+        let context = propagator.extract(&map);
+
+        assert_eq!(
+            context.remote_span_context(),
+            Some(&SpanContext::new(
+                TraceId::from_u128(TRACE_ID),
+                SpanId::from_u64(SPAN_ID),
+                TRACE_FLAG_SAMPLED | TRACE_FLAG_DEBUG,
+                true,
+                TraceState::default(),
+            ))
+        );
+        assert_eq!(context.baggage().get(JAEGER_DEBUG_HEADER), None);
+        assert_eq!(
+            context.get::<DebugId>().map(|debug_id| debug_id.0.as_str()),
+            Some("debug-123")
+        );
+    }
+
+    #[test]
+    fn test_extract_debug_id_only() {
+        let mut map: HashMap<String, String> = HashMap::new();
+        map.set(JAEGER_DEBUG_HEADER, "debug-123".to_string());
+        let propagator = Propagator::new();
+        let context = propagator.extract(&map);
+
+        assert_eq!(
+            context.remote_span_context(),
+            Some(&SpanContext::new(
+                TraceId::invalid(),
+                SpanId::invalid(),
+                TRACE_FLAG_SAMPLED | TRACE_FLAG_DEBUG,
+                true,
+                TraceState::default(),
+            ))
+        );
+        assert_eq!(context.baggage().get(JAEGER_DEBUG_HEADER), None);
+        assert_eq!(
+            context.get::<DebugId>().map(|debug_id| debug_id.0.as_str()),
+            Some("debug-123")
+        );
+    }
+
+    #[test]
+    fn test_debug_id_not_injected() {
+        let mut map: HashMap<String, String> = HashMap::new();
+        map.set(JAEGER_DEBUG_HEADER, "debug-123".to_string());
+        map.set("uberctx-tenant", "acme".to_string());
+        let propagator = Propagator::new();
+        let context = propagator.extract(&map);
+
+        let mut injector = HashMap::new();
+        propagator.inject_context(&context, &mut injector);
+
+        assert_eq!(injector.get("uberctx-tenant"), Some(&"acme".to_string()));
+        assert_eq!(injector.get("uberctx-jaeger-debug-id"), None);
+        assert_eq!(injector.get(JAEGER_DEBUG_HEADER), None);
+    }
+
+    #[test]
+    fn test_composite_extract_debug_only() {
+        let composite = sdk::propagation::TextMapCompositePropagator::new(vec![
+            Box::new(sdk::propagation::TraceContextPropagator::new()),
+            Box::new(Propagator::new()),
+        ]);
+        let mut map: HashMap<String, String> = HashMap::new();
+        map.set(JAEGER_DEBUG_HEADER, "debug-123".to_string());
+
+        let context = composite.extract(&map);
+
+        let span_context = context.remote_span_context().unwrap();
+        assert!(span_context.is_debug());
+        assert!(!span_context.is_valid());
+        assert_eq!(
+            context.get::<DebugId>(),
+            Some(&DebugId("debug-123".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_debug_id_processor() {
+        let (exporter, rx, _shutdown) = new_test_exporter();
+        let provider = sdk::trace::TracerProvider::builder()
+            .with_span_processor(DebugIdProcessor::new())
+            .with_simple_exporter(exporter)
+            .build();
+        let tracer = provider.get_tracer("test", None);
+        let mut map: HashMap<String, String> = HashMap::new();
+        map.set(JAEGER_DEBUG_HEADER, "debug-123".to_string());
+        let context = Propagator::new().extract(&map);
+
+        let root = tracer.start_from_context("root", &context);
+        assert!(root.span_context().is_valid());
+        assert!(root.span_context().is_debug());
+        let root_context = context.with_span(root);
+        tracer.start_from_context("child", &root_context).end();
+        drop(root_context);
+
+        let spans = rx.try_iter().collect::<Vec<_>>();
+        assert_eq!(spans.len(), 2);
+        for span in spans {
+            let debug_id = span.attributes.get(&JAEGER_DEBUG_HEADER.into());
+            if span.name == "root" {
+                assert_eq!(debug_id, Some(&Value::from("debug-123")));
+            } else {
+                assert_eq!(debug_id, None);
+            }
+        }
+    }
+
+    #[test]
+    fn test_inject_baggage() {
+        let propagator = Propagator::new();
+        let cx = Context::new().with_baggage(vec![
+            KeyValue::new("tenant", "acme corp"),
+            KeyValue::new("path", "/a?b=c"),
+        ]);
+        let mut injector = HashMap::new();
+        propagator.inject_context(&cx, &mut injector);
+
+        assert_eq!(
+            injector.get("uberctx-tenant"),
+            Some(&"acme%20corp".to_string())
+        );
+        assert_eq!(
+            injector.get("uberctx-path"),
+            Some(&"%2Fa%3Fb%3Dc".to_string())
+        );
+        assert_eq!(injector.get(JAEGER_HEADER), None);
+
+        let extracted = propagator.extract(&injector);
+        assert_eq!(
+            extracted.baggage().get("path"),
+            Some(&Value::from("/a?b=c"))
+        );
+    }
+
+    #[test]
+    fn test_percent_decode() {
+        assert_eq!(percent_decode("a%2Fb"), Some("a/b".to_string()));
+        assert_eq!(percent_decode("100%"), Some("100%".to_string()));
+        assert_eq!(percent_decode("%zz"), Some("%zz".to_string()));
+        assert_eq!(percent_decode("%C3%A9"), Some("\u{e9}".to_string()));
+        assert_eq!(percent_decode("%FF"), None);
+        assert_eq!(percent_decode("a+b%2B"), Some("a+b+".to_string()));
+    }
 }
@@ -49,6 +49,13 @@
 //! // a_value_recorder_sum{R="V",key="value"} 100
 //! // a_value_recorder_count{R="V",key="value"} 1
 //! ```
+//!
+//! ### Scrape Server
+//!
+//! With the `server` feature enabled, the registry can be served over HTTP
+//! directly. See [`server`] for the available options.
+//!
+//! [`server`]: fn.server.html
 #![warn(
     future_incompatible,
     missing_debug_implementations,
@@ -66,10 +73,16 @@
 
 use opentelemetry::global;
 use opentelemetry::sdk::{
-    export::metrics::{CheckpointSet, ExportKindSelector, Histogram, LastValue, Record, Sum},
+    export::metrics::{
+        AggregatorSelector, CheckpointSet, Count, ExportKindFor, ExportKindSelector, Histogram,
+        LastValue, Max, Min, Quantile, Record, Sum,
+    },
     metrics::{
-        aggregators::{HistogramAggregator, LastValueAggregator, SumAggregator},
-        controllers,
+        aggregators::{
+            ArrayAggregator, DDSKetchAggregator, HistogramAggregator, LastValueAggregator,
+            MinMaxSumCountAggregator, SumAggregator,
+        },
+        controllers::{self, PullReader},
         selectors::simple::Selector,
         PullController,
     },
@@ -77,22 +90,30 @@ use opentelemetry::sdk::{
 };
 use opentelemetry::{
     labels,
-    metrics::{registry::RegistryMeterProvider, MetricsError, NumberKind},
-    KeyValue,
+    metrics::{registry::RegistryMeterProvider, MetricsError, NumberKind, Result as MetricsResult},
+    Key, KeyValue,
 };
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
 mod sanitize;
+#[cfg(feature = "server")]
+mod server;
 
-use sanitize::sanitize;
+use sanitize::{metric_name, sanitize};
+#[cfg(feature = "server")]
+#[cfg_attr(docsrs, doc(cfg(feature = "server")))]
+pub use server::{server, MetricsServer, ServerBuilder};
 
 /// Cache disabled by default.
 const DEFAULT_CACHE_PERIOD: Duration = Duration::from_secs(0);
 
 const EXPORT_KIND_SELECTOR: ExportKindSelector = ExportKindSelector::Cumulative;
 
+const TARGET_INFO_NAME: &str = "target_info";
+const TARGET_INFO_DESCRIPTION: &str = "Target metadata";
+
 /// Create a new prometheus exporter builder.
 pub fn exporter() -> ExporterBuilder {
     ExporterBuilder::default()
@@ -119,10 +140,31 @@ pub struct ExporterBuilder {
     /// Defines the default histogram bucket boundaries.
     default_histogram_boundaries: Option<Vec<f64>>,
 
+    /// The aggregator selector used by the pull controller.
+    ///
+    /// If not set a histogram selector using the default histogram boundaries
+    /// is used.
+    aggregator_selector: Option<Box<dyn AggregatorSelector + Send + Sync>>,
+
     /// The prometheus registry that will be used to register instruments.
     ///
     /// If not set a new empty `Registry` is created.
     registry: Option<prometheus::Registry>,
+
+    /// Export resource attributes once as a `target_info` gauge instead of as
+    /// labels of every series.
+    target_info: bool,
+
+    /// The resource keys that are still added as labels to every series when
+    /// `target_info` is enabled.
+    resource_labels: Vec<Key>,
+
+    /// Add unit and `_total` suffixes to metric names.
+    name_suffixes: bool,
+
+    /// The pull reader of a `MultiController` collected on scrape, instead of
+    /// a pull controller created by the exporter.
+    pull_reader: Option<PullReader>,
 }
 
 impl ExporterBuilder {
@@ -161,6 +203,20 @@ impl ExporterBuilder {
         }
     }
 
+    /// Set the aggregator selector used by this exporter.
+    ///
+    /// Sketch, exact and min-max-sum-count aggregations are exported as
+    /// prometheus summaries using the default summary quantiles.
+    pub fn with_aggregator_selector<T>(self, selector: T) -> Self
+    where
+        T: AggregatorSelector + Send + Sync + 'static,
+    {
+        ExporterBuilder {
+            aggregator_selector: Some(Box::new(selector)),
+            ..self
+        }
+    }
+
     /// Set the prometheus registry to be used by this exporter
     pub fn with_registry(self, registry: prometheus::Registry) -> Self {
         ExporterBuilder {
@@ -169,6 +225,48 @@ impl ExporterBuilder {
         }
     }
 
+    /// Export the resource attributes once as a `target_info` gauge, instead of
+    /// adding them as labels to every series.
+    pub fn with_target_info(self, enabled: bool) -> Self {
+        ExporterBuilder {
+            target_info: enabled,
+            ..self
+        }
+    }
+
+    /// Set the resource keys that are promoted to labels of every series when
+    /// exporting the resource as `target_info`.
+    pub fn with_resource_labels<T: IntoIterator<Item = Key>>(self, keys: T) -> Self {
+        ExporterBuilder {
+            resource_labels: keys.into_iter().collect(),
+            ..self
+        }
+    }
+
+    /// Add the unit and, for monotonic counters, the `_total` suffix to metric
+    /// names as described by the OpenMetrics compatibility spec. Defaults to
+    /// `false`, exporting sanitized instrument names as is.
+    pub fn with_name_suffixes(self, enabled: bool) -> Self {
+        ExporterBuilder {
+            name_suffixes: enabled,
+            ..self
+        }
+    }
+
+    /// Collect the given pull reader of a `MultiController` on scrape, sharing
+    /// its accumulator with the other readers of the controller.
+    ///
+    /// The reader should use the cumulative export kind, its aggregator
+    /// selector and cache period are used instead of the ones of this
+    /// builder. The global meter provider is left unchanged, the resource is
+    /// only used to export `target_info`.
+    pub fn with_pull_reader(self, reader: PullReader) -> Self {
+        ExporterBuilder {
+            pull_reader: Some(reader),
+            ..self
+        }
+    }
+
     /// Sets up a complete export pipeline with the recommended setup, using the
     /// recommended selector and standard processor.
     pub fn try_init(self) -> Result<PrometheusExporter, MetricsError> {
@@ -179,22 +277,39 @@ impl ExporterBuilder {
         let default_histogram_boundaries = self
             .default_histogram_boundaries
             .unwrap_or_else(|| vec![0.5, 0.9, 0.99]);
-        let selector = Box::new(Selector::Histogram(default_histogram_boundaries.clone()));
-        let mut controller_builder = controllers::pull(selector, Box::new(EXPORT_KIND_SELECTOR))
-            .with_cache_period(self.cache_period.unwrap_or(DEFAULT_CACHE_PERIOD))
-            .with_memory(true);
-        if let Some(resource) = self.resource {
-            controller_builder = controller_builder.with_resource(resource);
-        }
-        let controller = controller_builder.build();
+        let selector = self
+            .aggregator_selector
+            .unwrap_or_else(|| Box::new(Selector::Histogram(default_histogram_boundaries.clone())));
+        let resource = self.resource.unwrap_or_default();
+        let controller = match self.pull_reader {
+            Some(reader) => Controller::Reader(reader),
+            None => {
+                let controller = controllers::pull(selector, Box::new(EXPORT_KIND_SELECTOR))
+                    .with_cache_period(self.cache_period.unwrap_or(DEFAULT_CACHE_PERIOD))
+                    .with_memory(true)
+                    .with_resource(resource.clone())
+                    .build();
+                global::set_meter_provider(controller.provider());
+                Controller::Pull(controller)
+            }
+        };
 
-        global::set_meter_provider(controller.provider());
+        let resource_mode = if self.target_info {
+            ResourceMode::TargetInfo {
+                resource,
+                labels: self.resource_labels,
+            }
+        } else {
+            ResourceMode::Labels
+        };
 
-        PrometheusExporter::new(
+        PrometheusExporter::with_config(
             registry,
             controller,
             default_summary_quantiles,
             default_histogram_boundaries,
+            resource_mode,
+            self.name_suffixes,
         )
     }
 
@@ -216,7 +331,7 @@ impl ExporterBuilder {
 #[derive(Clone, Debug)]
 pub struct PrometheusExporter {
     registry: prometheus::Registry,
-    controller: Arc<Mutex<PullController>>,
+    controller: Arc<Mutex<Controller>>,
     default_summary_quantiles: Vec<f64>,
     default_histogram_boundaries: Vec<f64>,
 }
@@ -228,9 +343,50 @@ impl PrometheusExporter {
         controller: PullController,
         default_summary_quantiles: Vec<f64>,
         default_histogram_boundaries: Vec<f64>,
+    ) -> Result<Self, MetricsError> {
+        Self::with_config(
+            registry,
+            Controller::Pull(controller),
+            default_summary_quantiles,
+            default_histogram_boundaries,
+            ResourceMode::Labels,
+            false,
+        )
+    }
+
+    /// Create a new prometheus exporter collecting the pull reader of a
+    /// `MultiController`
+    pub fn from_pull_reader(
+        registry: prometheus::Registry,
+        reader: PullReader,
+        default_summary_quantiles: Vec<f64>,
+        default_histogram_boundaries: Vec<f64>,
+    ) -> Result<Self, MetricsError> {
+        Self::with_config(
+            registry,
+            Controller::Reader(reader),
+            default_summary_quantiles,
+            default_histogram_boundaries,
+            ResourceMode::Labels,
+            false,
+        )
+    }
+
+    fn with_config(
+        registry: prometheus::Registry,
+        controller: Controller,
+        default_summary_quantiles: Vec<f64>,
+        default_histogram_boundaries: Vec<f64>,
+        resource_mode: ResourceMode,
+        name_suffixes: bool,
     ) -> Result<Self, MetricsError> {
         let controller = Arc::new(Mutex::new(controller));
-        let collector = Collector::with_controller(controller.clone());
+        let collector = Collector::new(
+            controller.clone(),
+            resource_mode,
+            default_summary_quantiles.clone(),
+            name_suffixes,
+        );
         registry
             .register(Box::new(collector))
             .map_err(|e| MetricsError::Other(e.to_string()))?;
@@ -257,14 +413,107 @@ impl PrometheusExporter {
     }
 }
 
+/// The source of the exported metrics.
+#[derive(Debug)]
+enum Controller {
+    /// A pull controller owned by the exporter.
+    Pull(PullController),
+    /// A pull reader of a `MultiController`.
+    Reader(PullReader),
+}
+
+impl Controller {
+    fn provider(&self) -> RegistryMeterProvider {
+        match self {
+            Controller::Pull(controller) => controller.provider(),
+            Controller::Reader(reader) => reader.provider(),
+        }
+    }
+
+    fn collect(&mut self) -> MetricsResult<()> {
+        match self {
+            Controller::Pull(controller) => controller.collect(),
+            Controller::Reader(reader) => reader.collect(),
+        }
+    }
+}
+
+impl CheckpointSet for Controller {
+    fn try_for_each(
+        &mut self,
+        export_selector: &dyn ExportKindFor,
+        f: &mut dyn FnMut(&Record<'_>) -> MetricsResult<()>,
+    ) -> MetricsResult<()> {
+        match self {
+            Controller::Pull(controller) => controller.try_for_each(export_selector, f),
+            Controller::Reader(reader) => reader.try_for_each(export_selector, f),
+        }
+    }
+}
+
+/// How resource attributes are exported.
+#[derive(Debug)]
+enum ResourceMode {
+    /// Add all resource attributes as labels to every series.
+    Labels,
+    /// Export the resource as a `target_info` gauge, only adding the given
+    /// keys as labels to every series.
+    TargetInfo {
+        resource: Resource,
+        labels: Vec<Key>,
+    },
+}
+
 #[derive(Debug)]
 struct Collector {
-    controller: Arc<Mutex<PullController>>,
+    controller: Arc<Mutex<Controller>>,
+    resource_mode: ResourceMode,
+    summary_quantiles: Vec<f64>,
+    name_suffixes: bool,
 }
 
 impl Collector {
-    fn with_controller(controller: Arc<Mutex<PullController>>) -> Self {
-        Collector { controller }
+    fn new(
+        controller: Arc<Mutex<Controller>>,
+        resource_mode: ResourceMode,
+        summary_quantiles: Vec<f64>,
+        name_suffixes: bool,
+    ) -> Self {
+        Collector {
+            controller,
+            resource_mode,
+            summary_quantiles,
+            name_suffixes,
+        }
+    }
+
+    fn target_info(&self) -> Option<prometheus::proto::MetricFamily> {
+        let resource = match &self.resource_mode {
+            ResourceMode::TargetInfo { resource, .. } if !resource.is_empty() => resource,
+            _ => return None,
+        };
+
+        let mut g = prometheus::proto::Gauge::default();
+        g.set_value(1.0);
+
+        let mut m = prometheus::proto::Metric::default();
+        m.set_label(protobuf::RepeatedField::from_vec(
+            resource
+                .iter()
+                .map(|(key, value)| {
+                    build_label_pair(KeyValue::new(sanitize(key.as_str()), value.clone()))
+                })
+                .collect(),
+        ));
+        m.set_gauge(g);
+
+        let mut mf = prometheus::proto::MetricFamily::default();
+        mf.set_name(TARGET_INFO_NAME.to_string());
+        mf.set_help(TARGET_INFO_DESCRIPTION.to_string());
+        mf.set_field_type(prometheus::proto::MetricType::GAUGE);
+        mf.set_metric(protobuf::RepeatedField::from_vec(vec![m]));
+
+        Some(mf)
     }
 }
 
@@ -284,6 +533,8 @@ impl prometheus::core::Collector for Collector {
                 return metrics;
             }
 
+            metrics.extend(self.target_info());
+
             if let Err(err) = controller.try_for_each(&EXPORT_KIND_SELECTOR, &mut |record| {
                 let agg = record.aggregator().ok_or(MetricsError::NoDataCollected)?;
                 let number_kind = record.descriptor().number_kind();
@@ -291,9 +542,14 @@ impl prometheus::core::Collector for Collector {
 
                 let mut label_keys = Vec::new();
                 let mut label_values = Vec::new();
-                merge_labels(record, &mut label_keys, Some(&mut label_values));
+                merge_labels(
+                    record,
+                    &self.resource_mode,
+                    &mut label_keys,
+                    Some(&mut label_values),
+                );
 
-                let desc = to_desc(&record, label_keys);
+                let desc = to_desc(&record, label_keys, self.name_suffixes);
 
                 if let Some(hist) = agg.as_any().downcast_ref::<HistogramAggregator>() {
                     metrics.push(build_histogram(hist, number_kind, desc, label_values)?);
@@ -307,6 +563,31 @@ impl prometheus::core::Collector for Collector {
                     metrics.push(counter);
                 } else if let Some(last) = agg.as_any().downcast_ref::<LastValueAggregator>() {
                     metrics.push(build_last_value(last, number_kind, desc, label_values)?);
+                } else if let Some(sketch) = agg.as_any().downcast_ref::<DDSKetchAggregator>() {
+                    let quantiles = &self.summary_quantiles;
+                    metrics.push(build_summary(
+                        sketch,
+                        quantiles,
+                        number_kind,
+                        desc,
+                        label_values,
+                    )?);
+                } else if let Some(array) = agg.as_any().downcast_ref::<ArrayAggregator>() {
+                    let quantiles = &self.summary_quantiles;
+                    metrics.push(build_summary(
+                        array,
+                        quantiles,
+                        number_kind,
+                        desc,
+                        label_values,
+                    )?);
+                } else if let Some(mmsc) = agg.as_any().downcast_ref::<MinMaxSumCountAggregator>() {
+                    metrics.push(build_min_max_sum_count(
+                        mmsc,
+                        number_kind,
+                        desc,
+                        label_values,
+                    )?);
                 }
 
                 Ok(())
@@ -440,6 +721,99 @@ fn build_histogram(
     Ok(mf)
 }
 
+fn build_summary<A: Quantile + Sum + Count>(
+    agg: &A,
+    quantiles: &[f64],
+    kind: &NumberKind,
+    desc: prometheus::core::Desc,
+    labels: Vec<KeyValue>,
+) -> Result<prometheus::proto::MetricFamily, MetricsError> {
+    let count = agg.count()?;
+    let quantiles = quantiles
+        .iter()
+        .map(|&q| {
+            let value = if count == 0 {
+                std::f64::NAN
+            } else {
+                agg.quantile(q)?.to_f64(kind)
+            };
+            Ok((q, value))
+        })
+        .collect::<Result<Vec<_>, MetricsError>>()?;
+
+    Ok(summary_family(
+        quantiles,
+        agg.sum()?.to_f64(kind),
+        count,
+        desc,
+        labels,
+    ))
+}
+
+/// MinMaxSumCount has no quantile information, min and max are exported as the
+/// 0 and 1 quantiles.
+fn build_min_max_sum_count(
+    mmsc: &MinMaxSumCountAggregator,
+    kind: &NumberKind,
+    desc: prometheus::core::Desc,
+    labels: Vec<KeyValue>,
+) -> Result<prometheus::proto::MetricFamily, MetricsError> {
+    let count = mmsc.count()?;
+    let quantiles = if count == 0 {
+        vec![(0.0, std::f64::NAN), (1.0, std::f64::NAN)]
+    } else {
+        vec![
+            (0.0, mmsc.min()?.to_f64(kind)),
+            (1.0, mmsc.max()?.to_f64(kind)),
+        ]
+    };
+
+    Ok(summary_family(
+        quantiles,
+        mmsc.sum()?.to_f64(kind),
+        count,
+        desc,
+        labels,
+    ))
+}
+
+fn summary_family(
+    quantiles: Vec<(f64, f64)>,
+    sum: f64,
+    count: u64,
+    desc: prometheus::core::Desc,
+    labels: Vec<KeyValue>,
+) -> prometheus::proto::MetricFamily {
+    let mut s = prometheus::proto::Summary::default();
+    s.set_sample_sum(sum);
+    s.set_sample_count(count);
+    s.set_quantile(protobuf::RepeatedField::from_vec(
+        quantiles
+            .into_iter()
+            .map(|(quantile, value)| {
+                let mut q = prometheus::proto::Quantile::default();
+                q.set_quantile(quantile);
+                q.set_value(value);
+                q
+            })
+            .collect(),
+    ));
+
+    let mut m = prometheus::proto::Metric::default();
+    m.set_label(protobuf::RepeatedField::from_vec(
+        labels.into_iter().map(build_label_pair).collect(),
+    ));
+    m.set_summary(s);
+
+    let mut mf = prometheus::proto::MetricFamily::default();
+    mf.set_name(desc.fq_name);
+    mf.set_help(desc.help);
+    mf.set_field_type(prometheus::proto::MetricType::SUMMARY);
+    mf.set_metric(protobuf::RepeatedField::from_vec(vec![m]));
+
+    mf
+}
+
 fn build_label_pair(label: KeyValue) -> prometheus::proto::LabelPair {
     let mut lp = prometheus::proto::LabelPair::new();
     lp.set_name(label.key.into());
@@ -450,25 +824,42 @@ fn build_label_pair(label: KeyValue) -> prometheus::proto::LabelPair {
 
 fn merge_labels(
     record: &Record<'_>,
+    resource_mode: &ResourceMode,
     keys: &mut Vec<String>,
     mut values: Option<&mut Vec<KeyValue>>,
 ) {
     // Duplicate keys are resolved by taking the record label value over
     // the resource value.
 
-    let iter = labels::merge_iters(record.labels().iter(), record.resource().iter());
+    let resource = record
+        .resource()
+        .iter()
+        .filter(|(key, _)| match resource_mode {
+            ResourceMode::Labels => true,
+            ResourceMode::TargetInfo { labels, .. } => labels.contains(key),
+        });
+    let iter = labels::merge_iters(record.labels().iter(), resource);
     for (key, value) in iter {
         keys.push(sanitize(key.as_str()));
         if let Some(ref mut values) = values {
-            values.push(KeyValue::new(key.clone(), value.clone()));
+            values.push(KeyValue::new(sanitize(key.as_str()), value.clone()));
         }
     }
 }
 
-fn to_desc(record: &Record<'_>, label_keys: Vec<String>) -> prometheus::core::Desc {
+fn to_desc(
+    record: &Record<'_>,
+    label_keys: Vec<String>,
+    name_suffixes: bool,
+) -> prometheus::core::Desc {
     let desc = record.descriptor();
+    let name = if name_suffixes {
+        metric_name(desc.name(), desc.unit(), desc.instrument_kind().monotonic())
+    } else {
+        sanitize(desc.name())
+    };
     prometheus::core::Desc::new(
-        sanitize(desc.name()),
+        name,
         desc.description()
             .cloned()
             .unwrap_or_else(|| desc.name().to_string()),
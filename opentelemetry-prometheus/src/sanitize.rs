@@ -18,6 +18,122 @@ pub(crate) fn sanitize<T: AsRef<str>>(raw: T) -> String {
     prefix.chars().chain(escaped).take(100).collect()
 }
 
+/// metric_name returns the sanitized prometheus name of an instrument, with
+/// the unit and `_total` suffixes of the OpenMetrics compatibility spec.
+pub(crate) fn metric_name(name: &str, unit: Option<&str>, monotonic: bool) -> String {
+    let mut name = sanitize(name);
+
+    if let Some(suffix) = unit.and_then(unit_suffix) {
+        if !name.ends_with(&format!("_{}", suffix)) {
+            name.push('_');
+            name.push_str(&suffix);
+        }
+    }
+
+    if monotonic && !name.ends_with("_total") {
+        name.push_str("_total");
+    }
+
+    name
+}
+
+/// unit_suffix converts a UCUM unit to its prometheus name suffix.
+///
+/// Annotations in curly braces are dropped, `/` is written as `_per_` and
+/// unknown units are sanitized.
+fn unit_suffix(unit: &str) -> Option<String> {
+    let unit = strip_annotations(unit);
+    let unit = unit.trim();
+    if unit.is_empty() || unit == "1" {
+        return None;
+    }
+
+    let mut parts = unit.splitn(2, '/');
+    let numerator = parts.next().map(str::trim).unwrap_or_default();
+    let denominator = parts.next().map(str::trim).filter(|d| !d.is_empty());
+
+    let suffix = match (numerator, denominator) {
+        ("", Some(denominator)) | ("1", Some(denominator)) => {
+            format!("per_{}", unit_name(denominator, true))
+        }
+        (numerator, Some(denominator)) => format!(
+            "{}_per_{}",
+            unit_name(numerator, false),
+            unit_name(denominator, true)
+        ),
+        (numerator, None) => unit_name(numerator, false),
+    };
+
+    let suffix = suffix
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect::<String>();
+    let suffix = suffix.trim_matches('_');
+    if suffix.is_empty() {
+        None
+    } else {
+        Some(suffix.to_string())
+    }
+}
+
+fn strip_annotations(unit: &str) -> String {
+    let mut depth = 0;
+    unit.chars()
+        .filter(|&c| match c {
+            '{' => {
+                depth += 1;
+                false
+            }
+            '}' => {
+                depth = (depth - 1).max(0);
+                false
+            }
+            _ => depth == 0,
+        })
+        .collect()
+}
+
+fn unit_name(unit: &str, per: bool) -> String {
+    let name = match unit {
+        // time
+        "d" => ("days", "day"),
+        "h" => ("hours", "hour"),
+        "min" => ("minutes", "minute"),
+        "s" => ("seconds", "second"),
+        "ms" => ("milliseconds", "millisecond"),
+        "us" => ("microseconds", "microsecond"),
+        "ns" => ("nanoseconds", "nanosecond"),
+        // bytes
+        "By" => ("bytes", "byte"),
+        "KiBy" => ("kibibytes", "kibibyte"),
+        "MiBy" => ("mebibytes", "mebibyte"),
+        "GiBy" => ("gibibytes", "gibibyte"),
+        "TiBy" => ("tebibytes", "tebibyte"),
+        "KBy" => ("kilobytes", "kilobyte"),
+        "MBy" => ("megabytes", "megabyte"),
+        "GBy" => ("gigabytes", "gigabyte"),
+        "TBy" => ("terabytes", "terabyte"),
+        // SI
+        "m" => ("meters", "meter"),
+        "V" => ("volts", "volt"),
+        "A" => ("amperes", "ampere"),
+        "J" => ("joules", "joule"),
+        "W" => ("watts", "watt"),
+        "g" => ("grams", "gram"),
+        // misc
+        "Cel" => ("celsius", "celsius"),
+        "Hz" => ("hertz", "hertz"),
+        "%" => ("percent", "percent"),
+        other => return other.to_string(),
+    };
+
+    if per {
+        name.1.to_string()
+    } else {
+        name.0.to_string()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -64,4 +180,74 @@ mod tests {
             assert_eq!(sanitize(raw), sanitized, "{} doesn't match", name)
         }
     }
+
+    #[test]
+    fn metric_names() {
+        let data = vec![
+            ("no unit", "requests", None, false, "requests"),
+            ("empty unit", "requests", Some(""), false, "requests"),
+            (
+                "dimensionless",
+                "utilization",
+                Some("1"),
+                false,
+                "utilization",
+            ),
+            (
+                "seconds",
+                "http.duration",
+                Some("s"),
+                false,
+                "http_duration_seconds",
+            ),
+            ("bytes", "rpc.size", Some("By"), false, "rpc_size_bytes"),
+            ("counter", "requests", None, true, "requests_total"),
+            ("counter unit", "io", Some("By"), true, "io_bytes_total"),
+            (
+                "annotation",
+                "requests",
+                Some("{request}"),
+                true,
+                "requests_total",
+            ),
+            (
+                "rate",
+                "throughput",
+                Some("By/s"),
+                false,
+                "throughput_bytes_per_second",
+            ),
+            (
+                "inverse",
+                "arrivals",
+                Some("1/s"),
+                false,
+                "arrivals_per_second",
+            ),
+            ("unknown", "speed", Some("furlong"), false, "speed_furlong"),
+            (
+                "existing suffix",
+                "latency_seconds",
+                Some("s"),
+                false,
+                "latency_seconds",
+            ),
+            (
+                "existing total",
+                "requests_total",
+                None,
+                true,
+                "requests_total",
+            ),
+        ];
+
+        for (name, raw, unit, monotonic, expected) in data {
+            assert_eq!(
+                metric_name(raw, unit, monotonic),
+                expected,
+                "{} doesn't match",
+                name
+            )
+        }
+    }
 }
@@ -0,0 +1,475 @@
+//! # Prometheus Scrape Server
+//!
+//! Serves the metrics of a `prometheus::Registry` over HTTP, so the metrics can
+//! be scraped without writing a custom HTTP handler.
+//!
+//! ```no_run
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let exporter = opentelemetry_prometheus::exporter().init();
+//! let server = opentelemetry_prometheus::server(exporter.registry().clone())
+//!     .with_address(([0, 0, 0, 0], 9464).into())
+//!     .with_health_path("/health")
+//!     .start()?;
+//!
+//! // ... run the application
+//!
+//! server.shutdown().await;
+//! # Ok(())
+//! # }
+//! ```
+use flate2::{write::GzEncoder, Compression};
+use hyper::{
+    header,
+    service::{make_service_fn, service_fn},
+    Body, Method, Request, Response, StatusCode,
+};
+use opentelemetry::metrics::MetricsError;
+use prometheus::proto::{LabelPair, Metric, MetricFamily, MetricType};
+use prometheus::{Encoder, TextEncoder};
+use std::convert::Infallible;
+use std::io::{self, Write};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::sync::oneshot;
+
+const DEFAULT_ADDRESS: ([u8; 4], u16) = ([0, 0, 0, 0], 9464);
+const DEFAULT_PATH: &str = "/metrics";
+
+const OPENMETRICS_MEDIA_TYPE: &str = "application/openmetrics-text";
+const OPENMETRICS_CONTENT_TYPE: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+/// Create a new scrape server builder for the given registry.
+pub fn server(registry: prometheus::Registry) -> ServerBuilder {
+    ServerBuilder {
+        registry,
+        address: DEFAULT_ADDRESS.into(),
+        path: DEFAULT_PATH.to_string(),
+        health_path: None,
+        gzip: true,
+    }
+}
+
+/// Configuration for the scrape server.
+#[derive(Debug)]
+pub struct ServerBuilder {
+    /// The registry that is gathered on every scrape.
+    registry: prometheus::Registry,
+
+    /// The address to listen on. Defaults to `0.0.0.0:9464`.
+    address: SocketAddr,
+
+    /// The path metrics are served on. Defaults to `/metrics`.
+    path: String,
+
+    /// The path of the health endpoint, if any.
+    health_path: Option<String>,
+
+    /// Whether responses are compressed for clients accepting gzip. Defaults to
+    /// `true`.
+    gzip: bool,
+}
+
+impl ServerBuilder {
+    /// Set the address to listen on.
+    ///
+    /// Use port `0` to listen on a port chosen by the operating system, the
+    /// actual address is returned by `MetricsServer::local_addr`.
+    pub fn with_address(self, address: SocketAddr) -> Self {
+        ServerBuilder { address, ..self }
+    }
+
+    /// Set the path metrics are served on.
+    pub fn with_path<T: Into<String>>(self, path: T) -> Self {
+        ServerBuilder {
+            path: path.into(),
+            ..self
+        }
+    }
+
+    /// Serve a health endpoint that always responds with `200 OK` on the given
+    /// path.
+    pub fn with_health_path<T: Into<String>>(self, path: T) -> Self {
+        ServerBuilder {
+            health_path: Some(path.into()),
+            ..self
+        }
+    }
+
+    /// Enable or disable gzip compression of the responses.
+    pub fn with_gzip(self, gzip: bool) -> Self {
+        ServerBuilder { gzip, ..self }
+    }
+
+    /// Bind the address and start serving on the current tokio runtime.
+    pub fn start(self) -> Result<MetricsServer, MetricsError> {
+        let config = Arc::new(Config {
+            registry: self.registry,
+            path: self.path,
+            health_path: self.health_path,
+            gzip: self.gzip,
+        });
+
+        let make_service = make_service_fn(move |_conn| {
+            let config = config.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |req| {
+                    let config = config.clone();
+                    async move { Ok::<_, Infallible>(config.handle(req)) }
+                }))
+            }
+        });
+
+        let (shutdown_tx, shutdown_rx) = oneshot::channel();
+        let server = hyper::Server::try_bind(&self.address)
+            .map_err(|err| MetricsError::Other(err.to_string()))?
+            .serve(make_service);
+        let local_addr = server.local_addr();
+        let server = server.with_graceful_shutdown(async {
+            let _ = shutdown_rx.await;
+        });
+
+        let handle = tokio::spawn(async move {
+            if let Err(err) = server.await {
+                opentelemetry::global::handle_error(MetricsError::Other(err.to_string()));
+            }
+        });
+
+        Ok(MetricsServer {
+            local_addr,
+            shutdown: Some(shutdown_tx),
+            handle: Some(handle),
+        })
+    }
+}
+
+/// A running scrape server.
+///
+/// The server stops accepting connections when this is dropped. Use
+/// `shutdown` to also wait for in-flight scrapes to complete.
+#[derive(Debug)]
+pub struct MetricsServer {
+    local_addr: SocketAddr,
+    shutdown: Option<oneshot::Sender<()>>,
+    handle: Option<tokio::task::JoinHandle<()>>,
+}
+
+impl MetricsServer {
+    /// The address the server is listening on.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Stop accepting connections and wait for in-flight requests to complete.
+    pub async fn shutdown(mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.await;
+        }
+    }
+}
+
+impl Drop for MetricsServer {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Config {
+    registry: prometheus::Registry,
+    path: String,
+    health_path: Option<String>,
+    gzip: bool,
+}
+
+impl Config {
+    fn handle(&self, req: Request<Body>) -> Response<Body> {
+        if req.method() != Method::GET && req.method() != Method::HEAD {
+            return status(StatusCode::METHOD_NOT_ALLOWED);
+        }
+
+        let path = req.uri().path();
+        if self.health_path.as_deref() == Some(path) {
+            return Response::new(Body::from("OK"));
+        }
+        if path != self.path {
+            return status(StatusCode::NOT_FOUND);
+        }
+
+        let metric_families = self.registry.gather();
+        let mut body = Vec::new();
+        let content_type = if accepts(&req, header::ACCEPT, OPENMETRICS_MEDIA_TYPE) {
+            encode_openmetrics(&metric_families, &mut body)
+                .map(|_| OPENMETRICS_CONTENT_TYPE)
+                .map_err(|err| err.to_string())
+        } else {
+            let encoder = TextEncoder::new();
+            encoder
+                .encode(&metric_families, &mut body)
+                .map(|_| prometheus::TEXT_FORMAT)
+                .map_err(|err| err.to_string())
+        };
+        let content_type = match content_type {
+            Ok(content_type) => content_type,
+            Err(err) => {
+                opentelemetry::global::handle_error(MetricsError::Other(err));
+                return status(StatusCode::INTERNAL_SERVER_ERROR);
+            }
+        };
+
+        let mut response = Response::builder()
+            .header(header::CONTENT_TYPE, content_type)
+            .header(header::VARY, "Accept, Accept-Encoding");
+        if self.gzip && accepts(&req, header::ACCEPT_ENCODING, "gzip") {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            if let Ok(compressed) = encoder.write_all(&body).and_then(|_| encoder.finish()) {
+                body = compressed;
+                response = response.header(header::CONTENT_ENCODING, "gzip");
+            }
+        }
+
+        response
+            .body(Body::from(body))
+            .unwrap_or_else(|_| status(StatusCode::INTERNAL_SERVER_ERROR))
+    }
+}
+
+fn status(status: StatusCode) -> Response<Body> {
+    let mut response = Response::new(Body::empty());
+    *response.status_mut() = status;
+    response
+}
+
+/// Whether any of the comma separated values of `name` has the media type or
+/// coding `value`, values with a `q=0` parameter being refused.
+fn accepts(req: &Request<Body>, name: header::HeaderName, value: &str) -> bool {
+    req.headers()
+        .get_all(name)
+        .iter()
+        .filter_map(|header| header.to_str().ok())
+        .flat_map(|header| header.split(','))
+        .any(|item| {
+            let mut parts = item.split(';');
+            match parts.next() {
+                Some(item) if item.trim().eq_ignore_ascii_case(value) => {
+                    !parts.any(is_zero_quality)
+                }
+                _ => false,
+            }
+        })
+}
+
+/// Whether `param` is a `q` parameter with a weight of zero.
+fn is_zero_quality(param: &str) -> bool {
+    let mut parts = param.splitn(2, '=');
+    match (parts.next(), parts.next()) {
+        (Some(key), Some(weight)) if key.trim().eq_ignore_ascii_case("q") => {
+            match weight.trim().parse::<f32>() {
+                Ok(weight) => weight <= 0.0,
+                Err(_) => false,
+            }
+        }
+        _ => false,
+    }
+}
+
+/// Encode metric families in the OpenMetrics text format.
+fn encode_openmetrics<W: Write>(
+    metric_families: &[MetricFamily],
+    writer: &mut W,
+) -> io::Result<()> {
+    for mf in metric_families {
+        let metric_type = mf.get_field_type();
+        let name = match metric_type {
+            MetricType::COUNTER => mf.get_name().trim_end_matches("_total"),
+            _ => mf.get_name(),
+        };
+        let type_name = match metric_type {
+            MetricType::COUNTER => "counter",
+            MetricType::GAUGE => "gauge",
+            MetricType::HISTOGRAM => "histogram",
+            MetricType::SUMMARY => "summary",
+            MetricType::UNTYPED => "unknown",
+        };
+
+        writeln!(writer, "# TYPE {} {}", name, type_name)?;
+        if !mf.get_help().is_empty() {
+            writeln!(writer, "# HELP {} {}", name, escape(mf.get_help()))?;
+        }
+
+        for m in mf.get_metric() {
+            match metric_type {
+                MetricType::COUNTER => {
+                    write_sample(writer, name, "_total", m, None, m.get_counter().get_value())?
+                }
+                MetricType::GAUGE => {
+                    write_sample(writer, name, "", m, None, m.get_gauge().get_value())?
+                }
+                MetricType::UNTYPED => {
+                    write_sample(writer, name, "", m, None, m.get_untyped().get_value())?
+                }
+                MetricType::HISTOGRAM => {
+                    let h = m.get_histogram();
+                    let mut inf_seen = false;
+                    for b in h.get_bucket() {
+                        let upper_bound = b.get_upper_bound();
+                        inf_seen |= upper_bound.is_infinite() && upper_bound.is_sign_positive();
+                        write_sample(
+                            writer,
+                            name,
+                            "_bucket",
+                            m,
+                            Some(("le", &format_float(upper_bound))),
+                            b.get_cumulative_count() as f64,
+                        )?;
+                    }
+                    if !inf_seen {
+                        write_sample(
+                            writer,
+                            name,
+                            "_bucket",
+                            m,
+                            Some(("le", "+Inf")),
+                            h.get_sample_count() as f64,
+                        )?;
+                    }
+                    write_sample(writer, name, "_sum", m, None, h.get_sample_sum())?;
+                    write_sample(writer, name, "_count", m, None, h.get_sample_count() as f64)?;
+                }
+                MetricType::SUMMARY => {
+                    let s = m.get_summary();
+                    for q in s.get_quantile() {
+                        write_sample(
+                            writer,
+                            name,
+                            "",
+                            m,
+                            Some(("quantile", &format_float(q.get_quantile()))),
+                            q.get_value(),
+                        )?;
+                    }
+                    write_sample(writer, name, "_sum", m, None, s.get_sample_sum())?;
+                    write_sample(writer, name, "_count", m, None, s.get_sample_count() as f64)?;
+                }
+            }
+        }
+    }
+
+    writer.write_all(b"# EOF\n")
+}
+
+fn write_sample<W: Write>(
+    writer: &mut W,
+    name: &str,
+    suffix: &str,
+    metric: &Metric,
+    additional_label: Option<(&str, &str)>,
+    value: f64,
+) -> io::Result<()> {
+    write!(writer, "{}{}", name, suffix)?;
+
+    let labels = metric
+        .get_label()
+        .iter()
+        .map(|lp: &LabelPair| (lp.get_name(), lp.get_value()))
+        .chain(additional_label);
+    let mut separator = '{';
+    for (key, value) in labels {
+        write!(writer, "{}{}=\"{}\"", separator, key, escape(value))?;
+        separator = ',';
+    }
+    if separator == ',' {
+        writer.write_all(b"}")?;
+    }
+
+    writeln!(writer, " {}", format_float(value))
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', r"\\")
+        .replace('\n', r"\n")
+        .replace('"', "\\\"")
+}
+
+fn format_float(value: f64) -> String {
+    if value.is_nan() {
+        "NaN".to_string()
+    } else if value.is_infinite() {
+        if value.is_sign_positive() {
+            "+Inf".to_string()
+        } else {
+            "-Inf".to_string()
+        }
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn openmetrics_counter_and_histogram() {
+        let registry = prometheus::Registry::new();
+        let counter = prometheus::IntCounterVec::new(
+            prometheus::Opts::new("requests_total", "Handled \"requests\""),
+            &["path"],
+        )
+        .unwrap();
+        counter.with_label_values(&["/a"]).inc_by(3);
+        let histogram = prometheus::Histogram::with_opts(
+            prometheus::HistogramOpts::new("latency", "Latency").buckets(vec![1.0]),
+        )
+        .unwrap();
+        histogram.observe(0.5);
+        registry.register(Box::new(counter)).unwrap();
+        registry.register(Box::new(histogram)).unwrap();
+
+        let mut output = Vec::new();
+        encode_openmetrics(&registry.gather(), &mut output).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "# TYPE latency histogram\n\
+             # HELP latency Latency\n\
+             latency_bucket{le=\"1\"} 1\n\
+             latency_bucket{le=\"+Inf\"} 1\n\
+             latency_sum 0.5\n\
+             latency_count 1\n\
+             # TYPE requests counter\n\
+             # HELP requests Handled \\\"requests\\\"\n\
+             requests_total{path=\"/a\"} 3\n\
+             # EOF\n"
+        );
+    }
+
+    #[test]
+    fn accepts_skips_zero_quality() {
+        let req = Request::builder()
+            .header(
+                header::ACCEPT,
+                "text/plain, application/openmetrics-text; q=0",
+            )
+            .header(header::ACCEPT_ENCODING, "deflate, gzip;q=0.5")
+            .body(Body::empty())
+            .unwrap();
+
+        assert!(accepts(&req, header::ACCEPT, "text/plain"));
+        assert!(!accepts(&req, header::ACCEPT, OPENMETRICS_MEDIA_TYPE));
+        assert!(accepts(&req, header::ACCEPT_ENCODING, "gzip"));
+
+        let req = Request::builder()
+            .header(header::ACCEPT_ENCODING, "gzip;Q=0.000")
+            .body(Body::empty())
+            .unwrap();
+
+        assert!(!accepts(&req, header::ACCEPT_ENCODING, "gzip"));
+    }
+}
@@ -1,7 +1,10 @@
-use opentelemetry::sdk::Resource;
+use opentelemetry::sdk::{
+    metrics::{aggregators::DDSketchConfig, selectors::simple::Selector},
+    Resource,
+};
 use opentelemetry::{
     metrics::{MeterProvider, ObserverResult},
-    KeyValue,
+    Key, KeyValue, Unit,
 };
 use opentelemetry_prometheus::PrometheusExporter;
 use prometheus::{Encoder, TextEncoder};
@@ -56,6 +59,95 @@ fn test_add() {
     compare_export(&exporter, expected)
 }
 
+#[test]
+fn test_target_info() {
+    let exporter = opentelemetry_prometheus::exporter()
+        .with_resource(Resource::new(vec![
+            KeyValue::new("service.name", "checkout"),
+            KeyValue::new("host.name", "host-1"),
+        ]))
+        .with_target_info(true)
+        .with_resource_labels(vec![Key::new("service.name")])
+        .with_name_suffixes(true)
+        .init();
+
+    let meter = exporter.provider().unwrap().meter("test");
+
+    let counter = meter
+        .u64_counter("rpc.received")
+        .with_unit(Unit::new("By"))
+        .init();
+    let value_recorder = meter
+        .f64_value_recorder("rpc.duration")
+        .with_unit(Unit::new("s"))
+        .init();
+
+    let labels = vec![KeyValue::new("A", "B")];
+
+    counter.add(512, &labels);
+    value_recorder.record(0.25, &labels);
+
+    let expected = vec![
+        r#"target_info{host_name="host-1",service_name="checkout"} 1"#,
+        r#"rpc_received_bytes_total{A="B",service_name="checkout"} 512"#,
+        r#"rpc_duration_seconds_bucket{A="B",service_name="checkout",le="0.5"} 1"#,
+        r#"rpc_duration_seconds_bucket{A="B",service_name="checkout",le="0.9"} 1"#,
+        r#"rpc_duration_seconds_bucket{A="B",service_name="checkout",le="0.99"} 1"#,
+        r#"rpc_duration_seconds_bucket{A="B",service_name="checkout",le="+Inf"} 1"#,
+        r#"rpc_duration_seconds_sum{A="B",service_name="checkout"} 0.25"#,
+        r#"rpc_duration_seconds_count{A="B",service_name="checkout"} 1"#,
+    ];
+
+    compare_export(&exporter, expected)
+}
+
+#[test]
+fn test_summaries() {
+    let cases = vec![
+        (
+            Selector::Exact,
+            vec![
+                r#"value_recorder{A="B",quantile="0.5"} 3"#,
+                r#"value_recorder{A="B",quantile="1"} 4"#,
+            ],
+        ),
+        (
+            Selector::Sketch(DDSketchConfig::new(0.01, 2048, 1e-9)),
+            vec![
+                r#"value_recorder{A="B",quantile="0.5"} 1.993661701417345"#,
+                r#"value_recorder{A="B",quantile="1"} 4"#,
+            ],
+        ),
+        (
+            Selector::Inexpensive,
+            vec![
+                r#"value_recorder{A="B",quantile="0"} 1"#,
+                r#"value_recorder{A="B",quantile="1"} 4"#,
+            ],
+        ),
+    ];
+
+    for (selector, quantiles) in cases {
+        let exporter = opentelemetry_prometheus::exporter()
+            .with_aggregator_selector(selector)
+            .with_default_summary_quantiles(vec![0.5, 1.0])
+            .init();
+
+        let meter = exporter.provider().unwrap().meter("test");
+        let value_recorder = meter.f64_value_recorder("value_recorder").init();
+        let labels = vec![KeyValue::new("A", "B")];
+        for value in &[1.0, 2.0, 3.0, 4.0] {
+            value_recorder.record(*value, &labels);
+        }
+
+        let mut expected = quantiles;
+        expected.push(r#"value_recorder_count{A="B"} 4"#);
+        expected.push(r#"value_recorder_sum{A="B"} 10"#);
+
+        compare_export(&exporter, expected)
+    }
+}
+
 fn compare_export(exporter: &PrometheusExporter, mut expected: Vec<&'static str>) {
     let mut output = Vec::new();
     let encoder = TextEncoder::new();
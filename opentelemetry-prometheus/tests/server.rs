@@ -0,0 +1,190 @@
+use flate2::read::GzDecoder;
+use hyper::{body, header, Body, Client, Request, StatusCode};
+use opentelemetry::metrics::{Descriptor, MeterProvider, NumberKind, Result};
+use opentelemetry::sdk::export::metrics::{
+    CheckpointSet, ExportKind, ExportKindFor, ExportKindSelector, Exporter, Sum,
+};
+use opentelemetry::sdk::metrics::{aggregators::SumAggregator, controllers, selectors};
+use opentelemetry::KeyValue;
+use opentelemetry_prometheus::MetricsServer;
+use std::io::Read;
+use std::sync::{Arc, Mutex};
+
+fn start(builder: opentelemetry_prometheus::ServerBuilder) -> MetricsServer {
+    builder
+        .with_address(([127, 0, 0, 1], 0).into())
+        .start()
+        .expect("server should bind loopback")
+}
+
+async fn scrape(
+    server: &MetricsServer,
+    path: &str,
+    headers: &[(header::HeaderName, &str)],
+) -> (StatusCode, header::HeaderMap, Vec<u8>) {
+    let mut request = Request::get(format!("http://{}{}", server.local_addr(), path));
+    for (name, value) in headers {
+        request = request.header(name, *value);
+    }
+    let response = Client::new()
+        .request(request.body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let (parts, body) = response.into_parts();
+    let body = body::to_bytes(body).await.unwrap().to_vec();
+    (parts.status, parts.headers, body)
+}
+
+#[tokio::test]
+async fn scrape_text_and_openmetrics() {
+    let exporter = opentelemetry_prometheus::exporter().init();
+    let meter = exporter.provider().unwrap().meter("test");
+    let counter = meter.u64_counter("requests").init();
+    counter.add(3, &[KeyValue::new("path", "/a")]);
+
+    let server =
+        start(opentelemetry_prometheus::server(exporter.registry().clone()).with_gzip(false));
+
+    let (status, headers, body) = scrape(&server, "/metrics", &[]).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(headers[header::CONTENT_TYPE], "text/plain; version=0.0.4");
+    let body = String::from_utf8(body).unwrap();
+    assert!(body.contains("# TYPE requests counter"), "{}", body);
+    assert!(body.contains(r#"requests{path="/a"} 3"#), "{}", body);
+
+    let (status, headers, body) = scrape(
+        &server,
+        "/metrics",
+        &[(
+            header::ACCEPT,
+            "application/openmetrics-text; version=1.0.0,text/plain;q=0.5",
+        )],
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        headers[header::CONTENT_TYPE],
+        "application/openmetrics-text; version=1.0.0; charset=utf-8"
+    );
+    let body = String::from_utf8(body).unwrap();
+    assert!(body.contains(r#"requests_total{path="/a"} 3"#), "{}", body);
+    assert!(body.ends_with("# EOF\n"), "{}", body);
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn scrape_gzip() {
+    let registry = prometheus::Registry::new();
+    let gauge = prometheus::Gauge::new("temperature", "Temperature").unwrap();
+    gauge.set(21.5);
+    registry.register(Box::new(gauge)).unwrap();
+
+    let server = start(opentelemetry_prometheus::server(registry));
+
+    let (status, headers, body) = scrape(
+        &server,
+        "/metrics",
+        &[(header::ACCEPT_ENCODING, "gzip, deflate")],
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(headers[header::CONTENT_ENCODING], "gzip");
+    let mut decoded = String::new();
+    GzDecoder::new(body.as_slice())
+        .read_to_string(&mut decoded)
+        .unwrap();
+    assert!(decoded.contains("temperature 21.5"), "{}", decoded);
+
+    let (_, headers, _) = scrape(&server, "/metrics", &[]).await;
+    assert!(headers.get(header::CONTENT_ENCODING).is_none());
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn health_and_unknown_paths() {
+    let server = start(
+        opentelemetry_prometheus::server(prometheus::Registry::new())
+            .with_path("/custom")
+            .with_health_path("/health"),
+    );
+
+    let (status, _, body) = scrape(&server, "/health", &[]).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, b"OK");
+
+    let (status, _, _) = scrape(&server, "/metrics", &[]).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, _, _) = scrape(&server, "/custom", &[]).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let addr = server.local_addr();
+    server.shutdown().await;
+    assert!(std::net::TcpStream::connect(addr).is_err());
+}
+
+/// Records the delta sums pushed by a `MultiController` push reader.
+#[derive(Debug)]
+struct SumExporter(Arc<Mutex<Vec<u64>>>);
+
+impl ExportKindFor for SumExporter {
+    fn export_kind_for(&self, descriptor: &Descriptor) -> ExportKind {
+        ExportKindSelector::Delta.export_kind_for(descriptor)
+    }
+}
+
+impl Exporter for SumExporter {
+    fn export(&self, checkpoint_set: &mut dyn CheckpointSet) -> Result<()> {
+        checkpoint_set.try_for_each(self, &mut |record| {
+            if let Some(sum) = record
+                .aggregator()
+                .and_then(|agg| agg.as_any().downcast_ref::<SumAggregator>())
+            {
+                self.0.lock()?.push(sum.sum()?.to_u64(&NumberKind::U64));
+            }
+            Ok(())
+        })
+    }
+}
+
+#[tokio::test]
+async fn scrape_multi_controller_pull_reader() {
+    let pushed = Arc::new(Mutex::new(Vec::new()));
+    let workers = Mutex::new(Vec::new());
+    let controller = controllers::multi()
+        .with_push_reader(controllers::push_reader(
+            selectors::simple::Selector::Inexpensive,
+            ExportKindSelector::Delta,
+            SumExporter(pushed.clone()),
+        ))
+        .with_pull_reader(controllers::pull_reader(
+            Box::new(selectors::simple::Selector::Inexpensive),
+            Box::new(ExportKindSelector::Cumulative),
+        ))
+        .build(
+            |worker| workers.lock().unwrap().push(worker),
+            |_| tokio::stream::pending::<()>(),
+        );
+    let exporter = opentelemetry_prometheus::exporter()
+        .with_pull_reader(controller.pull_readers()[0].clone())
+        .init();
+    let counter = controller
+        .provider()
+        .meter("test")
+        .u64_counter("requests")
+        .init();
+    counter.add(3, &[KeyValue::new("path", "/a")]);
+
+    let server =
+        start(opentelemetry_prometheus::server(exporter.registry().clone()).with_gzip(false));
+    let (status, _, body) = scrape(&server, "/metrics", &[]).await;
+    assert_eq!(status, StatusCode::OK);
+    let body = String::from_utf8(body).unwrap();
+    assert!(body.contains(r#"requests{path="/a"} 3"#), "{}", body);
+
+    // Dropping the push worker pushes one last time, from the same accumulator.
+    workers.lock().unwrap().clear();
+    assert_eq!(*pushed.lock().unwrap(), vec![3]);
+}
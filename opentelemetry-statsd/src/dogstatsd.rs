@@ -0,0 +1,77 @@
+//! DogStatsD line formatting, shared with the DogStatsD exporter of
+//! `opentelemetry-contrib`.
+//!
+//! Lines have the `name:value|type|#key:value,key:value` form. The characters
+//! delimiting the parts of lines are replaced with `_` in names, tag keys and
+//! tag values, `:` is allowed in tag values only.
+
+/// Replaces the delimiters of DogStatsD lines in metric names and tag keys.
+pub fn sanitize_name(name: &str) -> String {
+    sanitize(name, true)
+}
+
+/// Replaces the delimiters of DogStatsD lines in tag values.
+pub fn sanitize_tag_value(value: &str) -> String {
+    sanitize(value, false)
+}
+
+fn sanitize(s: &str, is_name: bool) -> String {
+    s.chars()
+        .map(|c| match c {
+            '|' | ',' | '#' | '\n' => '_',
+            ':' | '@' if is_name => '_',
+            c => c,
+        })
+        .collect()
+}
+
+/// Formats comma separated `key:value` tags.
+pub fn tags<'a, I>(tags: I) -> String
+where
+    I: IntoIterator<Item = (&'a str, &'a str)>,
+{
+    tags.into_iter()
+        .map(|(key, value)| format!("{}:{}", sanitize_name(key), sanitize_tag_value(value)))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Formats the `|type|#tags` end of lines, `tags` as formatted by [`tags`].
+///
+/// [`tags`]: fn.tags.html
+pub fn suffix(metric_type: &str, tags: &str) -> String {
+    let mut suffix = format!("|{}", metric_type);
+    if !tags.is_empty() {
+        suffix.push_str("|#");
+        suffix.push_str(tags);
+    }
+    suffix
+}
+
+/// Formats a `name:value|type|#tags` line, `tags` as formatted by [`tags`].
+///
+/// [`tags`]: fn.tags.html
+pub fn line(name: &str, value: &str, metric_type: &str, tags: &str) -> String {
+    let mut line = sanitize_name(name);
+    line.push(':');
+    line.push_str(value);
+    line.push_str(&suffix(metric_type, tags));
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_lines() {
+        let tags = tags(vec![("route:v1", "/users|all"), ("url", "http://a:80")]);
+
+        assert_eq!(tags, "route_v1:/users_all,url:http://a:80");
+        assert_eq!(
+            line("http.requests@v1", "3", "c", &tags),
+            "http.requests_v1:3|c|#route_v1:/users_all,url:http://a:80"
+        );
+        assert_eq!(line("latency", "1.5", "d", ""), "latency:1.5|d");
+    }
+}
@@ -0,0 +1,221 @@
+use crate::dogstatsd;
+use std::fmt::Write as _;
+
+/// Output format of exported metrics.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    /// StatsD lines with labels appended to the dotted metric path, e.g.
+    /// `requests.method.GET:1|c`.
+    Statsd,
+    /// StatsD lines with DogStatsD tags, e.g. `requests:1|c|#method:GET`.
+    DogStatsd,
+    /// Graphite plaintext lines with labels appended to the dotted metric
+    /// path, e.g. `requests.method.GET 1 1600000000`.
+    Graphite,
+    /// Graphite plaintext lines with Graphite tags, e.g.
+    /// `requests;method=GET 1 1600000000`.
+    GraphiteTagged,
+}
+
+impl Format {
+    /// Whether this is one of the StatsD formats.
+    pub(crate) fn is_statsd(self) -> bool {
+        match self {
+            Format::Statsd | Format::DogStatsd => true,
+            Format::Graphite | Format::GraphiteTagged => false,
+        }
+    }
+}
+
+/// StatsD metric type of a series, ignored by the Graphite formats.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum MetricType {
+    Counter,
+    Gauge,
+    Timer,
+}
+
+impl MetricType {
+    fn as_str(self) -> &'static str {
+        match self {
+            MetricType::Counter => "c",
+            MetricType::Gauge => "g",
+            MetricType::Timer => "ms",
+        }
+    }
+}
+
+/// A single value of an exported metric.
+#[derive(Debug)]
+pub(crate) struct Series<'a> {
+    /// The metric name.
+    pub(crate) name: &'a str,
+    /// Suffix of aggregations exported as several series, e.g. `max`.
+    pub(crate) suffix: Option<&'a str>,
+    /// The labels of the metric.
+    pub(crate) labels: &'a [(String, String)],
+    /// Extra label of the series, e.g. the bucket boundary of histograms.
+    pub(crate) extra_label: Option<(&'a str, &'a str)>,
+    pub(crate) value: String,
+    pub(crate) metric_type: MetricType,
+}
+
+impl Format {
+    /// Formats `series` as a line without trailing newline, `timestamp` is
+    /// the unix time in seconds of Graphite lines.
+    pub(crate) fn line(self, prefix: Option<&str>, series: &Series<'_>, timestamp: u64) -> String {
+        let labels = series
+            .labels
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+            .chain(series.extra_label);
+
+        let mut line = String::new();
+        if let Some(prefix) = prefix {
+            line.push_str(&sanitize_path(prefix));
+            line.push('.');
+        }
+        line.push_str(&sanitize_path(series.name));
+        let mut tags = Vec::new();
+        let mut dogstatsd_tags = String::new();
+        match self {
+            Format::Statsd | Format::Graphite => {
+                for (key, value) in labels {
+                    let _ = write!(
+                        line,
+                        ".{}.{}",
+                        sanitize_segment(key),
+                        sanitize_segment(value)
+                    );
+                }
+            }
+            Format::DogStatsd => dogstatsd_tags = dogstatsd::tags(labels),
+            Format::GraphiteTagged => tags.extend(
+                labels.map(|(key, value)| format!("{}={}", sanitize_tag(key), sanitize_tag(value))),
+            ),
+        }
+        if let Some(suffix) = series.suffix {
+            line.push('.');
+            line.push_str(suffix);
+        }
+
+        match self {
+            Format::Statsd => {
+                let _ = write!(line, ":{}|{}", series.value, series.metric_type.as_str());
+            }
+            Format::DogStatsd => {
+                line = dogstatsd::line(
+                    &line,
+                    &series.value,
+                    series.metric_type.as_str(),
+                    &dogstatsd_tags,
+                );
+            }
+            Format::Graphite | Format::GraphiteTagged => {
+                for tag in tags {
+                    line.push(';');
+                    line.push_str(&tag);
+                }
+                let _ = write!(line, " {} {}", series.value, timestamp);
+            }
+        }
+
+        line
+    }
+}
+
+/// Replaces the characters that are not allowed in dotted metric paths.
+fn sanitize_path(path: &str) -> String {
+    path.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '_' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Replaces the characters that are not allowed in a single segment of
+/// dotted metric paths.
+fn sanitize_segment(segment: &str) -> String {
+    segment
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Replaces the delimiters of Graphite tags.
+fn sanitize_tag(tag: &str) -> String {
+    tag.chars()
+        .map(|c| match c {
+            '\n' | ' ' | ';' | '=' | '!' | '^' | '~' => '_',
+            c => c,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn series<'a>(labels: &'a [(String, String)], suffix: Option<&'a str>) -> Series<'a> {
+        Series {
+            name: "http.requests",
+            suffix,
+            labels,
+            extra_label: None,
+            value: "3".to_string(),
+            metric_type: MetricType::Counter,
+        }
+    }
+
+    #[test]
+    fn formats_lines() {
+        let labels = vec![
+            ("method".to_string(), "GET".to_string()),
+            ("route".to_string(), "/users;all".to_string()),
+        ];
+        let series = series(&labels, None);
+
+        assert_eq!(
+            Format::Statsd.line(Some("app"), &series, 10),
+            "app.http.requests.method.GET.route._users_all:3|c"
+        );
+        assert_eq!(
+            Format::DogStatsd.line(None, &series, 10),
+            "http.requests:3|c|#method:GET,route:/users;all"
+        );
+        assert_eq!(
+            Format::Graphite.line(None, &series, 10),
+            "http.requests.method.GET.route._users_all 3 10"
+        );
+        assert_eq!(
+            Format::GraphiteTagged.line(None, &series, 10),
+            "http.requests;method=GET;route=/users_all 3 10"
+        );
+    }
+
+    #[test]
+    fn formats_series_suffix_and_extra_label() {
+        let labels = vec![("method".to_string(), "GET".to_string())];
+        let mut series = series(&labels, Some("bucket"));
+        series.extra_label = Some(("le", "0.5"));
+
+        assert_eq!(
+            Format::Statsd.line(None, &series, 10),
+            "http.requests.method.GET.le.0_5.bucket:3|c"
+        );
+        assert_eq!(
+            Format::GraphiteTagged.line(None, &series, 10),
+            "http.requests.bucket;method=GET;le=0.5 3 10"
+        );
+    }
+}
@@ -0,0 +1,723 @@
+//! # OpenTelemetry StatsD Exporter
+//!
+//! Exports metrics to [StatsD] over UDP or [Graphite] with its plaintext
+//! protocol over TCP, in either dotted-path or tagged output.
+//!
+//! Metrics are exported with delta temporality, each export reports the
+//! change of sums since the previous export:
+//!
+//! * Sums of counters and sum observers are reported as counters.
+//! * Last values of value observers are reported as gauges.
+//! * Value recorders are reported as several series, `min` and `max` gauges
+//!   and `sum` and `count` counters. Histograms report `sum`, `count` and a
+//!   `bucket` counter per boundary with a `le` label, and with the exact
+//!   aggregator selector the StatsD formats report each value as a timer.
+//!
+//! Metric names are the instrument name, optionally prefixed. The dotted-path
+//! formats append each label key and value to the name as path segments,
+//! while the tagged formats report them as tags. Resource attributes are
+//! reported as labels of all metrics.
+//!
+//! [StatsD]: https://github.com/statsd/statsd
+//! [Graphite]: https://graphite.readthedocs.io/en/latest/feeding-carbon.html
+//!
+//! ### StatsD Exporter Example
+//!
+//! ```no_run
+//! use opentelemetry::{global, KeyValue};
+//! use opentelemetry_statsd::Format;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> opentelemetry::metrics::Result<()> {
+//! let _controller = opentelemetry_statsd::new_pipeline(tokio::spawn, tokio::time::interval)
+//!     .with_format(Format::Graphite)
+//!     .with_endpoint("127.0.0.1:2003")
+//!     .with_prefix("my_app")
+//!     .try_init()?;
+//!
+//! let meter = global::meter("my-app");
+//! let counter = meter.u64_counter("requests").init();
+//! counter.add(1, &[KeyValue::new("method", "GET")]);
+//!
+//! // my_app.requests.method.GET 1 1600000000
+//! # Ok(())
+//! # }
+//! ```
+#![warn(
+    future_incompatible,
+    missing_debug_implementations,
+    missing_docs,
+    nonstandard_style,
+    rust_2018_idioms,
+    unreachable_pub,
+    unused
+)]
+#![cfg_attr(docsrs, feature(doc_cfg), deny(broken_intra_doc_links))]
+#![doc(
+    html_logo_url = "https://raw.githubusercontent.com/open-telemetry/opentelemetry-rust/master/assets/logo.svg"
+)]
+#![cfg_attr(test, deny(warnings))]
+
+use futures::Stream;
+use opentelemetry::global;
+use opentelemetry::metrics::{Descriptor, MetricsError, Number, NumberKind, Result};
+use opentelemetry::sdk::{
+    export::metrics::{
+        CheckpointSet, Count, ExportKind, ExportKindFor, ExportKindSelector, Exporter, Histogram,
+        LastValue, Max, Min, Points, Record, Sum,
+    },
+    metrics::{
+        aggregators::{
+            ArrayAggregator, DDSKetchAggregator, HistogramAggregator, LastValueAggregator,
+            MinMaxSumCountAggregator, SumAggregator,
+        },
+        controllers::{self, PushController, PushControllerWorker},
+        selectors::simple,
+    },
+    Resource,
+};
+use std::io::{self, Write};
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs, UdpSocket};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+pub mod dogstatsd;
+mod format;
+
+pub use format::Format;
+use format::{MetricType, Series};
+
+/// Default max packet size of UDP datagrams, fitting in an ethernet frame.
+const DEFAULT_MAX_PACKET_SIZE: usize = 1432;
+
+/// Timeout of Graphite TCP connections and writes.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Create a new StatsD exporter pipeline builder.
+pub fn new_pipeline<S, SO, I, IS, ISI>(spawn: S, interval: I) -> StatsdPipelineBuilder<S, I>
+where
+    S: Fn(PushControllerWorker) -> SO,
+    I: Fn(Duration) -> IS,
+    IS: Stream<Item = ISI> + Send + 'static,
+{
+    StatsdPipelineBuilder {
+        spawn,
+        interval,
+        format: Format::Statsd,
+        endpoint: None,
+        transport: None,
+        prefix: None,
+        max_packet_size: DEFAULT_MAX_PACKET_SIZE,
+        aggregator_selector: simple::Selector::Inexpensive,
+        period: None,
+        resource: None,
+    }
+}
+
+/// Transport protocol used to send metrics.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Transport {
+    /// Lines are batched in UDP datagrams, the default of StatsD formats.
+    Udp,
+    /// Lines are written to a TCP connection opened when the exporter is
+    /// built, the default of Graphite formats. The connection is reopened in
+    /// the background after write errors.
+    Tcp,
+}
+
+#[derive(Debug)]
+enum Connection {
+    Udp(UdpSocket),
+    Tcp(TcpConnection),
+}
+
+/// Graphite TCP connection, reopened by a background thread so that exports
+/// never wait for a connection to be established.
+#[derive(Debug)]
+struct TcpConnection {
+    addr: SocketAddr,
+    stream: Arc<Mutex<Option<TcpStream>>>,
+    reconnecting: Arc<AtomicBool>,
+}
+
+impl TcpConnection {
+    fn connect(addr: SocketAddr) -> io::Result<Self> {
+        Ok(TcpConnection {
+            addr,
+            stream: Arc::new(Mutex::new(Some(open_stream(&addr)?))),
+            reconnecting: Arc::new(AtomicBool::new(false)),
+        })
+    }
+
+    fn send(&self, lines: &[String]) -> io::Result<()> {
+        let mut stream = self.stream.lock().unwrap_or_else(PoisonError::into_inner);
+        let result = match stream.as_mut() {
+            Some(stream) => {
+                let mut writer = io::BufWriter::new(stream);
+                lines
+                    .iter()
+                    .try_for_each(|line| {
+                        writer.write_all(line.as_bytes())?;
+                        writer.write_all(b"\n")
+                    })
+                    .and_then(|_| writer.flush())
+            }
+            None => Err(io::Error::new(
+                io::ErrorKind::NotConnected,
+                format!("reconnecting to {}", self.addr),
+            )),
+        };
+        if result.is_err() {
+            *stream = None;
+            self.reconnect();
+        }
+
+        result
+    }
+
+    /// Opens a new connection in a background thread, unless one is already
+    /// being opened.
+    fn reconnect(&self) {
+        if self.reconnecting.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        let addr = self.addr;
+        let stream = self.stream.clone();
+        let reconnecting = self.reconnecting.clone();
+        thread::spawn(move || {
+            if let Ok(new_stream) = open_stream(&addr) {
+                *stream.lock().unwrap_or_else(PoisonError::into_inner) = Some(new_stream);
+            }
+            reconnecting.store(false, Ordering::SeqCst);
+        });
+    }
+}
+
+fn open_stream(addr: &SocketAddr) -> io::Result<TcpStream> {
+    let stream = TcpStream::connect_timeout(addr, CONNECT_TIMEOUT)?;
+    stream.set_write_timeout(Some(CONNECT_TIMEOUT))?;
+    Ok(stream)
+}
+
+/// StatsD and Graphite metrics exporter
+#[derive(Debug)]
+pub struct StatsdExporter {
+    connection: Connection,
+    format: Format,
+    prefix: Option<String>,
+    max_packet_size: usize,
+}
+
+impl StatsdExporter {
+    /// Appends the lines of `record` to `lines`.
+    fn write_lines(&self, record: &Record<'_>, lines: &mut Vec<String>) -> Result<()> {
+        let agg = record.aggregator().ok_or(MetricsError::NoDataCollected)?;
+        let desc = record.descriptor();
+        let kind = desc.number_kind();
+        let labels = record
+            .resource()
+            .iter()
+            .chain(record.labels().iter())
+            .map(|(key, value)| (key.as_str().to_string(), value.as_str().into_owned()))
+            .collect::<Vec<_>>();
+        let timestamp = record
+            .end_time()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|time| time.as_secs())
+            .unwrap_or(0);
+        let mut push = |suffix: Option<&str>,
+                        extra_label: Option<(&str, &str)>,
+                        value: String,
+                        metric_type: MetricType| {
+            let series = Series {
+                name: desc.name(),
+                suffix,
+                labels: &labels,
+                extra_label,
+                value,
+                metric_type,
+            };
+            lines.push(self.format.line(self.prefix.as_deref(), &series, timestamp));
+        };
+
+        if let Some(sum) = agg.as_any().downcast_ref::<SumAggregator>() {
+            push(
+                None,
+                None,
+                format_number(&sum.sum()?, kind),
+                MetricType::Counter,
+            );
+        } else if let Some(last_value) = agg.as_any().downcast_ref::<LastValueAggregator>() {
+            let (value, _) = last_value.last_value()?;
+            // Signed StatsD gauges are relative, negative values are set by
+            // resetting the gauge first.
+            if self.format == Format::Statsd && value.is_negative(kind) {
+                push(None, None, "0".to_string(), MetricType::Gauge);
+            }
+            push(None, None, format_number(&value, kind), MetricType::Gauge);
+        } else if let Some(histogram) = agg.as_any().downcast_ref::<HistogramAggregator>() {
+            let buckets = histogram.histogram()?;
+            let mut count = 0.0;
+            for (index, bucket_count) in buckets.counts().iter().enumerate() {
+                count += bucket_count;
+                let boundary = buckets
+                    .boundaries()
+                    .get(index)
+                    .map(|boundary| boundary.to_string())
+                    .unwrap_or_else(|| "+Inf".to_string());
+                push(
+                    Some("bucket"),
+                    Some(("le", &boundary)),
+                    count.to_string(),
+                    MetricType::Counter,
+                );
+            }
+            push(
+                Some("sum"),
+                None,
+                format_number(&histogram.sum()?, kind),
+                MetricType::Counter,
+            );
+            push(
+                Some("count"),
+                None,
+                histogram.count()?.to_string(),
+                MetricType::Counter,
+            );
+        } else if let Some(array) = agg.as_any().downcast_ref::<ArrayAggregator>() {
+            if self.format.is_statsd() {
+                for point in array.points()? {
+                    push(None, None, format_number(&point, kind), MetricType::Timer);
+                }
+            } else {
+                min_max_sum_count(array, kind, &mut push)?;
+            }
+        } else if let Some(mmsc) = agg.as_any().downcast_ref::<MinMaxSumCountAggregator>() {
+            min_max_sum_count(mmsc, kind, &mut push)?;
+        } else if let Some(sketch) = agg.as_any().downcast_ref::<DDSKetchAggregator>() {
+            min_max_sum_count(sketch, kind, &mut push)?;
+        }
+
+        Ok(())
+    }
+
+    fn send(&self, lines: Vec<String>) -> io::Result<()> {
+        match &self.connection {
+            Connection::Udp(socket) => {
+                // Lines longer than the max packet size are sent on their own.
+                let mut packet = String::with_capacity(self.max_packet_size);
+                for line in lines {
+                    if !packet.is_empty() && packet.len() + 1 + line.len() > self.max_packet_size {
+                        socket.send(packet.as_bytes())?;
+                        packet.clear();
+                    }
+                    if !packet.is_empty() {
+                        packet.push('\n');
+                    }
+                    packet.push_str(&line);
+                }
+                if !packet.is_empty() {
+                    socket.send(packet.as_bytes())?;
+                }
+                Ok(())
+            }
+            Connection::Tcp(connection) => connection.send(&lines),
+        }
+    }
+}
+
+/// Pushes the `min`, `max`, `sum` and `count` series of `agg`.
+fn min_max_sum_count<A, F>(agg: &A, kind: &NumberKind, push: &mut F) -> Result<()>
+where
+    A: Min + Max + Sum + Count,
+    F: FnMut(Option<&str>, Option<(&str, &str)>, String, MetricType),
+{
+    push(
+        Some("min"),
+        None,
+        format_number(&agg.min()?, kind),
+        MetricType::Gauge,
+    );
+    push(
+        Some("max"),
+        None,
+        format_number(&agg.max()?, kind),
+        MetricType::Gauge,
+    );
+    push(
+        Some("sum"),
+        None,
+        format_number(&agg.sum()?, kind),
+        MetricType::Counter,
+    );
+    push(
+        Some("count"),
+        None,
+        agg.count()?.to_string(),
+        MetricType::Counter,
+    );
+    Ok(())
+}
+
+fn format_number(number: &Number, kind: &NumberKind) -> String {
+    format!("{:?}", number.to_debug(kind))
+}
+
+impl Exporter for StatsdExporter {
+    fn export(&self, checkpoint_set: &mut dyn CheckpointSet) -> Result<()> {
+        let mut lines = Vec::new();
+        checkpoint_set.try_for_each(self, &mut |record| self.write_lines(record, &mut lines))?;
+        if lines.is_empty() {
+            return Ok(());
+        }
+
+        self.send(lines)
+            .map_err(|err| MetricsError::Other(err.to_string()))
+    }
+}
+
+impl ExportKindFor for StatsdExporter {
+    fn export_kind_for(&self, descriptor: &Descriptor) -> ExportKind {
+        ExportKindSelector::Delta.export_kind_for(descriptor)
+    }
+}
+
+/// Pipeline builder
+#[derive(Debug)]
+pub struct StatsdPipelineBuilder<S, I> {
+    spawn: S,
+    interval: I,
+    format: Format,
+    endpoint: Option<String>,
+    transport: Option<Transport>,
+    prefix: Option<String>,
+    max_packet_size: usize,
+    aggregator_selector: simple::Selector,
+    period: Option<Duration>,
+    resource: Option<Resource>,
+}
+
+impl<S, SO, I, IS, ISI> StatsdPipelineBuilder<S, I>
+where
+    S: Fn(PushControllerWorker) -> SO,
+    I: Fn(Duration) -> IS,
+    IS: Stream<Item = ISI> + Send + 'static,
+{
+    /// Assign the output format, defaults to [`Format::Statsd`].
+    ///
+    /// [`Format::Statsd`]: enum.Format.html#variant.Statsd
+    pub fn with_format(self, format: Format) -> Self {
+        StatsdPipelineBuilder { format, ..self }
+    }
+
+    /// Assign the endpoint metrics are sent to, defaults to `127.0.0.1:8125`
+    /// for StatsD formats and `127.0.0.1:2003` for Graphite formats.
+    pub fn with_endpoint<T: Into<String>>(self, endpoint: T) -> Self {
+        StatsdPipelineBuilder {
+            endpoint: Some(endpoint.into()),
+            ..self
+        }
+    }
+
+    /// Assign the transport protocol, defaults to UDP for StatsD formats and
+    /// TCP for Graphite formats.
+    pub fn with_transport(self, transport: Transport) -> Self {
+        StatsdPipelineBuilder {
+            transport: Some(transport),
+            ..self
+        }
+    }
+
+    /// Assign a prefix prepended to all metric names.
+    pub fn with_prefix<T: Into<String>>(self, prefix: T) -> Self {
+        StatsdPipelineBuilder {
+            prefix: Some(prefix.into()),
+            ..self
+        }
+    }
+
+    /// Assign the max size of UDP datagrams, defaults to 1432 bytes.
+    pub fn with_max_packet_size(self, max_packet_size: usize) -> Self {
+        StatsdPipelineBuilder {
+            max_packet_size,
+            ..self
+        }
+    }
+
+    /// Assign the aggregator selector, defaults to
+    /// `simple::Selector::Inexpensive`.
+    pub fn with_aggregator_selector(self, aggregator_selector: simple::Selector) -> Self {
+        StatsdPipelineBuilder {
+            aggregator_selector,
+            ..self
+        }
+    }
+
+    /// Set the frequency in which metrics are exported.
+    pub fn with_period(self, period: Duration) -> Self {
+        StatsdPipelineBuilder {
+            period: Some(period),
+            ..self
+        }
+    }
+
+    /// Set the resource reported as labels of all metrics.
+    pub fn with_resource(self, resource: Resource) -> Self {
+        StatsdPipelineBuilder {
+            resource: Some(resource),
+            ..self
+        }
+    }
+
+    /// Build a new push controller and install it as the global meter
+    /// provider, returning errors if they arise.
+    pub fn try_init(self) -> Result<PushController> {
+        let exporter = self.build_exporter()?;
+        let mut push_builder = controllers::push(
+            self.aggregator_selector,
+            ExportKindSelector::Delta,
+            exporter,
+            self.spawn,
+            self.interval,
+        );
+        if let Some(period) = self.period {
+            push_builder = push_builder.with_period(period);
+        }
+        if let Some(resource) = self.resource {
+            push_builder = push_builder.with_resource(resource);
+        }
+
+        let controller = push_builder.build();
+        global::set_meter_provider(controller.provider());
+        Ok(controller)
+    }
+
+    fn build_exporter(&self) -> Result<StatsdExporter> {
+        let (default_endpoint, default_transport) = if self.format.is_statsd() {
+            ("127.0.0.1:8125", Transport::Udp)
+        } else {
+            ("127.0.0.1:2003", Transport::Tcp)
+        };
+        let endpoint = self.endpoint.as_deref().unwrap_or(default_endpoint);
+        let connection = match self.transport.unwrap_or(default_transport) {
+            Transport::Udp => connect_udp(endpoint).map(Connection::Udp),
+            Transport::Tcp => resolve(endpoint)
+                .and_then(TcpConnection::connect)
+                .map(Connection::Tcp),
+        }
+        .map_err(|err| MetricsError::Other(err.to_string()))?;
+
+        Ok(StatsdExporter {
+            connection,
+            format: self.format,
+            prefix: self.prefix.clone(),
+            max_packet_size: self.max_packet_size,
+        })
+    }
+}
+
+/// Binds a UDP socket of the address family of `endpoint` and connects it to
+/// `endpoint`, shared with the UDP exporters of `opentelemetry-contrib`.
+pub fn connect_udp(endpoint: &str) -> io::Result<UdpSocket> {
+    let addr = resolve(endpoint)?;
+    let bind_addr = if addr.is_ipv4() {
+        "0.0.0.0:0"
+    } else {
+        "[::]:0"
+    };
+    let socket = UdpSocket::bind(bind_addr)?;
+    socket.connect(addr)?;
+    Ok(socket)
+}
+
+/// Resolves `endpoint` to its first socket address.
+fn resolve(endpoint: &str) -> io::Result<SocketAddr> {
+    endpoint.to_socket_addrs()?.next().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("endpoint {} not resolved", endpoint),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry::metrics::InstrumentKind;
+    use opentelemetry::sdk::metrics::aggregators;
+    use opentelemetry::testing::metrics::{new_test_descriptor, TestCheckpointSet};
+    use opentelemetry::KeyValue;
+    use std::io::Read;
+    use std::net::TcpListener;
+    use std::sync::Arc;
+
+    fn checkpoint_set() -> TestCheckpointSet {
+        TestCheckpointSet::new(Resource::new(vec![KeyValue::new("service", "app")]))
+    }
+
+    fn builder() -> StatsdPipelineBuilder<
+        impl Fn(PushControllerWorker),
+        impl Fn(Duration) -> futures::stream::Empty<()>,
+    > {
+        new_pipeline(|_| (), |_| futures::stream::empty::<()>())
+    }
+
+    fn receive(listener: &UdpSocket) -> String {
+        let mut buf = [0; 1024];
+        let len = listener.recv(&mut buf).unwrap();
+        String::from_utf8(buf[..len].to_vec()).unwrap()
+    }
+
+    #[test]
+    fn exports_statsd_lines() {
+        let listener = UdpSocket::bind("127.0.0.1:0").unwrap();
+        listener
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let exporter = builder()
+            .with_endpoint(listener.local_addr().unwrap().to_string())
+            .with_prefix("my app")
+            .build_exporter()
+            .unwrap();
+        let mut checkpoint_set = checkpoint_set();
+        checkpoint_set.add(
+            new_test_descriptor("requests", InstrumentKind::Counter, NumberKind::U64),
+            Arc::new(aggregators::sum()),
+            vec![1u64.into(), 2u64.into()],
+            vec![KeyValue::new("route", "/users")],
+        );
+        checkpoint_set.add(
+            new_test_descriptor(
+                "temperature",
+                InstrumentKind::ValueObserver,
+                NumberKind::F64,
+            ),
+            Arc::new(aggregators::last_value()),
+            vec![(-1.5f64).into()],
+            vec![],
+        );
+        let descriptor =
+            new_test_descriptor("latency", InstrumentKind::ValueRecorder, NumberKind::I64);
+        checkpoint_set.add(
+            descriptor.clone(),
+            Arc::new(aggregators::min_max_sum_count(&descriptor)),
+            vec![3i64.into(), 5i64.into()],
+            vec![],
+        );
+
+        exporter.export(&mut checkpoint_set).unwrap();
+
+        assert_eq!(
+            receive(&listener),
+            "my_app.requests.service.app.route._users:3|c\n\
+             my_app.temperature.service.app:0|g\n\
+             my_app.temperature.service.app:-1.5|g\n\
+             my_app.latency.service.app.min:3|g\n\
+             my_app.latency.service.app.max:5|g\n\
+             my_app.latency.service.app.sum:8|c\n\
+             my_app.latency.service.app.count:2|c"
+        );
+    }
+
+    #[test]
+    fn batches_lines_in_packets() {
+        let listener = UdpSocket::bind("127.0.0.1:0").unwrap();
+        listener
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let exporter = builder()
+            .with_format(Format::DogStatsd)
+            .with_endpoint(listener.local_addr().unwrap().to_string())
+            .with_max_packet_size(60)
+            .build_exporter()
+            .unwrap();
+        let mut checkpoint_set = checkpoint_set();
+        checkpoint_set.add(
+            new_test_descriptor("latency", InstrumentKind::ValueRecorder, NumberKind::U64),
+            Arc::new(aggregators::array()),
+            vec![1u64.into(), 2u64.into(), 3u64.into()],
+            vec![],
+        );
+
+        exporter.export(&mut checkpoint_set).unwrap();
+
+        assert_eq!(
+            receive(&listener),
+            "latency:1|ms|#service:app\nlatency:2|ms|#service:app"
+        );
+        assert_eq!(receive(&listener), "latency:3|ms|#service:app");
+    }
+
+    #[test]
+    fn exports_graphite_lines_over_tcp() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let exporter = builder()
+            .with_format(Format::GraphiteTagged)
+            .with_endpoint(listener.local_addr().unwrap().to_string())
+            .build_exporter()
+            .unwrap();
+        let descriptor =
+            new_test_descriptor("latency", InstrumentKind::ValueRecorder, NumberKind::F64);
+        let mut checkpoint_set = checkpoint_set();
+        checkpoint_set.add(
+            descriptor.clone(),
+            Arc::new(aggregators::histogram(&descriptor, &[1.0, 5.0])),
+            vec![0.5f64.into(), 2.0f64.into(), 3.0f64.into(), 7.0f64.into()],
+            vec![KeyValue::new("method", "GET")],
+        );
+
+        exporter.export(&mut checkpoint_set).unwrap();
+        drop(exporter);
+
+        let mut received = String::new();
+        listener
+            .accept()
+            .unwrap()
+            .0
+            .read_to_string(&mut received)
+            .unwrap();
+        assert_eq!(
+            received,
+            "latency.bucket;service=app;method=GET;le=1 1 1600000000\n\
+             latency.bucket;service=app;method=GET;le=5 3 1600000000\n\
+             latency.bucket;service=app;method=GET;le=+Inf 4 1600000000\n\
+             latency.sum;service=app;method=GET 12.5 1600000000\n\
+             latency.count;service=app;method=GET 4 1600000000\n"
+        );
+    }
+
+    #[test]
+    fn reconnects_graphite_connection_in_background() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let exporter = builder()
+            .with_format(Format::Graphite)
+            .with_endpoint(listener.local_addr().unwrap().to_string())
+            .build_exporter()
+            .unwrap();
+        drop(listener.accept().unwrap());
+        let mut checkpoint_set = checkpoint_set();
+        checkpoint_set.add(
+            new_test_descriptor("requests", InstrumentKind::Counter, NumberKind::U64),
+            Arc::new(aggregators::sum()),
+            vec![1u64.into()],
+            vec![],
+        );
+
+        // Writes fail once the closed connection is reset.
+        while exporter.export(&mut checkpoint_set).is_ok() {
+            thread::sleep(Duration::from_millis(10));
+        }
+        let (mut stream, _) = listener.accept().unwrap();
+        while exporter.export(&mut checkpoint_set).is_err() {
+            thread::sleep(Duration::from_millis(10));
+        }
+        drop(exporter);
+
+        let mut received = String::new();
+        stream.read_to_string(&mut received).unwrap();
+        assert_eq!(received, "requests.service.app 1 1600000000\n");
+    }
+}
@@ -0,0 +1,4 @@
+fn main() {
+    prost_build::compile_protos(&["src/proto/zipkin.proto"], &["src/proto"])
+        .expect("Error generating protobuf");
+}
@@ -1,4 +1,7 @@
 mod model;
+#[allow(clippy::all, unreachable_pub, dead_code)]
+#[rustfmt::skip]
+mod thrift;
 mod uploader;
 
 use async_trait::async_trait;
@@ -17,6 +20,9 @@ use std::net::SocketAddr;
 /// Default Zipkin collector endpoint
 const DEFAULT_COLLECTOR_ENDPOINT: &str = "http://127.0.0.1:9411/api/v2/spans";
 
+/// Default Zipkin collector endpoint for v1 spans
+const DEFAULT_V1_COLLECTOR_ENDPOINT: &str = "http://127.0.0.1:9411/api/v1/spans";
+
 /// Default service name if no service is configured.
 const DEFAULT_SERVICE_NAME: &str = "OpenTelemetry";
 
@@ -28,14 +34,30 @@ pub struct Exporter {
 }
 
 impl Exporter {
-    fn new(local_endpoint: Endpoint, client: Box<dyn HttpClient>, collector_endpoint: Uri) -> Self {
+    fn new(
+        local_endpoint: Endpoint,
+        client: Box<dyn HttpClient>,
+        collector_endpoint: Uri,
+        encoding: Encoding,
+    ) -> Self {
         Exporter {
             local_endpoint,
-            uploader: uploader::Uploader::new(client, collector_endpoint),
+            uploader: uploader::Uploader::new(client, collector_endpoint, encoding),
         }
     }
 }
 
+/// Wire format used to report spans to the Zipkin collector.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Encoding {
+    /// Zipkin v2 JSON spans, posted to `/api/v2/spans`.
+    Json,
+    /// Zipkin v2 protobuf `ListOfSpans`, posted to `/api/v2/spans`.
+    Proto,
+    /// Legacy Zipkin v1 thrift spans, posted to `/api/v1/spans`.
+    Thrift,
+}
+
 /// Create a new Zipkin exporter pipeline builder.
 pub fn new_pipeline() -> ZipkinPipelineBuilder {
     ZipkinPipelineBuilder::default()
@@ -46,7 +68,8 @@ pub fn new_pipeline() -> ZipkinPipelineBuilder {
 pub struct ZipkinPipelineBuilder {
     service_name: String,
     service_addr: Option<SocketAddr>,
-    collector_endpoint: String,
+    collector_endpoint: Option<String>,
+    encoding: Encoding,
     trace_config: Option<sdk::trace::Config>,
     client: Option<Box<dyn HttpClient>>,
 }
@@ -77,7 +100,8 @@ impl Default for ZipkinPipelineBuilder {
 
             service_name: DEFAULT_SERVICE_NAME.to_string(),
             service_addr: None,
-            collector_endpoint: DEFAULT_COLLECTOR_ENDPOINT.to_string(),
+            collector_endpoint: None,
+            encoding: Encoding::Proto,
             trace_config: None,
         }
     }
@@ -88,12 +112,16 @@ impl ZipkinPipelineBuilder {
     pub fn install(mut self) -> Result<(sdk::trace::Tracer, Uninstall), TraceError> {
         if let Some(client) = self.client {
             let endpoint = Endpoint::new(self.service_name, self.service_addr);
+            let collector_endpoint = match (self.collector_endpoint, self.encoding) {
+                (Some(collector_endpoint), _) => collector_endpoint,
+                (None, Encoding::Thrift) => DEFAULT_V1_COLLECTOR_ENDPOINT.to_string(),
+                (None, _) => DEFAULT_COLLECTOR_ENDPOINT.to_string(),
+            };
             let exporter = Exporter::new(
                 endpoint,
                 client,
-                self.collector_endpoint
-                    .parse()
-                    .map_err::<Error, _>(Into::into)?,
+                collector_endpoint.parse().map_err::<Error, _>(Into::into)?,
+                self.encoding,
             );
 
             let mut provider_builder =
@@ -131,8 +159,20 @@ impl ZipkinPipelineBuilder {
     }
 
     /// Assign the Zipkin collector endpoint
+    ///
+    /// Defaults to `http://127.0.0.1:9411/api/v2/spans`, or
+    /// `http://127.0.0.1:9411/api/v1/spans` for the thrift encoding.
     pub fn with_collector_endpoint<T: Into<String>>(mut self, endpoint: T) -> Self {
-        self.collector_endpoint = endpoint.into();
+        self.collector_endpoint = Some(endpoint.into());
+        self
+    }
+
+    /// Assign the encoding used to report spans, defaults to
+    /// [`Encoding::Proto`].
+    ///
+    /// [`Encoding::Proto`]: enum.Encoding.html#variant.Proto
+    pub fn with_encoding(mut self, encoding: Encoding) -> Self {
+        self.encoding = encoding;
         self
     }
 
@@ -1,14 +1,18 @@
 use opentelemetry::{
-    sdk::export::trace,
+    sdk::{export::trace, trace::EvictedHashMap},
     trace::{Event, SpanKind, StatusCode},
-    Key, KeyValue,
+    Key, KeyValue, Value,
 };
 use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::net::IpAddr;
 use std::time::{Duration, SystemTime};
 
 pub(crate) mod annotation;
 pub(crate) mod endpoint;
+pub(crate) mod proto;
 pub(crate) mod span;
+mod thrift;
 
 use endpoint::Endpoint;
 
@@ -17,6 +21,11 @@ const INSTRUMENTATION_LIBRARY_VERSION: &str = "otel.library.version";
 const OTEL_ERROR_DESCRIPTION: &str = "error";
 const OTEL_STATUS_CODE: &str = "otel.status_code";
 
+const PEER_SERVICE: &str = "peer.service";
+const NET_PEER_NAME: &str = "net.peer.name";
+const NET_PEER_IP: &str = "net.peer.ip";
+const NET_PEER_PORT: &str = "net.peer.port";
+
 /// Converts `Event` into an `annotation::Annotation`
 impl Into<annotation::Annotation> for Event {
     fn into(self) -> annotation::Annotation {
@@ -54,10 +63,51 @@ fn into_zipkin_span_kind(kind: SpanKind) -> Option<span::Kind> {
     }
 }
 
+/// Builds the remote endpoint of client and producer spans from the peer
+/// attributes of the span, `None` if it has none.
+fn remote_endpoint(kind: &SpanKind, attributes: &EvictedHashMap) -> Option<Endpoint> {
+    match kind {
+        SpanKind::Client | SpanKind::Producer => (),
+        _ => return None,
+    }
+
+    let service_name = attributes
+        .get(&Key::new(PEER_SERVICE))
+        .or_else(|| attributes.get(&Key::new(NET_PEER_NAME)))
+        .map(Value::to_string);
+    let ip = attributes
+        .get(&Key::new(NET_PEER_IP))
+        .and_then(|ip| ip.to_string().parse::<IpAddr>().ok());
+    let port = attributes
+        .get(&Key::new(NET_PEER_PORT))
+        .and_then(|port| match port {
+            Value::I64(port) => u16::try_from(*port).ok(),
+            port => port.to_string().parse().ok(),
+        });
+    if service_name.is_none() && ip.is_none() && port.is_none() {
+        return None;
+    }
+
+    Some(Endpoint {
+        service_name,
+        ipv4: match ip {
+            Some(IpAddr::V4(ipv4)) => Some(ipv4),
+            _ => None,
+        },
+        ipv6: match ip {
+            Some(IpAddr::V6(ipv6)) => Some(ipv6),
+            _ => None,
+        },
+        port,
+    })
+}
+
 /// Converts a `trace::SpanData` to a `span::SpanData` for a given `ExporterConfig`, which can then
 /// be ingested into a Zipkin collector.
 pub(crate) fn into_zipkin_span(local_endpoint: Endpoint, span_data: trace::SpanData) -> span::Span {
     // see tests in create/exporter/model/span.rs
+    let remote_endpoint = remote_endpoint(&span_data.span_kind, &span_data.attributes);
+    let debug = span_data.span_context.is_debug();
     let mut user_defined_span_kind = false;
     let mut tags = map_from_kvs(
         span_data
@@ -92,7 +142,7 @@ pub(crate) fn into_zipkin_span(local_endpoint: Endpoint, span_data: trace::SpanD
         tags.insert(OTEL_STATUS_CODE.into(), status_code.into());
     }
 
-    span::Span::builder()
+    let mut span = span::Span::builder()
         .trace_id(span_data.span_context.trace_id().to_hex())
         .parent_id(span_data.parent_span_id.to_hex())
         .id(span_data.span_context.span_id().to_hex())
@@ -125,7 +175,13 @@ pub(crate) fn into_zipkin_span(local_endpoint: Endpoint, span_data: trace::SpanD
                 .collect(),
         )
         .tags(tags)
-        .build()
+        .debug(debug)
+        // `shared` stays unset, the SDK never reuses the span id of the
+        // remote client for server spans.
+        .build();
+    span.remote_endpoint = remote_endpoint;
+
+    span
 }
 
 fn map_from_kvs<T>(kvs: T) -> HashMap<String, String>
@@ -138,3 +194,20 @@ where
     }
     map
 }
+
+/// Decodes a hex encoded id, ignoring invalid digits.
+fn hex_to_bytes(hex: &str) -> Vec<u8> {
+    hex.as_bytes()
+        .chunks(2)
+        .filter_map(|digits| {
+            std::str::from_utf8(digits)
+                .ok()
+                .and_then(|digits| u8::from_str_radix(digits, 16).ok())
+        })
+        .collect()
+}
+
+/// Decodes a hex encoded id of up to 64 bits.
+fn hex_to_i64(hex: &str) -> Option<i64> {
+    u64::from_str_radix(hex, 16).ok().map(|id| id as i64)
+}
@@ -0,0 +1,83 @@
+//! Zipkin v2 protobuf model, see [zipkin.proto].
+//!
+//! [zipkin.proto]: https://github.com/openzipkin/zipkin-api/blob/master/zipkin.proto
+//!
+//! The types are generated from `src/proto/zipkin.proto` by the build script.
+use crate::exporter::model::{annotation, endpoint, hex_to_bytes, span};
+
+#[allow(unreachable_pub, missing_docs)]
+mod zipkin_proto3 {
+    include!(concat!(env!("OUT_DIR"), "/zipkin.proto3.rs"));
+}
+
+pub(crate) use zipkin_proto3::{span::Kind, Annotation, Endpoint, ListOfSpans, Span};
+
+impl From<span::Span> for Span {
+    fn from(span: span::Span) -> Self {
+        let kind = match span.kind {
+            Some(span::Kind::Client) => Kind::Client,
+            Some(span::Kind::Server) => Kind::Server,
+            Some(span::Kind::Producer) => Kind::Producer,
+            Some(span::Kind::Consumer) => Kind::Consumer,
+            None => Kind::SpanKindUnspecified,
+        };
+        // Root spans have no parent id rather than a zero one.
+        let parent_id = span
+            .parent_id
+            .as_deref()
+            .map(hex_to_bytes)
+            .filter(|id| id.iter().any(|byte| *byte != 0))
+            .unwrap_or_default();
+
+        Span {
+            trace_id: span
+                .trace_id
+                .as_deref()
+                .map(hex_to_bytes)
+                .unwrap_or_default(),
+            parent_id,
+            id: span.id.as_deref().map(hex_to_bytes).unwrap_or_default(),
+            kind: kind as i32,
+            name: span.name.unwrap_or_default(),
+            timestamp: span.timestamp.unwrap_or_default(),
+            duration: span.duration.unwrap_or_default(),
+            local_endpoint: span.local_endpoint.map(Into::into),
+            remote_endpoint: span.remote_endpoint.map(Into::into),
+            annotations: span
+                .annotations
+                .into_iter()
+                .flatten()
+                .map(Into::into)
+                .collect(),
+            tags: span.tags.unwrap_or_default(),
+            debug: span.debug,
+            shared: span.shared,
+        }
+    }
+}
+
+impl From<endpoint::Endpoint> for Endpoint {
+    fn from(endpoint: endpoint::Endpoint) -> Self {
+        Endpoint {
+            service_name: endpoint.service_name.unwrap_or_default(),
+            ipv4: endpoint
+                .ipv4
+                .map(|ipv4| ipv4.octets().to_vec())
+                .unwrap_or_default(),
+            ipv6: endpoint
+                .ipv6
+                .map(|ipv6| ipv6.octets().to_vec())
+                .unwrap_or_default(),
+            port: endpoint.port.map(i32::from).unwrap_or_default(),
+        }
+    }
+}
+
+impl From<annotation::Annotation> for Annotation {
+    fn from(annotation: annotation::Annotation) -> Self {
+        Annotation {
+            timestamp: annotation.timestamp.unwrap_or_default(),
+            value: annotation.value.unwrap_or_default(),
+        }
+    }
+}
@@ -16,41 +16,41 @@ pub(crate) enum Kind {
 pub(crate) struct Span {
     #[builder(setter(strip_option), default)]
     #[serde(skip_serializing_if = "Option::is_none")]
-    trace_id: Option<String>,
+    pub(crate) trace_id: Option<String>,
     #[builder(setter(strip_option), default)]
     #[serde(skip_serializing_if = "Option::is_none")]
-    parent_id: Option<String>,
+    pub(crate) parent_id: Option<String>,
     #[builder(setter(strip_option), default)]
     #[serde(skip_serializing_if = "Option::is_none")]
-    id: Option<String>,
+    pub(crate) id: Option<String>,
     #[builder(default)]
     #[serde(skip_serializing_if = "Option::is_none")]
-    kind: Option<Kind>,
+    pub(crate) kind: Option<Kind>,
     #[builder(setter(strip_option), default)]
     #[serde(skip_serializing_if = "Option::is_none")]
-    name: Option<String>,
+    pub(crate) name: Option<String>,
     #[builder(setter(strip_option), default)]
     #[serde(skip_serializing_if = "Option::is_none")]
-    timestamp: Option<u64>,
+    pub(crate) timestamp: Option<u64>,
     #[builder(setter(strip_option), default)]
     #[serde(skip_serializing_if = "Option::is_none")]
-    duration: Option<u64>,
+    pub(crate) duration: Option<u64>,
     #[builder(setter(strip_option), default)]
     #[serde(skip_serializing_if = "Option::is_none")]
-    local_endpoint: Option<Endpoint>,
+    pub(crate) local_endpoint: Option<Endpoint>,
     #[builder(setter(strip_option), default)]
     #[serde(skip_serializing_if = "Option::is_none")]
-    remote_endpoint: Option<Endpoint>,
+    pub(crate) remote_endpoint: Option<Endpoint>,
     #[builder(setter(strip_option), default)]
     #[serde(skip_serializing_if = "Option::is_none")]
-    annotations: Option<Vec<Annotation>>,
+    pub(crate) annotations: Option<Vec<Annotation>>,
     #[builder(setter(strip_option), default)]
     #[serde(skip_serializing_if = "Option::is_none")]
-    tags: Option<HashMap<String, String>>,
+    pub(crate) tags: Option<HashMap<String, String>>,
     #[builder(default = false)]
-    debug: bool,
+    pub(crate) debug: bool,
     #[builder(default = false)]
-    shared: bool,
+    pub(crate) shared: bool,
 }
 
 #[cfg(test)]
@@ -61,7 +61,10 @@ mod tests {
     use crate::exporter::model::{into_zipkin_span, OTEL_ERROR_DESCRIPTION, OTEL_STATUS_CODE};
     use opentelemetry::sdk::export::trace::SpanData;
     use opentelemetry::sdk::trace::{EvictedHashMap, EvictedQueue};
-    use opentelemetry::trace::{SpanContext, SpanId, SpanKind, StatusCode, TraceId};
+    use opentelemetry::trace::{
+        SpanContext, SpanId, SpanKind, StatusCode, TraceId, TRACE_FLAG_DEBUG, TRACE_FLAG_SAMPLED,
+    };
+    use opentelemetry::KeyValue;
     use std::collections::HashMap;
     use std::net::Ipv4Addr;
     use std::sync::Arc;
@@ -191,4 +194,106 @@ mod tests {
 
         Ok(())
     }
+
+    fn span_data(span_kind: SpanKind, trace_flags: u8, attributes: Vec<KeyValue>) -> SpanData {
+        let mut attribute_map = EvictedHashMap::new(20, 20);
+        for attribute in attributes {
+            attribute_map.insert(attribute);
+        }
+
+        SpanData {
+            span_context: SpanContext::new(
+                TraceId::from_u128(1),
+                SpanId::from_u64(2),
+                trace_flags,
+                false,
+                Default::default(),
+            ),
+            parent_span_id: SpanId::from_u64(1),
+            span_kind,
+            name: "".to_string(),
+            start_time: SystemTime::now(),
+            end_time: SystemTime::now(),
+            attributes: attribute_map,
+            message_events: EvictedQueue::new(20),
+            links: EvictedQueue::new(20),
+            status_code: StatusCode::Unset,
+            status_message: "".to_string(),
+            resource: Arc::new(Default::default()),
+            instrumentation_lib: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_remote_endpoint() {
+        let span = into_zipkin_span(
+            Endpoint::new("test".into(), None),
+            span_data(
+                SpanKind::Client,
+                TRACE_FLAG_SAMPLED,
+                vec![
+                    KeyValue::new("peer.service", "backend"),
+                    KeyValue::new("net.peer.name", "backend.local"),
+                    KeyValue::new("net.peer.ip", "10.0.0.1"),
+                    KeyValue::new("net.peer.port", 8080),
+                ],
+            ),
+        );
+        let remote_endpoint = span.remote_endpoint.unwrap();
+        assert_eq!(remote_endpoint.service_name.as_deref(), Some("backend"));
+        assert_eq!(remote_endpoint.ipv4, Some(Ipv4Addr::new(10, 0, 0, 1)));
+        assert_eq!(remote_endpoint.port, Some(8080));
+        assert!(!span.shared);
+        assert!(!span.debug);
+
+        let span = into_zipkin_span(
+            Endpoint::new("test".into(), None),
+            span_data(
+                SpanKind::Producer,
+                TRACE_FLAG_SAMPLED,
+                vec![KeyValue::new("net.peer.name", "broker.local")],
+            ),
+        );
+        assert_eq!(
+            span.remote_endpoint.unwrap().service_name.as_deref(),
+            Some("broker.local")
+        );
+
+        let span = into_zipkin_span(
+            Endpoint::new("test".into(), None),
+            span_data(
+                SpanKind::Server,
+                TRACE_FLAG_SAMPLED,
+                vec![KeyValue::new("peer.service", "frontend")],
+            ),
+        );
+        assert!(span.remote_endpoint.is_none());
+    }
+
+    #[test]
+    fn test_debug_server_span_not_shared() {
+        let span = into_zipkin_span(
+            Endpoint::new("test".into(), None),
+            span_data(
+                SpanKind::Server,
+                TRACE_FLAG_SAMPLED | TRACE_FLAG_DEBUG,
+                vec![],
+            ),
+        );
+        assert!(!span.shared);
+        assert!(span.debug);
+    }
+
+    #[test]
+    fn test_remote_endpoint_invalid_port() {
+        let span = into_zipkin_span(
+            Endpoint::new("test".into(), None),
+            span_data(
+                SpanKind::Client,
+                TRACE_FLAG_SAMPLED,
+                vec![KeyValue::new("net.peer.port", 70_000)],
+            ),
+        );
+        assert!(span.remote_endpoint.is_none());
+    }
 }
@@ -0,0 +1,130 @@
+//! Conversion of Zipkin v2 spans to the legacy v1 thrift model.
+//!
+//! The span kind and remote endpoint of v2 spans are expressed in v1 with core
+//! annotations, such as `cs` and `cr` for client spans, and address binary
+//! annotations, such as `sa` for the server a client span called.
+use crate::exporter::model::{endpoint, hex_to_i64, span};
+use crate::exporter::thrift::zipkincore;
+
+impl From<span::Span> for zipkincore::Span {
+    fn from(span: span::Span) -> Self {
+        let (trace_id_high, trace_id) = match span.trace_id.as_deref() {
+            Some(trace_id) if trace_id.len() > 16 => {
+                let (high, low) = trace_id.split_at(trace_id.len() - 16);
+                (hex_to_i64(high), hex_to_i64(low))
+            }
+            trace_id => (None, trace_id.and_then(hex_to_i64)),
+        };
+        let local_endpoint: Option<zipkincore::Endpoint> = span.local_endpoint.map(Into::into);
+        let remote_endpoint: Option<zipkincore::Endpoint> = span.remote_endpoint.map(Into::into);
+
+        let mut annotations = span
+            .annotations
+            .into_iter()
+            .flatten()
+            .map(|annotation| {
+                zipkincore::Annotation::new(
+                    annotation.timestamp.map(|timestamp| timestamp as i64),
+                    annotation.value,
+                    local_endpoint.clone(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let (begin, end, address) = match span.kind {
+            Some(span::Kind::Client) => (
+                Some(zipkincore::C_L_I_E_N_T_S_E_N_D),
+                Some(zipkincore::C_L_I_E_N_T_R_E_C_V),
+                zipkincore::S_E_R_V_E_R_A_D_D_R,
+            ),
+            Some(span::Kind::Server) => (
+                Some(zipkincore::S_E_R_V_E_R_R_E_C_V),
+                Some(zipkincore::S_E_R_V_E_R_S_E_N_D),
+                zipkincore::C_L_I_E_N_T_A_D_D_R,
+            ),
+            Some(span::Kind::Producer) => (
+                Some(zipkincore::M_E_S_S_A_G_E_S_E_N_D),
+                Some(zipkincore::W_I_R_E_S_E_N_D),
+                zipkincore::M_E_S_S_A_G_E_A_D_D_R,
+            ),
+            Some(span::Kind::Consumer) => (
+                Some(zipkincore::W_I_R_E_R_E_C_V),
+                Some(zipkincore::M_E_S_S_A_G_E_R_E_C_V),
+                zipkincore::M_E_S_S_A_G_E_A_D_D_R,
+            ),
+            None => (None, None, zipkincore::S_E_R_V_E_R_A_D_D_R),
+        };
+        if let (Some(begin), Some(timestamp)) = (begin, span.timestamp) {
+            annotations.push(zipkincore::Annotation::new(
+                timestamp as i64,
+                begin.to_string(),
+                local_endpoint.clone(),
+            ));
+            if let (Some(end), Some(duration)) = (end, span.duration) {
+                annotations.push(zipkincore::Annotation::new(
+                    (timestamp + duration) as i64,
+                    end.to_string(),
+                    local_endpoint.clone(),
+                ));
+            }
+        }
+
+        let mut binary_annotations = span
+            .tags
+            .into_iter()
+            .flatten()
+            .map(|(key, value)| {
+                zipkincore::BinaryAnnotation::new(
+                    key,
+                    value.into_bytes(),
+                    zipkincore::AnnotationType::String,
+                    local_endpoint.clone(),
+                )
+            })
+            .collect::<Vec<_>>();
+        if remote_endpoint.is_some() {
+            binary_annotations.push(zipkincore::BinaryAnnotation::new(
+                address.to_string(),
+                vec![1],
+                zipkincore::AnnotationType::Bool,
+                remote_endpoint,
+            ));
+        } else if annotations.is_empty() && local_endpoint.is_some() {
+            // Local spans are identified by their local component in v1.
+            binary_annotations.push(zipkincore::BinaryAnnotation::new(
+                zipkincore::L_O_C_A_L_C_O_M_P_O_N_E_N_T.to_string(),
+                Vec::new(),
+                zipkincore::AnnotationType::String,
+                local_endpoint,
+            ));
+        }
+
+        zipkincore::Span::new(
+            trace_id,
+            span.name,
+            span.id.as_deref().and_then(hex_to_i64),
+            span.parent_id
+                .as_deref()
+                .and_then(hex_to_i64)
+                .filter(|parent_id| *parent_id != 0),
+            annotations,
+            binary_annotations,
+            span.debug,
+            span.timestamp.map(|timestamp| timestamp as i64),
+            span.duration.map(|duration| duration as i64),
+            trace_id_high.filter(|trace_id_high| *trace_id_high != 0),
+        )
+    }
+}
+
+impl From<endpoint::Endpoint> for zipkincore::Endpoint {
+    fn from(endpoint: endpoint::Endpoint) -> Self {
+        zipkincore::Endpoint::new(
+            endpoint.ipv4.map(|ipv4| u32::from(ipv4) as i32),
+            // v1 ports are unsigned values stored in signed 16 bits integers.
+            endpoint.port.map(|port| port as i16),
+            endpoint.service_name,
+            endpoint.ipv6.map(|ipv6| ipv6.octets().to_vec()),
+        )
+    }
+}
@@ -0,0 +1,10 @@
+//! Thrift generated Zipkin v1 model
+//!
+//! Generated from `src/thrift/zipkincore.thrift` with the Thrift 0.13 compiler:
+//!
+//! ```text
+//! thrift -out src/exporter/thrift --gen rs src/thrift/zipkincore.thrift
+//! ```
+//!
+//! Definitions: https://github.com/openzipkin/zipkin-api/blob/master/thrift/zipkinCore.thrift
+pub(crate) mod zipkincore;
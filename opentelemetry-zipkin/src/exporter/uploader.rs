@@ -1,28 +1,49 @@
 //! # Zipkin Span Exporter
-use crate::exporter::model::span::Span;
-use crate::exporter::Error;
+use crate::exporter::model::{proto, span::Span};
+use crate::exporter::thrift::zipkincore;
+use crate::exporter::{Encoding, Error};
 use http::{header::CONTENT_TYPE, Method, Request, Uri};
 use opentelemetry::sdk::export::trace::{ExportResult, HttpClient};
+use prost::Message;
 use std::fmt::Debug;
+use thrift::protocol::{TBinaryOutputProtocol, TListIdentifier, TOutputProtocol, TType};
 
 #[derive(Debug)]
 pub(crate) enum Uploader {
     Http(JsonV2Client),
+    HttpProto(ProtoV2Client),
+    HttpThrift(ThriftV1Client),
 }
 
 impl Uploader {
     /// Create a new http uploader
-    pub(crate) fn new(client: Box<dyn HttpClient>, collector_endpoint: Uri) -> Self {
-        Uploader::Http(JsonV2Client {
-            client,
-            collector_endpoint,
-        })
+    pub(crate) fn new(
+        client: Box<dyn HttpClient>,
+        collector_endpoint: Uri,
+        encoding: Encoding,
+    ) -> Self {
+        match encoding {
+            Encoding::Json => Uploader::Http(JsonV2Client {
+                client,
+                collector_endpoint,
+            }),
+            Encoding::Proto => Uploader::HttpProto(ProtoV2Client {
+                client,
+                collector_endpoint,
+            }),
+            Encoding::Thrift => Uploader::HttpThrift(ThriftV1Client {
+                client,
+                collector_endpoint,
+            }),
+        }
     }
 
     /// Upload spans to Zipkin
     pub(crate) async fn upload(&self, spans: Vec<Span>) -> ExportResult {
         match self {
             Uploader::Http(client) => client.upload(spans).await,
+            Uploader::HttpProto(client) => client.upload(spans).await,
+            Uploader::HttpThrift(client) => client.upload(spans).await,
         }
     }
 }
@@ -44,3 +65,184 @@ impl JsonV2Client {
         self.client.send(req).await
     }
 }
+
+#[derive(Debug)]
+pub(crate) struct ProtoV2Client {
+    client: Box<dyn HttpClient>,
+    collector_endpoint: Uri,
+}
+
+impl ProtoV2Client {
+    async fn upload(&self, spans: Vec<Span>) -> ExportResult {
+        let req = Request::builder()
+            .method(Method::POST)
+            .uri(self.collector_endpoint.clone())
+            .header(CONTENT_TYPE, "application/x-protobuf")
+            .body(encode_proto(spans)?)
+            .map_err::<Error, _>(Into::into)?;
+        self.client.send(req).await
+    }
+}
+
+/// Encodes spans as a protobuf `ListOfSpans`.
+fn encode_proto(spans: Vec<Span>) -> Result<Vec<u8>, Error> {
+    let spans = proto::ListOfSpans {
+        spans: spans.into_iter().map(Into::into).collect(),
+    };
+    let mut body = Vec::with_capacity(spans.encoded_len());
+    spans
+        .encode(&mut body)
+        .map_err(|err| Error::Other(err.to_string()))?;
+
+    Ok(body)
+}
+
+#[derive(Debug)]
+pub(crate) struct ThriftV1Client {
+    client: Box<dyn HttpClient>,
+    collector_endpoint: Uri,
+}
+
+impl ThriftV1Client {
+    async fn upload(&self, spans: Vec<Span>) -> ExportResult {
+        let req = Request::builder()
+            .method(Method::POST)
+            .uri(self.collector_endpoint.clone())
+            .header(CONTENT_TYPE, "application/x-thrift")
+            .body(encode_thrift(spans)?)
+            .map_err::<Error, _>(Into::into)?;
+        self.client.send(req).await
+    }
+}
+
+/// Encodes spans as a binary thrift list of v1 spans.
+fn encode_thrift(spans: Vec<Span>) -> Result<Vec<u8>, Error> {
+    let mut protocol = TBinaryOutputProtocol::new(Vec::new(), true);
+    let spans = spans
+        .into_iter()
+        .map(Into::into)
+        .collect::<Vec<zipkincore::Span>>();
+    protocol
+        .write_list_begin(&TListIdentifier::new(TType::Struct, spans.len() as i32))
+        .and_then(|_| {
+            spans
+                .iter()
+                .try_for_each(|span| span.write_to_out_protocol(&mut protocol))
+        })
+        .and_then(|_| protocol.write_list_end())
+        .and_then(|_| protocol.flush())
+        .map_err(|err| Error::Other(err.to_string()))?;
+
+    Ok(protocol.transport)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exporter::model::{endpoint::Endpoint, span::Kind};
+    use std::collections::HashMap;
+    use std::net::Ipv4Addr;
+    use thrift::protocol::{TBinaryInputProtocol, TInputProtocol};
+
+    fn client_span() -> Span {
+        let mut tags = HashMap::new();
+        tags.insert("http.method".to_string(), "GET".to_string());
+
+        Span::builder()
+            .trace_id("0000000000000001000000000000000a".to_owned())
+            .parent_id("0000000000000000".to_owned())
+            .id("000000000000000b".to_owned())
+            .kind(Some(Kind::Client))
+            .name("get".to_owned())
+            .timestamp(1_000)
+            .duration(50)
+            .local_endpoint(
+                Endpoint::builder()
+                    .service_name("frontend".to_owned())
+                    .ipv4(Ipv4Addr::new(127, 0, 0, 1))
+                    .port(8080)
+                    .build(),
+            )
+            .remote_endpoint(
+                Endpoint::builder()
+                    .service_name("backend".to_owned())
+                    .build(),
+            )
+            .tags(tags)
+            .build()
+    }
+
+    #[test]
+    fn proto_round_trip() {
+        let body = encode_proto(vec![client_span()]).unwrap();
+        let spans = proto::ListOfSpans::decode(&body[..]).unwrap().spans;
+
+        assert_eq!(spans.len(), 1);
+        let span = &spans[0];
+        assert_eq!(
+            span.trace_id,
+            vec![0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 10]
+        );
+        assert!(span.parent_id.is_empty());
+        assert_eq!(span.id, vec![0, 0, 0, 0, 0, 0, 0, 11]);
+        assert_eq!(span.kind, proto::Kind::Client as i32);
+        assert_eq!(span.name, "get");
+        assert_eq!(span.timestamp, 1_000);
+        assert_eq!(span.duration, 50);
+        let local_endpoint = span.local_endpoint.as_ref().unwrap();
+        assert_eq!(local_endpoint.service_name, "frontend");
+        assert_eq!(local_endpoint.ipv4, vec![127, 0, 0, 1]);
+        assert_eq!(local_endpoint.port, 8080);
+        assert_eq!(
+            span.remote_endpoint.as_ref().unwrap().service_name,
+            "backend"
+        );
+        assert_eq!(
+            span.tags.get("http.method").map(String::as_str),
+            Some("GET")
+        );
+    }
+
+    #[test]
+    fn thrift_round_trip() {
+        let body = encode_thrift(vec![client_span()]).unwrap();
+        let mut protocol = TBinaryInputProtocol::new(&body[..], true);
+        let list = protocol.read_list_begin().unwrap();
+        assert_eq!(list.element_type, TType::Struct);
+        assert_eq!(list.size, 1);
+        let span = zipkincore::Span::read_from_in_protocol(&mut protocol).unwrap();
+        protocol.read_list_end().unwrap();
+
+        assert_eq!(span.trace_id_high, Some(1));
+        assert_eq!(span.trace_id, Some(10));
+        assert_eq!(span.id, Some(11));
+        assert_eq!(span.parent_id, None);
+        assert_eq!(span.name.as_deref(), Some("get"));
+        assert_eq!(span.timestamp, Some(1_000));
+        assert_eq!(span.duration, Some(50));
+
+        let annotations = span
+            .annotations
+            .unwrap()
+            .into_iter()
+            .map(|annotation| (annotation.value.unwrap(), annotation.timestamp.unwrap()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            annotations,
+            vec![("cs".to_string(), 1_000), ("cr".to_string(), 1_050)]
+        );
+
+        let binary_annotations = span.binary_annotations.unwrap();
+        assert_eq!(binary_annotations.len(), 2);
+        assert_eq!(binary_annotations[0].key.as_deref(), Some("http.method"));
+        assert_eq!(binary_annotations[0].value.as_deref(), Some(&b"GET"[..]));
+        assert_eq!(binary_annotations[1].key.as_deref(), Some("sa"));
+        assert_eq!(
+            binary_annotations[1]
+                .host
+                .as_ref()
+                .and_then(|host| host.service_name.as_deref()),
+            Some("backend")
+        );
+    }
+}
@@ -69,6 +69,35 @@
 //!
 //! [`HttpClient`]: https://docs.rs/opentelemetry/0.10/opentelemetry/exporter/trace/trait.HttpClient.html
 //!
+//! ## Encodings
+//!
+//! Spans are reported as Zipkin v2 protobuf by default, a compact encoding
+//! suited to high volumes. Use [`Encoding::Json`] for collectors that only
+//! accept JSON, or [`Encoding::Thrift`] for legacy collectors accepting v1
+//! thrift spans on `/api/v1/spans`.
+//!
+//! ```no_run
+//! # fn main() -> Result<(), opentelemetry::trace::TraceError> {
+//! let (tracer, _uninstall) = opentelemetry_zipkin::new_pipeline()
+//!     .with_encoding(opentelemetry_zipkin::Encoding::Json)
+//!     .install()?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`Encoding::Json`]: enum.Encoding.html#variant.Json
+//! [`Encoding::Thrift`]: enum.Encoding.html#variant.Thrift
+//!
+//! ## Span Mapping
+//!
+//! The `remoteEndpoint` of client and producer spans is derived from the
+//! `peer.service`, `net.peer.name`, `net.peer.ip` and `net.peer.port`
+//! attributes, and spans with the debug trace flag are reported as `debug`.
+//!
+//! Spans are never reported as `shared`. Zipkin marks server spans sharing the
+//! span id of their remote client as shared, while the SDK gives every server
+//! span its own id, with the client span as its parent.
+//!
 //! ## Kitchen Sink Full Configuration
 //!
 //! Example showing how to override all configuration options. See the
@@ -172,5 +201,5 @@ extern crate typed_builder;
 mod exporter;
 mod propagator;
 
-pub use exporter::{new_pipeline, Error, Exporter, Uninstall, ZipkinPipelineBuilder};
+pub use exporter::{new_pipeline, Encoding, Error, Exporter, Uninstall, ZipkinPipelineBuilder};
 pub use propagator::{B3Encoding, Propagator};
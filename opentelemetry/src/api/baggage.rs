@@ -60,7 +60,7 @@ const MAX_LEN_OF_ALL_PAIRS: usize = 8192;
 ///
 /// ### Baggage Names
 ///
-/// * ASCII strings according to the token format, defined in [RFC2616, Section 2.2]
+/// * ASCII strings according to the token format, defined in [RFC7230, Section 3.2.6]
 ///
 /// ### Baggage Values
 ///
@@ -78,7 +78,10 @@ const MAX_LEN_OF_ALL_PAIRS: usize = 8192;
 /// * Maximum number of bytes per a single name-value pair: `4096`.
 /// * Maximum total length of all name-value pairs: `8192`.
 ///
-/// [RFC2616, Section 2.2]: https://tools.ietf.org/html/rfc2616#section-2.2
+/// Name-value pairs with invalid names or exceeding one of the limits are
+/// dropped as a whole.
+///
+/// [RFC7230, Section 3.2.6]: https://tools.ietf.org/html/rfc7230#section-3.2.6
 #[derive(Debug, Default)]
 pub struct Baggage {
     inner: HashMap<Key, (Value, BaggageMetadata)>,
@@ -208,12 +211,12 @@ impl Baggage {
     /// Determine whether the key value pair exceed one of the [limits](https://w3c.github.io/baggage/#limits).
     /// If not, update the total length of key values
     fn insertable(&mut self, key: &Key, value: &Value, metadata: &BaggageMetadata) -> bool {
-        if !key.as_str().is_ascii() {
+        if !valid_key(key.as_str()) {
             return false;
         }
         let value = value.as_str();
         if key_value_metadata_bytes_size(key.as_str(), value.as_ref(), metadata.as_str())
-            < MAX_BYTES_FOR_ONE_PAIR
+            <= MAX_BYTES_FOR_ONE_PAIR
         {
             match self.inner.get(key) {
                 None => {
@@ -260,6 +263,33 @@ fn key_value_metadata_bytes_size(key: &str, value: &str, metadata: &str) -> usiz
     key.bytes().len() + value.bytes().len() + metadata.bytes().len()
 }
 
+/// Whether `key` is a non-empty token as defined in [RFC7230, Section 3.2.6].
+///
+/// [RFC7230, Section 3.2.6]: https://tools.ietf.org/html/rfc7230#section-3.2.6
+pub(crate) fn valid_key(key: &str) -> bool {
+    !key.is_empty()
+        && key.bytes().all(|b| {
+            b.is_ascii_alphanumeric()
+                || matches!(
+                    b,
+                    b'!' | b'#'
+                        | b'$'
+                        | b'%'
+                        | b'&'
+                        | b'\''
+                        | b'*'
+                        | b'+'
+                        | b'-'
+                        | b'.'
+                        | b'^'
+                        | b'_'
+                        | b'`'
+                        | b'|'
+                        | b'~'
+                )
+        })
+}
+
 /// An iterator over the entries of a [`Baggage`].
 #[derive(Debug)]
 pub struct Iter<'a>(hash_map::Iter<'a, Key, (Value, BaggageMetadata)>);
@@ -415,6 +445,47 @@ impl BaggageMetadata {
     pub fn as_str(&self) -> &str {
         self.0.as_str()
     }
+
+    /// Returns an iterator over the properties of this metadata, as names with
+    /// optional values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use opentelemetry::baggage::BaggageMetadata;
+    ///
+    /// let metadata = BaggageMetadata::from("k1=v1;k2");
+    ///
+    /// assert_eq!(
+    ///     metadata.properties().collect::<Vec<_>>(),
+    ///     vec![("k1", Some("v1")), ("k2", None)],
+    /// )
+    /// ```
+    pub fn properties(&self) -> Properties<'_> {
+        Properties(self.0.split(';'))
+    }
+}
+
+/// An iterator over the properties of a [`BaggageMetadata`].
+#[derive(Debug)]
+pub struct Properties<'a>(std::str::Split<'a, char>);
+
+impl<'a> Iterator for Properties<'a> {
+    type Item = (&'a str, Option<&'a str>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let property = self.0.next()?.trim();
+            if property.is_empty() {
+                continue;
+            }
+
+            return Some(match property.find('=') {
+                Some(index) => (property[..index].trim(), Some(property[index + 1..].trim())),
+                None => (property, None),
+            });
+        }
+    }
 }
 
 impl From<String> for BaggageMetadata {
@@ -528,4 +599,34 @@ mod tests {
         let baggage = Baggage::from_iter(data.into_iter());
         assert_eq!(baggage.len(), 3)
     }
+
+    #[test]
+    fn insert_invalid_key() {
+        let mut baggage = Baggage::new();
+        baggage.insert("", "empty key");
+        baggage.insert("with space", "space in key");
+        baggage.insert("with,comma", "comma in key");
+        baggage.insert("with=equals", "equals in key");
+        assert_eq!(baggage.len(), 0, "did not insert invalid keys");
+
+        baggage.insert("valid_!#$%&'*+-.^`|~key", "value");
+        assert_eq!(baggage.len(), 1);
+    }
+
+    #[test]
+    fn insert_pair_at_length_limit() {
+        let mut baggage = Baggage::new();
+        baggage.insert("k", "v".repeat(MAX_BYTES_FOR_ONE_PAIR - 1));
+        assert_eq!(baggage.len(), 1);
+    }
+
+    #[test]
+    fn metadata_properties() {
+        let metadata = BaggageMetadata::from(" k1 = v1 ;; k2;k3=");
+        assert_eq!(
+            metadata.properties().collect::<Vec<_>>(),
+            vec![("k1", Some("v1")), ("k2", None), ("k3", Some(""))]
+        );
+        assert_eq!(BaggageMetadata::default().properties().count(), 0);
+    }
 }
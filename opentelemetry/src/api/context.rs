@@ -210,6 +210,15 @@ impl Context {
         new_context
     }
 
+    /// Returns a copy of this context without the value of type `T`.
+    #[cfg(feature = "trace")]
+    pub(crate) fn without_value<T: 'static>(&self) -> Self {
+        let mut new_context = self.clone();
+        new_context.entries.remove(&TypeId::of::<T>());
+
+        new_context
+    }
+
     /// Replaces the current context on this thread with this context.
     ///
     /// Dropping the returned [`ContextGuard`] will reset the current context to the
@@ -295,6 +304,39 @@ impl Context {
             _marker: PhantomData,
         }
     }
+
+    /// Wraps `f` so that it runs with this context attached, wherever it is
+    /// called from.
+    ///
+    /// This carries the context across thread boundaries, for example to work
+    /// handed to `std::thread::spawn`, scoped threads or thread pools, which
+    /// would otherwise start from an empty context.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use opentelemetry::Context;
+    ///
+    /// #[derive(Debug, PartialEq)]
+    /// struct ValueA(&'static str);
+    ///
+    /// let _guard = Context::new().with_value(ValueA("a")).attach();
+    ///
+    /// let handle = std::thread::spawn(Context::current().wrap_fn(|| {
+    ///     Context::current().get::<ValueA>().map(|value| value.0)
+    /// }));
+    ///
+    /// assert_eq!(handle.join().unwrap(), Some("a"));
+    /// ```
+    pub fn wrap_fn<F, T>(self, f: F) -> impl FnOnce() -> T
+    where
+        F: FnOnce() -> T,
+    {
+        move || {
+            let _guard = self.attach();
+            f()
+        }
+    }
 }
 
 impl fmt::Debug for Context {
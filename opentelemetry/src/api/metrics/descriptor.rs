@@ -1,4 +1,5 @@
 use crate::metrics::{InstrumentConfig, InstrumentKind, NumberKind};
+use crate::Unit;
 use fnv::FnvHasher;
 use std::hash::{Hash, Hasher};
 
@@ -62,6 +63,11 @@ impl Descriptor {
         self.config.description = Some(description);
     }
 
+    /// Assign a new unit
+    pub fn set_unit(&mut self, unit: Unit) {
+        self.config.unit = Some(unit);
+    }
+
     /// Unit describes the units of the metric instrument.
     pub fn unit(&self) -> Option<&str> {
         self.config.unit.as_ref().map(|unit| unit.as_ref())
@@ -2,6 +2,7 @@ use crate::metrics::{
     sdk_api, AsyncRunner, Descriptor, InstrumentKind, Meter, Number, NumberKind, Observation,
     Result,
 };
+use crate::Unit;
 use std::sync::Arc;
 
 /// An Observer callback that can report observations for multiple instruments.
@@ -71,6 +72,12 @@ impl<'a, T> SumObserverBuilder<'a, T> {
         self
     }
 
+    /// Set the unit of this `SumObserver`
+    pub fn with_unit(mut self, unit: Unit) -> Self {
+        self.descriptor.set_unit(unit);
+        self
+    }
+
     /// Create a `SumObserver` from this configuration.
     pub fn try_init(self) -> Result<SumObserver<T>> {
         let instrument = self
@@ -157,6 +164,12 @@ impl<'a, T> UpDownSumObserverBuilder<'a, T> {
         self
     }
 
+    /// Set the unit of this `UpDownSumObserver`
+    pub fn with_unit(mut self, unit: Unit) -> Self {
+        self.descriptor.set_unit(unit);
+        self
+    }
+
     /// Create a `UpDownSumObserver` from this configuration.
     pub fn try_init(self) -> Result<UpDownSumObserver<T>> {
         let instrument = self
@@ -241,6 +254,12 @@ impl<'a, T> ValueObserverBuilder<'a, T> {
         self
     }
 
+    /// Set the unit of this `ValueObserver`
+    pub fn with_unit(mut self, unit: Unit) -> Self {
+        self.descriptor.set_unit(unit);
+        self
+    }
+
     /// Create a `ValueObserver` from this configuration.
     pub fn try_init(self) -> Result<ValueObserver<T>> {
         let instrument = self
@@ -3,7 +3,7 @@ use crate::{
         sync_instrument::{SyncBoundInstrument, SyncInstrument},
         Descriptor, InstrumentKind, Measurement, Meter, Number, NumberKind, Result,
     },
-    KeyValue,
+    KeyValue, Unit,
 };
 use std::marker;
 
@@ -83,6 +83,12 @@ impl<'a, T> UpDownCounterBuilder<'a, T> {
         self
     }
 
+    /// Set the unit for this counter
+    pub fn with_unit(mut self, unit: Unit) -> Self {
+        self.descriptor.set_unit(unit);
+        self
+    }
+
     /// Creates a new counter instrument.
     pub fn try_init(self) -> Result<UpDownCounter<T>> {
         let instrument = self.meter.new_sync_instrument(self.descriptor)?;
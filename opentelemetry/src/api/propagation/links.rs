@@ -0,0 +1,115 @@
+//! # Span Links
+//!
+//! Consumers of batched messages can't pick a single parent for the span
+//! processing the batch, as every message carries its own propagated context.
+//! [`extract_links`] turns these contexts into span [`Link`]s instead.
+//!
+//! [`Link`]: crate::trace::Link
+use crate::{
+    propagation::{Extractor, TextMapPropagator},
+    trace::{Link, SpanId, TraceContextExt, TraceId},
+    Context,
+};
+use std::collections::HashSet;
+
+/// Extracts a [`Link`] for each distinct valid span context found in
+/// `extractors`, in order, stopping once `max_links` links were extracted.
+///
+/// Extractors without a valid span context are skipped, as are the ones
+/// pointing to an already linked span.
+///
+/// # Examples
+///
+/// ```
+/// use opentelemetry::{propagation::links::extract_links, sdk::propagation::TraceContextPropagator};
+/// use std::collections::HashMap;
+///
+/// let batch: Vec<HashMap<String, String>> = vec![
+///     vec![("traceparent".to_string(), "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01".to_string())].into_iter().collect(),
+///     vec![("traceparent".to_string(), "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01".to_string())].into_iter().collect(),
+///     HashMap::new(),
+/// ];
+///
+/// let links = extract_links(&TraceContextPropagator::new(), &batch, 128);
+/// assert_eq!(links.len(), 1);
+/// ```
+///
+/// [`Link`]: crate::trace::Link
+pub fn extract_links<'a, P, I, E>(propagator: &P, extractors: I, max_links: usize) -> Vec<Link>
+where
+    P: TextMapPropagator + ?Sized,
+    I: IntoIterator<Item = &'a E>,
+    E: Extractor + 'a,
+{
+    let mut linked: HashSet<(TraceId, SpanId)> = HashSet::new();
+    let mut links = Vec::new();
+    for extractor in extractors {
+        if links.len() >= max_links {
+            break;
+        }
+
+        let cx = propagator.extract_with_context(&Context::new(), extractor);
+        if let Some(span_context) = cx.remote_span_context() {
+            if span_context.is_valid()
+                && linked.insert((span_context.trace_id(), span_context.span_id()))
+            {
+                links.push(Link::new(span_context.clone(), Vec::new()));
+            }
+        }
+    }
+
+    links
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sdk::propagation::TraceContextPropagator;
+    use std::collections::HashMap;
+
+    fn carrier(traceparent: &str) -> HashMap<String, String> {
+        let mut carrier = HashMap::new();
+        carrier.insert("traceparent".to_string(), traceparent.to_string());
+        carrier
+    }
+
+    #[test]
+    fn extracts_distinct_valid_links() {
+        let batch = vec![
+            carrier("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01"),
+            carrier("garbage"),
+            carrier("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01"),
+            carrier("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b8-00"),
+            HashMap::new(),
+        ];
+
+        let links = extract_links(&TraceContextPropagator::new(), &batch, 10);
+        let span_ids = links
+            .iter()
+            .map(|link| link.span_context().span_id())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            span_ids,
+            vec![
+                SpanId::from_u64(0x00f0_67aa_0ba9_02b7),
+                SpanId::from_u64(0x00f0_67aa_0ba9_02b8)
+            ]
+        );
+        assert!(links.iter().all(|link| link.span_context().is_remote()));
+    }
+
+    #[test]
+    fn caps_number_of_links() {
+        let batch = (1..=5)
+            .map(|i| {
+                carrier(&format!(
+                    "00-4bf92f3577b34da6a3ce929d0e0e4736-{:016x}-01",
+                    i
+                ))
+            })
+            .collect::<Vec<_>>();
+        let links = extract_links(&TraceContextPropagator::new(), batch.iter(), 3);
+        assert_eq!(links.len(), 3);
+        assert_eq!(links[2].span_context().span_id(), SpanId::from_u64(3));
+    }
+}
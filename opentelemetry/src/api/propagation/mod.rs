@@ -163,6 +163,9 @@
 //!
 use std::collections::HashMap;
 
+#[cfg(feature = "trace")]
+#[cfg_attr(docsrs, doc(cfg(feature = "trace")))]
+pub mod links;
 pub mod text_map_propagator;
 
 pub use text_map_propagator::TextMapPropagator;
@@ -201,6 +204,56 @@ impl<S: std::hash::BuildHasher> Extractor for HashMap<String, String, S> {
     }
 }
 
+impl Injector for Vec<(String, Vec<u8>)> {
+    /// Replace all values of a key, compared case insensitively, with a single value.
+    fn set(&mut self, key: &str, value: String) {
+        self.retain(|(name, _)| !name.eq_ignore_ascii_case(key));
+        self.push((key.to_string(), value.into_bytes()));
+    }
+}
+
+impl Extractor for Vec<(String, Vec<u8>)> {
+    /// Get the first UTF-8 value of a key, compared case insensitively.
+    fn get(&self, key: &str) -> Option<&str> {
+        first_utf8(
+            self.iter()
+                .filter(|(name, _)| name.eq_ignore_ascii_case(key))
+                .map(|(_, value)| value.as_slice()),
+        )
+    }
+
+    /// Collect all the distinct keys from the Vec.
+    fn keys(&self) -> Vec<&str> {
+        distinct_keys(self.iter().map(|(name, _)| name.as_str()))
+    }
+}
+
+/// Returns the first of `values` that is valid UTF-8.
+///
+/// Helps implementing [`Extractor::get`] for carriers of multi-valued binary
+/// headers.
+///
+/// [`Extractor::get`]: trait.Extractor.html#tymethod.get
+pub fn first_utf8<'a, I: Iterator<Item = &'a [u8]>>(mut values: I) -> Option<&'a str> {
+    values.find_map(|value| std::str::from_utf8(value).ok())
+}
+
+/// Collects `keys` without repeating keys of multi-valued headers.
+///
+/// Helps implementing [`Extractor::keys`] for carriers of multi-valued
+/// headers.
+///
+/// [`Extractor::keys`]: trait.Extractor.html#tymethod.keys
+pub fn distinct_keys<'a, I: Iterator<Item = &'a str>>(keys: I) -> Vec<&'a str> {
+    let mut distinct = Vec::new();
+    for key in keys {
+        if !distinct.contains(&key) {
+            distinct.push(key);
+        }
+    }
+    distinct
+}
+
 #[cfg(feature = "http")]
 #[cfg_attr(docsrs, doc(cfg(feature = "http")))]
 impl Injector for http::HeaderMap {
@@ -289,6 +342,37 @@ mod tests {
         assert!(got.contains(&"headername2"));
     }
 
+    #[test]
+    fn byte_headers_get() {
+        let carrier = vec![
+            ("Key".to_string(), vec![0xff, 0xfe]),
+            ("other".to_string(), b"other".to_vec()),
+            ("key".to_string(), b"first".to_vec()),
+            ("KEY".to_string(), b"second".to_vec()),
+        ];
+
+        assert_eq!(
+            Extractor::get(&carrier, "key"),
+            Some("first"),
+            "first valid UTF-8 value, case insensitive"
+        );
+        assert_eq!(Extractor::get(&carrier, "missing"), None);
+    }
+
+    #[test]
+    fn byte_headers_set_and_keys() {
+        let mut carrier = vec![
+            ("Key".to_string(), b"old".to_vec()),
+            ("key".to_string(), b"older".to_vec()),
+            ("other".to_string(), b"other".to_vec()),
+            ("other".to_string(), b"another".to_vec()),
+        ];
+        carrier.set("key", "new".to_string());
+
+        assert_eq!(Extractor::get(&carrier, "KEY"), Some("new"));
+        assert_eq!(Extractor::keys(&carrier), vec!["other", "key"]);
+    }
+
     #[test]
     #[cfg(feature = "http")]
     fn http_headers_get() {
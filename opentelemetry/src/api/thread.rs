@@ -0,0 +1,181 @@
+//! # Thread Context Propagation
+//!
+//! The current [`Context`] is stored per thread, so work handed to another
+//! thread starts from an empty context and spans it creates lose their parent.
+//! These helpers capture [`Context::current`] when the work is handed off and
+//! attach it in the thread running it.
+//!
+//! For other thread APIs, such as scoped threads, wrap the closure with
+//! [`Context::wrap_fn`].
+//!
+//! [`Context`]: crate::Context
+//! [`Context::current`]: crate::Context::current
+//! [`Context::wrap_fn`]: crate::Context::wrap_fn
+use crate::Context;
+use std::thread::{self, JoinHandle};
+
+/// Spawns a new thread running `f` with the current context attached.
+///
+/// # Examples
+///
+/// ```
+/// use opentelemetry::{thread::spawn_with_context, Context};
+///
+/// #[derive(Debug, PartialEq)]
+/// struct ValueA(&'static str);
+///
+/// let _guard = Context::new().with_value(ValueA("a")).attach();
+///
+/// let handle = spawn_with_context(|| Context::current().get::<ValueA>().map(|value| value.0));
+///
+/// assert_eq!(handle.join().unwrap(), Some("a"));
+/// ```
+pub fn spawn_with_context<F, T>(f: F) -> JoinHandle<T>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    thread::spawn(Context::current().wrap_fn(f))
+}
+
+/// Extension to run work on rayon thread pools with the context that is
+/// current when the work is submitted attached.
+///
+/// The context is captured per job, so jobs submitted from different contexts
+/// keep their own parents even when they run on the same worker thread.
+///
+/// # Examples
+///
+/// ```
+/// use opentelemetry::{thread::ThreadPoolExt, Context};
+///
+/// #[derive(Debug, PartialEq)]
+/// struct ValueA(&'static str);
+///
+/// let pool = rayon::ThreadPoolBuilder::new()
+///     .num_threads(1)
+///     .build()
+///     .unwrap();
+///
+/// let _guard = Context::new().with_value(ValueA("a")).attach();
+///
+/// let value = pool.install_with_context(|| Context::current().get::<ValueA>().map(|value| value.0));
+/// assert_eq!(value, Some("a"));
+/// ```
+#[cfg(feature = "rayon")]
+#[cfg_attr(docsrs, doc(cfg(feature = "rayon")))]
+pub trait ThreadPoolExt {
+    /// Executes `op` within the thread pool with the current context attached,
+    /// see `ThreadPool::install`.
+    fn install_with_context<OP, R>(&self, op: OP) -> R
+    where
+        OP: FnOnce() -> R + Send,
+        R: Send;
+
+    /// Spawns an asynchronous task in the thread pool with the current context
+    /// attached, see `ThreadPool::spawn`.
+    fn spawn_with_context<OP>(&self, op: OP)
+    where
+        OP: FnOnce() + Send + 'static;
+}
+
+#[cfg(feature = "rayon")]
+impl ThreadPoolExt for rayon::ThreadPool {
+    fn install_with_context<OP, R>(&self, op: OP) -> R
+    where
+        OP: FnOnce() -> R + Send,
+        R: Send,
+    {
+        self.install(Context::current().wrap_fn(op))
+    }
+
+    fn spawn_with_context<OP>(&self, op: OP)
+    where
+        OP: FnOnce() + Send + 'static,
+    {
+        self.spawn(Context::current().wrap_fn(op))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        sdk,
+        testing::trace::new_test_exporter,
+        trace::{Span, TraceContextExt, Tracer, TracerProvider},
+    };
+
+    #[test]
+    fn spawned_spans_have_parent() {
+        let (exporter, rx_export, _rx_shutdown) = new_test_exporter();
+        let provider = sdk::trace::TracerProvider::builder()
+            .with_simple_exporter(exporter)
+            .build();
+        let tracer = provider.get_tracer("test", None);
+        let parent = tracer.start("parent");
+        let parent_span_id = parent.span_context().span_id();
+        let _guard = Context::current_with_span(parent).attach();
+
+        let child_tracer = tracer.clone();
+        spawn_with_context(move || child_tracer.start("child").end())
+            .join()
+            .unwrap();
+
+        let child = rx_export.recv().unwrap();
+        assert_eq!(child.name, "child");
+        assert_eq!(child.parent_span_id, parent_span_id);
+    }
+
+    #[test]
+    fn wrapped_fn_restores_worker_context() {
+        #[derive(Debug, PartialEq)]
+        struct ValueA(&'static str);
+
+        let wrapped = Context::new()
+            .with_value(ValueA("a"))
+            .wrap_fn(|| Context::current().get::<ValueA>().map(|value| value.0));
+        assert_eq!(wrapped(), Some("a"));
+        assert_eq!(Context::current().get::<ValueA>(), None);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn rayon_jobs_have_their_own_parent() {
+        let (exporter, rx_export, _rx_shutdown) = new_test_exporter();
+        let provider = sdk::trace::TracerProvider::builder()
+            .with_simple_exporter(exporter)
+            .build();
+        let tracer = provider.get_tracer("test", None);
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(1)
+            .build()
+            .unwrap();
+
+        let mut parent_span_ids = Vec::new();
+        for name in &["a", "b"] {
+            let parent = tracer.start("parent");
+            parent_span_ids.push(parent.span_context().span_id());
+            let _guard = Context::current_with_span(parent).attach();
+            let child_tracer = tracer.clone();
+            pool.spawn_with_context(move || child_tracer.start(*name).end());
+        }
+
+        let mut children = rx_export
+            .iter()
+            .filter(|span| span.name != "parent")
+            .take(2)
+            .collect::<Vec<_>>();
+        children.sort_by(|a, b| a.name.cmp(&b.name));
+        assert_eq!(children[0].parent_span_id, parent_span_ids[0]);
+        assert_eq!(children[1].parent_span_id, parent_span_ids[1]);
+
+        let parent = tracer.start("parent");
+        let parent_span_id = parent.span_context().span_id();
+        let _guard = Context::current_with_span(parent).attach();
+        pool.install_with_context(|| tracer.start("child").end());
+
+        let child = rx_export.iter().find(|span| span.name == "child").unwrap();
+        assert_eq!(child.parent_span_id, parent_span_id);
+    }
+}
@@ -90,6 +90,11 @@ impl TraceContextExt for Context {
     }
 }
 
+/// Returns a copy of the context without its remote span context.
+pub(crate) fn without_remote_span_context(cx: &Context) -> Context {
+    cx.without_value::<RemoteSpanContext>()
+}
+
 /// Mark a given `Span` as active.
 ///
 /// The `Tracer` MUST provide a way to update its active `Span`, and MAY provide convenience
@@ -9,4 +9,11 @@ pub trait IdGenerator: Send + Sync + fmt::Debug {
 
     /// Generate a new `SpanId`
     fn new_span_id(&self) -> SpanId;
+
+    /// Whether at least the rightmost 7 bytes of generated `TraceId`s are
+    /// random, in which case root spans carry the W3C trace context Level 2
+    /// random trace flag. Defaults to `false`.
+    fn random_trace_ids(&self) -> bool {
+        false
+    }
 }
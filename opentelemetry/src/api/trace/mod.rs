@@ -113,7 +113,7 @@
 use ::futures::channel::{mpsc::TrySendError, oneshot::Canceled};
 use thiserror::Error;
 
-mod context;
+pub(crate) mod context;
 mod event;
 mod futures;
 mod id_generator;
@@ -121,7 +121,7 @@ mod link;
 mod noop;
 mod provider;
 mod span;
-mod span_context;
+pub(crate) mod span_context;
 mod tracer;
 
 pub use self::{
@@ -134,11 +134,15 @@ pub use self::{
     provider::TracerProvider,
     span::{Span, SpanKind, StatusCode},
     span_context::{
-        SpanContext, SpanId, TraceId, TraceState, TRACE_FLAG_DEBUG, TRACE_FLAG_DEFERRED,
-        TRACE_FLAG_NOT_SAMPLED, TRACE_FLAG_SAMPLED,
+        SpanContext, SpanId, TraceId, TraceState, TRACE_FLAG_DEBUG, TRACE_FLAG_NOT_SAMPLED,
+        TRACE_FLAG_RANDOM, TRACE_FLAG_SAMPLED, TRACE_FLAG_SAMPLING_DEFERRED,
     },
     tracer::{SpanBuilder, Tracer},
 };
+
+#[allow(deprecated)]
+pub use self::span_context::TRACE_FLAG_DEFERRED;
+
 use crate::sdk::export::ExportError;
 use std::time;
 
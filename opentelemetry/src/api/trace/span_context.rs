@@ -20,16 +20,36 @@ pub const TRACE_FLAG_NOT_SAMPLED: u8 = 0x00;
 /// TRACE_FLAG_SAMPLED is a bitmask with the sampled bit set. A SpanContext
 /// with the sampling bit set means the span is sampled.
 pub const TRACE_FLAG_SAMPLED: u8 = 0x01;
-/// TRACE_FLAGS_DEFERRED is a bitmask with the deferred bit set. A SpanContext
-/// with the deferred bit set means the sampling decision has been
-/// defered to the receiver.
-pub const TRACE_FLAG_DEFERRED: u8 = 0x02;
+/// TRACE_FLAG_RANDOM is a bitmask with the W3C trace context Level 2 random
+/// bit set. A SpanContext with the random bit set has a trace id whose
+/// rightmost 7 bytes were randomly generated.
+pub const TRACE_FLAG_RANDOM: u8 = 0x02;
 /// TRACE_FLAGS_DEBUG is a bitmask with the debug bit set.
 pub const TRACE_FLAG_DEBUG: u8 = 0x04;
-
-lazy_static::lazy_static! {
-    static ref TRACE_STATE_KEY: regex::Regex = regex::Regex::new(r#"^[a-z0-9]+[a-z0-9|\\_|\\-|\\*|\\/]*$"#).unwrap();
-}
+/// TRACE_FLAG_SAMPLING_DEFERRED is a bitmask with the deferred bit set. A
+/// SpanContext with the deferred bit set means the sampling decision has been
+/// defered to the receiver.
+pub const TRACE_FLAG_SAMPLING_DEFERRED: u8 = 0x08;
+/// TRACE_FLAGS_DEFERRED is a bitmask with the deferred bit set.
+///
+/// The deferred bit used to be `0x02`, which is the W3C trace context Level 2
+/// random bit, see `TRACE_FLAG_RANDOM`.
+#[deprecated(
+    since = "0.11.0",
+    note = "the deferred bit moved from 0x02 to 0x08, use TRACE_FLAG_SAMPLING_DEFERRED"
+)]
+pub const TRACE_FLAG_DEFERRED: u8 = TRACE_FLAG_SAMPLING_DEFERRED;
+
+/// The maximum number of list-members in a `TraceState`.
+const TRACE_STATE_MAX_MEMBERS: usize = 32;
+/// List-members longer than this are the first to be removed when truncating.
+const TRACE_STATE_TRUNCATE_MEMBER_THRESHOLD: usize = 128;
+/// The `TraceState` key holding OpenTelemetry sub-keys.
+const OT_TRACE_STATE_KEY: &str = "ot";
+/// The `ot` sub-key holding the sampling probability as a negative power of two.
+pub(crate) const OT_P_VALUE_KEY: &str = "p";
+/// The `ot` sub-key holding the number of leading zeros of the trace randomness.
+const OT_R_VALUE_KEY: &str = "r";
 
 /// TraceId is an 16-byte value which uniquely identifies a given trace
 /// The actual `u128` value is wrapped in a tuple struct in order to leverage the newtype pattern
@@ -131,28 +151,59 @@ pub struct TraceState(Option<VecDeque<(String, String)>>);
 impl TraceState {
     /// Validates that the given `TraceState` list-member key is valid per the [W3 Spec]['spec'].
     ///
+    /// A key is either a simple key of up to 256 characters starting with a
+    /// lowercase letter, or a multi-tenant `tenant@system` key.
+    ///
     /// ['spec']: https://www.w3.org/TR/trace-context/#key
     fn valid_key(key: &str) -> bool {
-        if key.len() > 256 {
-            return false;
+        fn valid_chars(part: &str, max_len: usize, first_digit: bool) -> bool {
+            let mut chars = part.chars();
+            let valid_first = match chars.next() {
+                Some(c) => c.is_ascii_lowercase() || (first_digit && c.is_ascii_digit()),
+                None => false,
+            };
+
+            valid_first
+                && part.len() <= max_len
+                && chars.all(|c| {
+                    c.is_ascii_lowercase()
+                        || c.is_ascii_digit()
+                        || c == '_'
+                        || c == '-'
+                        || c == '*'
+                        || c == '/'
+                })
         }
 
-        TRACE_STATE_KEY.is_match(key)
+        match key.find('@') {
+            None => valid_chars(key, 256, false),
+            Some(index) => {
+                let (tenant, system) = (&key[..index], &key[index + 1..]);
+                valid_chars(tenant, 241, true) && valid_chars(system, 14, false)
+            }
+        }
     }
 
     /// Validates that the given `TraceState` list-member value is valid per the [W3 Spec]['spec'].
     ///
+    /// A value is 1 to 256 printable ASCII characters other than `,` and `=`,
+    /// and does not end with a space.
+    ///
     /// ['spec']: https://www.w3.org/TR/trace-context/#value
     fn valid_value(value: &str) -> bool {
-        if value.len() > 256 {
-            return false;
-        }
-
-        !(value.contains(',') || value.contains('='))
+        !value.is_empty()
+            && value.len() <= 256
+            && !value.ends_with(' ')
+            && value
+                .bytes()
+                .all(|b| (0x20..=0x7e).contains(&b) && b != b',' && b != b'=')
     }
 
     /// Creates a new `TraceState` from the given key-value collection.
     ///
+    /// Returns an `Err` if any key or value is invalid, a key is repeated, or
+    /// there are more than 32 entries.
+    ///
     /// # Examples
     ///
     /// ```
@@ -171,20 +222,22 @@ impl TraceState {
         K: ToString,
         V: ToString,
     {
-        let ordered_data = trace_state
-            .into_iter()
-            .map(|(key, value)| {
-                let (key, value) = (key.to_string(), value.to_string());
-                if !TraceState::valid_key(key.as_str()) || !TraceState::valid_value(value.as_str())
-                {
-                    return Err(());
-                }
+        let mut ordered_data = VecDeque::new();
+        for (key, value) in trace_state {
+            let (key, value) = (key.to_string(), value.to_string());
+            if !TraceState::valid_key(key.as_str())
+                || !TraceState::valid_value(value.as_str())
+                || ordered_data.iter().any(|(existing, _)| *existing == key)
+            {
+                return Err(());
+            }
 
-                Ok((key, value))
-            })
-            .collect::<Result<VecDeque<_>, ()>>()?;
+            ordered_data.push_back((key, value));
+        }
 
-        if ordered_data.is_empty() {
+        if ordered_data.len() > TRACE_STATE_MAX_MEMBERS {
+            Err(())
+        } else if ordered_data.is_empty() {
             Ok(TraceState(None))
         } else {
             Ok(TraceState(Some(ordered_data)))
@@ -209,6 +262,9 @@ impl TraceState {
     /// invalid per the [W3 Spec]['spec'] an `Err` is returned, else a new `TraceState` with the
     /// updated key/value is returned.
     ///
+    /// New entries are added to the front of the list, and the last entry is
+    /// dropped if the list already holds 32 entries.
+    ///
     /// ['spec']: https://www.w3.org/TR/trace-context/#list
     #[allow(clippy::all)]
     pub fn insert(&self, key: String, value: String) -> Result<TraceState, ()> {
@@ -216,9 +272,14 @@ impl TraceState {
             return Err(());
         }
 
-        let mut trace_state = self.delete(key.clone())?;
+        let mut trace_state = self.clone();
         let kvs = trace_state.0.get_or_insert(VecDeque::with_capacity(1));
 
+        if let Some(index) = kvs.iter().position(|x| *x.0 == *key) {
+            kvs.remove(index);
+        } else if kvs.len() >= TRACE_STATE_MAX_MEMBERS {
+            kvs.pop_back();
+        }
         kvs.push_front((key, value));
 
         Ok(trace_state)
@@ -244,9 +305,151 @@ impl TraceState {
             return Err(());
         }
 
+        if kvs.is_empty() {
+            owned.0 = None;
+        }
+
         Ok(owned)
     }
 
+    /// Returns a `TraceState` whose header is at most `max_len` characters long,
+    /// following the [W3 Spec]['spec'] truncation rules: entries longer than 128
+    /// characters are removed first, then entries are removed from the end of
+    /// the list.
+    ///
+    /// ['spec']: https://www.w3.org/TR/trace-context/#tracestate-limits
+    pub fn truncate(&self, max_len: usize) -> TraceState {
+        let mut kvs = match &self.0 {
+            Some(kvs) => kvs.clone(),
+            None => return TraceState(None),
+        };
+
+        let member_len = |(key, value): &(String, String)| key.len() + value.len() + 1;
+        let header_len = |kvs: &VecDeque<(String, String)>| {
+            kvs.iter().map(member_len).sum::<usize>() + kvs.len().saturating_sub(1)
+        };
+
+        while header_len(&kvs) > max_len {
+            match kvs
+                .iter()
+                .rposition(|kv| member_len(kv) > TRACE_STATE_TRUNCATE_MEMBER_THRESHOLD)
+            {
+                Some(index) => {
+                    kvs.remove(index);
+                }
+                None => {
+                    kvs.pop_back();
+                }
+            }
+        }
+
+        if kvs.is_empty() {
+            TraceState(None)
+        } else {
+            TraceState(Some(kvs))
+        }
+    }
+
+    /// Retrieves a sub-key value of the OpenTelemetry `ot` entry, which holds
+    /// `;` separated `key:value` pairs such as `ot=p:2;r:5`.
+    pub fn ot_value(&self, sub_key: &str) -> Option<&str> {
+        self.get(OT_TRACE_STATE_KEY)?
+            .split(';')
+            .filter_map(|item| {
+                let index = item.find(':')?;
+                Some((&item[..index], &item[index + 1..]))
+            })
+            .find(|(key, _)| *key == sub_key)
+            .map(|(_, value)| value)
+    }
+
+    /// Inserts a sub-key value into the OpenTelemetry `ot` entry, keeping the
+    /// other sub-keys. Sub-keys are lowercase letters and digits starting with a
+    /// letter, and values consist of letters, digits, `.`, `_` and `-`.
+    #[allow(clippy::all)]
+    pub fn insert_ot_value(&self, sub_key: &str, value: &str) -> Result<TraceState, ()> {
+        let valid_sub_key = sub_key.starts_with(|c: char| c.is_ascii_lowercase())
+            && sub_key
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit());
+        let valid_value = value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '_' || c == '-');
+        if !valid_sub_key || !valid_value {
+            return Err(());
+        }
+
+        let mut items = self.ot_items(sub_key);
+        items.insert(0, format!("{}:{}", sub_key, value));
+        self.insert(OT_TRACE_STATE_KEY.to_string(), items.join(";"))
+    }
+
+    /// Removes a sub-key from the OpenTelemetry `ot` entry, removing the entry
+    /// itself once it is empty.
+    #[allow(clippy::all)]
+    pub fn delete_ot_value(&self, sub_key: &str) -> Result<TraceState, ()> {
+        if self.ot_value(sub_key).is_none() {
+            return Err(());
+        }
+
+        let items = self.ot_items(sub_key);
+        if items.is_empty() {
+            self.delete(OT_TRACE_STATE_KEY.to_string())
+        } else {
+            self.insert(OT_TRACE_STATE_KEY.to_string(), items.join(";"))
+        }
+    }
+
+    /// The `ot` entry items other than `sub_key`.
+    fn ot_items(&self, sub_key: &str) -> Vec<String> {
+        self.get(OT_TRACE_STATE_KEY)
+            .map(|ot| {
+                ot.split(';')
+                    .filter(|item| !item.is_empty() && item.split(':').next() != Some(sub_key))
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// The probability sampling p-value, where the sampling probability is
+    /// `2^-p`. Valid p-values are 0 to 63, 63 meaning zero probability.
+    pub fn ot_p_value(&self) -> Option<u8> {
+        self.ot_value(OT_P_VALUE_KEY)
+            .and_then(|value| value.parse::<u8>().ok())
+            .filter(|p| *p <= 63)
+    }
+
+    /// The probability sampling r-value, the number of leading zeros of the
+    /// trace randomness. Valid r-values are 0 to 62.
+    pub fn ot_r_value(&self) -> Option<u8> {
+        self.ot_value(OT_R_VALUE_KEY)
+            .and_then(|value| value.parse::<u8>().ok())
+            .filter(|r| *r <= 62)
+    }
+
+    /// Sets the probability sampling p-value, see [`ot_p_value`].
+    ///
+    /// [`ot_p_value`]: #method.ot_p_value
+    #[allow(clippy::all)]
+    pub fn insert_ot_p_value(&self, p: u8) -> Result<TraceState, ()> {
+        if p > 63 {
+            return Err(());
+        }
+        self.insert_ot_value(OT_P_VALUE_KEY, &p.to_string())
+    }
+
+    /// Sets the probability sampling r-value, see [`ot_r_value`].
+    ///
+    /// [`ot_r_value`]: #method.ot_r_value
+    #[allow(clippy::all)]
+    pub fn insert_ot_r_value(&self, r: u8) -> Result<TraceState, ()> {
+        if r > 62 {
+            return Err(());
+        }
+        self.insert_ot_value(OT_R_VALUE_KEY, &r.to_string())
+    }
+
     /// Creates a new `TraceState` header string, delimiting each key and value with a `=` and each
     /// entry with a `,`.
     pub fn header(&self) -> String {
@@ -270,17 +473,22 @@ impl TraceState {
 impl FromStr for TraceState {
     type Err = ();
 
+    /// Parses a `tracestate` header. Empty list-members and optional white
+    /// space around list-members are ignored.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let list_members: Vec<&str> = s.split_terminator(',').collect();
-        let mut key_value_pairs: Vec<(String, String)> = Vec::with_capacity(list_members.len());
+        let mut key_value_pairs: Vec<(String, String)> = Vec::new();
+
+        for list_member in s.split(',') {
+            let list_member = list_member.trim_matches(|c| c == ' ' || c == '\t');
+            if list_member.is_empty() {
+                continue;
+            }
 
-        for list_member in list_members {
             match list_member.find('=') {
                 None => return Err(()),
                 Some(separator_index) => {
                     let (key, value) = list_member.split_at(separator_index);
-                    key_value_pairs
-                        .push((key.to_string(), value.trim_start_matches('=').to_string()));
+                    key_value_pairs.push((key.to_string(), value[1..].to_string()));
                 }
             }
         }
@@ -358,7 +566,12 @@ impl SpanContext {
 
     /// Returns if the deferred bit is set in the trace flags
     pub fn is_deferred(&self) -> bool {
-        (self.trace_flags & TRACE_FLAG_DEFERRED) == TRACE_FLAG_DEFERRED
+        (self.trace_flags & TRACE_FLAG_SAMPLING_DEFERRED) == TRACE_FLAG_SAMPLING_DEFERRED
+    }
+
+    /// Returns if the W3C trace context Level 2 random bit is set in the trace flags
+    pub fn is_random(&self) -> bool {
+        (self.trace_flags & TRACE_FLAG_RANDOM) == TRACE_FLAG_RANDOM
     }
 
     /// Returns if the debug bit is set in the trace flags
@@ -403,7 +616,7 @@ mod tests {
     fn trace_state_test_data() -> Vec<(TraceState, &'static str, &'static str)> {
         vec![
             (TraceState::from_key_value(vec![("foo", "bar")]).unwrap(), "foo=bar", "foo"),
-            (TraceState::from_key_value(vec![("foo", ""), ("apple", "banana")]).unwrap(), "foo=,apple=banana", "apple"),
+            (TraceState::from_key_value(vec![("foo", "1"), ("apple", "banana")]).unwrap(), "foo=1,apple=banana", "apple"),
             (TraceState::from_key_value(vec![("foo", "bar"), ("apple", "banana")]).unwrap(), "foo=bar,apple=banana", "apple"),
         ]
     }
@@ -456,4 +669,135 @@ mod tests {
             assert!(deleted_trace_state.get(test_case.2).is_none());
         }
     }
+
+    #[rustfmt::skip]
+    fn trace_state_key_test_data() -> Vec<(String, bool)> {
+        vec![
+            ("foo".to_string(), true),
+            ("foo_bar-baz*/1".to_string(), true),
+            ("tenant@system".to_string(), true),
+            ("1tenant@system".to_string(), true),
+            ("a".repeat(256), true),
+            ("a".repeat(257), false),
+            (format!("{}@{}", "t".repeat(241), "s".repeat(14)), true),
+            (format!("{}@{}", "t".repeat(242), "s".repeat(14)), false),
+            (format!("{}@{}", "t".repeat(241), "s".repeat(15)), false),
+            ("".to_string(), false),
+            ("1foo".to_string(), false),
+            ("Foo".to_string(), false),
+            ("foo bar".to_string(), false),
+            ("foo@".to_string(), false),
+            ("@foo".to_string(), false),
+            ("foo@1bar".to_string(), false),
+            ("foo@bar@baz".to_string(), false),
+        ]
+    }
+
+    #[rustfmt::skip]
+    fn trace_state_value_test_data() -> Vec<(String, bool)> {
+        vec![
+            ("bar".to_string(), true),
+            (" bar".to_string(), true),
+            ("b a:r!~".to_string(), true),
+            ("x".repeat(256), true),
+            ("x".repeat(257), false),
+            ("".to_string(), false),
+            ("bar ".to_string(), false),
+            ("b,ar".to_string(), false),
+            ("b=ar".to_string(), false),
+            ("b\tar".to_string(), false),
+            ("bär".to_string(), false),
+        ]
+    }
+
+    #[test]
+    fn test_trace_state_key_value_grammar() {
+        for (key, valid) in trace_state_key_test_data() {
+            assert_eq!(TraceState::valid_key(&key), valid, "key {:?}", key);
+        }
+        for (value, valid) in trace_state_value_test_data() {
+            assert_eq!(TraceState::valid_value(&value), valid, "value {:?}", value);
+        }
+    }
+
+    #[test]
+    fn test_trace_state_member_limit() {
+        let members = |count: usize| (0..count).map(|i| (format!("k{}", i), i));
+
+        assert!(TraceState::from_key_value(members(TRACE_STATE_MAX_MEMBERS)).is_ok());
+        assert!(TraceState::from_key_value(members(TRACE_STATE_MAX_MEMBERS + 1)).is_err());
+        assert!(TraceState::from_key_value(vec![("foo", "1"), ("foo", "2")]).is_err());
+
+        let full = TraceState::from_key_value(members(TRACE_STATE_MAX_MEMBERS)).unwrap();
+        let inserted = full.insert("new".to_string(), "1".to_string()).unwrap();
+        assert_eq!(inserted.get("new"), Some("1"));
+        assert_eq!(inserted.get("k31"), None);
+        assert!(inserted.header().starts_with("new=1,k0=0"));
+    }
+
+    #[test]
+    fn test_trace_state_truncate() {
+        let long = "x".repeat(150);
+        let trace_state = TraceState::from_key_value(vec![
+            ("a", "1"),
+            ("b", long.as_str()),
+            ("c", "3"),
+            ("d", long.as_str()),
+            ("e", "5"),
+        ])
+        .unwrap();
+
+        assert_eq!(trace_state.truncate(512), trace_state);
+        assert_eq!(
+            trace_state.truncate(200).header(),
+            format!("a=1,b={},c=3,e=5", long)
+        );
+        assert_eq!(trace_state.truncate(20).header(), "a=1,c=3,e=5");
+        assert_eq!(trace_state.truncate(7).header(), "a=1,c=3");
+        assert_eq!(trace_state.truncate(2), TraceState::default());
+    }
+
+    #[test]
+    fn test_trace_state_ot_values() {
+        let trace_state = TraceState::from_str("foo=bar,ot=r:5;x:y").unwrap();
+        assert_eq!(trace_state.ot_value("x"), Some("y"));
+        assert_eq!(trace_state.ot_r_value(), Some(5));
+        assert_eq!(trace_state.ot_p_value(), None);
+
+        let trace_state = trace_state.insert_ot_p_value(2).unwrap();
+        assert_eq!(trace_state.header(), "ot=p:2;r:5;x:y,foo=bar");
+        assert_eq!(trace_state.ot_p_value(), Some(2));
+
+        let trace_state = trace_state.insert_ot_r_value(7).unwrap();
+        assert_eq!(trace_state.get("ot"), Some("r:7;p:2;x:y"));
+
+        assert!(trace_state.insert_ot_p_value(64).is_err());
+        assert!(trace_state.insert_ot_r_value(63).is_err());
+        assert!(trace_state.insert_ot_value("Bad", "1").is_err());
+        assert!(trace_state.insert_ot_value("ok", "a;b").is_err());
+
+        let trace_state = trace_state
+            .delete_ot_value("r")
+            .and_then(|ts| ts.delete_ot_value("p"))
+            .and_then(|ts| ts.delete_ot_value("x"))
+            .unwrap();
+        assert_eq!(trace_state.header(), "foo=bar");
+        assert!(trace_state.delete_ot_value("p").is_err());
+
+        let invalid = TraceState::from_str("ot=p:64;r:abc").unwrap();
+        assert_eq!(invalid.ot_p_value(), None);
+        assert_eq!(invalid.ot_r_value(), None);
+    }
+
+    #[test]
+    fn test_trace_state_from_str() {
+        assert_eq!(
+            TraceState::from_str(" foo=bar ,,\tapple=banana")
+                .unwrap()
+                .header(),
+            "foo=bar,apple=banana"
+        );
+        assert!(TraceState::from_str("foo").is_err());
+        assert!(TraceState::from_str("Foo=bar").is_err());
+    }
 }
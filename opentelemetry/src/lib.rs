@@ -49,6 +49,7 @@
 //!
 //! * `http`: Propagate information via [http] header maps.
 //! * `tonic`: Propagate information via [tonic]'s metadata.
+//! * `rayon`: Propagate the current context to [rayon] thread pool jobs.
 //!
 //! Finally the following flags can be used by exporter authors:
 //!
@@ -60,6 +61,7 @@
 //! [serde]: https://crates.io/crates/serde
 //! [http]: https://crates.io/crates/http
 //! [tonic]: https://crates.io/crates/tonic
+//! [rayon]: https://crates.io/crates/rayon
 //! [`HttpClient`]: crate::sdk::export::trace::HttpClient
 //! [reqwest]: https://crates.io/crates/reqwest
 //! [surf]: https://crates.io/crates/surf
@@ -178,7 +180,7 @@ pub use api::{
     baggage,
     context::{Context, ContextGuard},
     core::{Array, Key, KeyValue, Unit, Value},
-    propagation,
+    propagation, thread,
 };
 
 pub(crate) mod time {
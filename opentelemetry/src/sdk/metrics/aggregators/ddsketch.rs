@@ -20,7 +20,7 @@ use std::{
 use crate::{
     metrics::{Descriptor, MetricsError, Number, NumberKind, Result},
     sdk::export::metrics::{
-        Aggregator, Count, Distribution, Max, Min, MinMaxSumCount, Quantile, Sum,
+        Aggregator, Count, Distribution, Max, Min, MinMaxSumCount, Quantile, Subtractor, Sum,
     },
 };
 
@@ -174,6 +174,54 @@ impl Quantile for DDSKetchAggregator {
     }
 }
 
+/// Bin counts, sum and count of the result are exact as long as neither sketch
+/// collapsed bins in between. The minimum and maximum are kept from this
+/// aggregator as they bound the values of the interval.
+impl Subtractor for DDSKetchAggregator {
+    fn subtract(
+        &self,
+        operand: &(dyn Aggregator + Send + Sync),
+        result: &(dyn Aggregator + Send + Sync),
+        _descriptor: &Descriptor,
+    ) -> Result<()> {
+        match (
+            operand.as_any().downcast_ref::<Self>(),
+            result.as_any().downcast_ref::<Self>(),
+        ) {
+            (Some(op), Some(res)) => self.inner.read().map_err(From::from).and_then(|inner| {
+                op.inner.read().map_err(From::from).and_then(|op| {
+                    if (inner.gamma - op.gamma).abs() > std::f64::EPSILON {
+                        return Err(MetricsError::InconsistentAggregator(format!(
+                            "When subtracting two DDSKetchAggregators, their gamma must be the same. Expect gamma to be {:?}, but get {:?}", inner.gamma, op.gamma
+                        )));
+                    }
+                    res.inner.write().map_err(From::from).map(|mut res| {
+                        let mut delta = inner.empty_copy();
+                        delta.positive_store = inner.positive_store.clone();
+                        delta.positive_store.subtract(&op.positive_store);
+                        delta.negative_store = inner.negative_store.clone();
+                        delta.negative_store.subtract(&op.negative_store);
+                        if delta.count() > 0 {
+                            delta.sum = match inner.kind {
+                                NumberKind::F64 => Number::from(inner.sum.to_f64(&inner.kind) - op.sum.to_f64(&op.kind)),
+                                NumberKind::U64 => Number::from(inner.sum.to_u64(&inner.kind).saturating_sub(op.sum.to_u64(&op.kind))),
+                                NumberKind::I64 => Number::from(inner.sum.to_i64(&inner.kind) - op.sum.to_i64(&op.kind)),
+                            };
+                            delta.min_value = inner.min_value.clone();
+                            delta.max_value = inner.max_value.clone();
+                        }
+                        *res = delta;
+                    })
+                })
+            }),
+            _ => Err(MetricsError::InconsistentAggregator(format!(
+                "Expected {:?}, got: {:?} and {:?}",
+                self, operand, result
+            ))),
+        }
+    }
+}
+
 impl Aggregator for DDSKetchAggregator {
     fn update(&self, number: &Number, descriptor: &Descriptor) -> Result<()> {
         self.inner
@@ -185,7 +233,7 @@ impl Aggregator for DDSKetchAggregator {
     fn synchronized_move(
         &self,
         destination: &Arc<(dyn Aggregator + Send + Sync)>,
-        descriptor: &Descriptor,
+        _descriptor: &Descriptor,
     ) -> Result<()> {
         if let Some(other) = destination.as_any().downcast_ref::<Self>() {
             other
@@ -194,16 +242,8 @@ impl Aggregator for DDSKetchAggregator {
                 .map_err(From::from)
                 .and_then(|mut other| {
                     self.inner.write().map_err(From::from).map(|mut inner| {
-                        let kind = descriptor.number_kind();
-                        other.max_value = mem::replace(&mut inner.max_value, kind.zero());
-                        other.min_value = mem::replace(&mut inner.min_value, kind.zero());
-                        other.key_epsilon = mem::take(&mut inner.key_epsilon);
-                        other.offset = mem::take(&mut inner.offset);
-                        other.gamma = mem::take(&mut inner.gamma);
-                        other.gamma_ln = mem::take(&mut inner.gamma_ln);
-                        other.positive_store = mem::take(&mut inner.positive_store);
-                        other.negative_store = mem::take(&mut inner.negative_store);
-                        other.sum = mem::replace(&mut inner.sum, kind.zero());
+                        let empty = inner.empty_copy();
+                        *other = mem::replace(&mut *inner, empty);
                     })
                 })
         } else {
@@ -365,6 +405,22 @@ impl Inner {
         inner
     }
 
+    /// Returns an empty sketch with the same configuration.
+    fn empty_copy(&self) -> Inner {
+        Inner {
+            positive_store: Store::new(self.positive_store.max_num_bins),
+            negative_store: Store::new(self.negative_store.max_num_bins),
+            kind: self.kind.clone(),
+            sum: self.kind.zero(),
+            gamma: self.gamma,
+            gamma_ln: self.gamma_ln,
+            key_epsilon: self.key_epsilon,
+            offset: self.offset,
+            min_value: self.kind.max(),
+            max_value: self.kind.min(),
+        }
+    }
+
     fn add(&mut self, v: &Number, kind: &NumberKind) {
         let key = self.key(v, kind);
         match v.partial_cmp(kind, &Number::from(0.0)) {
@@ -427,7 +483,7 @@ impl Inner {
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 struct Store {
     bins: Vec<u64>,
     count: u64,
@@ -479,6 +535,11 @@ impl Store {
     /// to represent any continuous space within length. That's why we need to offset the key
     /// with `min_key` so that we get the actual bin index.
     fn add(&mut self, key: i64) {
+        self.add_count(key, 1)
+    }
+
+    /// Add `count` values to the bin of `key`, see `add`.
+    fn add_count(&mut self, key: i64, count: u64) {
         if self.count == 0 {
             self.max_key = key;
             self.min_key = key - self.bins.len() as i64 + 1
@@ -496,8 +557,8 @@ impl Store {
         };
         // we unwrap here because grow_left or grow_right will make sure the idx is less than vector size
         let bin_count = self.bins.get_mut(idx as usize).unwrap();
-        *bin_count += 1;
-        self.count += 1;
+        *bin_count += count;
+        self.count += count;
     }
 
     fn grow_left(&mut self, key: i64) {
@@ -596,52 +657,30 @@ impl Store {
 
     /// Merge two stores
     fn merge(&mut self, other: &Store) {
-        if self.count == 0 {
-            return;
-        }
-        if other.count == 0 {
-            self.bins = other.bins.clone();
-            self.min_key = other.min_key;
-            self.max_key = other.max_key;
-            self.count = other.count;
-        }
-
-        if self.max_key > other.max_key {
-            if other.min_key < self.min_key {
-                self.grow_left(other.min_key);
+        for (idx, &count) in other.bins.iter().enumerate() {
+            if count > 0 {
+                self.add_count(other.min_key + idx as i64, count);
             }
-            let start = if other.min_key > self.min_key {
-                other.min_key
-            } else {
-                self.min_key
-            } as usize;
-            for i in start..other.max_key as usize {
-                self.bins[i - self.min_key as usize] = other.bins[i - other.min_key as usize];
-            }
-            let mut n = 0;
-            for i in other.min_key as usize..self.min_key as usize {
-                n += other.bins[i - other.min_key as usize]
-            }
-            self.bins[0] += n;
-        } else if other.min_key < self.min_key {
-            let mut tmp_bins = vec![0u64; other.bins.len()];
-            tmp_bins.as_mut_slice().copy_from_slice(&other.bins);
+        }
+    }
 
-            for i in self.min_key as usize..self.max_key as usize {
-                tmp_bins[i - other.min_key as usize] += self.bins[i - self.min_key as usize];
+    /// Remove the values of `other` from this store.
+    ///
+    /// Values of `other` that fall below `min_key` are removed from the first bin, in
+    /// the same way they were collapsed into it when added.
+    fn subtract(&mut self, other: &Store) {
+        for (idx, &count) in other.bins.iter().enumerate() {
+            let key = other.min_key + idx as i64;
+            if count == 0 || key > self.max_key {
+                continue;
             }
-
-            self.bins = tmp_bins;
-            self.max_key = other.max_key;
-            self.min_key = other.min_key;
-        } else {
-            self.grow_right(other.max_key);
-            for i in other.min_key as usize..(other.max_key + 1) as usize {
-                self.bins[i - self.min_key as usize] += other.bins[i - other.min_key as usize];
+            let bin = (key - self.min_key).max(0) as usize;
+            if let Some(bin_count) = self.bins.get_mut(bin) {
+                let removed = count.min(*bin_count);
+                *bin_count -= removed;
+                self.count -= removed;
             }
         }
-
-        self.count += other.count;
     }
 }
 
@@ -1034,4 +1073,33 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_subtract() {
+        let config = DDSketchConfig::new(TEST_ALPHA, TEST_MAX_BINS, TEST_KEY_EPSILON);
+        let descriptor = Descriptor::new(
+            "test".to_string(),
+            "test".to_string(),
+            InstrumentKind::SumObserver,
+            NumberKind::F64,
+        );
+        let previous = DDSKetchAggregator::new(&config, NumberKind::F64);
+        let current = DDSKetchAggregator::new(&config, NumberKind::F64);
+        for i in 1..=100 {
+            let _ = current.update(&Number::from(i as f64), &descriptor);
+            if i <= 50 {
+                let _ = previous.update(&Number::from(i as f64), &descriptor);
+            }
+        }
+
+        let delta = DDSKetchAggregator::new(&config, NumberKind::F64);
+        current
+            .subtract(&previous, &delta, &descriptor)
+            .expect("Fail to subtract");
+
+        assert_eq!(delta.count().unwrap(), 50);
+        assert!((delta.sum().unwrap().to_f64(&NumberKind::F64) - 3775.0).abs() < std::f64::EPSILON);
+        let median = delta.quantile(0.5).unwrap().to_f64(&NumberKind::F64);
+        assert!((median - 75.0).abs() <= 75.0 * TEST_ALPHA);
+    }
 }
@@ -1,5 +1,5 @@
 use crate::metrics::{AtomicNumber, Descriptor, MetricsError, Number, NumberKind, Result};
-use crate::sdk::export::metrics::{Buckets, Count, Histogram, Sum};
+use crate::sdk::export::metrics::{Buckets, Count, Histogram, Subtractor, Sum};
 use crate::sdk::metrics::export::metrics::Aggregator;
 use std::mem;
 use std::sync::{Arc, RwLock};
@@ -64,7 +64,7 @@ impl Count for HistogramAggregator {
         self.inner
             .read()
             .map_err(From::from)
-            .map(|inner| inner.state.sum.load().to_u64(&NumberKind::U64))
+            .map(|inner| inner.state.count.load().to_u64(&NumberKind::U64))
     }
 }
 
@@ -77,6 +77,64 @@ impl Histogram for HistogramAggregator {
     }
 }
 
+/// A count or bucket count below the one of `operand` means the cumulative
+/// state was reset, in which case the result is a copy of this aggregator.
+impl Subtractor for HistogramAggregator {
+    fn subtract(
+        &self,
+        operand: &(dyn Aggregator + Send + Sync),
+        result: &(dyn Aggregator + Send + Sync),
+        descriptor: &Descriptor,
+    ) -> Result<()> {
+        match (
+            operand.as_any().downcast_ref::<Self>(),
+            result.as_any().downcast_ref::<Self>(),
+        ) {
+            (Some(op), Some(res)) => self.inner.read().map_err(From::from).and_then(|inner| {
+                op.inner.read().map_err(From::from).and_then(|op| {
+                    if inner.boundaries != op.boundaries {
+                        return Err(MetricsError::InconsistentAggregator(format!(
+                            "Expected boundaries {:?}, got: {:?}",
+                            inner.boundaries, op.boundaries
+                        )));
+                    }
+                    res.inner.write().map_err(From::from).map(|mut res| {
+                        let sum = inner.state.sum.clone();
+                        let count = inner.state.count.clone();
+                        let mut bucket_counts = inner.state.bucket_counts.clone();
+                        let reset = count.load().to_u64(&NumberKind::U64)
+                            < op.state.count.load().to_u64(&NumberKind::U64)
+                            || bucket_counts
+                                .iter()
+                                .zip(op.state.bucket_counts.iter())
+                                .any(|(current, previous)| current < previous);
+                        if !reset {
+                            sum.fetch_sub(descriptor.number_kind(), &op.state.sum.load());
+                            count.fetch_sub(&NumberKind::U64, &op.state.count.load());
+                            for (current, previous) in
+                                bucket_counts.iter_mut().zip(op.state.bucket_counts.iter())
+                            {
+                                *current -= previous;
+                            }
+                        }
+
+                        res.boundaries = inner.boundaries.clone();
+                        res.state = State {
+                            bucket_counts,
+                            count,
+                            sum,
+                        };
+                    })
+                })
+            }),
+            _ => Err(MetricsError::InconsistentAggregator(format!(
+                "Expected {:?}, got: {:?} and {:?}",
+                self, operand, result
+            ))),
+        }
+    }
+}
+
 impl Aggregator for HistogramAggregator {
     fn update(&self, number: &Number, descriptor: &Descriptor) -> Result<()> {
         self.inner.write().map_err(From::from).map(|mut inner| {
@@ -1,5 +1,5 @@
 use crate::metrics::{AtomicNumber, Descriptor, MetricsError, Number, NumberKind, Result};
-use crate::sdk::export::metrics::{Aggregator, Count, Max, Min, MinMaxSumCount, Sum};
+use crate::sdk::export::metrics::{Aggregator, Count, Max, Min, MinMaxSumCount, Subtractor, Sum};
 use std::any::Any;
 use std::cmp::Ordering;
 use std::sync::{Arc, Mutex};
@@ -70,6 +70,49 @@ impl Count for MinMaxSumCountAggregator {
 
 impl MinMaxSumCount for MinMaxSumCountAggregator {}
 
+/// The sum and count of the result are exact. The minimum and maximum of an
+/// interval cannot be recovered from two cumulative states, so the result keeps
+/// the min and max of this aggregator, which bound the values of the interval.
+/// A count below the count of `operand` means the cumulative state was reset,
+/// in which case the result is a copy of this aggregator.
+impl Subtractor for MinMaxSumCountAggregator {
+    fn subtract(
+        &self,
+        operand: &(dyn Aggregator + Send + Sync),
+        result: &(dyn Aggregator + Send + Sync),
+        descriptor: &Descriptor,
+    ) -> Result<()> {
+        match (
+            operand.as_any().downcast_ref::<Self>(),
+            result.as_any().downcast_ref::<Self>(),
+        ) {
+            (Some(op), Some(res)) => self.inner.lock().map_err(From::from).and_then(|inner| {
+                op.inner.lock().map_err(From::from).and_then(|oi| {
+                    res.inner.lock().map_err(From::from).map(|mut ri| {
+                        ri.state = match (inner.state.as_ref(), oi.state.as_ref()) {
+                            (Some(state), Some(previous)) if state.count > previous.count => {
+                                let mut delta = state.clone();
+                                delta.count -= previous.count;
+                                delta
+                                    .sum
+                                    .fetch_sub(descriptor.number_kind(), &previous.sum.load());
+                                Some(delta)
+                            }
+                            (Some(state), Some(previous)) if state.count == previous.count => None,
+                            (Some(state), _) => Some(state.clone()),
+                            (None, _) => None,
+                        };
+                    })
+                })
+            }),
+            _ => Err(MetricsError::InconsistentAggregator(format!(
+                "Expected {:?}, got: {:?} and {:?}",
+                self, operand, result
+            ))),
+        }
+    }
+}
+
 impl Aggregator for MinMaxSumCountAggregator {
     fn update(&self, number: &Number, descriptor: &Descriptor) -> Result<()> {
         self.inner
@@ -34,7 +34,7 @@ impl Subtractor for SumAggregator {
             (Some(op), Some(res)) => {
                 res.value.store(&self.value.load());
                 res.value
-                    .fetch_add(descriptor.number_kind(), &op.value.load());
+                    .fetch_sub(descriptor.number_kind(), &op.value.load());
                 Ok(())
             }
             _ => Err(MetricsError::InconsistentAggregator(format!(
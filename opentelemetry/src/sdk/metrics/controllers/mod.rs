@@ -1,6 +1,11 @@
 //! SDK Metrics Controllers
+mod multi;
 mod pull;
 mod push;
 
+pub use multi::{
+    multi, pull_reader, push_reader, MultiController, MultiControllerBuilder, PullReader,
+    PullReaderBuilder, PushReaderBuilder, PushReaderWorker,
+};
 pub use pull::{pull, PullController};
 pub use push::{push, PushController, PushControllerWorker};
@@ -0,0 +1,852 @@
+use crate::global;
+use crate::labels::{hash_labels, LabelSet};
+use crate::metrics::{registry, Descriptor, MetricsError, Number, Result};
+use crate::sdk::{
+    export::metrics::{
+        self as export, Accumulation, Aggregator, AggregatorSelector, CheckpointSet, Checkpointer,
+        ExportKindFor, Exporter, LockedProcessor, Processor, Record,
+    },
+    metrics::{
+        self,
+        processors::{self, BasicProcessor},
+        Accumulator,
+    },
+    Resource,
+};
+use fnv::FnvHasher;
+use futures::{channel::mpsc, task, Future, Stream, StreamExt};
+use std::any::Any;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+lazy_static::lazy_static! {
+    static ref DEFAULT_PUSH_PERIOD: Duration = Duration::from_secs(10);
+}
+
+const DEFAULT_CACHE_DURATION: Duration = Duration::from_secs(10);
+
+/// Create a new `MultiControllerBuilder`.
+///
+/// A multi controller registers instruments once and fans collection out to
+/// several readers. Each reader has its own aggregator selector, export kind
+/// selector and schedule.
+///
+/// # Example
+///
+/// ```no_run
+/// use opentelemetry::sdk::export::metrics::{ExportKindSelector, Exporter};
+/// use opentelemetry::sdk::metrics::{controllers, selectors, MultiController};
+///
+/// fn init(exporter: impl Exporter + Send + Sync + 'static) -> MultiController {
+///     controllers::multi()
+///         // push deltas to `exporter` every 10 seconds
+///         .with_push_reader(controllers::push_reader(
+///             selectors::simple::Selector::Exact,
+///             ExportKindSelector::Delta,
+///             exporter,
+///         ))
+///         // keep cumulative values for a scrape endpoint
+///         .with_pull_reader(controllers::pull_reader(
+///             Box::new(selectors::simple::Selector::Inexpensive),
+///             Box::new(ExportKindSelector::Cumulative),
+///         ))
+///         .build(tokio::spawn, tokio::time::interval)
+/// }
+/// ```
+pub fn multi() -> MultiControllerBuilder {
+    MultiControllerBuilder {
+        resource: None,
+        push_readers: Vec::new(),
+        pull_readers: Vec::new(),
+    }
+}
+
+/// Create a new `PushReaderBuilder` for a `MultiController`.
+pub fn push_reader<AS, ES, E>(
+    aggregator_selector: AS,
+    export_selector: ES,
+    exporter: E,
+) -> PushReaderBuilder
+where
+    AS: AggregatorSelector + Send + Sync + 'static,
+    ES: ExportKindFor + Send + Sync + 'static,
+    E: Exporter + Send + Sync + 'static,
+{
+    PushReaderBuilder {
+        aggregator_selector: Box::new(aggregator_selector),
+        export_selector: Box::new(export_selector),
+        exporter: Box::new(exporter),
+        stateful: false,
+        period: None,
+    }
+}
+
+/// Create a new `PullReaderBuilder` for a `MultiController`.
+pub fn pull_reader(
+    aggregator_selector: Box<dyn AggregatorSelector + Send + Sync>,
+    export_selector: Box<dyn ExportKindFor + Send + Sync>,
+) -> PullReaderBuilder {
+    PullReaderBuilder {
+        aggregator_selector,
+        export_selector,
+        cache_period: None,
+        memory: true,
+    }
+}
+
+/// Configuration for a reader that periodically pushes to an exporter.
+#[derive(Debug)]
+pub struct PushReaderBuilder {
+    aggregator_selector: Box<dyn AggregatorSelector + Send + Sync>,
+    export_selector: Box<dyn ExportKindFor + Send + Sync>,
+    exporter: Box<dyn Exporter + Send + Sync>,
+    stateful: bool,
+    period: Option<Duration>,
+}
+
+impl PushReaderBuilder {
+    /// Configure the statefulness of this reader's processor.
+    pub fn with_stateful(self, stateful: bool) -> Self {
+        PushReaderBuilder { stateful, ..self }
+    }
+
+    /// Configure the period of this reader.
+    pub fn with_period(self, period: Duration) -> Self {
+        PushReaderBuilder {
+            period: Some(period),
+            ..self
+        }
+    }
+}
+
+/// Configuration for a reader that collects on demand.
+#[derive(Debug)]
+pub struct PullReaderBuilder {
+    aggregator_selector: Box<dyn AggregatorSelector + Send + Sync>,
+    export_selector: Box<dyn ExportKindFor + Send + Sync>,
+    cache_period: Option<Duration>,
+    memory: bool,
+}
+
+impl PullReaderBuilder {
+    /// Configure the cache period for this reader.
+    ///
+    /// Collecting within the cache period of the previous collection returns the
+    /// previous result.
+    pub fn with_cache_period(self, period: Duration) -> Self {
+        PullReaderBuilder {
+            cache_period: Some(period),
+            ..self
+        }
+    }
+
+    /// Sets the memory behavior of the reader's `Processor`. If this is `true`,
+    /// the processor will report metric instruments and label sets that were
+    /// previously reported but not updated in the most recent interval.
+    pub fn with_memory(self, memory: bool) -> Self {
+        PullReaderBuilder { memory, ..self }
+    }
+}
+
+/// Configuration for building a new `MultiController`.
+#[derive(Debug)]
+pub struct MultiControllerBuilder {
+    resource: Option<Resource>,
+    push_readers: Vec<PushReaderBuilder>,
+    pull_readers: Vec<PullReaderBuilder>,
+}
+
+impl MultiControllerBuilder {
+    /// Configure the resource used by this controller
+    pub fn with_resource(self, resource: Resource) -> Self {
+        MultiControllerBuilder {
+            resource: Some(resource),
+            ..self
+        }
+    }
+
+    /// Add a reader that periodically pushes to its exporter.
+    pub fn with_push_reader(mut self, reader: PushReaderBuilder) -> Self {
+        self.push_readers.push(reader);
+        self
+    }
+
+    /// Add a reader that collects on demand. Pull readers are returned by
+    /// `MultiController::pull_readers` in the order they were added.
+    pub fn with_pull_reader(mut self, reader: PullReaderBuilder) -> Self {
+        self.pull_readers.push(reader);
+        self
+    }
+
+    /// Build a new `MultiController` with this configuration. The `spawn` and
+    /// `interval` functions are used to run the workers of the push readers.
+    pub fn build<S, SO, I, IS, ISI>(self, spawn: S, interval: I) -> MultiController
+    where
+        S: Fn(PushReaderWorker) -> SO,
+        I: Fn(Duration) -> IS,
+        IS: Stream<Item = ISI> + Send + 'static,
+    {
+        let mut readers = Vec::new();
+        let mut push_readers = Vec::new();
+        for push_reader in self.push_readers {
+            push_readers.push((
+                readers.len(),
+                push_reader.exporter,
+                push_reader.period.unwrap_or(*DEFAULT_PUSH_PERIOD),
+            ));
+            readers.push(ReaderState::new(
+                push_reader.aggregator_selector.into(),
+                push_reader.export_selector,
+                push_reader.stateful,
+            ));
+        }
+        let mut pull_readers = Vec::new();
+        for pull_reader in self.pull_readers {
+            pull_readers.push((
+                readers.len(),
+                pull_reader.cache_period.unwrap_or(DEFAULT_CACHE_DURATION),
+            ));
+            readers.push(ReaderState::new(
+                pull_reader.aggregator_selector.into(),
+                pull_reader.export_selector,
+                pull_reader.memory,
+            ));
+        }
+
+        let processor = Arc::new(MultiProcessor {
+            selector: MultiSelector(
+                readers
+                    .iter()
+                    .map(|reader| reader.selector.0.clone())
+                    .collect(),
+            ),
+            readers: readers.into_iter().map(Mutex::new).collect(),
+        });
+        let accumulator = metrics::accumulator(processor.clone())
+            .with_resource(self.resource.unwrap_or_default())
+            .build();
+        let provider = registry::meter_provider(Arc::new(accumulator.clone()));
+        let shared = Arc::new(Shared {
+            accumulator,
+            processor,
+            collection: Mutex::new(()),
+        });
+
+        let mut message_senders = Vec::new();
+        for (reader, exporter, period) in push_readers {
+            let (message_sender, message_receiver) = mpsc::channel(256);
+            let ticker = (interval)(period).map(|_| PushMessage::Tick);
+            (spawn)(PushReaderWorker {
+                messages: Box::pin(futures::stream::select(message_receiver, ticker)),
+                shared: shared.clone(),
+                reader,
+                exporter,
+            });
+            message_senders.push(Mutex::new(message_sender));
+        }
+
+        let pull_readers = pull_readers
+            .into_iter()
+            .map(|(reader, period)| PullReader {
+                provider: provider.clone(),
+                shared: shared.clone(),
+                reader,
+                period,
+                last_collect: Arc::new(Mutex::new(None)),
+            })
+            .collect();
+
+        MultiController {
+            message_senders,
+            provider,
+            pull_readers,
+        }
+    }
+}
+
+/// Registers instruments once and fans the collected data out to several
+/// readers.
+#[derive(Debug)]
+pub struct MultiController {
+    message_senders: Vec<Mutex<mpsc::Sender<PushMessage>>>,
+    provider: registry::RegistryMeterProvider,
+    pull_readers: Vec<PullReader>,
+}
+
+impl MultiController {
+    /// The controller's meter provider.
+    pub fn provider(&self) -> registry::RegistryMeterProvider {
+        self.provider.clone()
+    }
+
+    /// The pull readers of this controller, in the order they were added.
+    pub fn pull_readers(&self) -> &[PullReader] {
+        &self.pull_readers
+    }
+}
+
+impl Drop for MultiController {
+    fn drop(&mut self) {
+        for message_sender in &self.message_senders {
+            if let Ok(mut sender) = message_sender.lock() {
+                let _ = sender.try_send(PushMessage::Shutdown);
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+enum PushMessage {
+    Tick,
+    Shutdown,
+}
+
+/// The future which executes the work of a push reader periodically. Can be
+/// run on a passed in executor.
+#[allow(missing_debug_implementations)]
+pub struct PushReaderWorker {
+    messages: Pin<Box<dyn Stream<Item = PushMessage> + Send>>,
+    shared: Arc<Shared>,
+    reader: usize,
+    exporter: Box<dyn Exporter + Send + Sync>,
+}
+
+impl PushReaderWorker {
+    fn on_tick(&mut self) {
+        let exporter = &self.exporter;
+        if let Err(err) = self.shared.collect(self.reader).and_then(|_| {
+            self.shared
+                .processor
+                .with_processor(self.reader, |processor| {
+                    exporter.export(processor.checkpoint_set())
+                })
+        }) {
+            global::handle_error(err)
+        }
+    }
+}
+
+impl Future for PushReaderWorker {
+    type Output = ();
+    fn poll(mut self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> task::Poll<Self::Output> {
+        loop {
+            match futures::ready!(self.messages.poll_next_unpin(cx)) {
+                Some(PushMessage::Tick) => self.on_tick(),
+                // Stream has terminated or controller is shutdown, return to finish execution.
+                None | Some(PushMessage::Shutdown) => {
+                    return task::Poll::Ready(());
+                }
+            }
+        }
+    }
+}
+
+impl Drop for PushReaderWorker {
+    fn drop(&mut self) {
+        // Try to push data one last time
+        self.on_tick()
+    }
+}
+
+/// A reader of a `MultiController` that collects on demand, e.g. when a
+/// Prometheus scrape request arrives.
+#[derive(Clone, Debug)]
+pub struct PullReader {
+    provider: registry::RegistryMeterProvider,
+    shared: Arc<Shared>,
+    reader: usize,
+    period: Duration,
+    last_collect: Arc<Mutex<Option<SystemTime>>>,
+}
+
+impl PullReader {
+    /// The meter provider of the controller this reader belongs to.
+    pub fn provider(&self) -> registry::RegistryMeterProvider {
+        self.provider.clone()
+    }
+
+    /// Collects all metrics for this reader if the last collection is older
+    /// than the cache period.
+    pub fn collect(&self) -> Result<()> {
+        let mut last_collect = self.last_collect.lock()?;
+        let expired = match *last_collect {
+            Some(last_collect) => last_collect
+                .elapsed()
+                .map_or(true, |elapsed| elapsed > self.period),
+            None => true,
+        };
+        if expired {
+            *last_collect = Some(crate::time::now());
+            self.shared.collect(self.reader)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl CheckpointSet for PullReader {
+    fn try_for_each(
+        &mut self,
+        export_selector: &dyn ExportKindFor,
+        f: &mut dyn FnMut(&Record<'_>) -> Result<()>,
+    ) -> Result<()> {
+        self.shared
+            .processor
+            .with_processor(self.reader, |processor| {
+                processor.checkpoint_set().try_for_each(export_selector, f)
+            })
+    }
+}
+
+#[derive(Debug)]
+struct Shared {
+    accumulator: Accumulator,
+    processor: Arc<MultiProcessor>,
+    /// Serializes collections triggered by different readers.
+    collection: Mutex<()>,
+}
+
+impl Shared {
+    /// Collects the accumulator into every reader and completes a collection
+    /// interval for `reader`.
+    ///
+    /// Readers that are not collecting keep the accumulations in their pending
+    /// state until their next collection, so that each reader observes every
+    /// update exactly once regardless of the schedule of the other readers.
+    fn collect(&self, reader: usize) -> Result<()> {
+        let _collection = self.collection.lock()?;
+        self.accumulator
+            .0
+            .collect(&mut MultiLockedProcessor(&self.processor));
+
+        let mut state = self.processor.readers[reader].lock()?;
+        let ReaderState {
+            processor, pending, ..
+        } = &mut *state;
+        let mut checkpointer = processor.lock()?;
+        checkpointer.start_collection();
+        for (_key, value) in pending.drain() {
+            checkpointer.process(export::accumulation(
+                &value.descriptor,
+                &value.labels,
+                &value.resource,
+                &value.aggregator,
+            ))?;
+        }
+        checkpointer.finish_collection()
+    }
+}
+
+#[derive(Debug)]
+struct MultiProcessor {
+    selector: MultiSelector,
+    readers: Vec<Mutex<ReaderState>>,
+}
+
+impl MultiProcessor {
+    fn with_processor<T>(
+        &self,
+        reader: usize,
+        f: impl FnOnce(&mut dyn Checkpointer) -> Result<T>,
+    ) -> Result<T> {
+        let state = self.readers[reader].lock()?;
+        let mut checkpointer = state.processor.lock()?;
+        f(&mut checkpointer)
+    }
+}
+
+impl Processor for MultiProcessor {
+    fn aggregation_selector(&self) -> &dyn AggregatorSelector {
+        &self.selector
+    }
+}
+
+struct MultiLockedProcessor<'a>(&'a MultiProcessor);
+
+impl LockedProcessor for MultiLockedProcessor<'_> {
+    fn process(&mut self, accumulation: Accumulation<'_>) -> Result<()> {
+        let aggregators = accumulation
+            .aggregator()
+            .as_any()
+            .downcast_ref::<MultiAggregator>()
+            .ok_or_else(|| {
+                MetricsError::InconsistentAggregator(format!(
+                    "Expected MultiAggregator, got: {:?}",
+                    accumulation.aggregator()
+                ))
+            })?;
+
+        let desc = accumulation.descriptor();
+        let mut hasher = FnvHasher::default();
+        desc.attribute_hash().hash(&mut hasher);
+        hash_labels(&mut hasher, accumulation.labels());
+        hash_labels(&mut hasher, accumulation.resource());
+        let key = hasher.finish();
+
+        for (reader, aggregator) in self.0.readers.iter().zip(aggregators.0.iter()) {
+            if let Some(aggregator) = aggregator {
+                reader.lock()?.add_pending(key, &accumulation, aggregator)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+struct ReaderState {
+    selector: SharedSelector,
+    processor: BasicProcessor,
+    /// Accumulations collected since the last collection of this reader.
+    pending: HashMap<u64, PendingValue>,
+}
+
+impl ReaderState {
+    fn new(
+        selector: Arc<dyn AggregatorSelector + Send + Sync>,
+        export_selector: Box<dyn ExportKindFor + Send + Sync>,
+        memory: bool,
+    ) -> Self {
+        let selector = SharedSelector(selector);
+        ReaderState {
+            processor: processors::basic(Box::new(selector.clone()), export_selector, memory),
+            selector,
+            pending: HashMap::new(),
+        }
+    }
+
+    fn add_pending(
+        &mut self,
+        key: u64,
+        accumulation: &Accumulation<'_>,
+        aggregator: &Arc<dyn Aggregator + Send + Sync>,
+    ) -> Result<()> {
+        let desc = accumulation.descriptor();
+        if let Some(value) = self.pending.get(&key) {
+            return if desc.instrument_kind().asynchronous() {
+                // Observers report their current value, the latest one wins.
+                aggregator.synchronized_move(&value.aggregator, desc)
+            } else {
+                value.aggregator.merge(aggregator.as_ref(), desc)
+            };
+        }
+
+        if let Some(owned) = self.selector.aggregator_for(desc) {
+            aggregator.synchronized_move(&owned, desc)?;
+            self.pending.insert(
+                key,
+                PendingValue {
+                    descriptor: desc.clone(),
+                    labels: accumulation.labels().clone(),
+                    resource: accumulation.resource().clone(),
+                    aggregator: owned,
+                },
+            );
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+struct PendingValue {
+    descriptor: Descriptor,
+    labels: LabelSet,
+    resource: Resource,
+    aggregator: Arc<dyn Aggregator + Send + Sync>,
+}
+
+/// An aggregator selector shared between a reader's processor and the
+/// `MultiSelector`.
+#[derive(Clone, Debug)]
+struct SharedSelector(Arc<dyn AggregatorSelector + Send + Sync>);
+
+impl AggregatorSelector for SharedSelector {
+    fn aggregator_for(&self, descriptor: &Descriptor) -> Option<Arc<dyn Aggregator + Send + Sync>> {
+        self.0.aggregator_for(descriptor)
+    }
+}
+
+/// Selects one aggregator per reader, wrapped in a `MultiAggregator`.
+#[derive(Debug)]
+struct MultiSelector(Vec<Arc<dyn AggregatorSelector + Send + Sync>>);
+
+impl AggregatorSelector for MultiSelector {
+    fn aggregator_for(&self, descriptor: &Descriptor) -> Option<Arc<dyn Aggregator + Send + Sync>> {
+        let aggregators = self
+            .0
+            .iter()
+            .map(|selector| selector.aggregator_for(descriptor))
+            .collect::<Vec<_>>();
+
+        // The instrument is disabled if every reader disabled it.
+        if aggregators.iter().all(Option::is_none) {
+            None
+        } else {
+            Some(Arc::new(MultiAggregator(aggregators)))
+        }
+    }
+}
+
+/// An aggregator holding one aggregator per reader, as selected by the reader's
+/// aggregator selector.
+#[derive(Debug)]
+struct MultiAggregator(Vec<Option<Arc<dyn Aggregator + Send + Sync>>>);
+
+impl MultiAggregator {
+    /// Calls `f` with each pair of enabled aggregators of `self` and `other`.
+    fn try_for_each_pair<F>(&self, other: &(dyn Aggregator + Send + Sync), mut f: F) -> Result<()>
+    where
+        F: FnMut(
+            &Arc<dyn Aggregator + Send + Sync>,
+            &Arc<dyn Aggregator + Send + Sync>,
+        ) -> Result<()>,
+    {
+        match other.as_any().downcast_ref::<Self>() {
+            Some(other) if other.0.len() == self.0.len() => {
+                for pair in self.0.iter().zip(other.0.iter()) {
+                    if let (Some(this), Some(other)) = pair {
+                        f(this, other)?;
+                    }
+                }
+                Ok(())
+            }
+            _ => Err(MetricsError::InconsistentAggregator(format!(
+                "Expected {:?}, got: {:?}",
+                self, other
+            ))),
+        }
+    }
+}
+
+impl Aggregator for MultiAggregator {
+    fn update(&self, number: &Number, descriptor: &Descriptor) -> Result<()> {
+        self.0
+            .iter()
+            .flatten()
+            .try_for_each(|aggregator| aggregator.update(number, descriptor))
+    }
+
+    fn synchronized_move(
+        &self,
+        destination: &Arc<dyn Aggregator + Send + Sync>,
+        descriptor: &Descriptor,
+    ) -> Result<()> {
+        self.try_for_each_pair(destination.as_ref(), |this, other| {
+            this.synchronized_move(other, descriptor)
+        })
+    }
+
+    fn merge(&self, other: &(dyn Aggregator + Send + Sync), descriptor: &Descriptor) -> Result<()> {
+        self.try_for_each_pair(other, |this, other| this.merge(other.as_ref(), descriptor))
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::{MeterProvider, NumberKind};
+    use crate::sdk::export::metrics::{ExportKindSelector, Sum};
+    use crate::sdk::metrics::{aggregators::SumAggregator, selectors};
+
+    fn sum(
+        checkpoint_set: &mut dyn CheckpointSet,
+        export_selector: &dyn ExportKindFor,
+    ) -> Result<Option<u64>> {
+        let mut result = None;
+        checkpoint_set.try_for_each(export_selector, &mut |record| {
+            if let Some(sum) = record
+                .aggregator()
+                .and_then(|agg| agg.as_any().downcast_ref::<SumAggregator>())
+            {
+                result = Some(sum.sum()?.to_u64(&NumberKind::U64));
+            }
+            Ok(())
+        })?;
+        Ok(result)
+    }
+
+    /// Records the sum exported on each push.
+    #[derive(Debug)]
+    struct TestExporter {
+        export_kind: ExportKindSelector,
+        sums: Arc<Mutex<Vec<Option<u64>>>>,
+    }
+
+    impl TestExporter {
+        fn new(export_kind: ExportKindSelector) -> (Self, Arc<Mutex<Vec<Option<u64>>>>) {
+            let sums = Arc::new(Mutex::new(Vec::new()));
+            let exporter = TestExporter {
+                export_kind,
+                sums: sums.clone(),
+            };
+            (exporter, sums)
+        }
+    }
+
+    impl ExportKindFor for TestExporter {
+        fn export_kind_for(&self, descriptor: &Descriptor) -> export::ExportKind {
+            self.export_kind.export_kind_for(descriptor)
+        }
+    }
+
+    impl Exporter for TestExporter {
+        fn export(&self, checkpoint_set: &mut dyn CheckpointSet) -> Result<()> {
+            let sum = sum(checkpoint_set, self)?;
+            self.sums.lock()?.push(sum);
+            Ok(())
+        }
+    }
+
+    /// Builds a controller whose push reader workers are handed back to the
+    /// test instead of being spawned, so ticks can be driven by hand.
+    fn build_with_workers(
+        builder: MultiControllerBuilder,
+    ) -> (MultiController, Vec<PushReaderWorker>) {
+        let workers = Mutex::new(Vec::new());
+        let controller = builder.build(
+            |worker| workers.lock().unwrap().push(worker),
+            |_| futures::stream::pending::<()>(),
+        );
+        (controller, workers.into_inner().unwrap())
+    }
+
+    #[test]
+    fn push_readers_export_delta_and_cumulative() -> Result<()> {
+        let (delta_exporter, delta_sums) = TestExporter::new(ExportKindSelector::Delta);
+        let (cumulative_exporter, cumulative_sums) =
+            TestExporter::new(ExportKindSelector::Cumulative);
+        let (controller, mut workers) = build_with_workers(
+            multi()
+                .with_push_reader(push_reader(
+                    selectors::simple::Selector::Inexpensive,
+                    ExportKindSelector::Delta,
+                    delta_exporter,
+                ))
+                .with_push_reader(push_reader(
+                    selectors::simple::Selector::Inexpensive,
+                    ExportKindSelector::Cumulative,
+                    cumulative_exporter,
+                )),
+        );
+        let counter = controller
+            .provider()
+            .meter("test")
+            .u64_counter("counter")
+            .init();
+
+        counter.add(1, &[]);
+        workers[0].on_tick();
+        workers[1].on_tick();
+
+        counter.add(2, &[]);
+        workers[0].on_tick();
+
+        counter.add(4, &[]);
+        workers[0].on_tick();
+        workers[1].on_tick();
+
+        assert_eq!(*delta_sums.lock()?, vec![Some(1), Some(2), Some(4)]);
+        assert_eq!(*cumulative_sums.lock()?, vec![Some(1), Some(7)]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn push_and_pull_readers_share_updates() -> Result<()> {
+        let (exporter, pushed_sums) = TestExporter::new(ExportKindSelector::Delta);
+        let (controller, mut workers) = build_with_workers(
+            multi()
+                .with_push_reader(push_reader(
+                    selectors::simple::Selector::Inexpensive,
+                    ExportKindSelector::Delta,
+                    exporter,
+                ))
+                .with_pull_reader(
+                    pull_reader(
+                        Box::new(selectors::simple::Selector::Exact),
+                        Box::new(ExportKindSelector::Cumulative),
+                    )
+                    .with_cache_period(Duration::from_secs(0)),
+                ),
+        );
+        let mut pull = controller.pull_readers()[0].clone();
+        let counter = controller
+            .provider()
+            .meter("test")
+            .u64_counter("counter")
+            .init();
+
+        counter.add(3, &[]);
+        workers[0].on_tick();
+        counter.add(5, &[]);
+        workers[0].on_tick();
+
+        pull.collect()?;
+        assert_eq!(sum(&mut pull, &ExportKindSelector::Cumulative)?, Some(8));
+        assert_eq!(*pushed_sums.lock()?, vec![Some(3), Some(5)]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn readers_with_independent_schedules() -> Result<()> {
+        let controller = multi()
+            .with_pull_reader(
+                pull_reader(
+                    Box::new(selectors::simple::Selector::Inexpensive),
+                    Box::new(ExportKindSelector::Delta),
+                )
+                .with_memory(false)
+                .with_cache_period(Duration::from_secs(0)),
+            )
+            .with_pull_reader(
+                pull_reader(
+                    Box::new(selectors::simple::Selector::Exact),
+                    Box::new(ExportKindSelector::Cumulative),
+                )
+                .with_cache_period(Duration::from_secs(0)),
+            )
+            .build(|_| (), |_| futures::stream::pending::<()>());
+        let mut delta = controller.pull_readers()[0].clone();
+        let mut cumulative = controller.pull_readers()[1].clone();
+        let counter = controller
+            .provider()
+            .meter("test")
+            .u64_counter("counter")
+            .init();
+
+        counter.add(1, &[]);
+        delta.collect()?;
+        assert_eq!(sum(&mut delta, &ExportKindSelector::Delta)?, Some(1));
+
+        counter.add(2, &[]);
+        delta.collect()?;
+        assert_eq!(sum(&mut delta, &ExportKindSelector::Delta)?, Some(2));
+
+        counter.add(4, &[]);
+        cumulative.collect()?;
+        assert_eq!(
+            sum(&mut cumulative, &ExportKindSelector::Cumulative)?,
+            Some(7)
+        );
+
+        counter.add(8, &[]);
+        delta.collect()?;
+        cumulative.collect()?;
+        assert_eq!(sum(&mut delta, &ExportKindSelector::Delta)?, Some(12));
+        assert_eq!(
+            sum(&mut cumulative, &ExportKindSelector::Cumulative)?,
+            Some(15)
+        );
+
+        Ok(())
+    }
+}
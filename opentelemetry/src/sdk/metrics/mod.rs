@@ -27,7 +27,7 @@ pub mod controllers;
 pub mod processors;
 pub mod selectors;
 
-pub use controllers::{PullController, PushController, PushControllerWorker};
+pub use controllers::{MultiController, PullController, PushController, PushControllerWorker};
 
 /// Creates a new accumulator builder
 pub fn accumulator(processor: Arc<dyn Processor + Send + Sync>) -> AccumulatorBuilder {
@@ -177,6 +177,7 @@ impl AccumulatorCore {
 
     fn collect_sync_instruments(&self, locked_processor: &mut dyn LockedProcessor) -> usize {
         let mut checkpointed = 0;
+        let mut unused = Vec::new();
 
         for element in self.current.iter() {
             let (key, value) = element.pair();
@@ -190,16 +191,24 @@ impl AccumulatorCore {
                 value.collected_count.store(mods);
             } else {
                 // Having no updates since last collection, try to remove if
-                // there are no bound handles
-                if Arc::strong_count(&value) == 1 {
-                    self.current.remove(key);
-
-                    // There's a potential race between loading collected count and
-                    // loading the strong count in this function.  Since this is the
-                    // last we'll see of this record, checkpoint.
-                    if mods.partial_cmp(&NumberKind::U64, coll) != Some(Ordering::Equal) {
-                        checkpointed += self.checkpoint_record(value, locked_processor);
-                    }
+                // there are no bound handles. Removal has to wait until the
+                // iteration is done as it would otherwise deadlock the map.
+                unused.push(key.clone());
+            }
+        }
+
+        for key in unused {
+            if let Some((_key, value)) = self
+                .current
+                .remove_if(&key, |_key, value| Arc::strong_count(value) == 1)
+            {
+                // There's a potential race between loading collected count and
+                // loading the strong count in this function.  Since this is the
+                // last we'll see of this record, checkpoint.
+                let mods = &value.update_count.load();
+                let coll = &value.collected_count.load();
+                if mods.partial_cmp(&NumberKind::U64, coll) != Some(Ordering::Equal) {
+                    checkpointed += self.checkpoint_record(&value, locked_processor);
                 }
             }
         }
@@ -562,3 +571,48 @@ impl sdk_api::MeterCore for Accumulator {
         Ok(instrument)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::{registry, MeterProvider};
+    use crate::sdk::export::metrics::{Checkpointer, ExportKindSelector};
+
+    #[test]
+    fn unused_records_are_removed_on_collect() -> Result<()> {
+        let processor = Arc::new(processors::basic(
+            Box::new(selectors::simple::Selector::Inexpensive),
+            Box::new(ExportKindSelector::Cumulative),
+            true,
+        ));
+        let accumulator = accumulator(processor.clone()).build();
+        let counter = registry::meter_provider(Arc::new(accumulator.clone()))
+            .meter("test")
+            .u64_counter("counter")
+            .init();
+        let collect = || {
+            processor.lock().and_then(|mut checkpointer| {
+                checkpointer.start_collection();
+                accumulator.0.collect(&mut checkpointer);
+                checkpointer.finish_collection()
+            })
+        };
+
+        counter.add(1, &[]);
+        collect()?;
+        assert_eq!(accumulator.0.current.len(), 1);
+
+        // No updates and no bound handles, the record is dropped.
+        collect()?;
+        assert_eq!(accumulator.0.current.len(), 0);
+
+        // Bound handles keep their record alive.
+        let bound = counter.bind(&[]);
+        bound.add(1);
+        collect()?;
+        collect()?;
+        assert_eq!(accumulator.0.current.len(), 1);
+
+        Ok(())
+    }
+}
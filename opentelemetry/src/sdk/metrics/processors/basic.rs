@@ -3,7 +3,9 @@ use crate::sdk::{
         self, Accumulation, Aggregator, AggregatorSelector, CheckpointSet, Checkpointer,
         ExportKind, ExportKindFor, LockedProcessor, Processor, Record, Subtractor,
     },
-    metrics::aggregators::SumAggregator,
+    metrics::aggregators::{
+        DDSKetchAggregator, HistogramAggregator, MinMaxSumCountAggregator, SumAggregator,
+    },
     Resource,
 };
 use crate::{
@@ -156,6 +158,7 @@ impl<'a> LockedProcessor for BasicLockedProcessor<'a> {
             None
         };
 
+        let start = self.state.interval_start;
         self.state.values.insert(
             key,
             StateValue {
@@ -168,6 +171,7 @@ impl<'a> LockedProcessor for BasicLockedProcessor<'a> {
                 cumulative,
                 stateful,
                 updated: finished_collection,
+                start,
             },
         );
 
@@ -215,17 +219,13 @@ impl Checkpointer for BasicLockedProcessor<'_> {
                 // If this processor does not require memory, stale, stateless
                 // entries can be removed. This implies that they were not updated
                 // over the previous full collection interval.
-                if stale && stateless && has_memory {
-                    return false;
-                }
+                return !(stale && stateless && !has_memory);
             }
 
             // Update Aggregator state to support exporting either a
             // delta or a cumulative aggregation.
             if mkind.precomputed_sum() {
-                if let Some(current_subtractor) =
-                    value.current.as_any().downcast_ref::<SumAggregator>()
-                {
+                if let Some(current_subtractor) = subtractor(value.current.as_ref()) {
                     // This line is equivalent to:
                     // value.delta = currentSubtractor - value.cumulative
                     if let (Some(cumulative), Some(delta)) =
@@ -257,6 +257,22 @@ impl Checkpointer for BasicLockedProcessor<'_> {
     }
 }
 
+/// Returns the `Subtractor` implementation of the given aggregator, if any.
+fn subtractor(aggregator: &(dyn Aggregator + Send + Sync)) -> Option<&dyn Subtractor> {
+    let any = aggregator.as_any();
+    if let Some(sum) = any.downcast_ref::<SumAggregator>() {
+        Some(sum)
+    } else if let Some(histogram) = any.downcast_ref::<HistogramAggregator>() {
+        Some(histogram)
+    } else if let Some(mmsc) = any.downcast_ref::<MinMaxSumCountAggregator>() {
+        Some(mmsc)
+    } else if let Some(sketch) = any.downcast_ref::<DDSKetchAggregator>() {
+        Some(sketch)
+    } else {
+        None
+    }
+}
+
 #[derive(Debug, Default)]
 struct BasicProcessorConfig {
     /// Memory controls whether the processor remembers metric instruments and label
@@ -270,7 +286,6 @@ struct BasicProcessorConfig {
 struct BasicProcessorState {
     config: BasicProcessorConfig,
     values: HashMap<StateKey, StateValue>,
-    // Note: the timestamp logic currently assumes all exports are deltas.
     process_start: SystemTime,
     interval_start: SystemTime,
     interval_end: SystemTime,
@@ -329,11 +344,13 @@ impl CheckpointSet for BasicProcessorState {
                     // checkpointed value:
                     if value.stateful {
                         agg = value.cumulative.as_ref();
+                        // The cumulative state of this series started when it
+                        // was first seen by the processor.
+                        start = value.start;
                     } else {
                         agg = Some(&value.current);
+                        start = self.process_start;
                     }
-
-                    start = self.process_start;
                 }
 
                 ExportKind::Delta => {
@@ -383,10 +400,14 @@ struct StateValue {
     /// accumulator.
     updated: u64,
 
-    /// Indicates that a cumulative aggregation is being maintained, taken from the
-    /// process start time.
+    /// Indicates that a cumulative aggregation is being maintained, taken from
+    /// `start`.
     stateful: bool,
 
+    /// The start of the collection interval in which this value was first
+    /// processed. Values that are removed and seen again start over.
+    start: SystemTime,
+
     /// Indicates that "current" was allocated
     /// by the processor in order to merge results from
     /// multiple `Accumulator`s during a single collection
@@ -409,3 +430,233 @@ struct StateValue {
     /// the last cumulative value.
     cumulative: Option<Arc<dyn Aggregator + Send + Sync>>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::{InstrumentKind, Number, NumberKind};
+    use crate::sdk::export::metrics::{Count, ExportKindSelector, Histogram, Max, Min, Sum};
+    use crate::sdk::metrics::aggregators;
+    use crate::KeyValue;
+
+    #[derive(Debug)]
+    struct HistogramSelector;
+
+    impl AggregatorSelector for HistogramSelector {
+        fn aggregator_for(
+            &self,
+            descriptor: &Descriptor,
+        ) -> Option<Arc<dyn Aggregator + Send + Sync>> {
+            Some(Arc::new(aggregators::histogram(descriptor, &[10.0, 20.0])))
+        }
+    }
+
+    #[derive(Debug)]
+    struct MinMaxSumCountSelector;
+
+    impl AggregatorSelector for MinMaxSumCountSelector {
+        fn aggregator_for(
+            &self,
+            descriptor: &Descriptor,
+        ) -> Option<Arc<dyn Aggregator + Send + Sync>> {
+            Some(Arc::new(aggregators::min_max_sum_count(descriptor)))
+        }
+    }
+
+    fn collect(
+        processor: &BasicProcessor,
+        descriptor: &Descriptor,
+        labels: &LabelSet,
+        values: &[u64],
+    ) -> Result<()> {
+        let resource = Resource::default();
+        let agg = processor
+            .aggregation_selector()
+            .aggregator_for(descriptor)
+            .expect("selector always returns an aggregator");
+        for value in values {
+            agg.update(&Number::from(*value), descriptor)?;
+        }
+        let mut locked = processor.lock()?;
+        locked.start_collection();
+        if !values.is_empty() {
+            locked.process(metrics::accumulation(descriptor, labels, &resource, &agg))?;
+        }
+        locked.finish_collection()
+    }
+
+    /// Aggregator, start and end time of a checkpointed record.
+    type TestRecord = (Arc<dyn Aggregator + Send + Sync>, SystemTime, SystemTime);
+
+    fn records(
+        processor: &BasicProcessor,
+        export_selector: &dyn ExportKindFor,
+    ) -> Result<Vec<TestRecord>> {
+        let mut records = Vec::new();
+        processor
+            .lock()?
+            .checkpoint_set()
+            .try_for_each(export_selector, &mut |record| {
+                if let Some(agg) = record.aggregator() {
+                    records.push((agg.clone(), *record.start_time(), *record.end_time()));
+                }
+                Ok(())
+            })?;
+        Ok(records)
+    }
+
+    #[test]
+    fn precomputed_sum_to_delta() -> Result<()> {
+        let descriptor = Descriptor::new(
+            "observer".to_string(),
+            "test".to_string(),
+            InstrumentKind::SumObserver,
+            NumberKind::U64,
+        );
+        let labels = LabelSet::from_labels(vec![KeyValue::new("key", "value")]);
+        let processor = basic(
+            Box::new(crate::sdk::metrics::selectors::simple::Selector::Inexpensive),
+            Box::new(ExportKindSelector::Delta),
+            false,
+        );
+
+        let mut deltas = Vec::new();
+        for cumulative in &[10, 25, 25, 40] {
+            collect(&processor, &descriptor, &labels, &[*cumulative])?;
+            let records = records(&processor, &ExportKindSelector::Delta)?;
+            assert_eq!(records.len(), 1);
+            let sum = records[0]
+                .0
+                .as_any()
+                .downcast_ref::<SumAggregator>()
+                .unwrap();
+            deltas.push(sum.sum()?.to_u64(&NumberKind::U64));
+        }
+
+        assert_eq!(deltas, vec![10, 15, 0, 15]);
+        Ok(())
+    }
+
+    #[test]
+    fn precomputed_histogram_to_delta() -> Result<()> {
+        let descriptor = Descriptor::new(
+            "observer".to_string(),
+            "test".to_string(),
+            InstrumentKind::SumObserver,
+            NumberKind::U64,
+        );
+        let labels = LabelSet::from_labels(vec![]);
+        let processor = basic(
+            Box::new(HistogramSelector),
+            Box::new(ExportKindSelector::Delta),
+            false,
+        );
+
+        collect(&processor, &descriptor, &labels, &[5, 15])?;
+        collect(&processor, &descriptor, &labels, &[5, 15, 15, 25])?;
+        let records = records(&processor, &ExportKindSelector::Delta)?;
+        let histogram = records[0]
+            .0
+            .as_any()
+            .downcast_ref::<HistogramAggregator>()
+            .unwrap();
+
+        assert_eq!(histogram.histogram()?.counts(), &vec![0.0, 1.0, 1.0]);
+        assert_eq!(histogram.count()?, 2);
+        assert_eq!(histogram.sum()?.to_u64(&NumberKind::U64), 40);
+        Ok(())
+    }
+
+    #[test]
+    fn precomputed_histogram_reset_to_delta() -> Result<()> {
+        let descriptor = Descriptor::new(
+            "observer".to_string(),
+            "test".to_string(),
+            InstrumentKind::SumObserver,
+            NumberKind::U64,
+        );
+        let labels = LabelSet::from_labels(vec![]);
+        let processor = basic(
+            Box::new(HistogramSelector),
+            Box::new(ExportKindSelector::Delta),
+            false,
+        );
+
+        collect(&processor, &descriptor, &labels, &[5, 15, 25])?;
+        collect(&processor, &descriptor, &labels, &[15, 15])?;
+        let records = records(&processor, &ExportKindSelector::Delta)?;
+        let histogram = records[0]
+            .0
+            .as_any()
+            .downcast_ref::<HistogramAggregator>()
+            .unwrap();
+
+        assert_eq!(histogram.histogram()?.counts(), &vec![0.0, 2.0, 0.0]);
+        assert_eq!(histogram.count()?, 2);
+        assert_eq!(histogram.sum()?.to_u64(&NumberKind::U64), 30);
+        Ok(())
+    }
+
+    #[test]
+    fn precomputed_min_max_sum_count_reset_to_delta() -> Result<()> {
+        let descriptor = Descriptor::new(
+            "observer".to_string(),
+            "test".to_string(),
+            InstrumentKind::SumObserver,
+            NumberKind::U64,
+        );
+        let labels = LabelSet::from_labels(vec![]);
+        let processor = basic(
+            Box::new(MinMaxSumCountSelector),
+            Box::new(ExportKindSelector::Delta),
+            false,
+        );
+
+        collect(&processor, &descriptor, &labels, &[5, 15, 25])?;
+        collect(&processor, &descriptor, &labels, &[1, 2])?;
+        let records = records(&processor, &ExportKindSelector::Delta)?;
+        let mmsc = records[0]
+            .0
+            .as_any()
+            .downcast_ref::<MinMaxSumCountAggregator>()
+            .unwrap();
+
+        assert_eq!(mmsc.count()?, 2);
+        assert_eq!(mmsc.sum()?.to_u64(&NumberKind::U64), 3);
+        assert_eq!(mmsc.min()?.to_u64(&NumberKind::U64), 1);
+        assert_eq!(mmsc.max()?.to_u64(&NumberKind::U64), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn cumulative_start_time_per_series() -> Result<()> {
+        let descriptor = Descriptor::new(
+            "counter".to_string(),
+            "test".to_string(),
+            InstrumentKind::Counter,
+            NumberKind::U64,
+        );
+        let first = LabelSet::from_labels(vec![KeyValue::new("series", "first")]);
+        let second = LabelSet::from_labels(vec![KeyValue::new("series", "second")]);
+        let processor = basic(
+            Box::new(crate::sdk::metrics::selectors::simple::Selector::Inexpensive),
+            Box::new(ExportKindSelector::Cumulative),
+            true,
+        );
+
+        collect(&processor, &descriptor, &first, &[1])?;
+        let first_records = records(&processor, &ExportKindSelector::Cumulative)?;
+        let (_, first_start, first_end) = first_records[0];
+
+        collect(&processor, &descriptor, &second, &[2])?;
+        let records = records(&processor, &ExportKindSelector::Cumulative)?;
+        let mut starts = records
+            .iter()
+            .map(|(_, start, _)| *start)
+            .collect::<Vec<_>>();
+        starts.sort();
+
+        assert_eq!(starts, vec![first_start, first_end]);
+        Ok(())
+    }
+}
@@ -41,7 +41,8 @@
 //! assert!(header_value.contains("server_id=42"), "contains new name-value pair");
 //! ```
 use crate::{
-    baggage::{BaggageExt, KeyValueMetadata},
+    api::baggage::valid_key,
+    baggage::{BaggageExt, BaggageMetadata, KeyValueMetadata},
     propagation::{text_map_propagator::FieldIter, Extractor, Injector, TextMapPropagator},
     Context,
 };
@@ -49,7 +50,22 @@ use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, CONTRO
 use std::iter;
 
 static BAGGAGE_HEADER: &str = "baggage";
-const FRAGMENT: &AsciiSet = &CONTROLS.add(b' ').add(b'"').add(b';').add(b',').add(b'=');
+const FRAGMENT: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'%')
+    .add(b';')
+    .add(b',')
+    .add(b'=')
+    .add(b'\\');
+
+/// Characters percent encoded in metadata property values, which are kept
+/// encoded in the metadata so `%` is not escaped again.
+const PROPERTY_VALUE: &AsciiSet = &CONTROLS.add(b' ').add(b'"').add(b';').add(b',').add(b'\\');
+
+const MAX_LIST_MEMBERS: usize = 180;
+const MAX_BYTES_FOR_ONE_LIST_MEMBER: usize = 4096;
+const MAX_HEADER_LEN: usize = 8192;
 
 lazy_static::lazy_static! {
     static ref BAGGAGE_FIELDS: [String; 1] = [BAGGAGE_HEADER.to_string()];
@@ -113,64 +129,62 @@ impl BaggagePropagator {
 
 impl TextMapPropagator for BaggagePropagator {
     /// Encodes the values of the `Context` and injects them into the provided `Injector`.
+    ///
+    /// Name-value pairs that would exceed the [W3C Baggage limits] are dropped
+    /// as a whole.
+    ///
+    /// [W3C Baggage limits]: https://w3c.github.io/baggage/#limits
     fn inject_context(&self, cx: &Context, injector: &mut dyn Injector) {
         let baggage = cx.baggage();
-        if !baggage.is_empty() {
-            let header_value = baggage
-                .iter()
-                .map(|(name, (value, metadata))| {
-                    let metadata_str = metadata.as_str().trim();
-                    let metadata_prefix = if metadata_str.is_empty() { "" } else { ";" };
-                    utf8_percent_encode(name.as_str().trim(), FRAGMENT)
-                        .chain(iter::once("="))
-                        .chain(utf8_percent_encode(value.as_str().trim(), FRAGMENT))
-                        .chain(iter::once(metadata_prefix))
-                        .chain(iter::once(metadata_str))
-                        .collect()
-                })
-                .collect::<Vec<String>>()
-                .join(",");
+        let mut header_value = String::new();
+        let mut list_members = 0;
+        for (name, (value, metadata)) in baggage {
+            if list_members == MAX_LIST_MEMBERS {
+                break;
+            }
+
+            let list_member: String = iter::once(name.as_str().trim())
+                .chain(iter::once("="))
+                .chain(utf8_percent_encode(value.as_str().trim(), FRAGMENT))
+                .chain(encode_properties(metadata))
+                .collect();
+            let separator_len = if list_members == 0 { 0 } else { 1 };
+            if list_member.len() > MAX_BYTES_FOR_ONE_LIST_MEMBER
+                || header_value.len() + separator_len + list_member.len() > MAX_HEADER_LEN
+            {
+                continue;
+            }
+
+            if list_members > 0 {
+                header_value.push(',');
+            }
+            header_value.push_str(&list_member);
+            list_members += 1;
+        }
+
+        if !header_value.is_empty() {
             injector.set(BAGGAGE_HEADER, header_value);
         }
     }
 
     /// Extracts a `Context` with baggage values from a `Extractor`.
+    ///
+    /// List-members that do not conform to the [W3C Baggage] format are
+    /// dropped, as are the ones exceeding its limits. Values are percent
+    /// decoded, property values are kept encoded in the metadata so that
+    /// they never contain the `;` property separator.
+    ///
+    /// [W3C Baggage]: https://w3c.github.io/baggage
     fn extract_with_context(&self, cx: &Context, extractor: &dyn Extractor) -> Context {
         if let Some(header_value) = extractor.get(BAGGAGE_HEADER) {
-            let baggage = header_value.split(',').flat_map(|context_value| {
-                if let Some((name_and_value, props)) = context_value
-                    .split(';')
-                    .collect::<Vec<&str>>()
-                    .split_first()
-                {
-                    let mut iter = name_and_value.split('=');
-                    if let (Some(name), Some(value)) = (iter.next(), iter.next()) {
-                        let name = percent_decode_str(name).decode_utf8().map_err(|_| ())?;
-                        let value = percent_decode_str(value).decode_utf8().map_err(|_| ())?;
-
-                        // Here we don't store the first ; into baggage since it should be treated
-                        // as separator rather part of metadata
-                        let decoded_props = props
-                            .iter()
-                            .flat_map(|prop| percent_decode_str(prop).decode_utf8())
-                            .map(|prop| prop.trim().to_string())
-                            .collect::<Vec<String>>()
-                            .join(";"); // join with ; because we deleted all ; when calling split above
-
-                        Ok(KeyValueMetadata::new(
-                            name.trim().to_owned(),
-                            value.trim().to_string(),
-                            decoded_props.as_str(),
-                        ))
-                    } else {
-                        // Invalid name-value format
-                        Err(())
-                    }
-                } else {
-                    // Invalid baggage value format
-                    Err(())
-                }
-            });
+            let baggage = header_value
+                .split(',')
+                .map(trim_ows)
+                .filter(|list_member| {
+                    !list_member.is_empty() && list_member.len() <= MAX_BYTES_FOR_ONE_LIST_MEMBER
+                })
+                .take(MAX_LIST_MEMBERS)
+                .flat_map(parse_list_member);
             cx.with_baggage(baggage)
         } else {
             cx.clone()
@@ -182,6 +196,85 @@ impl TextMapPropagator for BaggagePropagator {
     }
 }
 
+/// Encodes metadata properties, each prefixed with the `;` separator. Property
+/// values are already percent encoded, only invalid characters are escaped.
+fn encode_properties(metadata: &BaggageMetadata) -> impl Iterator<Item = &str> {
+    metadata.properties().flat_map(|(name, value)| {
+        iter::once(";")
+            .chain(iter::once(name))
+            .chain(value.map(|_| "="))
+            .chain(
+                value
+                    .into_iter()
+                    .flat_map(|value| utf8_percent_encode(value, PROPERTY_VALUE)),
+            )
+    })
+}
+
+/// Optional white space as defined in [RFC7230, Section 3.2.3].
+///
+/// [RFC7230, Section 3.2.3]: https://tools.ietf.org/html/rfc7230#section-3.2.3
+fn trim_ows(s: &str) -> &str {
+    s.trim_matches(|c| c == ' ' || c == '\t')
+}
+
+/// Parses a `key OWS "=" OWS value *( OWS ";" OWS property )` list-member.
+fn parse_list_member(list_member: &str) -> Result<KeyValueMetadata, ()> {
+    let mut parts = list_member.split(';');
+    let (name, value) = match parse_key_value(parts.next().unwrap_or(""))? {
+        (name, Some(value)) => (name, decode(value)?),
+        // A list-member requires a value
+        (_, None) => return Err(()),
+    };
+
+    let properties = parts
+        .map(|property| {
+            parse_key_value(property).map(|(key, value)| match value {
+                Some(value) => format!("{}={}", key, value),
+                None => key.to_string(),
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(KeyValueMetadata::new(
+        name.to_string(),
+        value,
+        properties.join(";"),
+    ))
+}
+
+/// Percent decodes a list-member value.
+fn decode(value: &str) -> Result<String, ()> {
+    percent_decode_str(value)
+        .decode_utf8()
+        .map(|value| value.into_owned())
+        .map_err(|_| ())
+}
+
+/// Parses a `key [OWS "=" OWS value]` pair, checking the value is made of
+/// baggage octets.
+fn parse_key_value(s: &str) -> Result<(&str, Option<&str>), ()> {
+    let s = trim_ows(s);
+    let (key, value) = match s.find('=') {
+        Some(index) => (trim_ows(&s[..index]), Some(trim_ows(&s[index + 1..]))),
+        None => (s, None),
+    };
+    if !valid_key(key) {
+        return Err(());
+    }
+
+    match value {
+        Some(value) if !value.bytes().all(is_baggage_octet) => Err(()),
+        _ => Ok((key, value)),
+    }
+}
+
+/// US-ASCII characters excluding CTLs, whitespace, DQUOTE, comma, semicolon
+/// and backslash.
+fn is_baggage_octet(b: u8) -> bool {
+    matches!(b, 0x21 | 0x23..=0x2b | 0x2d..=0x3a | 0x3c..=0x5b | 0x5d..=0x7e)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -276,6 +369,153 @@ mod tests {
         ]
     }
 
+    // Cases from the W3C baggage test suite, as (header, expected list-members).
+    #[rustfmt::skip]
+    #[allow(clippy::type_complexity)]
+    fn w3c_extract_data() -> Vec<(&'static str, Vec<(&'static str, &'static str, &'static str)>)> {
+        vec![
+            ("SomeKey=SomeValue", vec![("SomeKey", "SomeValue", "")]),
+            ("SomeKey=SomeValue;SomeProp", vec![("SomeKey", "SomeValue", "SomeProp")]),
+            ("SomeKey=SomeValue;ValueProp=PropValue", vec![("SomeKey", "SomeValue", "ValueProp=PropValue")]),
+            ("SomeKey=SomeValue;ValueProp=Prop%20Value", vec![("SomeKey", "SomeValue", "ValueProp=Prop%20Value")]),
+            ("SomeKey=SomeValue;ValueProp=a%3Bb;Other", vec![("SomeKey", "SomeValue", "ValueProp=a%3Bb;Other")]),
+            ("SomeKey=SomeValue,SomeKey2=SomeValue2", vec![("SomeKey", "SomeValue", ""), ("SomeKey2", "SomeValue2", "")]),
+            ("SomeKey \t = \t SomeValue \t ; \t SomeProp \t , \t SomeKey2 \t = \t SomeValue2", vec![("SomeKey", "SomeValue", "SomeProp"), ("SomeKey2", "SomeValue2", "")]),
+            ("SomeKey=Some%20Value%E2%98%83", vec![("SomeKey", "Some Value\u{2603}", "")]),
+            ("SomeKey=Some=Value", vec![("SomeKey", "Some=Value", "")]),
+            ("SomeKey=", vec![("SomeKey", "", "")]),
+            ("SomeKey=SomeValue,,SomeKey2=SomeValue2", vec![("SomeKey", "SomeValue", ""), ("SomeKey2", "SomeValue2", "")]),
+            // Invalid list-members are dropped as a whole
+            ("SomeKey", vec![]),
+            ("=SomeValue", vec![]),
+            ("Some Key=SomeValue", vec![]),
+            ("SomeKey=Some Value", vec![]),
+            ("SomeKey=Some\"Value", vec![]),
+            ("SomeKey=Some\\Value", vec![]),
+            ("S\u{f6}meKey=SomeValue", vec![]),
+            ("SomeKey=%FF", vec![]),
+            ("SomeKey=SomeValue;", vec![]),
+            ("SomeKey=SomeValue;=PropValue", vec![]),
+            ("SomeKey=SomeValue;Prop Key=PropValue", vec![]),
+            ("SomeKey=SomeValue;Prop=Prop Value,SomeKey2=SomeValue2", vec![("SomeKey2", "SomeValue2", "")]),
+        ]
+    }
+
+    fn extract_header(header_value: &str) -> Context {
+        let mut extractor: HashMap<String, String> = HashMap::new();
+        extractor.insert(BAGGAGE_HEADER.to_string(), header_value.to_string());
+        BaggagePropagator::new().extract(&extractor)
+    }
+
+    #[test]
+    fn extract_w3c_baggage() {
+        for (header_value, list_members) in w3c_extract_data() {
+            let context = extract_header(header_value);
+            let baggage = context.baggage();
+
+            assert_eq!(baggage.len(), list_members.len(), "{}", header_value);
+            for (key, value, metadata) in list_members {
+                assert_eq!(
+                    baggage.get_with_metadata(key),
+                    Some(&(Value::from(value), BaggageMetadata::from(metadata))),
+                    "{}",
+                    header_value
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn extract_baggage_limits() {
+        let too_many = (0..MAX_LIST_MEMBERS + 1)
+            .map(|i| format!("key{}=value", i))
+            .collect::<Vec<_>>()
+            .join(",");
+        assert_eq!(extract_header(&too_many).baggage().len(), MAX_LIST_MEMBERS);
+
+        let too_long = format!(
+            "long={},short=value",
+            "v".repeat(MAX_BYTES_FOR_ONE_LIST_MEMBER)
+        );
+        let context = extract_header(&too_long);
+        assert_eq!(context.baggage().len(), 1);
+        assert_eq!(context.baggage().get("short"), Some(&Value::from("value")));
+
+        let over_total = (0..3)
+            .map(|i| format!("key{}={}", i, "v".repeat(2990)))
+            .collect::<Vec<_>>()
+            .join(",");
+        assert_eq!(extract_header(&over_total).baggage().len(), 2);
+    }
+
+    #[test]
+    fn inject_baggage_limits() {
+        let propagator = BaggagePropagator::new();
+
+        // Percent encoding spaces makes this list-member exceed the limit
+        let cx = Context::new().with_baggage(vec![
+            KeyValue::new("spaces", " x".repeat(1500)),
+            KeyValue::new("short", "value"),
+        ]);
+        let mut injector = HashMap::new();
+        propagator.inject_context(&cx, &mut injector);
+        assert_eq!(
+            injector.get(BAGGAGE_HEADER),
+            Some(&"short=value".to_string())
+        );
+
+        let cx = Context::new()
+            .with_baggage((0..3).map(|i| KeyValue::new(format!("key{}", i), " x".repeat(900))));
+        let mut injector = HashMap::new();
+        propagator.inject_context(&cx, &mut injector);
+        let header_value = injector.get(BAGGAGE_HEADER).unwrap();
+        assert!(header_value.len() <= MAX_HEADER_LEN);
+        assert_eq!(header_value.split(',').count(), 2);
+        assert_eq!(extract_header(header_value).baggage().len(), 2);
+    }
+
+    #[test]
+    fn baggage_round_trip() {
+        let propagator = BaggagePropagator::new();
+        let value = "a b;c,d=e%f\\g\"h\u{2603}";
+        let cx = Context::new().with_baggage(vec![KeyValueMetadata::new(
+            "key",
+            value,
+            "prop1;prop2=x%2Cy",
+        )]);
+
+        let mut injector = HashMap::new();
+        propagator.inject_context(&cx, &mut injector);
+        let extracted = propagator.extract(&injector);
+
+        assert_eq!(
+            extracted.baggage().get_with_metadata("key"),
+            Some(&(
+                Value::from(value),
+                BaggageMetadata::from("prop1;prop2=x%2Cy")
+            ))
+        );
+    }
+
+    #[test]
+    fn encoded_property_separator_round_trip() {
+        let propagator = BaggagePropagator::new();
+        let cx = extract_header("key=value;prop=a%3Bb;flag");
+
+        let (_, metadata) = cx.baggage().get_with_metadata("key").unwrap();
+        assert_eq!(
+            metadata.properties().collect::<Vec<_>>(),
+            vec![("prop", Some("a%3Bb")), ("flag", None)]
+        );
+
+        let mut injector = HashMap::new();
+        propagator.inject_context(&cx, &mut injector);
+        assert_eq!(
+            injector.get(BAGGAGE_HEADER),
+            Some(&"key=value;prop=a%3Bb;flag".to_string())
+        );
+    }
+
     #[test]
     fn extract_baggage() {
         let propagator = BaggagePropagator::new();
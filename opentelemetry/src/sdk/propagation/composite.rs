@@ -1,8 +1,146 @@
 use crate::{
+    baggage::{Baggage, BaggageExt},
+    global,
     propagation::{text_map_propagator::FieldIter, Extractor, Injector, TextMapPropagator},
+    trace::{
+        context::without_remote_span_context, SpanContext, TraceContextExt, TraceError, TraceId,
+    },
     Context,
 };
 use std::collections::HashSet;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+/// Default minimum interval between two malformed header reports.
+const DEFAULT_ERROR_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Strategy used by [`TextMapCompositePropagator`] to choose between the span
+/// contexts extracted by its propagators.
+///
+/// Propagators that extract no valid span context never replace one extracted
+/// by an earlier propagator, regardless of the strategy. Every propagator is
+/// still run so that baggage from all of them is kept.
+///
+/// Span contexts that only carry part of a trace, a trace id without span id
+/// or the debug flag without a trace, are only used if no propagator extracts
+/// a valid span context. The strategy chooses between them the same way.
+///
+/// [`TextMapCompositePropagator`]: struct.TextMapCompositePropagator.html
+#[derive(Clone, Debug, PartialEq)]
+pub enum ExtractionStrategy {
+    /// The first propagator extracting a valid span context wins.
+    FirstValid,
+    /// The last propagator extracting a valid span context wins.
+    LastValid,
+    /// The span context extracted by the propagator with the given name wins
+    /// if it is valid, otherwise the first valid span context is used.
+    Prefer(String),
+}
+
+impl Default for ExtractionStrategy {
+    fn default() -> Self {
+        ExtractionStrategy::LastValid
+    }
+}
+
+/// Records which propagator of a [`TextMapCompositePropagator`] produced the
+/// span context of an extracted `Context`.
+///
+/// # Examples
+///
+/// ```
+/// use opentelemetry::propagation::TextMapPropagator;
+/// use opentelemetry::sdk::propagation::{
+///     ExtractionSource, TextMapCompositePropagator, TraceContextPropagator,
+/// };
+/// use std::collections::HashMap;
+///
+/// let propagator = TextMapCompositePropagator::named(vec![(
+///     "tracecontext",
+///     Box::new(TraceContextPropagator::new()) as Box<_>,
+/// )]);
+///
+/// let mut headers = HashMap::new();
+/// headers.insert(
+///     "traceparent".to_string(),
+///     "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01".to_string(),
+/// );
+///
+/// let cx = propagator.extract(&headers);
+/// let source = cx.get::<ExtractionSource>().unwrap();
+/// assert_eq!(source.propagator(), "tracecontext");
+/// ```
+///
+/// [`TextMapCompositePropagator`]: struct.TextMapCompositePropagator.html
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExtractionSource {
+    propagator: String,
+}
+
+impl ExtractionSource {
+    /// The name of the propagator the span context was extracted by.
+    pub fn propagator(&self) -> &str {
+        self.propagator.as_str()
+    }
+}
+
+#[derive(Debug)]
+struct NamedPropagator {
+    name: String,
+    propagator: Box<dyn TextMapPropagator + Send + Sync>,
+}
+
+/// Limits how often malformed header errors are reported, counting the ones
+/// that were suppressed in between.
+#[derive(Debug)]
+struct ErrorRateLimiter {
+    interval: Duration,
+    last_report: Mutex<Option<SystemTime>>,
+    suppressed: AtomicUsize,
+}
+
+impl ErrorRateLimiter {
+    fn new(interval: Duration) -> Self {
+        ErrorRateLimiter {
+            interval,
+            last_report: Mutex::new(None),
+            suppressed: AtomicUsize::new(0),
+        }
+    }
+
+    fn report(&self, message: impl FnOnce() -> String) {
+        let now = crate::time::now();
+        let allowed = match self.last_report.lock() {
+            Ok(mut last_report) => match *last_report {
+                Some(last)
+                    if now
+                        .duration_since(last)
+                        .map_or(false, |elapsed| elapsed < self.interval) =>
+                {
+                    false
+                }
+                _ => {
+                    *last_report = Some(now);
+                    true
+                }
+            },
+            Err(_) => false,
+        };
+
+        if !allowed {
+            self.suppressed.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+
+        let mut message = message();
+        let suppressed = self.suppressed.swap(0, Ordering::Relaxed);
+        if suppressed > 0 {
+            message.push_str(&format!(" ({} similar errors suppressed)", suppressed));
+        }
+        global::handle_error(TraceError::Other(message.into()));
+    }
+}
 
 /// Composite propagator
 ///
@@ -10,8 +148,18 @@ use std::collections::HashSet;
 /// injecting or extracting by their respective HTTP header names.
 ///
 /// Injection and extraction from this propagator will preserve the order of the
-/// injectors and extractors passed in during initialization.
+/// injectors and extractors passed in during initialization. Which extracted
+/// span context ends up in the returned `Context` is decided by the configured
+/// [`ExtractionStrategy`], and the name of the propagator that produced it is
+/// recorded as an [`ExtractionSource`] in the same `Context`.
+///
+/// Headers that are present but could not be extracted by the propagator that
+/// owns them are reported through [`global::handle_error`], at most once per
+/// error interval.
 ///
+/// [`ExtractionStrategy`]: enum.ExtractionStrategy.html
+/// [`ExtractionSource`]: struct.ExtractionSource.html
+/// [`global::handle_error`]: ../../global/fn.handle_error.html
 /// [`TextMapPropagator`]: ../../api/propagation/text_map_propagator/trait.TextMapPropagator.html
 ///
 /// # Examples
@@ -60,18 +208,57 @@ use std::collections::HashSet;
 /// ```
 #[derive(Debug)]
 pub struct TextMapCompositePropagator {
-    propagators: Vec<Box<dyn TextMapPropagator + Send + Sync>>,
+    propagators: Vec<NamedPropagator>,
     fields: Vec<String>,
+    strategy: ExtractionStrategy,
+    errors: ErrorRateLimiter,
 }
 
 impl TextMapCompositePropagator {
     /// Constructs a new propagator out of instances of [`TextMapPropagator`].
     ///
+    /// Each propagator is named after its position in `propagators`, starting
+    /// at `"0"`. Use [`named`] to choose the names reported in
+    /// [`ExtractionSource`].
+    ///
     /// [`TextMapPropagator`]: ../../api/propagation/text_map_propagator/trait.TextMapPropagator.html
+    /// [`named`]: #method.named
+    /// [`ExtractionSource`]: struct.ExtractionSource.html
     pub fn new(propagators: Vec<Box<dyn TextMapPropagator + Send + Sync>>) -> Self {
+        Self::from_named(
+            propagators
+                .into_iter()
+                .enumerate()
+                .map(|(index, propagator)| NamedPropagator {
+                    name: index.to_string(),
+                    propagator,
+                })
+                .collect(),
+        )
+    }
+
+    /// Constructs a new propagator out of named instances of
+    /// [`TextMapPropagator`].
+    ///
+    /// [`TextMapPropagator`]: ../../api/propagation/text_map_propagator/trait.TextMapPropagator.html
+    pub fn named<N: Into<String>>(
+        propagators: Vec<(N, Box<dyn TextMapPropagator + Send + Sync>)>,
+    ) -> Self {
+        Self::from_named(
+            propagators
+                .into_iter()
+                .map(|(name, propagator)| NamedPropagator {
+                    name: name.into(),
+                    propagator,
+                })
+                .collect(),
+        )
+    }
+
+    fn from_named(propagators: Vec<NamedPropagator>) -> Self {
         let mut fields = HashSet::new();
-        for propagator in &propagators {
-            for field in propagator.fields() {
+        for named in &propagators {
+            for field in named.propagator.fields() {
                 fields.insert(field.to_string());
             }
         }
@@ -79,15 +266,75 @@ impl TextMapCompositePropagator {
         TextMapCompositePropagator {
             propagators,
             fields: fields.into_iter().collect(),
+            strategy: ExtractionStrategy::default(),
+            errors: ErrorRateLimiter::new(DEFAULT_ERROR_INTERVAL),
+        }
+    }
+
+    /// Sets the strategy used to choose between extracted span contexts.
+    ///
+    /// Defaults to [`ExtractionStrategy::LastValid`].
+    ///
+    /// [`ExtractionStrategy::LastValid`]: enum.ExtractionStrategy.html#variant.LastValid
+    pub fn with_extraction_strategy(self, strategy: ExtractionStrategy) -> Self {
+        TextMapCompositePropagator { strategy, ..self }
+    }
+
+    /// Sets the minimum interval between two reports of malformed headers.
+    ///
+    /// Defaults to 10 seconds.
+    pub fn with_error_interval(self, interval: Duration) -> Self {
+        TextMapCompositePropagator {
+            errors: ErrorRateLimiter::new(interval),
+            ..self
+        }
+    }
+
+    /// Reports headers owned by `named` that were present in the extractor
+    /// but did not produce a span context or baggage.
+    fn report_malformed(&self, named: &NamedPropagator, extractor: &dyn Extractor) {
+        let present = named
+            .propagator
+            .fields()
+            .filter(|field| matches!(extractor.get(field), Some(value) if !value.is_empty()))
+            .collect::<Vec<_>>();
+        if present.is_empty() {
+            return;
         }
+
+        self.errors.report(|| {
+            format!(
+                "propagator {} could not extract context from malformed headers {:?}",
+                named.name, present
+            )
+        });
     }
 }
 
+fn baggage_changed(before: &Baggage, after: &Baggage) -> bool {
+    before.len() != after.len()
+        || after
+            .iter()
+            .any(|(key, (value, _))| before.get(key.clone()) != Some(value))
+}
+
+/// The extracted span context of `cx`, if it is valid or carries part of a
+/// trace, and whether it is valid.
+fn extracted_span_context(cx: &Context) -> Option<(SpanContext, bool)> {
+    cx.remote_span_context()
+        .filter(|span_context| {
+            span_context.is_valid()
+                || span_context.trace_id() != TraceId::invalid()
+                || span_context.is_debug()
+        })
+        .map(|span_context| (span_context.clone(), span_context.is_valid()))
+}
+
 impl TextMapPropagator for TextMapCompositePropagator {
     /// Encodes the values of the `Context` and injects them into the `Injector`.
     fn inject_context(&self, context: &Context, injector: &mut dyn Injector) {
-        for propagator in &self.propagators {
-            propagator.inject_context(context, injector)
+        for named in &self.propagators {
+            named.propagator.inject_context(context, injector)
         }
     }
 
@@ -95,11 +342,52 @@ impl TextMapPropagator for TextMapCompositePropagator {
     /// retrieved OR if the retrieved data is invalid, then the current `Context` is
     /// returned.
     fn extract_with_context(&self, cx: &Context, extractor: &dyn Extractor) -> Context {
-        self.propagators
-            .iter()
-            .fold(cx.clone(), |current_cx, propagator| {
-                propagator.extract_with_context(&current_cx, extractor)
-            })
+        let mut current_cx = cx.clone();
+        let mut selected: Option<(SpanContext, bool, &str)> = None;
+
+        for named in &self.propagators {
+            // Each propagator starts from an empty remote span context so that
+            // a context it did not extract can not be mistaken for its own.
+            let next_cx = named.propagator.extract_with_context(
+                &current_cx.with_remote_span_context(SpanContext::empty_context()),
+                extractor,
+            );
+
+            match extracted_span_context(&next_cx) {
+                Some((span_context, is_valid)) => {
+                    let replace = match (&self.strategy, &selected) {
+                        (_, None) => true,
+                        (_, Some((_, selected_valid, _))) if is_valid != *selected_valid => {
+                            is_valid
+                        }
+                        (ExtractionStrategy::FirstValid, Some(_)) => false,
+                        (ExtractionStrategy::LastValid, Some(_)) => true,
+                        (ExtractionStrategy::Prefer(preferred), Some((_, _, name))) => {
+                            named.name == *preferred && name != preferred
+                        }
+                    };
+                    if replace {
+                        selected = Some((span_context, is_valid, named.name.as_str()));
+                    }
+                }
+                None if !baggage_changed(current_cx.baggage(), next_cx.baggage()) => {
+                    self.report_malformed(named, extractor)
+                }
+                None => {}
+            }
+
+            current_cx = next_cx;
+        }
+
+        match (selected, cx.remote_span_context()) {
+            (Some((span_context, _, name)), _) => current_cx
+                .with_remote_span_context(span_context)
+                .with_value(ExtractionSource {
+                    propagator: name.to_string(),
+                }),
+            (None, Some(span_context)) => current_cx.with_remote_span_context(span_context.clone()),
+            (None, None) => without_remote_span_context(&current_cx),
+        }
     }
 
     fn fields(&self) -> FieldIter<'_> {
@@ -109,15 +397,21 @@ impl TextMapPropagator for TextMapCompositePropagator {
 
 #[cfg(test)]
 mod tests {
-    use crate::sdk::propagation::{TextMapCompositePropagator, TraceContextPropagator};
+    use super::ErrorRateLimiter;
+    use crate::sdk::propagation::{
+        ExtractionSource, ExtractionStrategy, TextMapCompositePropagator, TraceContextPropagator,
+    };
     use crate::testing::trace::TestSpan;
     use crate::{
         propagation::{text_map_propagator::FieldIter, Extractor, Injector, TextMapPropagator},
-        trace::{SpanContext, SpanId, TraceContextExt, TraceId, TraceState},
+        trace::{SpanContext, SpanId, TraceContextExt, TraceId, TraceState, TRACE_FLAG_DEBUG},
         Context,
     };
+    use std::cell::Cell;
     use std::collections::HashMap;
     use std::str::FromStr;
+    use std::sync::atomic::Ordering;
+    use std::time::Duration;
 
     /// Dummy propagator for testing
     ///
@@ -299,4 +593,153 @@ mod tests {
 
         assert_eq!(fields, expected);
     }
+
+    fn extractor(headers: &[(&str, &str)]) -> HashMap<String, String> {
+        headers
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    fn composite(strategy: ExtractionStrategy) -> TextMapCompositePropagator {
+        TextMapCompositePropagator::named(vec![
+            ("test", Box::new(TestPropagator::new()) as Box<_>),
+            ("tracecontext", Box::new(TraceContextPropagator::new())),
+        ])
+        .with_extraction_strategy(strategy)
+    }
+
+    #[test]
+    fn extraction_strategies() {
+        let extractor = extractor(&[
+            ("testheader", "1-1-1"),
+            (
+                "traceparent",
+                "00-00000000000000000000000000000002-0000000000000002-01",
+            ),
+        ]);
+
+        for (strategy, expected_id, expected_source) in vec![
+            (ExtractionStrategy::FirstValid, 1, "test"),
+            (ExtractionStrategy::LastValid, 2, "tracecontext"),
+            (ExtractionStrategy::Prefer("test".to_string()), 1, "test"),
+            (
+                ExtractionStrategy::Prefer("tracecontext".to_string()),
+                2,
+                "tracecontext",
+            ),
+            (ExtractionStrategy::Prefer("missing".to_string()), 1, "test"),
+        ] {
+            let cx = composite(strategy.clone()).extract(&extractor);
+            assert_eq!(
+                cx.remote_span_context().map(|sc| sc.trace_id()),
+                Some(TraceId::from_u128(expected_id)),
+                "{:?}",
+                strategy
+            );
+            assert_eq!(
+                cx.get::<ExtractionSource>()
+                    .map(|source| source.propagator()),
+                Some(expected_source),
+                "{:?}",
+                strategy
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_extraction_does_not_overwrite() {
+        let composite_propagator = TextMapCompositePropagator::new(vec![
+            Box::new(TraceContextPropagator::new()),
+            Box::new(TestPropagator::new()),
+        ]);
+        let cx = composite_propagator.extract(&extractor(&[
+            (
+                "traceparent",
+                "00-00000000000000000000000000000002-0000000000000002-01",
+            ),
+            ("testheader", "garbage"),
+        ]));
+
+        assert_eq!(
+            cx.remote_span_context().map(|sc| sc.trace_id()),
+            Some(TraceId::from_u128(2))
+        );
+        assert_eq!(
+            cx.get::<ExtractionSource>()
+                .map(|source| source.propagator()),
+            Some("0")
+        );
+    }
+
+    #[test]
+    fn partial_extraction_only_used_without_valid_context() {
+        let debug_only = format!("0-0-{}", TRACE_FLAG_DEBUG);
+        let cx = composite(ExtractionStrategy::default())
+            .extract(&extractor(&[("testheader", debug_only.as_str())]));
+        assert_eq!(cx.remote_span_context().map(|sc| sc.is_debug()), Some(true));
+        assert_eq!(
+            cx.get::<ExtractionSource>()
+                .map(|source| source.propagator()),
+            Some("test")
+        );
+
+        let cx = composite(ExtractionStrategy::FirstValid).extract(&extractor(&[
+            ("testheader", debug_only.as_str()),
+            (
+                "traceparent",
+                "00-00000000000000000000000000000002-0000000000000002-01",
+            ),
+        ]));
+        assert_eq!(
+            cx.remote_span_context().map(|sc| sc.trace_id()),
+            Some(TraceId::from_u128(2))
+        );
+        assert_eq!(
+            cx.get::<ExtractionSource>()
+                .map(|source| source.propagator()),
+            Some("tracecontext")
+        );
+    }
+
+    #[test]
+    fn nothing_extracted_keeps_context() {
+        let composite_propagator = composite(ExtractionStrategy::default());
+        let cx = composite_propagator.extract(&extractor(&[("testheader", "garbage")]));
+        assert_eq!(cx.remote_span_context(), None);
+        assert_eq!(cx.get::<ExtractionSource>(), None);
+
+        let parent = SpanContext::new(
+            TraceId::from_u128(3),
+            SpanId::from_u64(3),
+            1,
+            true,
+            TraceState::default(),
+        );
+        let cx = composite_propagator.extract_with_context(
+            &Context::new().with_remote_span_context(parent.clone()),
+            &extractor(&[("testheader", "garbage")]),
+        );
+        assert_eq!(cx.remote_span_context(), Some(&parent));
+    }
+
+    #[test]
+    fn malformed_errors_are_rate_limited() {
+        let limiter = ErrorRateLimiter::new(Duration::from_secs(60));
+        let reported = Cell::new(0);
+        for _ in 0..3 {
+            limiter.report(|| {
+                reported.set(reported.get() + 1);
+                "malformed".to_string()
+            });
+        }
+
+        assert_eq!(reported.get(), 1);
+        assert_eq!(limiter.suppressed.load(Ordering::Relaxed), 2);
+
+        let limiter = ErrorRateLimiter::new(Duration::from_secs(0));
+        limiter.report(|| "malformed".to_string());
+        limiter.report(|| "malformed".to_string());
+        assert_eq!(limiter.suppressed.load(Ordering::Relaxed), 0);
+    }
 }
@@ -4,5 +4,5 @@ mod composite;
 mod trace_context;
 
 pub use baggage::BaggagePropagator;
-pub use composite::TextMapCompositePropagator;
+pub use composite::{ExtractionSource, ExtractionStrategy, TextMapCompositePropagator};
 pub use trace_context::TraceContextPropagator;
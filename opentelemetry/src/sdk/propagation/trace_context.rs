@@ -19,7 +19,10 @@
 //! [w3c trace-context docs]: https://w3c.github.io/trace-context/
 use crate::{
     propagation::{text_map_propagator::FieldIter, Extractor, Injector, TextMapPropagator},
-    trace::{SpanContext, SpanId, TraceContextExt, TraceId, TraceState, TRACE_FLAG_SAMPLED},
+    trace::{
+        SpanContext, SpanId, TraceContextExt, TraceId, TraceState, TRACE_FLAG_RANDOM,
+        TRACE_FLAG_SAMPLED,
+    },
     Context,
 };
 use std::str::FromStr;
@@ -28,6 +31,9 @@ const SUPPORTED_VERSION: u8 = 0;
 const MAX_VERSION: u8 = 254;
 const TRACEPARENT_HEADER: &str = "traceparent";
 const TRACESTATE_HEADER: &str = "tracestate";
+const MAX_TRACESTATE_LEN: usize = 512;
+/// The trace flags defined by W3C trace context Level 2.
+const SUPPORTED_TRACE_FLAGS: u8 = TRACE_FLAG_SAMPLED | TRACE_FLAG_RANDOM;
 
 lazy_static::lazy_static! {
     static ref TRACE_CONTEXT_HEADER_FIELDS: [String; 2] = [
@@ -89,13 +95,13 @@ impl TraceContextPropagator {
         let opts = u8::from_str_radix(parts[3], 16).map_err(|_| ())?;
 
         // Ensure opts are valid for version 0
-        if version == 0 && opts > 2 {
+        if version == 0 && opts > SUPPORTED_TRACE_FLAGS {
             return Err(());
         }
 
         // Build trace flags clearing all flags other than the trace-context
-        // supported sampling bit.
-        let trace_flags = opts & TRACE_FLAG_SAMPLED;
+        // supported sampled and random bits.
+        let trace_flags = opts & SUPPORTED_TRACE_FLAGS;
 
         let trace_state: TraceState =
             TraceState::from_str(extractor.get(TRACESTATE_HEADER).unwrap_or(""))
@@ -124,10 +130,16 @@ impl TextMapPropagator for TraceContextPropagator {
                 SUPPORTED_VERSION,
                 span_context.trace_id().to_u128(),
                 span_context.span_id().to_u64(),
-                span_context.trace_flags() & TRACE_FLAG_SAMPLED
+                span_context.trace_flags() & SUPPORTED_TRACE_FLAGS
             );
             injector.set(TRACEPARENT_HEADER, header_value);
-            injector.set(TRACESTATE_HEADER, span_context.trace_state().header());
+            injector.set(
+                TRACESTATE_HEADER,
+                span_context
+                    .trace_state()
+                    .truncate(MAX_TRACESTATE_LEN)
+                    .header(),
+            );
         }
     }
 
@@ -168,6 +180,9 @@ mod tests {
             ("02-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-09-XYZxsf09", "foo=bar", SpanContext::new(TraceId::from_u128(0x4bf9_2f35_77b3_4da6_a3ce_929d_0e0e_4736), SpanId::from_u64(0x00f0_67aa_0ba9_02b7), 1, true, TraceState::from_str("foo=bar").unwrap())),
             ("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01-", "foo=bar", SpanContext::new(TraceId::from_u128(0x4bf9_2f35_77b3_4da6_a3ce_929d_0e0e_4736), SpanId::from_u64(0x00f0_67aa_0ba9_02b7), 1, true, TraceState::from_str("foo=bar").unwrap())),
             ("01-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-09-", "foo=bar", SpanContext::new(TraceId::from_u128(0x4bf9_2f35_77b3_4da6_a3ce_929d_0e0e_4736), SpanId::from_u64(0x00f0_67aa_0ba9_02b7), 1, true, TraceState::from_str("foo=bar").unwrap())),
+            ("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-02", "foo=bar", SpanContext::new(TraceId::from_u128(0x4bf9_2f35_77b3_4da6_a3ce_929d_0e0e_4736), SpanId::from_u64(0x00f0_67aa_0ba9_02b7), 2, true, TraceState::from_str("foo=bar").unwrap())),
+            ("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-03", "foo=bar", SpanContext::new(TraceId::from_u128(0x4bf9_2f35_77b3_4da6_a3ce_929d_0e0e_4736), SpanId::from_u64(0x00f0_67aa_0ba9_02b7), 3, true, TraceState::from_str("foo=bar").unwrap())),
+            ("02-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-0b", "foo=bar", SpanContext::new(TraceId::from_u128(0x4bf9_2f35_77b3_4da6_a3ce_929d_0e0e_4736), SpanId::from_u64(0x00f0_67aa_0ba9_02b7), 3, true, TraceState::from_str("foo=bar").unwrap())),
         ]
     }
 
@@ -198,7 +213,8 @@ mod tests {
         vec![
             ("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01", "foo=bar", SpanContext::new(TraceId::from_u128(0x4bf9_2f35_77b3_4da6_a3ce_929d_0e0e_4736), SpanId::from_u64(0x00f0_67aa_0ba9_02b7), 1, true, TraceState::from_str("foo=bar").unwrap())),
             ("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-00", "foo=bar", SpanContext::new(TraceId::from_u128(0x4bf9_2f35_77b3_4da6_a3ce_929d_0e0e_4736), SpanId::from_u64(0x00f0_67aa_0ba9_02b7), 0, true, TraceState::from_str("foo=bar").unwrap())),
-            ("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01", "foo=bar", SpanContext::new(TraceId::from_u128(0x4bf9_2f35_77b3_4da6_a3ce_929d_0e0e_4736), SpanId::from_u64(0x00f0_67aa_0ba9_02b7), 0xff, true, TraceState::from_str("foo=bar").unwrap())),
+            ("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-03", "foo=bar", SpanContext::new(TraceId::from_u128(0x4bf9_2f35_77b3_4da6_a3ce_929d_0e0e_4736), SpanId::from_u64(0x00f0_67aa_0ba9_02b7), 0xff, true, TraceState::from_str("foo=bar").unwrap())),
+            ("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-02", "foo=bar", SpanContext::new(TraceId::from_u128(0x4bf9_2f35_77b3_4da6_a3ce_929d_0e0e_4736), SpanId::from_u64(0x00f0_67aa_0ba9_02b7), 2, true, TraceState::from_str("foo=bar").unwrap())),
             ("", "", SpanContext::empty_context()),
         ]
     }
@@ -292,4 +308,36 @@ mod tests {
 
         assert_eq!(Extractor::get(&injector, TRACESTATE_HEADER), Some(state))
     }
+
+    #[test]
+    fn inject_w3c_truncates_tracestate() {
+        let propagator = TraceContextPropagator::new();
+        let long_value = "x".repeat(200);
+        let trace_state = TraceState::from_key_value(
+            (0..5)
+                .map(|i| (format!("key{}", i), long_value.clone()))
+                .chain(vec![("short".to_string(), "1".to_string())]),
+        )
+        .unwrap();
+        let context = SpanContext::new(
+            TraceId::from_u128(1),
+            SpanId::from_u64(1),
+            1,
+            true,
+            trace_state,
+        );
+
+        let mut injector: HashMap<String, String> = HashMap::new();
+        propagator.inject_context(
+            &Context::current_with_span(TestSpan(context)),
+            &mut injector,
+        );
+
+        let header = Extractor::get(&injector, TRACESTATE_HEADER).unwrap();
+        assert!(header.len() <= MAX_TRACESTATE_LEN);
+        assert_eq!(
+            header,
+            format!("key0={},key1={},short=1", long_value, long_value)
+        );
+    }
 }
@@ -65,6 +65,12 @@ impl IdGenerator for XrayIdGenerator {
     fn new_span_id(&self) -> SpanId {
         self.sdk_default_generator.new_span_id()
     }
+
+    /// Only the leading 4 bytes hold the timestamp, the rightmost 12 bytes
+    /// are random.
+    fn random_trace_ids(&self) -> bool {
+        true
+    }
 }
 
 #[cfg(test)]
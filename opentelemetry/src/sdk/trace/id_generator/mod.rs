@@ -22,6 +22,11 @@ impl crate::trace::IdGenerator for IdGenerator {
     fn new_span_id(&self) -> SpanId {
         CURRENT_RNG.with(|rng| SpanId::from_u64(rng.borrow_mut().gen()))
     }
+
+    /// Trace ids are entirely random.
+    fn random_trace_ids(&self) -> bool {
+        true
+    }
 }
 
 thread_local! {
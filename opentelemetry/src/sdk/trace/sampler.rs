@@ -38,10 +38,14 @@
 //! MUST NOT allow this combination.
 
 use crate::{
+    api::trace::span_context::OT_P_VALUE_KEY,
     trace::{Link, SpanKind, TraceContextExt, TraceId, TraceState},
     Context, KeyValue,
 };
 
+/// Span attribute holding the probability a span was sampled with.
+const SAMPLING_PROBABILITY_ATTRIBUTE: &str = "sampling.probability";
+
 /// The `ShouldSample` interface allows implementations to provide samplers
 /// which will return a sampling `SamplingResult` based on information that
 /// is typically available just before the `Span` was created.
@@ -57,6 +61,13 @@ pub trait ShouldSample: Send + Sync + std::fmt::Debug {
         attributes: &[KeyValue],
         links: &[Link],
     ) -> SamplingResult;
+
+    /// Whether spans sampled by this sampler carry the consistent probability
+    /// `sampling.probability` attribute. Children of local sampled parents
+    /// only inherit the attribute if this returns `true`.
+    fn records_sampling_probability(&self) -> bool {
+        false
+    }
 }
 
 /// The result of sampling logic for a given `Span`.
@@ -90,11 +101,27 @@ pub enum Sampler {
     /// Never sample the trace
     AlwaysOff,
     /// Respects the parent span's sampling decision or delegates a delegate sampler for root spans.
+    /// Spans whose parent has the debug flag set are always sampled.
     ParentBased(Box<Sampler>),
     /// Sample a given fraction of traces. Fractions >= 1 will always sample. If the parent span is
     /// sampled, then it's child spans will automatically be sampled. Fractions < 0 are treated as
     /// zero, but spans may still be sampled if their parent is.
+    ///
+    /// Root spans and spans whose parent has the W3C trace context Level 2
+    /// random flag are sampled based on the rightmost 7 bytes of the trace id.
     TraceIdRatioBased(f64),
+    /// Sample a given fraction of traces using consistent probability sampling.
+    ///
+    /// The fraction is rounded to a power of two p-value, and spans are
+    /// sampled if it does not exceed the trace's r-value. Both are propagated
+    /// in the `ot` trace state entry as `ot=p:..;r:..`, so services using the
+    /// same r-value make consistent decisions and downstream consumers can
+    /// compute adjusted counts. Sampled spans carry the probability in the
+    /// `sampling.probability` attribute.
+    ///
+    /// Wrap it in `ParentBased` for children of sampled spans to inherit the
+    /// probability of their parent.
+    ConsistentProbabilityBased(f64),
 }
 
 impl ShouldSample for Sampler {
@@ -107,54 +134,198 @@ impl ShouldSample for Sampler {
         attributes: &[KeyValue],
         links: &[Link],
     ) -> SamplingResult {
+        // all sampler in SDK except the consistent probability sampler will not
+        // modify trace state.
+        let trace_state = match parent_context {
+            Some(ctx) => ctx.span().span_context().trace_state().clone(),
+            None => TraceState::default(),
+        };
         let decision = match self {
             // Always sample the trace
             Sampler::AlwaysOn => SamplingDecision::RecordAndSample,
             // Never sample the trace
             Sampler::AlwaysOff => SamplingDecision::Drop,
             // The parent decision if sampled; otherwise the decision of delegate_sampler
-            Sampler::ParentBased(delegate_sampler) => parent_context.map_or(
-                delegate_sampler
-                    .should_sample(parent_context, trace_id, name, span_kind, attributes, links)
-                    .decision,
-                |ctx| {
+            Sampler::ParentBased(delegate_sampler) => match parent_context {
+                Some(ctx) => {
                     let parent_span_context = ctx.span().span_context();
-                    if parent_span_context.is_sampled() {
-                        SamplingDecision::RecordAndSample
+                    // Debug traces are sampled regardless of the delegate.
+                    if parent_span_context.is_sampled() || parent_span_context.is_debug() {
+                        // Children of consistent samplers inherit the adjusted
+                        // count of their parent.
+                        let attributes = if delegate_sampler.records_sampling_probability() {
+                            sampling_probability_attributes(&trace_state)
+                        } else {
+                            Vec::new()
+                        };
+                        return SamplingResult {
+                            decision: SamplingDecision::RecordAndSample,
+                            attributes,
+                            trace_state,
+                        };
                     } else {
                         SamplingDecision::Drop
                     }
-                },
-            ),
+                }
+                None => {
+                    return delegate_sampler.should_sample(
+                        parent_context,
+                        trace_id,
+                        name,
+                        span_kind,
+                        attributes,
+                        links,
+                    )
+                }
+            },
             // Probabilistically sample the trace.
             Sampler::TraceIdRatioBased(prob) => {
-                if *prob >= 1.0 {
-                    SamplingDecision::RecordAndSample
-                } else {
-                    let prob_upper_bound = (prob.max(0.0) * (1u64 << 63) as f64) as u64;
-                    // The trace_id is already randomly generated, so we don't need a new one here
-                    let rnd_from_trace_id = (trace_id.to_u128() as u64) >> 1;
-
-                    if rnd_from_trace_id < prob_upper_bound {
-                        SamplingDecision::RecordAndSample
-                    } else {
-                        SamplingDecision::Drop
-                    }
-                }
+                sample_based_on_probability(*prob, trace_id, is_random(parent_context))
+            }
+            // Probabilistically sample the trace, recording the probability.
+            Sampler::ConsistentProbabilityBased(prob) => {
+                return sample_consistent_probability(
+                    *prob,
+                    trace_id,
+                    is_random(parent_context),
+                    trace_state,
+                )
             }
         };
 
         SamplingResult {
             decision,
-            // No extra attributes ever set by the SDK samplers.
+            // No extra attributes ever set by the other SDK samplers.
             attributes: Vec::new(),
-            // all sampler in SDK will not modify trace state.
-            trace_state: match parent_context {
-                Some(ctx) => ctx.span().span_context().trace_state().clone(),
-                None => TraceState::default(),
-            },
+            trace_state,
         }
     }
+
+    fn records_sampling_probability(&self) -> bool {
+        match self {
+            Sampler::ConsistentProbabilityBased(_) => true,
+            Sampler::ParentBased(delegate_sampler) => {
+                delegate_sampler.records_sampling_probability()
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Root trace ids and trace ids flagged as random by the parent are sampled
+/// from the part W3C trace context Level 2 requires to be random.
+fn is_random(parent_context: Option<&Context>) -> bool {
+    match parent_context {
+        Some(ctx) => ctx.span().span_context().is_random(),
+        None => true,
+    }
+}
+
+fn sample_based_on_probability(prob: f64, trace_id: TraceId, random: bool) -> SamplingDecision {
+    if prob >= 1.0 {
+        return SamplingDecision::RecordAndSample;
+    }
+
+    let (rnd_from_trace_id, bits) = if random {
+        // The rightmost 7 bytes of the trace id are random.
+        (trace_id.to_u128() as u64 & ((1 << 56) - 1), 56)
+    } else {
+        // The trace_id is assumed to be randomly generated, so we don't need a new one here
+        ((trace_id.to_u128() as u64) >> 1, 63)
+    };
+    let prob_upper_bound = (prob.max(0.0) * (1u64 << bits) as f64) as u64;
+
+    if rnd_from_trace_id < prob_upper_bound {
+        SamplingDecision::RecordAndSample
+    } else {
+        SamplingDecision::Drop
+    }
+}
+
+fn sample_consistent_probability(
+    prob: f64,
+    trace_id: TraceId,
+    random: bool,
+    trace_state: TraceState,
+) -> SamplingResult {
+    let r = trace_state
+        .ot_r_value()
+        .unwrap_or_else(|| r_value(trace_id, random));
+    let p = p_value(prob);
+    let decision = if p <= r {
+        SamplingDecision::RecordAndSample
+    } else {
+        SamplingDecision::Drop
+    };
+
+    // The r-value is kept for consistent decisions further down the trace, the
+    // p-value is only meaningful for sampled spans.
+    let updated_trace_state = trace_state.insert_ot_r_value(r).and_then(|ts| {
+        if decision == SamplingDecision::RecordAndSample {
+            ts.insert_ot_p_value(p)
+        } else if ts.ot_value(OT_P_VALUE_KEY).is_some() {
+            ts.delete_ot_value(OT_P_VALUE_KEY)
+        } else {
+            Ok(ts)
+        }
+    });
+    let trace_state = updated_trace_state.unwrap_or(trace_state);
+
+    SamplingResult {
+        attributes: if decision == SamplingDecision::RecordAndSample {
+            sampling_probability_attributes(&trace_state)
+        } else {
+            Vec::new()
+        },
+        decision,
+        trace_state,
+    }
+}
+
+/// The r-value, the number of leading zeros of the trace randomness. It is
+/// taken from the random part of the trace id if there is one.
+fn r_value(trace_id: TraceId, random: bool) -> u8 {
+    let leading_zeros = if random {
+        // The rightmost 7 bytes of the trace id are random.
+        ((trace_id.to_u128() as u64) << 8).leading_zeros().min(56)
+    } else {
+        rand::random::<u64>().leading_zeros()
+    };
+
+    leading_zeros.min(62) as u8
+}
+
+/// The p-value for a sampling probability. Probabilities that are not a power
+/// of two pick one of the two nearest p-values so that the expected sampling
+/// probability is `prob`.
+fn p_value(prob: f64) -> u8 {
+    if prob.is_nan() || prob <= 0.0 {
+        return 63;
+    } else if prob >= 1.0 {
+        return 0;
+    }
+
+    let p_floor = (-prob.log2()).floor();
+    let prob_floor = (-p_floor).exp2();
+    let prob_ceil = (-p_floor - 1.0).exp2();
+    let p = if rand::random::<f64>() < (prob - prob_ceil) / (prob_floor - prob_ceil) {
+        p_floor
+    } else {
+        p_floor + 1.0
+    };
+
+    p.min(63.0) as u8
+}
+
+/// The sampling probability recorded in the trace state, as span attributes.
+pub(crate) fn sampling_probability_attributes(trace_state: &TraceState) -> Vec<KeyValue> {
+    match trace_state.ot_p_value() {
+        Some(p) if p < 63 => vec![KeyValue::new(
+            SAMPLING_PROBABILITY_ATTRIBUTE,
+            (-f64::from(p)).exp2(),
+        )],
+        _ => Vec::new(),
+    }
 }
 
 #[cfg(test)]
@@ -162,8 +333,9 @@ mod tests {
     use super::*;
     use crate::sdk::trace::{Sampler, SamplingDecision, ShouldSample};
     use crate::testing::trace::TestSpan;
-    use crate::trace::{SpanContext, SpanId, TraceState, TRACE_FLAG_SAMPLED};
+    use crate::trace::{SpanContext, SpanId, TraceState, TRACE_FLAG_RANDOM, TRACE_FLAG_SAMPLED};
     use rand::Rng;
+    use std::str::FromStr;
 
     #[rustfmt::skip]
     fn sampler_data() -> Vec<(&'static str, Sampler, f64, bool, bool)> {
@@ -177,6 +349,12 @@ mod tests {
             ("ratio_.75", Sampler::TraceIdRatioBased(0.75), 0.75, false, false),
             ("ratio_2.0", Sampler::TraceIdRatioBased(2.0), 1.0, false, false),
 
+            ("consistent_0", Sampler::ConsistentProbabilityBased(0.0), 0.0, false, false),
+            ("consistent_.25", Sampler::ConsistentProbabilityBased(0.25), 0.25, false, false),
+            ("consistent_.30", Sampler::ConsistentProbabilityBased(0.30), 0.30, false, false),
+            ("consistent_.75", Sampler::ConsistentProbabilityBased(0.75), 0.75, false, false),
+            ("consistent_1.0", Sampler::ConsistentProbabilityBased(1.0), 1.0, false, false),
+
             // Spans w/o a parent delegate
             ("delegate_to_always_on", Sampler::ParentBased(Box::new(Sampler::AlwaysOn)), 1.0, false, false),
             ("delegate_to_always_off", Sampler::ParentBased(Box::new(Sampler::AlwaysOff)), 0.0, false, false),
@@ -185,6 +363,7 @@ mod tests {
             ("delegate_to_ratio_.50", Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(0.50))), 0.50, false, false),
             ("delegate_to_ratio_.75", Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(0.75))), 0.75, false, false),
             ("delegate_to_ratio_2.0", Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(2.0))), 1.0, false, false),
+            ("delegate_to_consistent_.30", Sampler::ParentBased(Box::new(Sampler::ConsistentProbabilityBased(0.30))), 0.30, false, false),
 
             // Spans with a parent that is *not* sampled act like spans w/o a parent
             ("unsampled_parent_with_ratio_-1", Sampler::TraceIdRatioBased(-1.0), 0.0, true, false),
@@ -192,6 +371,7 @@ mod tests {
             ("unsampled_parent_with_ratio_.50", Sampler::TraceIdRatioBased(0.50), 0.5, true, false),
             ("unsampled_parent_with_ratio_.75", Sampler::TraceIdRatioBased(0.75), 0.75, true, false),
             ("unsampled_parent_with_ratio_2.0", Sampler::TraceIdRatioBased(2.0), 1.0, true, false),
+            ("unsampled_parent_with_consistent_.30", Sampler::ConsistentProbabilityBased(0.30), 0.30, true, false),
             ("unsampled_parent_or_else_with_always_on", Sampler::ParentBased(Box::new(Sampler::AlwaysOn)), 0.0, true, false),
             ("unsampled_parent_or_else_with_always_off", Sampler::ParentBased(Box::new(Sampler::AlwaysOff)), 0.0, true, false),
             ("unsampled_parent_or_else_with_ratio_.25", Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(0.25))), 0.0, true, false),
@@ -270,4 +450,122 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn ratio_sampling_uses_random_trace_id_bits() {
+        let sampler = Sampler::TraceIdRatioBased(0.5);
+        // Only the upper bits of the lower 64 bits are set, so the 56 random bits
+        // are all zero while the legacy 63 bit value is above the threshold.
+        let trace_id = TraceId::from_u128(0xff00_0000_0000_0000);
+        let decision = |trace_flags| {
+            let parent_context = Context::current_with_span(TestSpan(SpanContext::new(
+                TraceId::from_u128(1),
+                SpanId::from_u64(1),
+                trace_flags,
+                true,
+                TraceState::default(),
+            )));
+            sampler
+                .should_sample(
+                    Some(&parent_context),
+                    trace_id,
+                    "test",
+                    &SpanKind::Internal,
+                    &[],
+                    &[],
+                )
+                .decision
+        };
+
+        assert_eq!(
+            decision(TRACE_FLAG_RANDOM),
+            SamplingDecision::RecordAndSample
+        );
+        assert_eq!(decision(0), SamplingDecision::Drop);
+    }
+
+    fn consistent_sample(sampler: &Sampler, parent: Option<SpanContext>) -> SamplingResult {
+        let parent_context =
+            parent.map(|span_context| Context::current_with_span(TestSpan(span_context)));
+        sampler.should_sample(
+            parent_context.as_ref(),
+            TraceId::from_u128(rand::thread_rng().gen()),
+            "test",
+            &SpanKind::Internal,
+            &[],
+            &[],
+        )
+    }
+
+    #[test]
+    fn consistent_probability_trace_state() {
+        let sampler = Sampler::ConsistentProbabilityBased(0.25);
+        let parent = |trace_state: &str| {
+            SpanContext::new(
+                TraceId::from_u128(1),
+                SpanId::from_u64(1),
+                0,
+                true,
+                TraceState::from_str(trace_state).unwrap(),
+            )
+        };
+
+        // The parent r-value decides, and the p-value is recorded if sampled.
+        let result = consistent_sample(&sampler, Some(parent("foo=bar,ot=r:2")));
+        assert_eq!(result.decision, SamplingDecision::RecordAndSample);
+        assert_eq!(result.trace_state.get("ot"), Some("p:2;r:2"));
+        assert_eq!(result.trace_state.get("foo"), Some("bar"));
+        assert_eq!(
+            result.attributes,
+            vec![KeyValue::new("sampling.probability", 0.25)]
+        );
+
+        // A stale p-value is removed if not sampled.
+        let result = consistent_sample(&sampler, Some(parent("ot=p:0;r:1")));
+        assert_eq!(result.decision, SamplingDecision::Drop);
+        assert_eq!(result.trace_state.get("ot"), Some("r:1"));
+        assert!(result.attributes.is_empty());
+
+        // Root spans get an r-value from the trace id.
+        let result = consistent_sample(&Sampler::ConsistentProbabilityBased(1.0), None);
+        assert_eq!(result.decision, SamplingDecision::RecordAndSample);
+        assert_eq!(result.trace_state.ot_p_value(), Some(0));
+        assert!(result.trace_state.ot_r_value().is_some());
+        assert_eq!(
+            result.attributes,
+            vec![KeyValue::new("sampling.probability", 1.0)]
+        );
+    }
+
+    #[test]
+    fn consistent_probability_r_value() {
+        let random_trace_id = TraceId::from_u128(0xffff_ffff_ff00_0000_0000_0000_0000_0001);
+        assert_eq!(r_value(random_trace_id, true), 55);
+        assert_eq!(r_value(TraceId::from_u128(0x00ff_0000_0000_0000), true), 0);
+        assert_eq!(r_value(TraceId::from_u128(0), true), 56);
+    }
+
+    #[test]
+    fn parent_based_inherits_probability() {
+        let sampler = Sampler::ParentBased(Box::new(Sampler::ConsistentProbabilityBased(0.5)));
+        let parent = SpanContext::new(
+            TraceId::from_u128(1),
+            SpanId::from_u64(1),
+            TRACE_FLAG_SAMPLED,
+            true,
+            TraceState::from_str("ot=p:3;r:10").unwrap(),
+        );
+
+        let result = consistent_sample(&sampler, Some(parent));
+        assert_eq!(result.decision, SamplingDecision::RecordAndSample);
+        assert_eq!(result.trace_state.get("ot"), Some("p:3;r:10"));
+        assert_eq!(
+            result.attributes,
+            vec![KeyValue::new("sampling.probability", 0.125)]
+        );
+
+        // Root spans are sampled by the delegate, including its trace state.
+        let result = consistent_sample(&sampler, None);
+        assert!(result.trace_state.ot_r_value().is_some());
+    }
 }
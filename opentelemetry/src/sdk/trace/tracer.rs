@@ -10,6 +10,7 @@
 use crate::sdk::{
     trace::{
         provider::{TracerProvider, TracerProviderInner},
+        sampler::sampling_probability_attributes,
         span::{Span, SpanData},
         EvictedHashMap, EvictedQueue, SamplingDecision, SamplingResult,
     },
@@ -17,7 +18,7 @@ use crate::sdk::{
 };
 use crate::trace::{
     Link, SpanBuilder, SpanContext, SpanId, SpanKind, StatusCode, TraceContextExt, TraceId,
-    TraceState, TRACE_FLAG_SAMPLED,
+    TraceState, TRACE_FLAG_RANDOM, TRACE_FLAG_SAMPLED,
 };
 use crate::{Context, KeyValue};
 use std::fmt;
@@ -186,28 +187,36 @@ impl crate::trace::Tracer for Tracer {
                 }
             })
             .or_else(|| cx.remote_span_context());
-        // Build context for sampling decision
-        let (no_parent, trace_id, parent_span_id, remote_parent, parent_trace_flags) =
-            parent_span_context
-                .as_ref()
-                .map(|ctx| {
-                    (
-                        false,
-                        ctx.trace_id(),
-                        ctx.span_id(),
-                        ctx.is_remote(),
-                        ctx.trace_flags(),
-                    )
-                })
-                .unwrap_or((
-                    true,
+        // A parent without a valid trace, e.g. a remote context only carrying
+        // sampling flags, has no trace to join. The span starts a new trace
+        // while the sampler still decides based on the parent's flags. A
+        // remote parent with a trace id but no span id, e.g. a Datadog
+        // synthetics request, starts the root span of that trace.
+        let joined_parent = parent_span_context.filter(|ctx| ctx.trace_id() != TraceId::invalid());
+        // The random flag is a property of the trace id, so it is kept even if
+        // this span is not sampled.
+        let random_flag = match joined_parent {
+            Some(ctx) => ctx.trace_flags() & TRACE_FLAG_RANDOM,
+            None if builder.trace_id.is_none() && config.id_generator.random_trace_ids() => {
+                TRACE_FLAG_RANDOM
+            }
+            None => 0,
+        };
+        let (trace_id, parent_span_id) = joined_parent
+            .map(|ctx| (ctx.trace_id(), ctx.span_id()))
+            .unwrap_or_else(|| {
+                (
                     builder
                         .trace_id
                         .unwrap_or_else(|| config.id_generator.new_trace_id()),
                     SpanId::invalid(),
-                    false,
-                    0,
-                ));
+                )
+            });
+        // Build context for sampling decision
+        let (no_parent, remote_parent, parent_trace_flags) = parent_span_context
+            .as_ref()
+            .map(|ctx| (false, ctx.is_remote(), ctx.trace_flags()))
+            .unwrap_or((true, false, 0));
 
         // There are 3 paths for sampling.
         //
@@ -228,12 +237,18 @@ impl crate::trace::Tracer for Tracer {
             )
         } else {
             // has parent that is local: use parent if sampled, or don't record.
+            let records_probability = config.default_sampler.records_sampling_probability();
             parent_span_context
                 .filter(|span_context| span_context.is_sampled())
                 .map(|span_context| {
+                    let attributes = if records_probability {
+                        sampling_probability_attributes(span_context.trace_state())
+                    } else {
+                        Vec::new()
+                    };
                     (
                         parent_trace_flags,
-                        Vec::new(),
+                        attributes,
                         span_context.trace_state().clone(),
                     )
                 })
@@ -278,7 +293,13 @@ impl crate::trace::Tracer for Tracer {
             }
         });
 
-        let span_context = SpanContext::new(trace_id, span_id, flags, false, span_trace_state);
+        let span_context = SpanContext::new(
+            trace_id,
+            span_id,
+            flags | random_flag,
+            false,
+            span_trace_state,
+        );
         let span = Span::new(span_context, inner, self.clone());
 
         // Call `on_start` for all processors
@@ -297,10 +318,11 @@ mod tests {
             self,
             trace::{Config, Sampler, SamplingDecision, SamplingResult, ShouldSample},
         },
-        testing::trace::TestSpan,
+        testing::trace::{new_test_exporter, TestSpan},
         trace::{
             Link, Span, SpanBuilder, SpanContext, SpanId, SpanKind, TraceContextExt, TraceId,
-            TraceState, Tracer, TracerProvider, TRACE_FLAG_SAMPLED,
+            TraceState, Tracer, TracerProvider, TRACE_FLAG_DEBUG, TRACE_FLAG_RANDOM,
+            TRACE_FLAG_SAMPLED,
         },
         Context, KeyValue,
     };
@@ -373,4 +395,138 @@ mod tests {
 
         assert!(!span.span_context().is_sampled());
     }
+
+    #[test]
+    fn sampled_parent_without_trace_starts_sampled_root() {
+        let config = Config::default()
+            .with_default_sampler(Sampler::ParentBased(Box::new(Sampler::AlwaysOff)));
+        let tracer_provider = sdk::trace::TracerProvider::builder()
+            .with_config(config)
+            .build();
+        let tracer = tracer_provider.get_tracer("test", None);
+
+        let context = Context::new().with_remote_span_context(SpanContext::new(
+            TraceId::invalid(),
+            SpanId::invalid(),
+            TRACE_FLAG_SAMPLED | TRACE_FLAG_DEBUG,
+            true,
+            TraceState::default(),
+        ));
+        let span = tracer.start_from_context("debug_root", &context);
+
+        assert!(span.span_context().is_valid());
+        assert!(span.span_context().is_sampled());
+        assert!(span.span_context().is_debug());
+    }
+
+    #[test]
+    fn random_trace_flag() {
+        let tracer_provider = sdk::trace::TracerProvider::builder().build();
+        let tracer = tracer_provider.get_tracer("test", None);
+
+        let root = tracer.start("root");
+        assert!(root.span_context().is_random());
+
+        let child = tracer.start_from_context("child", &Context::current_with_span(root));
+        assert!(child.span_context().is_random());
+
+        let mut builder = SpanBuilder::from_name("explicit_trace_id".to_string());
+        builder.trace_id = Some(TraceId::from_u128(42));
+        assert!(!tracer.build(builder).span_context().is_random());
+
+        let remote_parent = Context::current_with_span(TestSpan(SpanContext::new(
+            TraceId::from_u128(128),
+            SpanId::from_u64(64),
+            TRACE_FLAG_SAMPLED,
+            true,
+            TraceState::default(),
+        )));
+        let span = tracer.start_from_context("non_random_parent", &remote_parent);
+        assert_eq!(span.span_context().trace_flags() & TRACE_FLAG_RANDOM, 0);
+    }
+
+    #[test]
+    fn remote_parent_without_span_id_starts_root_of_its_trace() {
+        let (exporter, rx_export, _rx_shutdown) = new_test_exporter();
+        let tracer_provider = sdk::trace::TracerProvider::builder()
+            .with_simple_exporter(exporter)
+            .build();
+        let tracer = tracer_provider.get_tracer("test", None);
+
+        let remote_parent = Context::new().with_remote_span_context(SpanContext::new(
+            TraceId::from_u128(128),
+            SpanId::invalid(),
+            TRACE_FLAG_SAMPLED,
+            true,
+            "origin=synthetics".parse().unwrap(),
+        ));
+        let span = tracer.start_from_context("root", &remote_parent);
+        assert_eq!(span.span_context().trace_id(), TraceId::from_u128(128));
+        assert_eq!(
+            span.span_context().trace_state().get("origin"),
+            Some("synthetics")
+        );
+        span.end();
+
+        let exported = rx_export.recv().unwrap();
+        assert_eq!(exported.parent_span_id, SpanId::invalid());
+    }
+
+    #[test]
+    fn local_children_inherit_sampling_probability() {
+        let sampler = Sampler::ParentBased(Box::new(Sampler::ConsistentProbabilityBased(1.0)));
+        let config = Config::default().with_default_sampler(sampler);
+        let (exporter, rx_export, _rx_shutdown) = new_test_exporter();
+        let tracer_provider = sdk::trace::TracerProvider::builder()
+            .with_simple_exporter(exporter)
+            .with_config(config)
+            .build();
+        let tracer = tracer_provider.get_tracer("test", None);
+
+        let parent = tracer.start("parent");
+        assert_eq!(parent.span_context().trace_state().ot_p_value(), Some(0));
+
+        let child = tracer.start_from_context("child", &Context::current_with_span(parent));
+        assert_eq!(child.span_context().trace_state().ot_p_value(), Some(0));
+        child.end();
+
+        let exported = rx_export.recv().unwrap();
+        assert_eq!(
+            exported.attributes.get(&"sampling.probability".into()),
+            Some(&1.0.into())
+        );
+    }
+
+    #[test]
+    fn only_consistent_samplers_inherit_sampling_probability() {
+        let sampler = Sampler::ParentBased(Box::new(Sampler::AlwaysOn));
+        let config = Config::default().with_default_sampler(sampler);
+        let (exporter, rx_export, _rx_shutdown) = new_test_exporter();
+        let tracer_provider = sdk::trace::TracerProvider::builder()
+            .with_simple_exporter(exporter)
+            .with_config(config)
+            .build();
+        let tracer = tracer_provider.get_tracer("test", None);
+
+        let remote_parent = Context::current_with_span(TestSpan(SpanContext::new(
+            TraceId::from_u128(128),
+            SpanId::from_u64(64),
+            TRACE_FLAG_SAMPLED,
+            true,
+            "ot=p:2;r:3".parse().unwrap(),
+        )));
+        let parent = tracer.start_from_context("parent", &remote_parent);
+        let child = tracer.start_from_context("child", &Context::current_with_span(parent));
+        assert_eq!(child.span_context().trace_state().ot_p_value(), Some(2));
+        child.end();
+
+        let exported = rx_export.try_iter().collect::<Vec<_>>();
+        assert_eq!(exported.len(), 2);
+        for span_data in exported {
+            assert!(span_data
+                .attributes
+                .get(&"sampling.probability".into())
+                .is_none());
+        }
+    }
 }
@@ -0,0 +1,76 @@
+use crate::{
+    labels::LabelSet,
+    metrics::{Descriptor, InstrumentKind, Number, NumberKind, Result},
+    sdk::{
+        export::metrics::{record, Aggregator, CheckpointSet, ExportKindFor, Record},
+        Resource,
+    },
+    KeyValue,
+};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+pub fn new_test_descriptor(
+    name: &str,
+    instrument_kind: InstrumentKind,
+    number_kind: NumberKind,
+) -> Descriptor {
+    Descriptor::new(
+        name.to_string(),
+        "test".to_string(),
+        instrument_kind,
+        number_kind,
+    )
+}
+
+/// Checkpoint set of records ending at the `1_600_000_000` unix timestamp,
+/// after a 10 seconds period.
+#[derive(Debug)]
+pub struct TestCheckpointSet {
+    resource: Resource,
+    records: Vec<(Descriptor, LabelSet, Arc<dyn Aggregator + Send + Sync>)>,
+}
+
+impl TestCheckpointSet {
+    pub fn new(resource: Resource) -> Self {
+        TestCheckpointSet {
+            resource,
+            records: Vec::new(),
+        }
+    }
+
+    pub fn add(
+        &mut self,
+        descriptor: Descriptor,
+        aggregator: Arc<dyn Aggregator + Send + Sync>,
+        values: Vec<Number>,
+        labels: Vec<KeyValue>,
+    ) {
+        for value in values {
+            aggregator.update(&value, &descriptor).unwrap();
+        }
+        self.records
+            .push((descriptor, LabelSet::from_labels(labels), aggregator));
+    }
+}
+
+impl CheckpointSet for TestCheckpointSet {
+    fn try_for_each(
+        &mut self,
+        _export_selector: &dyn ExportKindFor,
+        f: &mut dyn FnMut(&Record<'_>) -> Result<()>,
+    ) -> Result<()> {
+        let end = SystemTime::UNIX_EPOCH + Duration::from_secs(1_600_000_000);
+        for (descriptor, labels, aggregator) in &self.records {
+            f(&record(
+                descriptor,
+                labels,
+                &self.resource,
+                Some(aggregator),
+                end - Duration::from_secs(10),
+                end,
+            ))?;
+        }
+        Ok(())
+    }
+}
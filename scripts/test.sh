@@ -10,4 +10,5 @@ cargo test --manifest-path=opentelemetry-contrib/Cargo.toml --all-features
 cargo test --manifest-path=opentelemetry-jaeger/Cargo.toml --all-features
 cargo test --manifest-path=opentelemetry-otlp/Cargo.toml --all-features
 cargo test --manifest-path=opentelemetry-otlp/Cargo.toml --features "grpc-sys" --no-default-features
+cargo test --manifest-path=opentelemetry-statsd/Cargo.toml --all-features
 cargo test --manifest-path=opentelemetry-zipkin/Cargo.toml --all-features